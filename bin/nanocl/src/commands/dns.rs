@@ -0,0 +1,41 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::stubs::{dns::DnsRecordQuery, generic::NetworkKind};
+
+use crate::{
+  config::CliConfig,
+  models::{DnsArg, DnsCommand, DnsRecordRow, DnsRecordsOpts},
+  utils,
+};
+
+/// Function that execute when running `nanocl dns records`
+/// The records are the ones ncdns answers to the clients of the view
+async fn exec_dns_records(
+  cli_conf: &CliConfig,
+  opts: &DnsRecordsOpts,
+) -> IoResult<()> {
+  let client = &cli_conf.client;
+  let query = DnsRecordQuery {
+    network: Some(NetworkKind::from(&opts.view)),
+    namespace: opts.namespace.clone(),
+  };
+  let records = client.list_dns_record(Some(&query)).await?;
+  if opts.quiet {
+    for record in records {
+      println!("{}", record.name);
+    }
+    return Ok(());
+  }
+  let rows = records
+    .into_iter()
+    .map(DnsRecordRow::from)
+    .collect::<Vec<_>>();
+  utils::print::print_table(rows);
+  Ok(())
+}
+
+/// Function that execute when running `nanocl dns`
+pub async fn exec_dns(cli_conf: &CliConfig, args: &DnsArg) -> IoResult<()> {
+  match &args.command {
+    DnsCommand::Records(opts) => exec_dns_records(cli_conf, opts).await,
+  }
+}
@@ -103,6 +103,9 @@ pub async fn exec_install(args: &InstallOpts) -> IoResult<()> {
     gid: group.gid.into(),
     home_dir: home_dir.clone(),
     channel: crate::version::CHANNEL.to_owned(),
+    dns_backend: args.dns_backend.clone(),
+    network_policies: args.network_policies,
+    overlay_subnet: args.overlay_subnet.clone(),
   };
   let installer = utils::installer::get_template(args.template.clone()).await?;
   let data: liquid::Object = nanocld_args.clone().into();
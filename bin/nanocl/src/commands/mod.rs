@@ -1,6 +1,7 @@
 mod backup;
 mod cargo;
 mod context;
+mod dns;
 mod event;
 mod generic;
 mod info;
@@ -19,12 +20,14 @@ mod uninstall;
 mod version;
 mod vm;
 mod vm_image;
+mod volume;
 
 pub use generic::*;
 
 pub use backup::exec_backup;
 pub use cargo::exec_cargo;
 pub use context::exec_context;
+pub use dns::exec_dns;
 pub use event::exec_event;
 pub use info::exec_info;
 #[cfg(not(target_os = "windows"))]
@@ -41,3 +44,4 @@ pub use state::exec_state;
 pub use uninstall::exec_uninstall;
 pub use version::exec_version;
 pub use vm::exec_vm;
+pub use volume::exec_volume;
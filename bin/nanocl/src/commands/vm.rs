@@ -10,16 +10,21 @@ use futures::{
   channel::mpsc,
   {SinkExt, StreamExt},
 };
+use indicatif::{ProgressBar, ProgressStyle};
 use ntex::{rt, time, util::Bytes, ws};
 #[cfg(not(target_os = "windows"))]
 use termios::{tcsetattr, Termios, ECHO, ICANON, TCSANOW};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::{TcpListener, TcpStream},
+};
 
-use nanocl_error::io::{FromIo, IoResult};
+use nanocl_error::io::{FromIo, IoError, IoResult};
 use nanocld_client::{
   stubs::{
     process::{OutputKind, OutputLog},
     system::{EventActorKind, NativeEventAction},
-    vm::VmInspect,
+    vm::{VmHotplug, VmInspect, VmMigrateStream, VmSnapshotPartial},
     vm_spec::VmSpecPartial,
   },
   NanocldClient,
@@ -28,8 +33,9 @@ use nanocld_client::{
 use crate::{
   config::CliConfig,
   models::{
-    GenericDefaultOpts, VmArg, VmCommand, VmCreateOpts, VmPatchOpts, VmRow,
-    VmRunOpts,
+    GenericDefaultOpts, VmArg, VmCommand, VmConsoleOpts, VmCreateOpts,
+    VmHotplugOpts, VmMigrateOpts, VmPatchOpts, VmRow, VmRunOpts, VmSnapshotArg,
+    VmSnapshotCommand, VmSnapshotRow,
   },
   utils,
 };
@@ -246,6 +252,202 @@ pub async fn exec_vm_attach(
   Ok(())
 }
 
+/// Tunnel a local vnc client to the display of a vm
+async fn forward_vnc(
+  client: NanocldClient,
+  name: String,
+  namespace: Option<String>,
+  stream: TcpStream,
+) -> IoResult<()> {
+  /// How often heartbeat pings are sent
+  const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+  let conn = client.vnc_vm(&name, namespace.as_deref()).await?;
+  let (mut reader, mut writer) = stream.into_split();
+  // start heartbeat task
+  let sink = conn.sink();
+  rt::spawn(async move {
+    loop {
+      time::sleep(HEARTBEAT_INTERVAL).await;
+      if sink.send(ws::Message::Ping(Bytes::new())).await.is_err() {
+        return;
+      }
+    }
+  });
+  // send what the vnc client writes to the vm
+  let sink = conn.sink();
+  rt::spawn(async move {
+    let mut buf = vec![0; 64 * 1024];
+    while let Ok(n) = reader.read(&mut buf).await {
+      if n == 0 {
+        break;
+      }
+      let msg = ws::Message::Binary(Bytes::copy_from_slice(&buf[..n]));
+      if sink.send(msg).await.is_err() {
+        return;
+      }
+    }
+    let _ = sink.send(ws::Message::Close(None)).await;
+  });
+  // run ws dispatcher
+  let sink = conn.sink();
+  let mut rx = conn.seal().receiver();
+  while let Some(frame) = rx.next().await {
+    match frame {
+      Ok(ws::Frame::Binary(data)) => writer.write_all(&data).await?,
+      Ok(ws::Frame::Ping(msg)) => {
+        sink
+          .send(ws::Message::Pong(msg))
+          .await
+          .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+      }
+      Ok(ws::Frame::Close(_)) | Err(_) => break,
+      _ => (),
+    }
+  }
+  Ok(())
+}
+
+/// Function executed when running `nanocl vm console`
+/// It will attach to the tty of a vm or forward its vnc display to a local port
+pub async fn exec_vm_console(
+  cli_conf: &CliConfig,
+  args: &VmArg,
+  options: &VmConsoleOpts,
+) -> IoResult<()> {
+  if !options.vnc {
+    #[cfg(not(target_os = "windows"))]
+    {
+      return exec_vm_attach(cli_conf, args, &options.name).await;
+    }
+    #[cfg(target_os = "windows")]
+    {
+      println!("Attach is not supported on windows yet");
+      return Ok(());
+    }
+  }
+  let addr = format!("{}:{}", options.listen, options.port);
+  let listener = TcpListener::bind(&addr)
+    .await
+    .map_err(|err| err.map_err_context(|| &addr))?;
+  println!("Forwarding the display of {} on vnc://{addr}", options.name);
+  loop {
+    let (stream, peer) = listener
+      .accept()
+      .await
+      .map_err(|err| err.map_err_context(|| &addr))?;
+    let client = cli_conf.client.clone();
+    let name = options.name.clone();
+    let namespace = args.namespace.clone();
+    rt::spawn(async move {
+      if let Err(err) = forward_vnc(client, name, namespace, stream).await {
+        eprintln!("Vnc connection from {peer} closed: {err}");
+      }
+    });
+  }
+}
+
+/// Function executed when running `nanocl vm snapshot`
+pub async fn exec_vm_snapshot(
+  client: &NanocldClient,
+  args: &VmArg,
+  snapshot_args: &VmSnapshotArg,
+) -> IoResult<()> {
+  let namespace = args.namespace.as_deref();
+  match &snapshot_args.command {
+    VmSnapshotCommand::List { name } => {
+      let snapshots = client.list_vm_snapshots(name, namespace).await?;
+      let rows = snapshots
+        .into_iter()
+        .map(VmSnapshotRow::from)
+        .collect::<Vec<_>>();
+      utils::print::print_table(rows);
+    }
+    VmSnapshotCommand::Create { name, snapshot } => {
+      let snapshot = VmSnapshotPartial {
+        name: snapshot.clone(),
+      };
+      client
+        .create_vm_snapshot(name, &snapshot, namespace)
+        .await?;
+    }
+    VmSnapshotCommand::Restore { name, snapshot } => {
+      client
+        .restore_vm_snapshot(name, snapshot, namespace)
+        .await?;
+    }
+    VmSnapshotCommand::Remove { name, snapshot } => {
+      client.delete_vm_snapshot(name, snapshot, namespace).await?;
+    }
+  }
+  Ok(())
+}
+
+/// Function executed when running `nanocl vm hotplug`
+pub async fn exec_vm_hotplug(
+  client: &NanocldClient,
+  args: &VmArg,
+  options: &VmHotplugOpts,
+) -> IoResult<()> {
+  let devices: Vec<VmHotplug> = options.clone().into();
+  if devices.is_empty() {
+    return Err(IoError::invalid_input(
+      "VmHotplug",
+      "Expected at least one of --cpu, --mem or --nic",
+    ));
+  }
+  for device in devices {
+    client
+      .hotplug_process("vm", &options.name, &device, args.namespace.as_deref())
+      .await?;
+  }
+  Ok(())
+}
+
+/// Function executed when running `nanocl vm migrate`
+pub async fn exec_vm_migrate(
+  client: &NanocldClient,
+  args: &VmArg,
+  options: &VmMigrateOpts,
+) -> IoResult<()> {
+  let payload = options.clone().into();
+  let mut stream = client
+    .migrate_vm(&options.name, &payload, args.namespace.as_deref())
+    .await?;
+  let pg = ProgressBar::new(100);
+  let style = ProgressStyle::with_template(
+    "[{elapsed_precise}] [{bar:20.cyan/blue}] {pos:>7}% {msg}",
+  )
+  .unwrap()
+  .progress_chars("=> ");
+  pg.set_style(style);
+  while let Some(item) = stream.next().await {
+    match item? {
+      VmMigrateStream::Transfer(transfer) => {
+        pg.set_position(utils::math::calculate_percentage(
+          transfer.current,
+          transfer.total,
+        ));
+        pg.set_message(format!("copying {}", transfer.image));
+      }
+      VmMigrateStream::Memory(memory) => {
+        pg.set_position(utils::math::calculate_percentage(
+          memory.transferred,
+          memory.total,
+        ));
+        pg.set_message(format!(
+          "memory {}/{} MB",
+          memory.transferred >> 20,
+          memory.total >> 20
+        ));
+      }
+      VmMigrateStream::Done(_) => {
+        pg.finish_and_clear();
+      }
+    }
+  }
+  Ok(())
+}
+
 /// Function executed when running `nanocl vm`
 /// It will execute the subcommand passed as argument
 pub async fn exec_vm(cli_conf: &CliConfig, args: &VmArg) -> IoResult<()> {
@@ -269,6 +471,24 @@ pub async fn exec_vm(cli_conf: &CliConfig, args: &VmArg) -> IoResult<()> {
     }
     VmCommand::Run(options) => exec_vm_run(cli_conf, args, options).await,
     VmCommand::Patch(options) => exec_vm_patch(cli_conf, args, options).await,
+    VmCommand::Snapshot(snapshot_args) => {
+      exec_vm_snapshot(client, args, snapshot_args).await
+    }
+    VmCommand::Pause { name } => {
+      client.pause_process("vm", name, Some(&namespace)).await?;
+      Ok(())
+    }
+    VmCommand::Resume { name } => {
+      client.resume_process("vm", name, Some(&namespace)).await?;
+      Ok(())
+    }
+    VmCommand::Reset { name } => {
+      client.reset_process("vm", name, Some(&namespace)).await?;
+      Ok(())
+    }
+    VmCommand::Hotplug(opts) => exec_vm_hotplug(client, args, opts).await,
+    VmCommand::Console(opts) => exec_vm_console(cli_conf, args, opts).await,
+    VmCommand::Migrate(opts) => exec_vm_migrate(client, args, opts).await,
     VmCommand::Attach { name } => {
       #[cfg(not(target_os = "windows"))]
       {
@@ -2,18 +2,21 @@ use std::path::Path;
 
 use futures::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
+use tokio::io::AsyncWriteExt;
 use tokio_util::codec;
 
 use nanocl_error::io::{FromIo, IoResult};
 use nanocld_client::{
-  stubs::vm_image::{VmImage, VmImageCloneStream},
+  stubs::vm_image::{
+    VmImage, VmImageCloneStream, VmImageExportQuery, VmImagePullStream,
+  },
   NanocldClient,
 };
 
 use crate::{
   models::{
     GenericDefaultOpts, VmImageArg, VmImageCommand, VmImageCreateOpts,
-    VmImageResizeOpts, VmImageRow,
+    VmImageExportOpts, VmImagePullOpts, VmImageResizeOpts, VmImageRow,
   },
   utils,
 };
@@ -105,6 +108,77 @@ async fn exec_vm_image_clone(
   Ok(())
 }
 
+/// Function that execute when running `nanocl vm image pull`
+async fn exec_vm_image_pull(
+  client: &NanocldClient,
+  options: &VmImagePullOpts,
+) -> IoResult<()> {
+  let payload = options.clone().into();
+  let mut stream = client.pull_vm_image(&options.name, &payload).await?;
+  let pg = ProgressBar::new(100);
+  let style = ProgressStyle::with_template(
+    "[{elapsed_precise}] [{bar:20.cyan/blue}] {pos:>7}% {msg}",
+  )
+  .unwrap()
+  .progress_chars("=> ");
+  pg.set_style(style);
+  while let Some(item) = stream.next().await {
+    let item = item?;
+    match item {
+      VmImagePullStream::Progress(progress) => match progress.total {
+        Some(total) => {
+          pg.set_position(utils::math::calculate_percentage(
+            progress.current,
+            total,
+          ));
+        }
+        None => pg.set_message(format!("{} MB", progress.current >> 20)),
+      },
+      VmImagePullStream::Converting(format) => {
+        pg.set_message(format!("converting from {format} to qcow2"));
+      }
+      VmImagePullStream::Done(_) => {
+        pg.finish_and_clear();
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Function that execute when running `nanocl vm image export`
+async fn exec_vm_image_export(
+  client: &NanocldClient,
+  options: &VmImageExportOpts,
+) -> IoResult<()> {
+  let query: VmImageExportQuery = options.clone().into();
+  let output = options.output.clone().unwrap_or(format!(
+    "{}.{}",
+    options.name,
+    query.format.clone().unwrap_or_default()
+  ));
+  let mut stream = client.export_vm_image(&options.name, Some(&query)).await?;
+  let mut file = tokio::fs::File::create(&output)
+    .await
+    .map_err(|err| err.map_err_context(|| &output))?;
+  let pg = ProgressBar::new_spinner();
+  let mut written: u64 = 0;
+  while let Some(bytes) = stream.next().await {
+    let bytes = bytes?;
+    file
+      .write_all(&bytes)
+      .await
+      .map_err(|err| err.map_err_context(|| &output))?;
+    written += bytes.len() as u64;
+    pg.set_message(format!("{} MB written to {output}", written >> 20));
+  }
+  file
+    .flush()
+    .await
+    .map_err(|err| err.map_err_context(|| &output))?;
+  pg.finish_and_clear();
+  Ok(())
+}
+
 /// Function that execute when running `nanocl vm resize`
 async fn exec_vm_resize(
   client: &NanocldClient,
@@ -124,6 +198,7 @@ pub async fn exec_vm_image(
     VmImageCommand::Create(options) => {
       exec_vm_image_create(client, options).await
     }
+    VmImageCommand::Pull(options) => exec_vm_image_pull(client, options).await,
     VmImageCommand::List(opts) => VmImageArg::exec_ls(client, args, opts).await,
     VmImageCommand::Remove(opts) => {
       VmImageArg::exec_rm(client, opts, None).await
@@ -132,5 +207,6 @@ pub async fn exec_vm_image(
       exec_vm_image_clone(client, name, clone_name).await
     }
     VmImageCommand::Resize(opts) => exec_vm_resize(client, opts).await,
+    VmImageCommand::Export(opts) => exec_vm_image_export(client, opts).await,
   }
 }
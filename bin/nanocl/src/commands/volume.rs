@@ -0,0 +1,110 @@
+use nanocl_error::io::IoResult;
+use nanocld_client::stubs::{
+  generic::{GenericFilter, GenericListQueryNsp, GenericNspQuery},
+  volume::{Volume, VolumeInspect},
+};
+
+use crate::{
+  config::CliConfig,
+  models::{
+    GenericDefaultOpts, GenericRemoveOpts, VolumeArg, VolumeCommand,
+    VolumeCreateOpts, VolumePatchOpts, VolumeRow,
+  },
+};
+
+use super::{
+  GenericCommand, GenericCommandInspect, GenericCommandLs, GenericCommandRm,
+};
+
+impl GenericCommand for VolumeArg {
+  fn object_name() -> &'static str {
+    "volumes"
+  }
+}
+
+impl GenericCommandLs for VolumeArg {
+  type Item = VolumeRow;
+  type Args = VolumeArg;
+  type ApiItem = Volume;
+
+  fn get_key(item: &Self::Item) -> String {
+    item.name.clone()
+  }
+
+  fn transform_filter(
+    args: &Self::Args,
+    filter: &GenericFilter,
+  ) -> impl serde::Serialize {
+    GenericListQueryNsp::try_from(filter.clone())
+      .unwrap()
+      .with_namespace(args.namespace.as_deref())
+  }
+}
+
+impl GenericCommandRm<GenericDefaultOpts, GenericNspQuery> for VolumeArg {
+  fn get_query(
+    _opts: &GenericRemoveOpts<GenericDefaultOpts>,
+    namespace: Option<String>,
+  ) -> Option<GenericNspQuery>
+  where
+    GenericNspQuery: serde::Serialize,
+  {
+    Some(GenericNspQuery::new(namespace.as_deref()))
+  }
+}
+
+impl GenericCommandInspect for VolumeArg {
+  type ApiItem = VolumeInspect;
+}
+
+/// Execute the `nanocl volume create` command to create a new volume
+async fn exec_volume_create(
+  cli_conf: &CliConfig,
+  args: &VolumeArg,
+  opts: &VolumeCreateOpts,
+) -> IoResult<()> {
+  let volume = opts.clone().into();
+  let item = cli_conf
+    .client
+    .create_volume(&volume, args.namespace.as_deref())
+    .await?;
+  println!("{}", item.key);
+  Ok(())
+}
+
+/// Execute the `nanocl volume patch` command to update the backup policy
+async fn exec_volume_patch(
+  cli_conf: &CliConfig,
+  args: &VolumeArg,
+  opts: &VolumePatchOpts,
+) -> IoResult<()> {
+  let update = opts.clone().into();
+  cli_conf
+    .client
+    .patch_volume(&opts.name, &update, args.namespace.as_deref())
+    .await?;
+  Ok(())
+}
+
+/// Function that execute when running `nanocl volume`
+pub async fn exec_volume(
+  cli_conf: &CliConfig,
+  args: &VolumeArg,
+) -> IoResult<()> {
+  let namespace = args.namespace.clone();
+  match &args.command {
+    VolumeCommand::Create(opts) => {
+      exec_volume_create(cli_conf, args, opts).await
+    }
+    VolumeCommand::List(opts) => {
+      VolumeArg::exec_ls(&cli_conf.client, args, opts).await
+    }
+    VolumeCommand::Remove(opts) => {
+      VolumeArg::exec_rm(&cli_conf.client, opts, namespace).await
+    }
+    VolumeCommand::Inspect(opts) => {
+      VolumeArg::exec_inspect(cli_conf, opts, namespace).await
+    }
+    VolumeCommand::Patch(opts) => exec_volume_patch(cli_conf, args, opts).await,
+  }
+}
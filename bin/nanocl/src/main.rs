@@ -94,6 +94,7 @@ async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
     Command::State(args) => commands::exec_state(&cli_conf, args).await,
     Command::Version => commands::exec_version(&cli_conf).await,
     Command::Vm(args) => commands::exec_vm(&cli_conf, args).await,
+    Command::Volume(args) => commands::exec_volume(&cli_conf, args).await,
     Command::Logs(args) => commands::logs_process(&cli_conf, args).await,
     Command::Inspect(args) => commands::inspect_process(&cli_conf, args).await,
     Command::Ps(args) => commands::exec_process(&cli_conf, args).await,
@@ -123,6 +124,7 @@ async fn execute_arg(cli_args: &Cli) -> IoResult<()> {
     Command::Context(args) => commands::exec_context(&cli_conf, args).await,
     Command::Info => commands::exec_info(&cli_conf).await,
     Command::Metric(args) => commands::exec_metric(&cli_conf, args).await,
+    Command::Dns(args) => commands::exec_dns(&cli_conf, args).await,
     Command::Backup(opts) => commands::exec_backup(&cli_conf, opts).await,
   }
 }
@@ -350,6 +352,25 @@ mod tests {
     assert_cli_ok!("state", "rm", "-ys", "../../examples/deploy_example.yml");
   }
 
+  /// Test cargo run with published ports
+  #[ntex::test]
+  async fn cargo_publish() {
+    const CARGO_NAME: &str = "cli-publish";
+    const IMAGE_NAME: &str = "ghcr.io/next-hat/nanocl-get-started:latest";
+    assert_cli_ok!(
+      "cargo",
+      "run",
+      CARGO_NAME,
+      IMAGE_NAME,
+      "-p",
+      "9191:9000",
+      "-p",
+      "9191:9000/udp",
+    );
+    assert_cli_ok!("resource", "inspect", "cli-publish.global.ports");
+    assert_cli_ok!("cargo", "rm", "-fy", CARGO_NAME);
+  }
+
   /// Test cargo exec command
   #[ntex::test]
   async fn cargo_exec() {
@@ -735,6 +756,13 @@ mod tests {
     assert_cli_ok!("metric", "ls", "-q", "--limit", "2", "--offset", "1");
   }
 
+  #[ntex::test]
+  async fn dns() {
+    assert_cli_ok!("dns", "records");
+    assert_cli_ok!("dns", "ls", "--view", "public", "-q");
+    assert_cli_ok!("dns", "ls", "--view", "internal", "-n", "global");
+  }
+
   #[ntex::test]
   async fn event() {
     assert_cli_ok!("event", "ls");
@@ -781,6 +809,24 @@ mod tests {
     assert_cli_ok!("secret", "rm", "-y", "test-cli");
   }
 
+  #[ntex::test]
+  async fn volume() {
+    assert_cli_ok!("volume", "create", "test-cli-volume");
+    assert_cli_err!("volume", "create", "test-cli-volume");
+    assert_cli_ok!("volume", "ls");
+    assert_cli_ok!("volume", "ls", "-q");
+    assert_cli_ok!("volume", "inspect", "test-cli-volume");
+    assert_cli_ok!(
+      "volume",
+      "patch",
+      "test-cli-volume",
+      "--backup-schedule",
+      "0 0 * * *"
+    );
+    assert_cli_ok!("volume", "patch", "test-cli-volume");
+    assert_cli_ok!("volume", "rm", "-y", "test-cli-volume");
+  }
+
   #[ntex::test]
   async fn virtual_machine() {
     assert_cli_ok!(
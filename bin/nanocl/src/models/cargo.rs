@@ -6,7 +6,10 @@ use bollard_next::exec::CreateExecOptions;
 
 use nanocld_client::stubs::{
   cargo::CargoSummary,
-  cargo_spec::{CargoSpecPartial, CargoSpecUpdate, Config, HostConfig},
+  cargo_spec::{
+    CargoPort, CargoSpecPartial, CargoSpecUpdate, Config, HostConfig,
+  },
+  volume::VolumeMount,
 };
 
 use super::{
@@ -27,6 +30,12 @@ pub struct CargoCreateOpts {
   /// Environment variables of the cargo
   #[clap(short, long = "env")]
   pub(crate) env: Option<Vec<String>>,
+  /// Ports to publish on the nodes as [host_port:]container_port[/tcp|udp]
+  #[clap(short, long = "publish")]
+  pub ports: Option<Vec<CargoPort>>,
+  /// Named volumes to mount as name:path[:ro]
+  #[clap(long = "mount")]
+  pub mounts: Option<Vec<VolumeMount>>,
 }
 
 /// Convert CargoCreateOpts to CargoSpecPartial
@@ -45,6 +54,8 @@ impl From<CargoCreateOpts> for CargoSpecPartial {
         }),
         ..Default::default()
       },
+      ports: val.ports,
+      volumes: val.mounts,
       ..Default::default()
     }
   }
@@ -63,6 +74,12 @@ pub struct CargoRunOpts {
   /// Environment variables of the cargo
   #[clap(short, long = "env")]
   pub env: Option<Vec<String>>,
+  /// Ports to publish on the nodes as [host_port:]container_port[/tcp|udp]
+  #[clap(short, long = "publish")]
+  pub ports: Option<Vec<CargoPort>>,
+  /// Named volumes to mount as name:path[:ro]
+  #[clap(long = "mount")]
+  pub mounts: Option<Vec<VolumeMount>>,
   #[clap(long = "rm", default_value = "false")]
   pub auto_remove: bool,
   /// Command to execute
@@ -87,6 +104,8 @@ impl From<CargoRunOpts> for CargoSpecPartial {
         }),
         ..Default::default()
       },
+      ports: val.ports,
+      volumes: val.mounts,
       ..Default::default()
     }
   }
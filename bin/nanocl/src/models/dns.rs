@@ -0,0 +1,84 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use tabled::Tabled;
+
+use nanocld_client::stubs::{dns::DnsServedRecord, generic::NetworkKind};
+
+/// `nanocl dns` available arguments
+#[derive(Clone, Parser)]
+pub struct DnsArg {
+  #[clap(subcommand)]
+  pub command: DnsCommand,
+}
+
+/// `nanocl dns` available commands
+#[derive(Clone, Subcommand)]
+pub enum DnsCommand {
+  /// List the records answered to the clients of a view
+  #[clap(alias("ls"))]
+  Records(DnsRecordsOpts),
+}
+
+/// Network the clients query the records from
+#[derive(Clone, Default, ValueEnum)]
+pub enum DnsView {
+  /// Clients of the host itself
+  Local,
+  /// Clients reaching the public address of the host
+  Public,
+  /// Cargoes and virtual machines on the bridge network
+  #[default]
+  Internal,
+}
+
+impl From<&DnsView> for NetworkKind {
+  fn from(view: &DnsView) -> Self {
+    match view {
+      DnsView::Local => NetworkKind::Local,
+      DnsView::Public => NetworkKind::Public,
+      DnsView::Internal => NetworkKind::Internal,
+    }
+  }
+}
+
+/// `nanocl dns records` available options
+#[derive(Clone, Parser)]
+pub struct DnsRecordsOpts {
+  /// View to list the records of
+  #[clap(long, value_enum, default_value = "internal")]
+  pub view: DnsView,
+  /// Namespace of the clients, only global records are listed when empty
+  #[clap(long, short)]
+  pub namespace: Option<String>,
+  /// Only show names
+  #[clap(long, short)]
+  pub quiet: bool,
+}
+
+/// A row of the dns records table
+#[derive(Clone, Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct DnsRecordRow {
+  /// Name of the record
+  pub name: String,
+  /// Type of the record eg: A, CNAME
+  #[tabled(rename = "TYPE")]
+  pub kind: String,
+  /// Value answered for the record
+  pub value: String,
+  /// Namespace the record is scoped to
+  pub namespace: String,
+  /// Zone serving the record
+  pub zone: String,
+}
+
+impl From<DnsServedRecord> for DnsRecordRow {
+  fn from(record: DnsServedRecord) -> Self {
+    Self {
+      name: record.name,
+      kind: record.kind,
+      value: record.value,
+      namespace: record.namespace.unwrap_or("<all>".to_owned()),
+      zone: record.zone,
+    }
+  }
+}
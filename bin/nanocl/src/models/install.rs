@@ -33,6 +33,18 @@ pub struct InstallOpts {
   /// Installation template to use for nanocl by default it's detected
   #[clap(short, long)]
   pub(crate) template: Option<String>,
+  /// Server answering the dns queries, dnsmasq also installs the `ndns` component
+  #[clap(long, default_value = "embedded", value_parser = ["embedded", "dnsmasq"])]
+  pub(crate) dns_backend: String,
+  /// Enforce the network policies, it gives the daemon the pid namespace
+  /// of the host with the NET_ADMIN and SYS_ADMIN capabilities
+  #[clap(long)]
+  pub(crate) network_policies: bool,
+  /// Address pool of the overlay network between the nodes eg: 10.210.0.0/16,
+  /// it gives the daemon the pid namespace of the host with the NET_ADMIN
+  /// and SYS_ADMIN capabilities
+  #[clap(long)]
+  pub(crate) overlay_subnet: Option<String>,
   /// Force re pull of the nanocl components
   #[clap(short = 'p', long)]
   pub(crate) force_pull: bool,
@@ -67,6 +79,12 @@ pub struct NanocldArg {
   pub(crate) is_docker_desktop: bool,
   /// Build channel used
   pub(crate) channel: String,
+  /// Server answering the dns queries
+  pub(crate) dns_backend: String,
+  /// Enforce the network policies
+  pub(crate) network_policies: bool,
+  /// Address pool of the overlay network
+  pub(crate) overlay_subnet: Option<String>,
   /// Specify if the docker host is unix socket
   pub(crate) docker_uds_path: Option<String>,
   /// Specify if the docker host is different on host
@@ -88,6 +106,9 @@ impl From<NanocldArg> for liquid::Object {
       "is_docker_desktop": arg.is_docker_desktop,
       "home_dir": arg.home_dir,
       "channel": arg.channel,
+      "dns_backend": arg.dns_backend,
+      "network_policies": arg.network_policies,
+      "overlay_subnet": arg.overlay_subnet,
       "docker_uds_path": arg.docker_uds_path,
       "docker_uds_host_path": arg.docker_uds_host_path,
     })
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 mod backup;
 mod cargo;
 mod context;
+mod dns;
 mod event;
 mod generic;
 mod install;
@@ -20,10 +21,12 @@ mod uninstall;
 mod version;
 mod vm;
 mod vm_image;
+mod volume;
 
 pub use backup::*;
 pub use cargo::*;
 pub use context::*;
+pub use dns::*;
 pub use event::*;
 pub use generic::*;
 pub use install::*;
@@ -38,6 +41,7 @@ pub use state::*;
 pub use uninstall::*;
 pub use vm::*;
 pub use vm_image::*;
+pub use volume::*;
 
 /// Cli available options and commands
 #[derive(Parser)]
@@ -101,10 +105,14 @@ pub enum Command {
   Cargo(CargoArg),
   /// Manage virtual machines
   Vm(VmArg),
+  /// Manage volumes
+  Volume(VolumeArg),
   /// Manage resources
   Resource(ResourceArg),
   /// Manage metrics
   Metric(MetricArg),
+  /// Show dns records
+  Dns(DnsArg),
   /// Manage contexts
   Context(ContextArg),
   /// Manage nodes (experimental)
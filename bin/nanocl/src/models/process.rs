@@ -83,15 +83,14 @@ pub struct ProcessRow {
 /// Convert Process to ProcessRow
 impl From<Process> for ProcessRow {
   fn from(process: Process) -> Self {
+    let network_address = process.network_address();
     let container = process.data;
     let name = container.name.unwrap_or_default().replace('/', "");
     let mut names = name.split('.');
     let _next_name = names.next().unwrap_or(&name);
     let config = container.config.unwrap_or_default();
-    let network = container.network_settings.unwrap_or_default();
-    let networks = network.networks.unwrap_or_default();
-    let mut ip_addr = if let Some(network) = networks.get("nanoclbr0") {
-      network.ip_address.clone().unwrap_or("<none>".to_owned())
+    let mut ip_addr = if let Some(ip_address) = network_address {
+      ip_address
     } else {
       format!(
         "<{}>",
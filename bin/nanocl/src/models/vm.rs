@@ -2,7 +2,9 @@ use chrono::TimeZone;
 use clap::{Parser, Subcommand};
 use tabled::Tabled;
 
-use nanocld_client::stubs::vm::VmSummary;
+use nanocld_client::stubs::vm::{
+  VmHotplug, VmMigratePayload, VmNic, VmSnapshot, VmSummary,
+};
 use nanocld_client::stubs::vm_spec::{
   VmDisk, VmHostConfig, VmSpecPartial, VmSpecUpdate,
 };
@@ -40,6 +42,179 @@ pub enum VmCommand {
   },
   /// Patch a vm
   Patch(VmPatchOpts),
+  /// Manage the snapshots of a vm
+  Snapshot(VmSnapshotArg),
+  /// Freeze the cpus of a running vm
+  Pause {
+    /// Name of the vm
+    name: String,
+  },
+  /// Resume a paused vm
+  Resume {
+    /// Name of the vm
+    name: String,
+  },
+  /// Reset a running vm
+  Reset {
+    /// Name of the vm
+    name: String,
+  },
+  /// Add cpus, memory or a network card to a running vm
+  Hotplug(VmHotplugOpts),
+  /// Open the console of a vm, its serial tty or its vnc display
+  Console(VmConsoleOpts),
+  /// Move a vm and its disks to another node
+  Migrate(VmMigrateOpts),
+}
+
+/// `nanocl vm migrate` available options
+#[derive(Clone, Parser)]
+pub struct VmMigrateOpts {
+  /// Name of the vm
+  pub name: String,
+  /// Name of the node to migrate the vm to
+  pub node: String,
+}
+
+/// Convert VmMigrateOpts to VmMigratePayload
+impl From<VmMigrateOpts> for VmMigratePayload {
+  fn from(val: VmMigrateOpts) -> Self {
+    Self { node: val.node }
+  }
+}
+
+/// `nanocl vm console` available options
+#[derive(Clone, Parser)]
+pub struct VmConsoleOpts {
+  /// Forward the vnc display of the vm to a local port instead of attaching its tty
+  #[clap(long)]
+  pub vnc: bool,
+  /// Local address to listen on for vnc clients
+  #[clap(long, default_value = "127.0.0.1", requires = "vnc")]
+  pub listen: String,
+  /// Local port to listen on for vnc clients
+  #[clap(long, short, default_value = "5900", requires = "vnc")]
+  pub port: u16,
+  /// Name of the vm
+  pub name: String,
+}
+
+/// `nanocl vm hotplug` available options
+#[derive(Clone, Parser)]
+pub struct VmHotplugOpts {
+  /// Number of cpu to add
+  #[clap(long)]
+  pub cpu: Option<u64>,
+  /// Memory to add in MB
+  #[clap(long = "mem")]
+  pub memory: Option<u64>,
+  /// Id of a network card to add
+  #[clap(long)]
+  pub nic: Option<String>,
+  /// Mac address of the network card
+  #[clap(long, requires = "nic")]
+  pub mac: Option<String>,
+  /// Name of the vm
+  pub name: String,
+}
+
+/// Convert VmHotplugOpts to the list of devices to plug
+impl From<VmHotplugOpts> for Vec<VmHotplug> {
+  fn from(val: VmHotplugOpts) -> Self {
+    let mut devices = Vec::new();
+    if let Some(cpu) = val.cpu {
+      devices.push(VmHotplug::Cpu(cpu));
+    }
+    if let Some(memory) = val.memory {
+      devices.push(VmHotplug::Memory(memory));
+    }
+    if let Some(id) = val.nic {
+      devices.push(VmHotplug::Nic(VmNic { id, mac: val.mac }));
+    }
+    devices
+  }
+}
+
+/// `nanocl vm snapshot` available commands
+#[derive(Clone, Subcommand)]
+pub enum VmSnapshotCommand {
+  /// List the snapshots of a vm
+  #[clap(alias = "ls")]
+  List {
+    /// Name of the vm
+    name: String,
+  },
+  /// Snapshot the disks and the memory of a running vm
+  Create {
+    /// Name of the vm
+    name: String,
+    /// Name of the snapshot
+    snapshot: String,
+  },
+  /// Restore a running vm to a snapshot
+  Restore {
+    /// Name of the vm
+    name: String,
+    /// Name of the snapshot
+    snapshot: String,
+  },
+  /// Remove a snapshot of a vm
+  #[clap(alias = "rm")]
+  Remove {
+    /// Name of the vm
+    name: String,
+    /// Name of the snapshot
+    snapshot: String,
+  },
+}
+
+/// `nanocl vm snapshot` available arguments
+#[derive(Clone, Parser)]
+pub struct VmSnapshotArg {
+  /// Command to run
+  #[clap(subcommand)]
+  pub command: VmSnapshotCommand,
+}
+
+/// A row for the vm snapshot table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct VmSnapshotRow {
+  /// Name of the snapshot
+  pub name: String,
+  /// Size of the saved memory
+  #[tabled(rename = "MEMORY")]
+  pub vm_state_size: String,
+  /// Time the vm was running
+  #[tabled(rename = "VM CLOCK")]
+  pub vm_clock: String,
+  /// When the snapshot was created
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+}
+
+/// Convert VmSnapshot to VmSnapshotRow
+impl From<VmSnapshot> for VmSnapshotRow {
+  fn from(item: VmSnapshot) -> Self {
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    let created_at = tz
+      .timestamp_opt(item.created_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    let clock = item.vm_clock;
+    Self {
+      name: item.name,
+      vm_state_size: format!("{} MB", item.vm_state_size / 1024 / 1024),
+      vm_clock: format!(
+        "{:02}:{:02}:{:02}",
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60
+      ),
+      created_at: format!("{created_at}"),
+    }
+  }
 }
 
 /// `nanocl vm patch` available options
@@ -124,6 +299,9 @@ pub struct VmRunOpts {
   /// Enable KVM
   #[clap(long)]
   pub kvm: bool,
+  /// Expose the display of the vm through vnc
+  #[clap(long)]
+  pub vnc: bool,
   /// Attach to the vm
   #[clap(short, long)]
   pub attach: bool,
@@ -151,6 +329,7 @@ impl From<VmRunOpts> for VmSpecPartial {
         memory: val.memory.unwrap_or(512),
         net_iface: val.net_iface,
         kvm: Some(val.kvm),
+        vnc: Some(val.vnc),
         ..Default::default()
       }),
       ..Default::default()
@@ -185,6 +364,9 @@ pub struct VmCreateOpts {
   /// Enable KVM
   #[clap(long)]
   pub kvm: bool,
+  /// Expose the display of the vm through vnc
+  #[clap(long)]
+  pub vnc: bool,
   /// Name of the vm
   pub name: String,
   /// Name of the vm image
@@ -205,6 +387,7 @@ impl From<VmCreateOpts> for VmSpecPartial {
         memory: val.memory.unwrap_or(512),
         net_iface: val.net_iface,
         kvm: Some(val.kvm),
+        vnc: Some(val.vnc),
         ..Default::default()
       }),
       disk: VmDisk {
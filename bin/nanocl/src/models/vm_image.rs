@@ -1,8 +1,11 @@
 use chrono::TimeZone;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use tabled::Tabled;
 
-use nanocld_client::stubs::vm_image::{VmImage, VmImageResizePayload};
+use nanocld_client::stubs::vm_image::{
+  VmImage, VmImageExportFormat, VmImageExportQuery, VmImagePullPayload,
+  VmImageResizePayload, VmImageSource,
+};
 
 use super::{GenericListOpts, GenericRemoveOpts};
 
@@ -11,6 +14,8 @@ use super::{GenericListOpts, GenericRemoveOpts};
 pub enum VmImageCommand {
   /// Create a base VM image
   Create(VmImageCreateOpts),
+  /// Pull a base VM image from an url or an OCI registry
+  Pull(VmImagePullOpts),
   /// Clone a VM image
   Clone {
     /// Name of the VM image
@@ -20,6 +25,8 @@ pub enum VmImageCommand {
   },
   /// Resize a VM image
   Resize(VmImageResizeOpts),
+  /// Export a VM image to a local file
+  Export(VmImageExportOpts),
   /// List VM images
   #[clap(alias("ls"))]
   List(GenericListOpts),
@@ -37,6 +44,75 @@ pub struct VmImageCreateOpts {
   pub file_path: String,
 }
 
+/// `nanocl vm image pull` available options
+#[derive(Clone, Parser)]
+pub struct VmImagePullOpts {
+  /// Expected sha256 of the image as sha256:<hex>
+  #[clap(long)]
+  pub checksum: Option<String>,
+  /// Secret holding the credentials of the OCI registry
+  #[clap(long)]
+  pub image_pull_secret: Option<String>,
+  /// Name of the VM image
+  pub name: String,
+  /// Http(s) url of the image or reference of an OCI artifact
+  pub source: String,
+}
+
+/// Convert VmImagePullOpts to VmImagePullPayload
+impl From<VmImagePullOpts> for VmImagePullPayload {
+  fn from(opts: VmImagePullOpts) -> Self {
+    let is_url =
+      opts.source.starts_with("http://") || opts.source.starts_with("https://");
+    Self {
+      source: if is_url {
+        VmImageSource::Url(opts.source)
+      } else {
+        VmImageSource::Oci(opts.source)
+      },
+      checksum: opts.checksum,
+      image_pull_secret: opts.image_pull_secret,
+    }
+  }
+}
+
+/// Format of an exported VM image
+#[derive(Clone, Default, ValueEnum)]
+pub enum VmImageFormatArg {
+  #[default]
+  Qcow2,
+  Raw,
+}
+
+/// `nanocl vm image export` available options
+#[derive(Clone, Parser)]
+pub struct VmImageExportOpts {
+  /// Path of the file to write (default: name.format)
+  #[clap(short, long)]
+  pub output: Option<String>,
+  /// Format of the exported image
+  #[clap(long, value_enum, default_value_t)]
+  pub format: VmImageFormatArg,
+  /// Compress the exported image, only supported by qcow2
+  #[clap(long)]
+  pub compress: bool,
+  /// Name of the VM image
+  pub name: String,
+}
+
+/// Convert VmImageExportOpts to VmImageExportQuery
+impl From<VmImageExportOpts> for VmImageExportQuery {
+  fn from(opts: VmImageExportOpts) -> Self {
+    Self {
+      format: Some(match opts.format {
+        VmImageFormatArg::Qcow2 => VmImageExportFormat::Qcow2,
+        VmImageFormatArg::Raw => VmImageExportFormat::Raw,
+      }),
+      compress: Some(opts.compress),
+    }
+  }
+}
+
 /// `nanocl vm image resize` available options
 #[derive(Clone, Parser)]
 pub struct VmImageResizeOpts {
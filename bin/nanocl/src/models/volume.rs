@@ -0,0 +1,162 @@
+use chrono::TimeZone;
+use clap::{Parser, Subcommand};
+use tabled::Tabled;
+
+use nanocld_client::stubs::volume::{
+  Volume, VolumeBackup, VolumePartial, VolumeUpdate,
+};
+
+use super::{GenericInspectOpts, GenericListOpts, GenericRemoveOpts};
+
+/// `nanocl volume` available commands
+#[derive(Clone, Subcommand)]
+pub enum VolumeCommand {
+  /// Create a new volume on the current node
+  Create(VolumeCreateOpts),
+  /// List existing volumes
+  #[clap(alias("ls"))]
+  List(GenericListOpts),
+  /// Remove volumes that aren't mounted anymore
+  #[clap(alias("rm"))]
+  Remove(GenericRemoveOpts),
+  /// Inspect a volume
+  Inspect(GenericInspectOpts),
+  /// Update the backup policy of a volume
+  Patch(VolumePatchOpts),
+}
+
+/// `nanocl volume` available arguments
+#[derive(Clone, Parser)]
+pub struct VolumeArg {
+  /// namespace to target by default global is used
+  #[clap(long, short)]
+  pub namespace: Option<String>,
+  /// Volume command
+  #[clap(subcommand)]
+  pub command: VolumeCommand,
+}
+
+/// `nanocl volume create` available options
+#[derive(Clone, Parser)]
+pub struct VolumeCreateOpts {
+  /// Name of the volume
+  pub name: String,
+  /// Docker volume driver, default to local
+  #[clap(long)]
+  pub driver: Option<String>,
+  /// Expected size of the volume in MB
+  #[clap(long)]
+  pub size: Option<u64>,
+  /// Labels of the docker volume as key=value
+  #[clap(long = "label")]
+  pub labels: Option<Vec<String>>,
+  /// Schedule of the backups (cron)
+  #[clap(long)]
+  pub backup_schedule: Option<String>,
+  /// Number of backups to keep, default to 7
+  #[clap(long, requires = "backup_schedule")]
+  pub backup_retention: Option<usize>,
+}
+
+/// Convert VolumeCreateOpts to VolumePartial
+impl From<VolumeCreateOpts> for VolumePartial {
+  fn from(val: VolumeCreateOpts) -> Self {
+    let labels = val.labels.map(|labels| {
+      labels
+        .into_iter()
+        .map(|label| match label.split_once('=') {
+          Some((key, value)) => (key.to_owned(), value.to_owned()),
+          None => (label, String::new()),
+        })
+        .collect()
+    });
+    Self {
+      name: val.name,
+      driver: val.driver,
+      size: val.size,
+      labels,
+      backup: val.backup_schedule.map(|schedule| VolumeBackup {
+        schedule,
+        retention: val.backup_retention,
+      }),
+      metadata: None,
+    }
+  }
+}
+
+/// `nanocl volume patch` available options
+#[derive(Clone, Parser)]
+pub struct VolumePatchOpts {
+  /// Name of the volume
+  pub name: String,
+  /// Schedule of the backups (cron), the backups are disabled when not set
+  #[clap(long)]
+  pub backup_schedule: Option<String>,
+  /// Number of backups to keep, default to 7
+  #[clap(long, requires = "backup_schedule")]
+  pub backup_retention: Option<usize>,
+}
+
+/// Convert VolumePatchOpts to VolumeUpdate
+impl From<VolumePatchOpts> for VolumeUpdate {
+  fn from(val: VolumePatchOpts) -> Self {
+    Self {
+      backup: val.backup_schedule.map(|schedule| VolumeBackup {
+        schedule,
+        retention: val.backup_retention,
+      }),
+      metadata: None,
+    }
+  }
+}
+
+/// A row of the volume table
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+pub struct VolumeRow {
+  /// The name of the volume
+  pub name: String,
+  /// The namespace of the volume
+  pub namespace: String,
+  /// The node storing the volume
+  pub node: String,
+  /// The docker volume driver
+  pub driver: String,
+  /// The schedule of the backups
+  pub backup: String,
+  /// When the volume have been created
+  #[tabled(rename = "CREATED AT")]
+  pub created_at: String,
+  /// When the volume have been updated
+  #[tabled(rename = "UPDATED AT")]
+  pub updated_at: String,
+}
+
+impl From<Volume> for VolumeRow {
+  fn from(volume: Volume) -> Self {
+    // Get the current timezone
+    let binding = chrono::Local::now();
+    let tz = binding.offset();
+    // Convert the created_at and updated_at to the current timezone
+    let created_at = tz
+      .timestamp_opt(volume.created_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    let updated_at = tz
+      .timestamp_opt(volume.updated_at.and_utc().timestamp(), 0)
+      .unwrap()
+      .format("%Y-%m-%d %H:%M:%S");
+    Self {
+      name: volume.name,
+      namespace: volume.namespace_name,
+      node: volume.node_name,
+      driver: volume.driver,
+      backup: volume
+        .backup
+        .map(|backup| backup.schedule)
+        .unwrap_or_else(|| "-".to_owned()),
+      created_at: format!("{created_at}"),
+      updated_at: format!("{updated_at}"),
+    }
+  }
+}
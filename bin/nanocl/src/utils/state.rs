@@ -1,5 +1,4 @@
 use liquid::ObjectView;
-use regex::Regex;
 
 use crate::models::{DisplayFormat, StateRef, StateRoot};
 use nanocl_error::io::{FromIo, IoError, IoResult};
@@ -140,20 +139,9 @@ pub fn compile(
   obj: &dyn ObjectView,
   root: StateRoot,
 ) -> IoResult<String> {
-  // replace "${{ }}" with "{{ }}" syntax for liquid
-  let reg = Regex::new(r"\$\{\{(.+?)\}\}")
-    .map_err(|err| IoError::invalid_data("Regex", &format!("{err}")))?;
-  let template_file = reg.replace_all(raw, "{{ $1 }}");
-  let template = liquid::ParserBuilder::with_stdlib()
+  let parser = liquid::ParserBuilder::with_stdlib()
     .partials(liquid::partials::LazyCompiler::new(StateSource { root }))
     .build()
-    .unwrap()
-    .parse(&template_file)
-    .map_err(|err| {
-      IoError::invalid_data("Template parsing", &format!("{err}"))
-    })?;
-  let output = template.render(&obj).map_err(|err| {
-    IoError::invalid_data("Template rendering", &format!("{err}"))
-  })?;
-  Ok(output)
+    .unwrap();
+  nanocl_utils::liquid::compile_with(&parser, raw, obj)
 }
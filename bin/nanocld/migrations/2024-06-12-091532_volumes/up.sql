@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "volumes" (
+  "key" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "name" VARCHAR NOT NULL,
+  "namespace_name" VARCHAR NOT NULL REFERENCES namespaces("name"),
+  "node_name" VARCHAR NOT NULL REFERENCES nodes("name"),
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  "driver" VARCHAR NOT NULL,
+  "size" BIGINT,
+  "labels" JSONB,
+  "backup" JSONB,
+  "metadata" JSONB
+);
+
+CREATE INDEX "volumes_key_idx" ON "volumes" ("key");
+CREATE INDEX "volumes_name_idx" ON "volumes" ("name");
+CREATE INDEX "volumes_namespace_name_idx" ON "volumes" ("namespace_name");
+CREATE INDEX "volumes_node_name_idx" ON "volumes" ("node_name");
+CREATE INDEX "volumes_created_at_idx" ON "volumes" ("created_at");
+CREATE INDEX "volumes_updated_at_idx" ON "volumes" ("updated_at");
+CREATE INDEX "volumes_driver_idx" ON "volumes" ("driver");
+CREATE INDEX "volumes_labels_idx" ON "volumes" USING GIN ("labels");
+CREATE INDEX "volumes_metadata_idx" ON "volumes" USING GIN ("metadata");
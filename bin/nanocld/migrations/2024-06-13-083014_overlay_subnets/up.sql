@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "overlay_subnets" (
+  "subnet" VARCHAR NOT NULL UNIQUE PRIMARY KEY,
+  "node_name" VARCHAR NOT NULL UNIQUE REFERENCES nodes("name") ON DELETE CASCADE,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX "overlay_subnets_node_name_idx" ON "overlay_subnets" ("node_name");
+CREATE INDEX "overlay_subnets_created_at_idx" ON "overlay_subnets" ("created_at");
@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "vm_migrations" (
+  "vm_key" VARCHAR NOT NULL UNIQUE PRIMARY KEY REFERENCES vms("key") ON DELETE CASCADE,
+  "node_name" VARCHAR NOT NULL REFERENCES nodes("name") ON DELETE CASCADE,
+  "token" VARCHAR NOT NULL UNIQUE,
+  "psk" VARCHAR NOT NULL,
+  "created_at" TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX "vm_migrations_node_name_idx" ON "vm_migrations" ("node_name");
+CREATE INDEX "vm_migrations_created_at_idx" ON "vm_migrations" ("created_at");
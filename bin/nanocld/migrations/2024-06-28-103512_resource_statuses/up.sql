@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS "resource_statuses" (
+  "resource_key" VARCHAR NOT NULL UNIQUE PRIMARY KEY REFERENCES resources("key") ON DELETE CASCADE,
+  "status" VARCHAR NOT NULL,
+  "message" VARCHAR NOT NULL,
+  "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX "resource_statuses_status_idx" ON "resource_statuses" ("status");
@@ -42,6 +42,14 @@ pub struct Cli {
   /// Optional ssl options
   #[clap(flatten)]
   pub ssl: Option<SslConfig>,
+  /// Address pool of the overlay network between the nodes eg: 10.210.0.0/16
+  /// each node gets a /24 of it, the overlay is disabled if not set
+  #[clap(long)]
+  pub overlay_subnet: Option<String>,
+  /// Enforce the network policies on the host firewall, nanocld must share
+  /// the pid namespace of the host with the NET_ADMIN and SYS_ADMIN capabilities
+  #[clap(long)]
+  pub network_policies: bool,
 }
 
 impl Default for Cli {
@@ -58,6 +66,8 @@ impl Default for Cli {
       advertise_addr: None,
       gid: 0,
       ssl: None,
+      overlay_subnet: None,
+      network_policies: false,
     }
   }
 }
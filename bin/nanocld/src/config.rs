@@ -58,6 +58,11 @@ fn gen_daemon_conf(
   } else {
     config.store_addr.clone()
   };
+  let overlay_subnet = if let Some(ref overlay_subnet) = args.overlay_subnet {
+    Some(overlay_subnet.to_owned())
+  } else {
+    config.overlay_subnet.clone()
+  };
   Ok(DaemonConfig {
     hosts,
     gateway,
@@ -70,6 +75,9 @@ fn gen_daemon_conf(
     nodes: args.nodes.clone(),
     conf_dir: args.conf_dir.clone(),
     ssl: args.ssl.clone(),
+    overlay_subnet,
+    network_policies: args.network_policies
+      || config.network_policies.unwrap_or_default(),
   })
 }
 
@@ -124,11 +132,15 @@ mod tests {
       store_addr: None,
       gateway: None,
       hostname: None,
+      overlay_subnet: Some(String::from("10.210.0.0/16")),
+      network_policies: Some(true),
     };
     let merged = gen_daemon_conf(&args, &config).unwrap();
     assert_eq!(merged.hosts, args.hosts.unwrap());
     assert_eq!(merged.state_dir, args.state_dir.unwrap());
     assert_eq!(merged.docker_host, args.docker_host.unwrap());
+    assert_eq!(merged.overlay_subnet, config.overlay_subnet);
+    assert!(merged.network_policies);
   }
 
   /// Test read config file
@@ -8,6 +8,9 @@ pub use ws::*;
 mod node;
 pub use node::*;
 
+mod overlay_subnet;
+pub use overlay_subnet::*;
+
 mod system;
 pub use system::*;
 
@@ -26,18 +29,27 @@ pub use vm::*;
 pub mod vm_image;
 pub use vm_image::*;
 
+mod vm_migration;
+pub use vm_migration::*;
+
 mod resource;
 pub use resource::*;
 
 mod resource_kind;
 pub use resource_kind::*;
 
+mod resource_status;
+pub use resource_status::*;
+
 mod secret;
 pub use secret::*;
 
 mod job;
 pub use job::*;
 
+mod volume;
+pub use volume::*;
+
 mod spec;
 pub use spec::*;
 
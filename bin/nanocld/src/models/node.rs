@@ -26,3 +26,11 @@ pub struct NodeDb {
   #[serde(skip_serializing_if = "Option::is_none")]
   pub metadata: Option<serde_json::Value>,
 }
+
+/// This structure is used to update a node in the database.
+#[derive(Clone, Debug, Default, AsChangeset)]
+#[diesel(table_name = nodes)]
+pub struct NodeUpdateDb {
+  /// User defined metadata
+  pub metadata: Option<serde_json::Value>,
+}
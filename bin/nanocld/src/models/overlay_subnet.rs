@@ -0,0 +1,22 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::overlay_subnets;
+
+/// This structure represent the subnet of the overlay given to a node.
+/// The subnet and the node are both unique so concurrent allocations
+/// of the same subnet or for the same node are rejected by the database.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(subnet))]
+#[diesel(table_name = overlay_subnets)]
+#[serde(rename_all = "PascalCase")]
+pub struct OverlaySubnetDb {
+  /// The subnet in CIDR notation
+  pub subnet: String,
+  /// The node owning the subnet
+  pub node_name: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+}
@@ -0,0 +1,50 @@
+use diesel::prelude::*;
+
+use nanocl_stubs::resource::ResourceStatus;
+
+use crate::schema::resource_statuses;
+
+/// This structure represent the result of the last apply of a resource
+#[derive(Clone, Debug, Queryable, Identifiable, Insertable)]
+#[diesel(primary_key(resource_key))]
+#[diesel(table_name = resource_statuses)]
+pub struct ResourceStatusDb {
+  /// The key of the resource
+  pub resource_key: String,
+  /// The status of the last apply
+  pub status: String,
+  /// The note or error of the last apply
+  pub message: String,
+  /// The updated at date
+  pub updated_at: chrono::NaiveDateTime,
+}
+
+/// This structure represent the update of the status of a resource
+#[derive(Clone, Debug, Default, AsChangeset)]
+#[diesel(table_name = resource_statuses)]
+pub struct ResourceStatusUpdateDb {
+  pub status: Option<String>,
+  pub message: Option<String>,
+  pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<ResourceStatusDb> for ResourceStatusUpdateDb {
+  fn from(status: ResourceStatusDb) -> Self {
+    Self {
+      status: Some(status.status),
+      message: Some(status.message),
+      updated_at: Some(status.updated_at),
+    }
+  }
+}
+
+impl From<ResourceStatusDb> for ResourceStatus {
+  fn from(status: ResourceStatusDb) -> Self {
+    Self {
+      resource_key: status.resource_key,
+      status: status.status,
+      message: status.message,
+      updated_at: status.updated_at,
+    }
+  }
+}
@@ -23,3 +23,12 @@ pub struct SpecDb {
   /// Metadata (user defined) of the resource kind version
   pub metadata: Option<serde_json::Value>,
 }
+
+/// This structure is used to update the metadata of a spec in place
+/// eg: to save the status of a resource without creating a new version.
+#[derive(Clone, Debug, Default, AsChangeset)]
+#[diesel(table_name = specs)]
+pub struct SpecUpdateDb {
+  /// Metadata of the spec
+  pub metadata: Option<serde_json::Value>,
+}
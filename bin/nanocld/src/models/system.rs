@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use futures::channel::mpsc;
+use futures_util::lock::Mutex;
 use ntex::rt;
 
 use nanocl_stubs::{config::DaemonConfig, system::Event};
@@ -25,6 +26,10 @@ pub struct SystemStateInner {
   pub(crate) event_emitter_raw: RawEventEmitter,
   /// task event loop
   pub(crate) arbiter: rt::Arbiter,
+  /// Serialize the syncs of the network policies
+  pub(crate) network_policy_lock: Mutex<()>,
+  /// Names of the vm images being pulled, they share their partial download
+  pub(crate) vm_image_pulls: std::sync::Mutex<HashSet<String>>,
 }
 
 #[derive(Clone)]
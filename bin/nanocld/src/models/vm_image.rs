@@ -1,7 +1,7 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use nanocl_stubs::vm_image::VmImage;
+use nanocl_stubs::{vm::VmSnapshot, vm_image::VmImage};
 
 use crate::schema::vm_images;
 
@@ -41,13 +41,15 @@ pub struct VmImageDb {
 }
 
 /// This structure is used to update a virtual machine image in the database.
-#[derive(Clone, Debug, AsChangeset)]
+#[derive(Clone, Debug, Default, AsChangeset)]
 #[diesel(table_name = vm_images)]
 pub struct VmImageUpdateDb {
   /// The actual size of the virtual machine image
-  pub size_actual: i64,
+  pub size_actual: Option<i64>,
   /// The virtual size of the virtual machine image
-  pub size_virtual: i64,
+  pub size_virtual: Option<i64>,
+  /// The node where the image is stored
+  pub node_name: Option<String>,
 }
 
 /// This structure is used to parse the output of the qemu-img info command.
@@ -60,6 +62,36 @@ pub struct QemuImgInfo {
   pub virtual_size: i64,
   /// The actual size of the virtual machine image
   pub actual_size: i64,
+  /// The internal snapshots of the virtual machine image
+  pub snapshots: Option<Vec<QemuImgSnapshot>>,
+}
+
+/// An internal snapshot in the output of the qemu-img info command
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct QemuImgSnapshot {
+  /// The name of the snapshot
+  pub name: String,
+  /// The creation date of the snapshot as a unix timestamp
+  pub date_sec: i64,
+  /// The size of the saved memory state
+  pub vm_state_size: u64,
+  /// The time the virtual machine was running in seconds
+  pub vm_clock_sec: u64,
+}
+
+/// Helper to convert a `QemuImgSnapshot` to a `VmSnapshot`
+impl From<QemuImgSnapshot> for VmSnapshot {
+  fn from(snapshot: QemuImgSnapshot) -> Self {
+    Self {
+      name: snapshot.name,
+      created_at: chrono::DateTime::from_timestamp(snapshot.date_sec, 0)
+        .unwrap_or_default()
+        .naive_utc(),
+      vm_state_size: snapshot.vm_state_size,
+      vm_clock: snapshot.vm_clock_sec,
+    }
+  }
 }
 
 /// Helper to convert a `VmImageDb` to a `VmImage`
@@ -0,0 +1,25 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::vm_migrations;
+
+/// This structure represent a vm being migrated to another node.
+/// A vm has at most one migration, a concurrent one is rejected by the database.
+#[derive(
+  Debug, Clone, Queryable, Identifiable, Insertable, Serialize, Deserialize,
+)]
+#[diesel(primary_key(vm_key))]
+#[diesel(table_name = vm_migrations)]
+#[serde(rename_all = "PascalCase")]
+pub struct VmMigrationDb {
+  /// The key of the migrated vm
+  pub vm_key: String,
+  /// The node receiving the vm
+  pub node_name: String,
+  /// The token the target node expects with the images of the vm
+  pub token: String,
+  /// The hex pre shared key encrypting the memory and the disks of the vm
+  pub psk: String,
+  /// The created at date
+  pub created_at: chrono::NaiveDateTime,
+}
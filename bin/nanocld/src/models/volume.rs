@@ -0,0 +1,128 @@
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use nanocl_error::io::{IoError, IoResult};
+use nanocl_stubs::volume::{Volume, VolumePartial, VolumeUpdate};
+
+use crate::schema::volumes;
+
+/// This structure represent the volume in the database.
+/// A volume is a docker volume of a node tracked by nanocl
+/// to be mounted by the cargoes and the jobs.
+#[derive(
+  Clone, Debug, Serialize, Deserialize, Queryable, Identifiable, Insertable,
+)]
+#[serde(rename_all = "PascalCase")]
+#[diesel(primary_key(key))]
+#[diesel(table_name = volumes)]
+pub struct VolumeDb {
+  /// The key of the volume generated with `namespace_name` and `name`
+  pub key: String,
+  /// The name of the volume
+  pub name: String,
+  /// The namespace name
+  pub namespace_name: String,
+  /// The node where the volume is stored
+  pub node_name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// The docker volume driver
+  pub driver: String,
+  /// The expected size in MB
+  pub size: Option<i64>,
+  /// The labels of the docker volume
+  pub labels: Option<serde_json::Value>,
+  /// The backup policy
+  pub backup: Option<serde_json::Value>,
+  // The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl VolumeDb {
+  pub fn try_from_partial(
+    key: &str,
+    namespace: &str,
+    node: &str,
+    volume: &VolumePartial,
+  ) -> IoResult<Self> {
+    Ok(Self {
+      key: key.to_owned(),
+      name: volume.name.clone(),
+      namespace_name: namespace.to_owned(),
+      node_name: node.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      updated_at: chrono::Utc::now().naive_utc(),
+      driver: volume.driver.clone().unwrap_or("local".to_owned()),
+      size: volume.size.map(|size| size as i64),
+      labels: volume
+        .labels
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()?,
+      backup: volume
+        .backup
+        .as_ref()
+        .map(serde_json::to_value)
+        .transpose()?,
+      metadata: volume.metadata.clone(),
+    })
+  }
+}
+
+impl TryFrom<VolumeDb> for Volume {
+  type Error = IoError;
+
+  fn try_from(db: VolumeDb) -> Result<Self, Self::Error> {
+    Ok(Volume {
+      key: db.key,
+      name: db.name,
+      namespace_name: db.namespace_name,
+      node_name: db.node_name,
+      created_at: db.created_at,
+      updated_at: db.updated_at,
+      driver: db.driver,
+      size: db.size.map(|size| size as u64),
+      labels: db.labels.map(serde_json::from_value).transpose()?,
+      backup: db.backup.map(serde_json::from_value).transpose()?,
+      metadata: db.metadata,
+    })
+  }
+}
+
+/// Arguments to create a new volume obj
+pub struct VolumeObjCreateIn {
+  pub namespace: String,
+  pub spec: VolumePartial,
+}
+
+/// This structure is used to update a volume in the database.
+#[derive(Debug, Default, AsChangeset)]
+#[diesel(table_name = volumes)]
+pub struct VolumeUpdateDb {
+  /// The last update date
+  pub updated_at: Option<chrono::NaiveDateTime>,
+  /// The backup policy, removed when set to `None`
+  pub backup: Option<Option<serde_json::Value>>,
+  // The metadata (user defined)
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl TryFrom<&VolumeUpdate> for VolumeUpdateDb {
+  type Error = IoError;
+
+  fn try_from(update: &VolumeUpdate) -> Result<Self, Self::Error> {
+    Ok(Self {
+      updated_at: Some(chrono::Utc::now().naive_utc()),
+      backup: Some(
+        update
+          .backup
+          .as_ref()
+          .map(serde_json::to_value)
+          .transpose()?,
+      ),
+      metadata: update.metadata.clone(),
+    })
+  }
+}
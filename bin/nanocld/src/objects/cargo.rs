@@ -41,6 +41,16 @@ impl ObjCreate for CargoDb {
         "Cargo name can only contain a-z, A-Z, 0-9, and -_",
       ));
     }
+    obj
+      .spec
+      .validate_ports()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    obj
+      .spec
+      .validate_configs()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    utils::volume::validate_mounts(&obj.spec.volumes, &obj.namespace, state)
+      .await?;
     let key = utils::key::gen_key(&obj.namespace, &obj.spec.name);
     let new_spec =
       SpecDb::try_from_cargo_partial(&key, &obj.version, &obj.spec)?;
@@ -71,6 +81,17 @@ impl ObjCreate for CargoDb {
           .try_into()
           .map_err(HttpError::internal_server_error)?,
       ));
+    // A port rejected by ncproxy fails the creation
+    if let Err(err) = utils::container::cargo::ensure_ports(
+      &cargo.spec.cargo_key,
+      &cargo.spec.ports,
+      state,
+    )
+    .await
+    {
+      CargoDb::clear_by_pk(&cargo.spec.cargo_key, &state.inner.pool).await?;
+      return Err(err);
+    }
     Ok(cargo)
   }
 }
@@ -123,6 +144,23 @@ impl ObjPutByPk for CargoDb {
     obj: &Self::ObjPutIn,
     state: &SystemState,
   ) -> HttpResult<Self::ObjPutOut> {
+    obj
+      .spec
+      .validate_ports()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    obj
+      .spec
+      .validate_configs()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    let (cargo, _, _) = CargoDb::read_by_pk(pk, &state.inner.pool).await?;
+    utils::volume::validate_mounts(
+      &obj.spec.volumes,
+      &cargo.namespace_name,
+      state,
+    )
+    .await?;
+    // A port rejected by ncproxy fails the update before it's saved
+    utils::container::cargo::ensure_ports(pk, &obj.spec.ports, state).await?;
     let status = ObjPsStatusDb::read_by_pk(pk, &state.inner.pool).await?;
     let new_status = ObjPsStatusUpdate {
       wanted: Some(ObjPsStatusKind::Start.to_string()),
@@ -258,6 +296,21 @@ impl ObjPatchByPk for CargoDb {
       } else {
         cargo.spec.image_pull_policy
       },
+      ports: if obj.spec.ports.is_some() {
+        obj.spec.ports.clone()
+      } else {
+        cargo.spec.ports
+      },
+      configs: if obj.spec.configs.is_some() {
+        obj.spec.configs.clone()
+      } else {
+        cargo.spec.configs
+      },
+      volumes: if obj.spec.volumes.is_some() {
+        obj.spec.volumes.clone()
+      } else {
+        cargo.spec.volumes
+      },
     };
     let obj = &CargoObjPutIn {
       spec,
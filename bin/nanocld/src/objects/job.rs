@@ -20,6 +20,7 @@ impl ObjCreate for JobDb {
     obj: &Self::ObjCreateIn,
     state: &crate::models::SystemState,
   ) -> HttpResult<Self::ObjCreateOut> {
+    utils::volume::validate_mounts(&obj.volumes, "global", state).await?;
     let db_model = JobDb::try_from_partial(obj)?;
     let status = ObjPsStatusPartial {
       key: obj.name.clone(),
@@ -1,8 +1,16 @@
+use std::collections::HashMap;
+
+use bollard_next::{
+  network::CreateNetworkOptions,
+  service::{Ipam, IpamConfig},
+};
 use nanocl_error::http::{HttpError, HttpResult};
-use nanocl_stubs::namespace::{Namespace, NamespaceInspect, NamespacePartial};
+use nanocl_stubs::namespace::{
+  Namespace, NamespaceInspect, NamespaceNetwork, NamespacePartial,
+};
 
 use crate::{
-  models::{CargoDb, NamespaceDb, SystemState},
+  models::{CargoDb, NamespaceDb, SystemState, VolumeDb},
   repositories::generic::*,
 };
 
@@ -25,13 +33,64 @@ impl ObjCreate for NamespaceDb {
         &obj.name
       )));
     }
-    let item = NamespaceDb::create_from(obj, &state.inner.pool)
-      .await?
-      .into();
-    Ok(item)
+    let network = NamespaceNetwork::from_metadata(obj.metadata.as_ref())
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    if let Some(network) = &network {
+      create_network(&obj.name, network, state).await?;
+    }
+    let item = match NamespaceDb::create_from(obj, &state.inner.pool).await {
+      Ok(item) => item,
+      Err(err) => {
+        if network.is_some() {
+          let _ = state.inner.docker_api.remove_network(&obj.name).await;
+        }
+        return Err(err.into());
+      }
+    };
+    Ok(item.into())
   }
 }
 
+/// Create the dedicated docker network of a namespace
+async fn create_network(
+  name: &str,
+  network: &NamespaceNetwork,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let labels = HashMap::from([("io.nanocl", "enabled"), ("io.nanocl.n", name)]);
+  let config = match (&network.subnet, &network.gateway) {
+    (None, None) => None,
+    (subnet, gateway) => Some(vec![IpamConfig {
+      subnet: subnet.clone(),
+      gateway: gateway.clone(),
+      ..Default::default()
+    }]),
+  };
+  let ipam = Ipam {
+    config,
+    ..Default::default()
+  };
+  state
+    .inner
+    .docker_api
+    .create_network(CreateNetworkOptions {
+      name,
+      check_duplicate: true,
+      driver: "bridge",
+      attachable: true,
+      ipam,
+      labels,
+      ..Default::default()
+    })
+    .await
+    .map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to create network {name} got error: {err}"
+      ))
+    })?;
+  Ok(())
+}
+
 impl ObjInspectByPk for NamespaceDb {
   type ObjInspectOut = NamespaceInspect;
 
@@ -66,10 +125,20 @@ impl ObjDelByPk for NamespaceDb {
     state: &SystemState,
   ) -> HttpResult<Self::ObjDelOut> {
     let item = NamespaceDb::read_by_pk(pk, &state.inner.pool).await?;
+    let volumes = VolumeDb::read_by_namespace(pk, &state.inner.pool).await?;
+    if !volumes.is_empty() {
+      return Err(HttpError::conflict(format!(
+        "Namespace {pk}: still has {} volume(s)",
+        volumes.len()
+      )));
+    }
     CargoDb::delete_by_namespace(pk, state).await?;
     NamespaceDb::del_by_pk(pk, &state.inner.pool).await?;
-    if let Err(err) = state.inner.docker_api.remove_network(pk).await {
-      log::error!("Unable to remove network {} got error: {}", pk, err);
+    let network = NamespaceNetwork::from_metadata(item.metadata.as_ref());
+    if let Ok(Some(_)) = network {
+      if let Err(err) = state.inner.docker_api.remove_network(pk).await {
+        log::error!("Unable to remove network {} got error: {}", pk, err);
+      }
     }
     Ok(item.into())
   }
@@ -11,7 +11,7 @@ use nanocl_stubs::{
 use crate::{
   models::{
     ObjPsStatusDb, ObjPsStatusUpdate, ProcessDb, SpecDb, SystemState, VmDb,
-    VmImageDb, VmObjCreateIn, VmObjPatchIn, VmObjPutIn,
+    VmObjCreateIn, VmObjPatchIn, VmObjPutIn,
   },
   repositories::generic::*,
   utils,
@@ -43,11 +43,10 @@ impl ObjCreate for VmDb {
     if name.contains('.') {
       return Err(HttpError::bad_request("VM name cannot contain '.'"));
     }
+    vm.validate_disks()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
     let image =
-      VmImageDb::read_by_pk(&vm.disk.image, &state.inner.pool).await?;
-    if image.kind.as_str() != "Base" {
-      return Err(HttpError::bad_request(format!("Image {} is not a base image please convert the snapshot into a base image first", &vm.disk.image)));
-    }
+      utils::vm_image::read_base(&vm.disk.image, &state.inner.pool).await?;
     let snap_name = format!("{}.{vm_key}", &image.name);
     let size = vm.disk.size.unwrap_or(20);
     log::debug!("Creating snapshot {snap_name} with size {size}");
@@ -57,6 +56,13 @@ impl ObjCreate for VmDb {
     // Use the snapshot image
     vm.disk.image.clone_from(&image.name);
     vm.disk.size = Some(size);
+    utils::vm_image::sync_disks(
+      &vm_key,
+      vm.disks.as_deref().unwrap_or_default(),
+      None,
+      state,
+    )
+    .await?;
     let status = ObjPsStatusPartial {
       key: vm_key.clone(),
       wanted: ObjPsStatusKind::Create,
@@ -122,6 +128,15 @@ impl ObjPutByPk for VmDb {
     state: &SystemState,
   ) -> HttpResult<Self::ObjPutOut> {
     let vm = VmDb::transform_read_by_pk(pk, &state.inner.pool).await?;
+    obj
+      .spec
+      .validate_disks()
+      .map_err(|err| HttpError::bad_request(err.to_string()))?;
+    for disk in obj.spec.disks.as_deref().unwrap_or_default() {
+      if let Some(image) = &disk.image {
+        utils::vm_image::read_base(image, &state.inner.pool).await?;
+      }
+    }
     let status = ObjPsStatusDb::read_by_pk(pk, &state.inner.pool).await?;
     let new_status = ObjPsStatusUpdate {
       wanted: Some(ObjPsStatusKind::Start.to_string()),
@@ -182,6 +197,11 @@ impl ObjPatchByPk for VmDb {
       } else {
         old_spec.ssh_key
       },
+      disks: if spec.disks.is_some() {
+        spec.disks.clone()
+      } else {
+        old_spec.disks
+      },
       mac_address: old_spec.mac_address,
       labels: if spec.labels.is_some() {
         spec.labels.clone()
@@ -193,6 +213,11 @@ impl ObjPatchByPk for VmDb {
       } else {
         old_spec.metadata
       },
+      cloud_init: if spec.cloud_init.is_some() {
+        spec.cloud_init.clone()
+      } else {
+        old_spec.cloud_init
+      },
     };
     let obj = &VmObjPutIn {
       spec: vm_partial,
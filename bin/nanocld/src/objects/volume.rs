@@ -0,0 +1,128 @@
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  system::NativeEventAction,
+  volume::{Volume, VolumeInspect, VolumeUpdate},
+};
+
+use crate::{
+  models::{
+    NamespaceDb, SystemState, VolumeDb, VolumeObjCreateIn, VolumeUpdateDb,
+  },
+  repositories::generic::*,
+  utils,
+};
+
+use super::generic::*;
+
+impl ObjCreate for VolumeDb {
+  type ObjCreateIn = VolumeObjCreateIn;
+  type ObjCreateOut = Volume;
+
+  async fn fn_create_obj(
+    obj: &Self::ObjCreateIn,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjCreateOut> {
+    // validate the name of a volume to include on a-z, A-Z, 0-9, and -_
+    if obj.spec.name.is_empty()
+      || !obj
+        .spec
+        .name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+      return Err(HttpError::bad_request(
+        "Volume name can only contain a-z, A-Z, 0-9, and -_",
+      ));
+    }
+    NamespaceDb::read_by_pk(&obj.namespace, &state.inner.pool).await?;
+    let key = utils::key::gen_key(&obj.namespace, &obj.spec.name);
+    if VolumeDb::read_by_pk(&key, &state.inner.pool).await.is_ok() {
+      return Err(HttpError::conflict(format!("Volume {key}: already exist")));
+    }
+    let item = VolumeDb::try_from_partial(
+      &key,
+      &obj.namespace,
+      &state.inner.config.hostname,
+      &obj.spec,
+    )?;
+    let volume: Volume = item.clone().try_into()?;
+    utils::volume::create(&volume, state).await?;
+    if let Err(err) = VolumeDb::create_from(item, &state.inner.pool).await {
+      let _ = utils::volume::remove(&key, state).await;
+      return Err(err.into());
+    }
+    utils::volume::sync_backup(&volume, state).await?;
+    Ok(volume)
+  }
+}
+
+impl ObjDelByPk for VolumeDb {
+  type ObjDelOut = Volume;
+  type ObjDelOpts = ();
+
+  async fn fn_del_obj_by_pk(
+    pk: &str,
+    _opts: &Self::ObjDelOpts,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjDelOut> {
+    let volume = VolumeDb::transform_read_by_pk(pk, &state.inner.pool).await?;
+    utils::volume::ensure_local(&volume, state)?;
+    let used_by = utils::volume::used_by(&volume, state).await?;
+    if !used_by.is_empty() {
+      return Err(HttpError::conflict(format!(
+        "Volume {pk}: still mounted by {}",
+        used_by.join(", ")
+      )));
+    }
+    let no_backup = Volume {
+      backup: None,
+      ..volume.clone()
+    };
+    utils::volume::sync_backup(&no_backup, state).await?;
+    utils::volume::remove(pk, state).await?;
+    VolumeDb::del_by_pk(pk, &state.inner.pool).await?;
+    Ok(volume)
+  }
+}
+
+impl ObjPatchByPk for VolumeDb {
+  type ObjPatchIn = VolumeUpdate;
+  type ObjPatchOut = Volume;
+
+  fn get_patch_event() -> NativeEventAction {
+    NativeEventAction::Update
+  }
+
+  async fn fn_patch_obj_by_pk(
+    pk: &str,
+    obj: &Self::ObjPatchIn,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjPatchOut> {
+    let current = VolumeDb::transform_read_by_pk(pk, &state.inner.pool).await?;
+    utils::volume::ensure_local(&current, state)?;
+    let update = VolumeUpdateDb::try_from(obj)?;
+    let volume: Volume = VolumeDb::update_pk(pk, update, &state.inner.pool)
+      .await?
+      .try_into()?;
+    utils::volume::sync_backup(&volume, state).await?;
+    Ok(volume)
+  }
+}
+
+impl ObjInspectByPk for VolumeDb {
+  type ObjInspectOut = VolumeInspect;
+
+  async fn inspect_obj_by_pk(
+    pk: &str,
+    state: &SystemState,
+  ) -> HttpResult<Self::ObjInspectOut> {
+    let volume = VolumeDb::transform_read_by_pk(pk, &state.inner.pool).await?;
+    let mountpoint = utils::volume::mountpoint(pk, state).await;
+    let used_by = utils::volume::used_by(&volume, state).await?;
+    Ok(VolumeInspect {
+      spec: volume,
+      mountpoint,
+      used_by,
+    })
+  }
+}
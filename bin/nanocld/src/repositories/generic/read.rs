@@ -8,6 +8,9 @@ use crate::{models::Pool, utils};
 
 use super::RepositoryBase;
 
+/// Number of items read at once when reading every item of a filter
+const PAGE_SIZE: usize = 100;
+
 pub trait RepositoryReadBy: super::RepositoryBase {
   type Output;
 
@@ -116,6 +119,28 @@ pub trait RepositoryReadByTransform: RepositoryReadBy {
       .map(Self::transform)
       .collect()
   }
+
+  /// Read every item matching the filter page by page,
+  /// the limit and the offset of the filter are ignored
+  async fn transform_read_all(
+    filter: &GenericFilter,
+    pool: &Pool,
+  ) -> IoResult<Vec<Self::NewOutput>>
+  where
+    Self::Output: Sized + Send + 'static,
+  {
+    let mut items = Vec::new();
+    loop {
+      let filter = filter.clone().limit(PAGE_SIZE).offset(items.len());
+      let page = Self::transform_read_by(&filter, pool).await?;
+      let len = page.len();
+      items.extend(page);
+      if len < PAGE_SIZE {
+        break;
+      }
+    }
+    Ok(items)
+  }
 }
 
 // pub trait RepositoryCountBy
@@ -5,12 +5,16 @@ mod metric;
 mod namespace;
 mod node;
 mod object_process_status;
+mod overlay_subnet;
 mod process;
 mod resource;
 mod resource_kind;
+mod resource_status;
 mod secret;
 mod spec;
 mod vm;
 mod vm_image;
+mod vm_migration;
+mod volume;
 
 pub mod generic;
@@ -2,12 +2,15 @@ use std::collections::HashMap;
 
 use diesel::prelude::*;
 
-use nanocl_error::http::HttpResult;
-use nanocl_stubs::{generic::GenericFilter, namespace::NamespaceSummary};
+use nanocl_error::{http::HttpResult, io::IoResult};
+use nanocl_stubs::{
+  generic::GenericFilter,
+  namespace::{NamespaceNetwork, NamespaceSummary},
+};
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{CargoDb, ColumnType, NamespaceDb, ProcessDb, SystemState},
+  models::{CargoDb, ColumnType, NamespaceDb, Pool, ProcessDb, SystemState},
   schema::namespaces,
 };
 
@@ -91,4 +94,14 @@ impl NamespaceDb {
     }
     Ok(new_items)
   }
+
+  /// Name of the docker network the processes of a namespace are created on,
+  /// the namespace itself when it has a dedicated network or `nanoclbr0`
+  pub async fn get_network(name: &str, pool: &Pool) -> IoResult<String> {
+    let namespace = NamespaceDb::read_by_pk(name, pool).await?;
+    match NamespaceNetwork::from_metadata(namespace.metadata.as_ref())? {
+      Some(_) => Ok(namespace.name),
+      None => Ok("nanoclbr0".to_owned()),
+    }
+  }
 }
@@ -8,7 +8,7 @@ use nanocl_stubs::generic::GenericFilter;
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{ColumnType, NodeDb, Pool, SystemState},
+  models::{ColumnType, NodeDb, NodeUpdateDb, Pool, SystemState},
   schema::nodes,
   vars,
 };
@@ -29,6 +29,10 @@ impl RepositoryCreate for NodeDb {}
 
 impl RepositoryDelByPk for NodeDb {}
 
+impl RepositoryUpdate for NodeDb {
+  type UpdateItem = NodeUpdateDb;
+}
+
 impl RepositoryReadBy for NodeDb {
   type Output = NodeDb;
 
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_stubs::generic::GenericFilter;
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, OverlaySubnetDb},
+  schema::overlay_subnets,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for OverlaySubnetDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("subnet", (ColumnType::Text, "overlay_subnets.subnet")),
+      ("node_name", (ColumnType::Text, "overlay_subnets.node_name")),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "overlay_subnets.created_at"),
+      ),
+    ])
+  }
+}
+
+impl RepositoryCreate for OverlaySubnetDb {}
+
+impl RepositoryReadBy for OverlaySubnetDb {
+  type Output = OverlaySubnetDb;
+
+  fn get_pk() -> &'static str {
+    "subnet"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = overlay_subnets::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(overlay_subnets::created_at.asc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
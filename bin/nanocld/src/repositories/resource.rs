@@ -188,8 +188,10 @@ impl ResourceDb {
     Ok(item)
   }
 
-  /// This hook is called when a resource is created.
+  /// This hook is called when a resource is created or updated.
   /// It call a custom controller at a specific url or just validate a schema.
+  /// The controller is asked to validate the resource before applying it,
+  /// so an invalid resource is rejected without altering the current one.
   /// If the resource is a Kind Kind, it will create a resource Kind with an associated version.
   /// To call a custom controller, the resource Kind must have a Url field in his config.
   /// Unless it must have a Schema field in his config that is a Validator to validate the resource.
@@ -214,8 +216,12 @@ impl ResourceDb {
         HttpError::bad_request(format!("Invalid schema {err}"))
       })?;
     }
+    utils::resource::validate_data(&kind.name, &resource.data)?;
     if let Some(url) = &kind.data.url {
       let ctrl_client = utils::ctrl_client::CtrlClient::new(&kind.name, url);
+      ctrl_client
+        .validate_rule(&version, &resource.name, &resource.data)
+        .await?;
       let config = ctrl_client
         .apply_rule(&version, &resource.name, &resource.data)
         .await?;
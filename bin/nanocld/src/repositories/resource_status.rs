@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_error::io::IoResult;
+use nanocl_stubs::generic::GenericFilter;
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, Pool, ResourceStatusDb, ResourceStatusUpdateDb},
+  schema::resource_statuses,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for ResourceStatusDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      (
+        "resource_key",
+        (ColumnType::Text, "resource_statuses.resource_key"),
+      ),
+      ("status", (ColumnType::Text, "resource_statuses.status")),
+      (
+        "updated_at",
+        (ColumnType::Timestamptz, "resource_statuses.updated_at"),
+      ),
+    ])
+  }
+}
+
+impl RepositoryCreate for ResourceStatusDb {}
+
+impl RepositoryUpdate for ResourceStatusDb {
+  type UpdateItem = ResourceStatusUpdateDb;
+}
+
+impl RepositoryReadBy for ResourceStatusDb {
+  type Output = ResourceStatusDb;
+
+  fn get_pk() -> &'static str {
+    "resource_key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = resource_statuses::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(resource_statuses::updated_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
+
+impl ResourceStatusDb {
+  /// Save the status of a resource, replacing the previous one
+  pub async fn save(status: ResourceStatusDb, pool: &Pool) -> IoResult<Self> {
+    if ResourceStatusDb::read_by_pk(&status.resource_key, pool)
+      .await
+      .is_err()
+    {
+      return ResourceStatusDb::create_from(status, pool).await;
+    }
+    let key = status.resource_key.clone();
+    ResourceStatusDb::update_pk(&key, status, pool).await
+  }
+}
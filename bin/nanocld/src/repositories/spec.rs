@@ -12,7 +12,7 @@ use nanocl_stubs::{
 
 use crate::{
   gen_sql_multiple, gen_sql_order_by, gen_sql_query,
-  models::{ColumnType, Pool, SpecDb},
+  models::{ColumnType, Pool, SpecDb, SpecUpdateDb},
   schema::specs,
 };
 
@@ -34,6 +34,10 @@ impl RepositoryBase for SpecDb {
 
 impl RepositoryCreate for SpecDb {}
 
+impl RepositoryUpdate for SpecDb {
+  type UpdateItem = SpecUpdateDb;
+}
+
 impl RepositoryDelBy for SpecDb {
   fn gen_del_query(
     filter: &GenericFilter,
@@ -155,6 +159,9 @@ impl SpecDb {
       replication: p.replication,
       image_pull_secret: p.image_pull_secret,
       image_pull_policy: p.image_pull_policy,
+      ports: p.ports,
+      configs: p.configs,
+      volumes: p.volumes,
     };
     Ok(spec)
   }
@@ -171,11 +178,13 @@ impl SpecDb {
       hostname: p.hostname,
       password: p.password,
       disk: p.disk,
+      disks: p.disks,
       host_config: p.host_config.unwrap_or_default(),
       ssh_key: p.ssh_key,
       user: p.user,
       mac_address: p.mac_address,
       labels: p.labels,
+      cloud_init: p.cloud_init,
     };
     Ok(spec)
   }
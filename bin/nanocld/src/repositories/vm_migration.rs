@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use nanocl_stubs::generic::GenericFilter;
+
+use crate::{
+  gen_sql_multiple, gen_sql_order_by, gen_sql_query,
+  models::{ColumnType, VmMigrationDb},
+  schema::vm_migrations,
+};
+
+use super::generic::*;
+
+impl RepositoryBase for VmMigrationDb {
+  fn get_columns<'a>() -> HashMap<&'a str, (ColumnType, &'a str)> {
+    HashMap::from([
+      ("vm_key", (ColumnType::Text, "vm_migrations.vm_key")),
+      ("node_name", (ColumnType::Text, "vm_migrations.node_name")),
+      ("token", (ColumnType::Text, "vm_migrations.token")),
+      (
+        "created_at",
+        (ColumnType::Timestamptz, "vm_migrations.created_at"),
+      ),
+    ])
+  }
+}
+
+impl RepositoryCreate for VmMigrationDb {}
+
+impl RepositoryDelByPk for VmMigrationDb {}
+
+impl RepositoryReadBy for VmMigrationDb {
+  type Output = VmMigrationDb;
+
+  fn get_pk() -> &'static str {
+    "vm_key"
+  }
+
+  fn gen_read_query(
+    filter: &GenericFilter,
+    is_multiple: bool,
+  ) -> impl diesel::query_dsl::methods::LoadQuery<
+    'static,
+    diesel::pg::PgConnection,
+    Self::Output,
+  > {
+    let mut query = vm_migrations::table.into_boxed();
+    let columns = Self::get_columns();
+    query = gen_sql_query!(query, filter, columns);
+    if let Some(orders) = &filter.order_by {
+      query = gen_sql_order_by!(query, orders, columns);
+    } else {
+      query = query.order(vm_migrations::created_at.desc());
+    }
+    if is_multiple {
+      gen_sql_multiple!(query, filter);
+    }
+    query
+  }
+}
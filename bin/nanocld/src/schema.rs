@@ -96,6 +96,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    overlay_subnets (subnet) {
+        subnet -> Varchar,
+        node_name -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     processes (key) {
         key -> Varchar,
@@ -117,6 +125,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    resource_statuses (resource_key) {
+        resource_key -> Varchar,
+        status -> Varchar,
+        message -> Varchar,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     resources (key) {
         key -> Varchar,
@@ -164,6 +181,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    vm_migrations (vm_key) {
+        vm_key -> Varchar,
+        node_name -> Varchar,
+        token -> Varchar,
+        psk -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     vms (key) {
         key -> Varchar,
@@ -175,19 +202,41 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    volumes (key) {
+        key -> Varchar,
+        name -> Varchar,
+        namespace_name -> Varchar,
+        node_name -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+        driver -> Varchar,
+        size -> Nullable<Int8>,
+        labels -> Nullable<Jsonb>,
+        backup -> Nullable<Jsonb>,
+        metadata -> Nullable<Jsonb>,
+    }
+}
+
 diesel::joinable!(cargoes -> namespaces (namespace_name));
 diesel::joinable!(cargoes -> object_process_statuses (status_key));
 diesel::joinable!(cargoes -> specs (spec_key));
 diesel::joinable!(jobs -> object_process_statuses (status_key));
 diesel::joinable!(node_group_links -> node_groups (node_group_name));
 diesel::joinable!(node_group_links -> nodes (node_name));
+diesel::joinable!(overlay_subnets -> nodes (node_name));
 diesel::joinable!(processes -> nodes (node_name));
 diesel::joinable!(resource_kinds -> specs (spec_key));
+diesel::joinable!(resource_statuses -> resources (resource_key));
 diesel::joinable!(resources -> specs (spec_key));
 diesel::joinable!(vm_images -> nodes (node_name));
+diesel::joinable!(vm_migrations -> nodes (node_name));
+diesel::joinable!(vm_migrations -> vms (vm_key));
 diesel::joinable!(vms -> namespaces (namespace_name));
 diesel::joinable!(vms -> object_process_statuses (status_key));
 diesel::joinable!(vms -> specs (spec_key));
+diesel::joinable!(volumes -> namespaces (namespace_name));
+diesel::joinable!(volumes -> nodes (node_name));
 
 diesel::allow_tables_to_appear_in_same_query!(
   cargoes,
@@ -199,11 +248,15 @@ diesel::allow_tables_to_appear_in_same_query!(
   node_groups,
   nodes,
   object_process_statuses,
+  overlay_subnets,
   processes,
   resource_kinds,
+  resource_statuses,
   resources,
   secrets,
   specs,
   vm_images,
+  vm_migrations,
   vms,
+  volumes,
 );
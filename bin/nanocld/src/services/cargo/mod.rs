@@ -41,7 +41,7 @@ mod tests {
     cargo::{
       Cargo, CargoDeleteQuery, CargoInspect, CargoKillOptions, CargoSummary,
     },
-    cargo_spec::{CargoSpec, CargoSpecPartial},
+    cargo_spec::{CargoPort, CargoSpec, CargoSpecPartial},
     proxy::ProxySslConfig,
     secret::SecretPartial,
     system::{EventActorKind, EventCondition, EventKind, NativeEventAction},
@@ -398,4 +398,64 @@ mod tests {
     ntex::time::sleep(std::time::Duration::from_secs(1)).await;
     system.state.wait_event_loop().await;
   }
+
+  /// A cargo publishing a port already published by another one isn't created
+  #[ntex::test]
+  async fn port_in_use() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let new_cargo = |name: &str| CargoSpecPartial {
+      name: name.to_owned(),
+      container: bollard_next::container::Config {
+        image: Some("ghcr.io/next-hat/nanocl-get-started:latest".to_owned()),
+        ..Default::default()
+      },
+      ports: Some(vec![CargoPort {
+        container_port: 9000,
+        host_port: Some(18090),
+        protocol: None,
+        network: None,
+      }]),
+      ..Default::default()
+    };
+    let res = client
+      .send_post(ENDPOINT, Some(new_cargo("test-cargo-port")), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create cargo");
+    let res = client
+      .send_post(
+        ENDPOINT,
+        Some(new_cargo("test-cargo-port-used")),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CONFLICT,
+      "create cargo with a used port"
+    );
+    let res = client
+      .send_get(
+        &format!("{ENDPOINT}/test-cargo-port-used/inspect"),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::NOT_FOUND,
+      "inspect rejected cargo"
+    );
+    let res = client
+      .send_delete(
+        &format!("{ENDPOINT}/test-cargo-port"),
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete cargo");
+    ntex::time::sleep(std::time::Duration::from_secs(1)).await;
+    system.state.wait_event_loop().await;
+  }
 }
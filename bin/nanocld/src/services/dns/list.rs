@@ -0,0 +1,34 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::dns::{DnsRecordQuery, DnsServedRecord};
+
+use crate::{models::SystemState, utils, vars};
+
+/// List the records answered by ncdns to the clients of a network
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Dns",
+  path = "/dns/records",
+  params(
+    ("network" = Option<String>, Query, description = "Network of the clients default to Internal"),
+    ("namespace" = Option<String>, Query, description = "Namespace of the clients"),
+  ),
+  responses(
+    (status = 200, description = "The served records", body = [nanocl_stubs::dns::DnsServedRecord]),
+  ),
+))]
+#[web::get("/dns/records")]
+pub async fn list_dns_record(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<DnsRecordQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let records: Vec<DnsServedRecord> = utils::resource::get_from_controller(
+    vars::DNS_RULE_KIND,
+    "/records",
+    &qs.into_inner(),
+    &state,
+  )
+  .await?;
+  Ok(web::HttpResponse::Ok().json(&records))
+}
@@ -0,0 +1,9 @@
+pub use ntex::web;
+
+pub mod list;
+
+pub use list::*;
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_dns_record);
+}
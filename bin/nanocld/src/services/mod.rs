@@ -6,6 +6,7 @@ use nanocl_error::http::{HttpError, HttpResult};
 pub mod openapi;
 
 mod cargo;
+mod dns;
 mod event;
 mod exec;
 mod job;
@@ -19,6 +20,7 @@ mod secret;
 mod system;
 mod vm;
 mod vm_image;
+mod volume;
 
 pub async fn unhandled() -> HttpResult<web::HttpResponse> {
   Err(HttpError::not_found("Route or method unhandled"))
@@ -56,12 +58,14 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
       .configure(system::ntex_config)
       .configure(resource::ntex_config)
       .configure(cargo::ntex_config)
+      .configure(dns::ntex_config)
       .configure(vm_image::ntex_config)
       .configure(vm::ntex_config)
       .configure(metric::ntex_config)
       .configure(secret::ntex_config)
       .configure(process::ntex_config)
       .configure(job::ntex_config)
+      .configure(volume::ntex_config)
       .configure(event::ntex_config)
       .configure(resource_kind::ntex_config),
   );
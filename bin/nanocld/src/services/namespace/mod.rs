@@ -22,6 +22,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
 
 #[cfg(test)]
 mod test_namespace {
+  use bollard_next::network::InspectNetworkOptions;
   use serde_json::json;
 
   use nanocl_stubs::namespace::{Namespace, NamespacePartial};
@@ -83,6 +84,56 @@ mod test_namespace {
     assert!(res.status().is_success(), "Expect success on delete");
   }
 
+  #[ntex::test]
+  async fn network() {
+    const NAME: &str = "controller-network";
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let docker_api = &system.state.inner.docker_api;
+    let res = client
+      .send_post(
+        ENDPOINT,
+        Some(&json!({
+          "Name": NAME,
+          "Metadata": { "Network": { "Subnet": "10.45.0.0/33" } },
+        })),
+        None::<String>,
+      )
+      .await;
+    assert_eq!(res.status(), 400, "Expect error for an invalid subnet");
+    let res = client
+      .send_post(
+        ENDPOINT,
+        Some(&json!({
+          "Name": NAME,
+          "Metadata": {
+            "Network": { "Subnet": "10.45.0.0/24", "Gateway": "10.45.0.1" }
+          },
+        })),
+        None::<String>,
+      )
+      .await;
+    assert!(res.status().is_success(), "Expect success on create");
+    let network = docker_api
+      .inspect_network(NAME, None::<InspectNetworkOptions<String>>)
+      .await
+      .expect("Expect the network of the namespace to exist");
+    let subnet = network
+      .ipam
+      .and_then(|ipam| ipam.config)
+      .and_then(|config| config.into_iter().find_map(|config| config.subnet));
+    assert_eq!(subnet.as_deref(), Some("10.45.0.0/24"));
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/{NAME}"), None::<String>)
+      .await;
+    assert!(res.status().is_success(), "Expect success on delete");
+    let network = docker_api
+      .inspect_network(NAME, None::<InspectNetworkOptions<String>>)
+      .await;
+    assert!(network.is_err(), "Expect the network to be removed");
+    system.state.wait_event_loop().await;
+  }
+
   #[ntex::test]
   async fn basic() {
     let system = gen_default_test_system().await;
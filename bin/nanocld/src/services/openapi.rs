@@ -7,8 +7,8 @@ use nanocl_stubs::{
 use crate::vars;
 
 use super::{
-  cargo, event, exec, job, metric, namespace, node, process, resource,
-  resource_kind, secret, system, vm, vm_image,
+  cargo, dns, event, exec, job, metric, namespace, node, process, resource,
+  resource_kind, secret, system, vm, vm_image, volume,
 };
 
 /// When returning a [HttpError](nanocl_error::http::HttpError)
@@ -67,6 +67,13 @@ impl Modify for VersionModifier {
     secret::delete_secret,
     secret::patch_secret,
     secret::count_secret,
+    // Volume
+    volume::list_volume,
+    volume::inspect_volume,
+    volume::create_volume,
+    volume::delete_volume,
+    volume::patch_volume,
+    volume::count_volume,
     // Job
     job::list_job,
     job::delete_job,
@@ -90,10 +97,13 @@ impl Modify for VersionModifier {
     // VM Image
     vm_image::list_vm_images,
     vm_image::import_vm_image,
+    vm_image::pull_vm_image,
+    vm_image::export_vm_image,
     vm_image::delete_vm_image,
     vm_image::resize_vm_image,
     vm_image::clone_vm_image,
     vm_image::snapshot_vm_image,
+    vm_image::receive_vm_image,
     // Vm
     vm::list_vm,
     vm::inspect_vm,
@@ -102,6 +112,17 @@ impl Modify for VersionModifier {
     vm::list_vm_history,
     vm::patch_vm,
     vm::vm_attach,
+    vm::vm_vnc,
+    vm::list_vm_snapshots,
+    vm::create_vm_snapshot,
+    vm::restore_vm_snapshot,
+    vm::delete_vm_snapshot,
+    vm::migrate_vm,
+    vm::start_vm_migration,
+    vm::finish_vm_migration,
+    vm::abort_vm_migration,
+    // Dns
+    dns::list_dns_record,
     // Resource Kind
     resource_kind::list_resource_kind,
     resource_kind::create_resource_kind,
@@ -112,6 +133,7 @@ impl Modify for VersionModifier {
     // Resource
     resource::list_resource,
     resource::inspect_resource,
+    resource::inspect_resource_status,
     resource::create_resource,
     resource::delete_resource,
     resource::put_resource,
@@ -134,6 +156,10 @@ impl Modify for VersionModifier {
     process::wait_processes,
     process::stats_processes,
     process::count_processes,
+    process::pause_processes,
+    process::resume_processes,
+    process::reset_processes,
+    process::hotplug_processes,
     process::inspect_process,
     process::start_process_by_pk,
     // Event
@@ -147,6 +173,7 @@ impl Modify for VersionModifier {
     (name = "Namespaces", description = "Namespaces management endpoints."),
     (name = "Nodes", description = "Nodes management endpoints."),
     (name = "Resources", description = "Resources management endpoints."),
+    (name = "Dns", description = "Records served by ncdns."),
     (name = "System", description = "General system endpoints."),
     (name = "VmImages", description = "Virtual machine images management endpoints."),
     (name = "Vms", description = "Virtual machines management endpoints."),
@@ -154,6 +181,7 @@ impl Modify for VersionModifier {
     (name = "Processes", description = "Processes management endpoints."),
     (name = "Secrets", description = "Secrets management endpoints."),
     (name = "Jobs", description = "Jobs management endpoints."),
+    (name = "Volumes", description = "Volumes management endpoints."),
     (name = "Events", description = "Events management endpoints."),
   ),
   modifiers(&VersionModifier),
@@ -0,0 +1,36 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::{generic::GenericNspQuery, vm::VmHotplug};
+
+use crate::{models::SystemState, utils};
+
+/// Hotplug a cpu, memory or network card in a vm process
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Processes",
+  request_body = VmHotplug,
+  path = "/processes/{kind}/{name}/hotplug",
+  params(
+    ("kind" = String, Path, description = "Kind of the process, only vm is supported", example = "vm"),
+    ("name" = String, Path, description = "Name of the process", example = "my-vm"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the process belongs if needed"),
+  ),
+  responses(
+    (status = 202, description = "Device plugged"),
+    (status = 400, description = "The device can't be plugged", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/processes/{kind}/{name}/hotplug")]
+pub async fn hotplug_processes(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+  payload: web::types::Json<VmHotplug>,
+) -> HttpResult<web::HttpResponse> {
+  let (_, kind, name) = path.into_inner();
+  let vm =
+    utils::vm_control::read_vm(&kind, &name, &qs.namespace, &state).await?;
+  utils::vm_control::hotplug(&vm, &payload, &state).await?;
+  Ok(web::HttpResponse::Accepted().finish())
+}
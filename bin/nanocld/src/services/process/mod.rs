@@ -1,22 +1,30 @@
 use ntex::web;
 
 pub mod count;
+pub mod hotplug;
 pub mod inspect;
 pub mod kill;
 pub mod list;
 pub mod log;
+pub mod pause;
+pub mod reset;
 pub mod restart;
+pub mod resume;
 pub mod start;
 pub mod stats;
 pub mod stop;
 pub mod wait;
 
 pub use count::*;
+pub use hotplug::*;
 pub use inspect::*;
 pub use kill::*;
 pub use list::*;
 pub use log::*;
+pub use pause::*;
+pub use reset::*;
 pub use restart::*;
+pub use resume::*;
 pub use start::*;
 pub use stats::*;
 pub use stop::*;
@@ -34,6 +42,10 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(wait_processes);
   config.service(stats_processes);
   config.service(count_processes);
+  config.service(pause_processes);
+  config.service(resume_processes);
+  config.service(reset_processes);
+  config.service(hotplug_processes);
 }
 
 #[cfg(test)]
@@ -124,4 +136,22 @@ mod tests {
       "basic process inspect"
     );
   }
+
+  #[ntex::test]
+  async fn vm_actions() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    let res = client
+      .send_post(
+        "/processes/cargo/nstore/pause",
+        None::<String>,
+        Some(&serde_json::json!({ "namespace": "system" })),
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "pause cargo process"
+    );
+  }
 }
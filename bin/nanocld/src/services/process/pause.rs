@@ -0,0 +1,34 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{models::SystemState, utils};
+
+/// Freeze the cpus of a vm process
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Processes",
+  path = "/processes/{kind}/{name}/pause",
+  params(
+    ("kind" = String, Path, description = "Kind of the process, only vm is supported", example = "vm"),
+    ("name" = String, Path, description = "Name of the process", example = "my-vm"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the process belongs if needed"),
+  ),
+  responses(
+    (status = 202, description = "Process instances paused"),
+    (status = 400, description = "The process isn't a running vm", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/processes/{kind}/{name}/pause")]
+pub async fn pause_processes(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let (_, kind, name) = path.into_inner();
+  let vm =
+    utils::vm_control::read_vm(&kind, &name, &qs.namespace, &state).await?;
+  utils::vm_control::pause(&vm, &state).await?;
+  Ok(web::HttpResponse::Accepted().finish())
+}
@@ -0,0 +1,34 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{models::SystemState, utils};
+
+/// Resume the cpus of a paused vm process
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Processes",
+  path = "/processes/{kind}/{name}/resume",
+  params(
+    ("kind" = String, Path, description = "Kind of the process, only vm is supported", example = "vm"),
+    ("name" = String, Path, description = "Name of the process", example = "my-vm"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the process belongs if needed"),
+  ),
+  responses(
+    (status = 202, description = "Process instances resumed"),
+    (status = 400, description = "The process isn't a running vm", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/processes/{kind}/{name}/resume")]
+pub async fn resume_processes(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let (_, kind, name) = path.into_inner();
+  let vm =
+    utils::vm_control::read_vm(&kind, &name, &qs.namespace, &state).await?;
+  utils::vm_control::resume(&vm, &state).await?;
+  Ok(web::HttpResponse::Accepted().finish())
+}
@@ -2,10 +2,10 @@ use ntex::web;
 
 use bollard_next::container::StartContainerOptions;
 use nanocl_error::http::{HttpError, HttpResult};
-use nanocl_stubs::generic::GenericNspQuery;
+use nanocl_stubs::{generic::GenericNspQuery, process::ProcessKind};
 
 use crate::{
-  models::{ProcessDb, SystemState},
+  models::{CargoDb, ProcessDb, SystemState},
   repositories::generic::*,
   utils,
 };
@@ -60,6 +60,13 @@ pub async fn start_processes(
   let (_, kind, name) = path.into_inner();
   let kind = kind.parse().map_err(HttpError::bad_request)?;
   let kind_key = utils::key::gen_kind_key(&kind, &name, &qs.namespace);
+  // A port rejected by ncproxy fails the start instead of the background task
+  if kind == ProcessKind::Cargo {
+    let cargo =
+      CargoDb::transform_read_by_pk(&kind_key, &state.inner.pool).await?;
+    utils::container::cargo::ensure_ports(&kind_key, &cargo.spec.ports, &state)
+      .await?;
+  }
   utils::container::generic::emit_starting(&kind_key, &kind, &state).await?;
   Ok(web::HttpResponse::Accepted().finish())
 }
@@ -0,0 +1,34 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::resource::ResourceStatus;
+
+use crate::{
+  models::{ResourceStatusDb, SystemState},
+  repositories::generic::*,
+};
+
+/// Get the result of the last apply of a resource managed by nanocld
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Resources",
+  path = "/resources/{name}/status",
+  params(
+    ("name" = String, Path, description = "The resource name")
+  ),
+  responses(
+    (status = 200, description = "Status of the last apply", body = nanocl_stubs::resource::ResourceStatus),
+    (status = 404, description = "Resource doesn't have a status", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::get("/resources/{name}/status")]
+pub async fn inspect_resource_status(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+) -> HttpResult<web::HttpResponse> {
+  let status: ResourceStatus =
+    ResourceStatusDb::read_by_pk(&path.1, &state.inner.pool)
+      .await?
+      .into();
+  Ok(web::HttpResponse::Ok().json(&status))
+}
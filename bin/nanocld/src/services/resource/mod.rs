@@ -4,6 +4,7 @@ pub mod count;
 pub mod create;
 pub mod delete;
 pub mod inspect;
+pub mod inspect_status;
 pub mod list;
 pub mod list_history;
 pub mod put;
@@ -13,6 +14,7 @@ pub use count::*;
 pub use create::*;
 pub use delete::*;
 pub use inspect::*;
+pub use inspect_status::*;
 pub use list::*;
 pub use list_history::*;
 pub use put::*;
@@ -23,6 +25,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(delete_resource);
   config.service(list_resource);
   config.service(inspect_resource);
+  config.service(inspect_resource_status);
   config.service(put_resource);
   config.service(count_resource);
   config.service(list_resource_history);
@@ -108,6 +111,18 @@ mod tests {
     let resource = res.json::<Resource>().await.unwrap();
     assert_eq!(resource.spec.resource_key, TEST_RESOURCE);
     assert_eq!(resource.kind, TEST_RESOURCE_KIND);
+    // Only the resources managed by nanocld have a status
+    let res = client
+      .send_get(
+        &format!("{ENDPOINT}/{TEST_RESOURCE}/status"),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::NOT_FOUND,
+      "inspect resource status"
+    );
     // Basic list
     let mut res = client.send_get(ENDPOINT, None::<String>).await;
     test_status_code!(res.status(), http::StatusCode::OK, "list resource");
@@ -2,7 +2,9 @@ use ntex::web;
 
 use bollard_next::auth::DockerCredentials;
 use nanocl_error::http::{HttpError, HttpResult};
-use nanocl_stubs::{proxy::ProxySslConfig, secret::SecretPartial};
+use nanocl_stubs::{
+  proxy::ProxySslConfig, secret::SecretPartial, vm_spec::VmCloudInit,
+};
 
 use crate::{
   models::{SecretDb, SystemState},
@@ -32,10 +34,24 @@ pub async fn create_secret(
       serde_json::from_value::<ProxySslConfig>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
     }
-    "nanocl.io/env" => {
+    "nanocl.io/env" | "nanocl.io/htpasswd" => {
       serde_json::from_value::<Vec<String>>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
     }
+    "nanocl.io/ca" => {
+      serde_json::from_value::<String>(payload.data.clone())
+        .map_err(|e| HttpError::bad_request(e.to_string()))?;
+    }
+    "nanocl.io/cloud-init" => {
+      let cloud_init =
+        serde_json::from_value::<VmCloudInit>(payload.data.clone())
+          .map_err(|e| HttpError::bad_request(e.to_string()))?;
+      if cloud_init.secret.is_some() {
+        return Err(HttpError::bad_request(
+          "A cloud-init secret can't reference another secret",
+        ));
+      }
+    }
     "nanocl.io/container-registry" => {
       serde_json::from_value::<DockerCredentials>(payload.data.clone())
         .map_err(|e| HttpError::bad_request(e.to_string()))?;
@@ -0,0 +1,125 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::{
+  generic::GenericNspQuery,
+  vm::{VmMigratePayload, VmMigrateQuery},
+};
+
+use crate::{
+  models::{SystemState, VmDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// Migrate a virtual machine to another node
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  request_body = VmMigratePayload,
+  path = "/vms/{name}/migrate",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 200, description = "Stream of the migration progress", body = nanocl_stubs::vm::VmMigrateStream),
+    (status = 400, description = "The virtual machine can't be migrated from this node", body = crate::services::openapi::ApiError),
+    (status = 404, description = "The virtual machine or the node does not exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/{name}/migrate")]
+pub async fn migrate_vm(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+  web::types::Json(payload): web::types::Json<VmMigratePayload>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  let rx = utils::vm_migrate::migrate(&vm, &payload, &state).await?;
+  Ok(web::HttpResponse::Ok().streaming(rx))
+}
+
+/// Start a virtual machine waiting to be migrated from another node, used internally
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  path = "/vms/{name}/migrate/incoming",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+    ("token" = String, Query, description = "Token of the migration issued by the source node"),
+  ),
+  responses(
+    (status = 200, description = "Address of the incoming virtual machine", body = nanocl_stubs::vm::VmMigrateIncoming),
+    (status = 403, description = "The token doesn't match a migration to this node", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/{name}/migrate/incoming")]
+pub async fn start_vm_migration(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<VmMigrateQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  let incoming = utils::vm_migrate::incoming(&vm, &qs.token, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&incoming))
+}
+
+/// Resume a migrated virtual machine, used internally
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  path = "/vms/{name}/migrate/finish",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+    ("token" = String, Query, description = "Token of the migration issued by the source node"),
+  ),
+  responses(
+    (status = 202, description = "The virtual machine is running on this node"),
+  ),
+))]
+#[web::post("/vms/{name}/migrate/finish")]
+pub async fn finish_vm_migration(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<VmMigrateQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  utils::vm_migrate::finish(&vm, &qs.token, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+/// Remove a virtual machine waiting to be migrated, used internally
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Vms",
+  path = "/vms/{name}/migrate/incoming",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+    ("token" = String, Query, description = "Token of the migration issued by the source node"),
+  ),
+  responses(
+    (status = 202, description = "The incoming virtual machine have been removed"),
+  ),
+))]
+#[web::delete("/vms/{name}/migrate/incoming")]
+pub async fn abort_vm_migration(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<VmMigrateQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  utils::vm_migrate::abort(&vm, &qs.token, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
@@ -7,7 +7,10 @@ pub mod delete;
 pub mod inspect;
 pub mod list;
 pub mod list_history;
+pub mod migrate;
 pub mod patch;
+pub mod snapshot;
+pub mod vnc;
 
 pub use attach::*;
 pub use count::*;
@@ -16,7 +19,10 @@ pub use delete::*;
 pub use inspect::*;
 pub use list::*;
 pub use list_history::*;
+pub use migrate::*;
 pub use patch::*;
+pub use snapshot::*;
+pub use vnc::*;
 
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(list_vm);
@@ -26,9 +32,18 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(count_vm);
   config.service(list_vm_history);
   config.service(patch_vm);
+  config.service(list_vm_snapshots);
+  config.service(create_vm_snapshot);
+  config.service(restore_vm_snapshot);
+  config.service(delete_vm_snapshot);
+  config.service(migrate_vm);
+  config.service(start_vm_migration);
+  config.service(finish_vm_migration);
+  config.service(abort_vm_migration);
   config.service(
     web::resource("/vms/{name}/attach").route(web::get().to(vm_attach)),
   );
+  config.service(web::resource("/vms/{name}/vnc").route(web::get().to(vm_vnc)));
 }
 
 #[cfg(test)]
@@ -73,6 +88,16 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "inspect vm");
     let vm = res.json::<VmInspect>().await.unwrap();
     assert_eq!(vm.spec.name, name);
+    let res = client
+      .get(&format!("/vms/{name}/vnc"))
+      .send()
+      .await
+      .unwrap();
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "vnc without display"
+    );
     let mut res = client.get("/vms").send().await.unwrap();
     test_status_code!(res.status(), http::StatusCode::OK, "list vm");
     let vms = res.json::<Vec<VmSummary>>().await.unwrap();
@@ -0,0 +1,123 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::{generic::GenericNspQuery, vm::VmSnapshotPartial};
+
+use crate::{
+  models::{SystemState, VmDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// List the snapshots of a virtual machine
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Vms",
+  path = "/vms/{name}/snapshots",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 200, description = "List of snapshots", body = [nanocl_stubs::vm::VmSnapshot]),
+    (status = 404, description = "The virtual machine does not exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::get("/vms/{name}/snapshots")]
+pub async fn list_vm_snapshots(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  let snapshots = utils::vm_snapshot::list(&vm, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&snapshots))
+}
+
+/// Snapshot the disks and the memory of a running virtual machine
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  request_body = VmSnapshotPartial,
+  path = "/vms/{name}/snapshots",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 201, description = "The snapshot have been created", body = nanocl_stubs::vm::VmSnapshot),
+    (status = 404, description = "The virtual machine does not exist", body = crate::services::openapi::ApiError),
+    (status = 409, description = "The snapshot already exists", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/{name}/snapshots")]
+pub async fn create_vm_snapshot(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+  payload: web::types::Json<VmSnapshotPartial>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  let snapshot = utils::vm_snapshot::create(&vm, &payload, &state).await?;
+  Ok(web::HttpResponse::Created().json(&snapshot))
+}
+
+/// Restore a running virtual machine to a snapshot
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Vms",
+  path = "/vms/{name}/snapshots/{snapshot}/restore",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("snapshot" = String, Path, description = "The name of the snapshot"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 202, description = "The virtual machine have been restored"),
+    (status = 404, description = "The snapshot does not exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/{name}/snapshots/{snapshot}/restore")]
+pub async fn restore_vm_snapshot(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  utils::vm_snapshot::restore(&vm, &path.2, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
+
+/// Delete a snapshot of a virtual machine
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Vms",
+  path = "/vms/{name}/snapshots/{snapshot}",
+  params(
+    ("name" = String, Path, description = "The name of the virtual machine"),
+    ("snapshot" = String, Path, description = "The name of the snapshot"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 202, description = "The snapshot have been deleted"),
+    (status = 404, description = "The snapshot does not exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::delete("/vms/{name}/snapshots/{snapshot}")]
+pub async fn delete_vm_snapshot(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  utils::vm_snapshot::delete(&vm, &path.2, &state).await?;
+  Ok(web::HttpResponse::Accepted().into())
+}
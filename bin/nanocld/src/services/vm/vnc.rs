@@ -0,0 +1,140 @@
+use std::{cell::RefCell, io, rc::Rc, time::Instant};
+
+use futures::{future::ready, StreamExt};
+use ntex::{
+  chain,
+  channel::{mpsc, oneshot},
+  fn_service, rt,
+  service::{fn_factory_with_config, fn_shutdown, map_config},
+  util::Bytes,
+  web, ws, Service,
+};
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::UnixStream,
+};
+
+use nanocl_error::http::HttpError;
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{
+  models::{SystemState, VmDb, WsConState},
+  repositories::generic::*,
+  utils,
+};
+
+/// Size of the chunks read from the VNC socket
+const CHUNK_SIZE: usize = 64 * 1024;
+
+async fn ws_vnc_service(
+  (path, sink): (String, ws::WsSink),
+) -> Result<
+  impl Service<ws::Frame, Response = Option<ws::Message>, Error = io::Error>,
+  web::Error,
+> {
+  let stream = UnixStream::connect(&path).await.map_err(|err| {
+    HttpError::bad_request(format!("Unable to reach the vm display: {err}"))
+  })?;
+  let (mut reader, mut writer) = stream.into_split();
+  // start heartbeat task
+  let con_state = Rc::new(RefCell::new(WsConState::new()));
+  let (tx, rx) = oneshot::channel();
+  rt::spawn(utils::ws::heartbeat(con_state.clone(), sink.clone(), rx));
+  let (s_rfb, mut r_rfb) = mpsc::channel::<Bytes>();
+  // the RFB stream of qemu is forwarded as is to the client
+  rt::spawn(async move {
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+      let n = match reader.read(&mut buf).await {
+        Ok(0) => break,
+        Ok(n) => n,
+        Err(err) => {
+          log::error!("Error reading vnc socket: {err}");
+          break;
+        }
+      };
+      let msg = ws::Message::Binary(Bytes::copy_from_slice(&buf[..n]));
+      if sink.send(msg).await.is_err() {
+        return;
+      }
+    }
+    let _ = sink.send(ws::Message::Close(None)).await;
+  });
+  rt::spawn(async move {
+    while let Some(data) = r_rfb.next().await {
+      if writer.write_all(&data).await.is_err() {
+        break;
+      }
+    }
+  });
+  // handler service for incoming websocket frames
+  let service = fn_service(move |frame| {
+    let item = match frame {
+      ws::Frame::Ping(msg) => {
+        con_state.borrow_mut().hb = Instant::now();
+        Some(ws::Message::Pong(msg))
+      }
+      // update heartbeat
+      ws::Frame::Pong(_) => {
+        con_state.borrow_mut().hb = Instant::now();
+        None
+      }
+      ws::Frame::Binary(data) => {
+        let _ = s_rfb.send(data);
+        None
+      }
+      ws::Frame::Text(_) => None,
+      ws::Frame::Close(reason) => Some(ws::Message::Close(reason)),
+      _ => Some(ws::Message::Close(None)),
+    };
+    ready(Ok(item))
+  });
+  // handler service for shutdown notification that stop heartbeat task
+  let on_shutdown = fn_shutdown(move || {
+    let _ = tx.send(());
+  });
+  // pipe our service with on_shutdown callback
+  Ok(chain(service).and_then(on_shutdown))
+}
+
+/// Tunnel the VNC display of a virtual machine via websocket
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Vms",
+  path = "/vms/{name}/vnc",
+  params(
+    ("name" = String, Path, description = "Name of the virtual machine"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the virtual machine belongs default to 'global'"),
+  ),
+  responses(
+    (status = 101, description = "Websocket connection carrying the RFB protocol"),
+    (status = 400, description = "The virtual machine has no VNC display", body = crate::services::openapi::ApiError),
+  ),
+))]
+pub async fn vm_vnc(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  req: web::HttpRequest,
+  qs: web::types::Query<GenericNspQuery>,
+) -> Result<web::HttpResponse, web::Error> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool)
+    .await
+    .map_err(HttpError::from)?;
+  if !vm.spec.host_config.vnc.unwrap_or_default() {
+    return Err(
+      HttpError::bad_request(format!("Vm {key} doesn't have vnc enabled"))
+        .into(),
+    );
+  }
+  let path = utils::vnc::socket_path(&key, &state);
+  web::ws::start(
+    req,
+    // inject the socket path to ws_vnc_service factory
+    map_config(fn_factory_with_config(ws_vnc_service), move |cfg| {
+      (path.clone(), cfg)
+    }),
+  )
+  .await
+}
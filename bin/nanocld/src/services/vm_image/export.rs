@@ -0,0 +1,46 @@
+use ntex::{http, web};
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::vm_image::VmImageExportQuery;
+
+use crate::{
+  models::{SystemState, VmImageDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// Export a virtual machine image, a snapshot is flattened with its parents
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "VmImages",
+  path = "/vms/images/{name}/export",
+  params(
+    ("name" = String, Path, description = "The name of the vm image"),
+    ("format" = Option<String>, Query, description = "Format of the exported image qcow2 or raw (default: qcow2)"),
+    ("compress" = Option<bool>, Query, description = "Compress the exported image, only supported by qcow2"),
+  ),
+  responses(
+    (status = 200, description = "Stream of the image file", content_type = "application/octet-stream"),
+    (status = 404, description = "The vm image does not exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::get("/vms/images/{name}/export")]
+pub async fn export_vm_image(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<VmImageExportQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let name = path.1.to_owned();
+  let image = VmImageDb::read_by_pk(&name, &state.inner.pool).await?;
+  let stream = utils::vm_image::export(&image, &qs, &state).await?;
+  let format = qs.format.clone().unwrap_or_default();
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type("application/octet-stream")
+      .header(
+        http::header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{name}.{format}\""),
+      )
+      .streaming(Box::pin(stream)),
+  )
+}
@@ -4,22 +4,30 @@ pub mod clone;
 pub mod count;
 pub mod create_snapshot;
 pub mod delete;
+pub mod export;
 pub mod import;
 pub mod inspect;
 pub mod list;
+pub mod pull;
+pub mod receive;
 pub mod resize;
 
 pub use clone::*;
 pub use count::*;
 pub use create_snapshot::*;
 pub use delete::*;
+pub use export::*;
 pub use import::*;
 pub use inspect::*;
 pub use list::*;
+pub use pull::*;
+pub use receive::*;
 pub use resize::*;
 
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(import_vm_image);
+  config.service(pull_vm_image);
+  config.service(export_vm_image);
   config.service(list_vm_images);
   config.service(delete_vm_image);
   config.service(snapshot_vm_image);
@@ -27,6 +35,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(count_vm_image);
   config.service(resize_vm_image);
   config.service(inspect_vm_image);
+  config.service(receive_vm_image);
 }
 
 #[cfg(test)]
@@ -117,4 +126,36 @@ pub mod tests {
       .unwrap();
     test_status_code!(res.status(), StatusCode::OK, "Unable to delete image");
   }
+
+  #[ntex::test]
+  async fn export() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    ensure_test_image().await;
+    let name = "ubuntu-22-test";
+    let mut res = client
+      .get(&format!("/vms/images/{name}/export"))
+      .send()
+      .await
+      .unwrap();
+    test_status_code!(res.status(), StatusCode::OK, "Unable to export image");
+    let mut size = 0;
+    while let Some(bytes) = res.next().await {
+      size += bytes.unwrap().len() as u64;
+    }
+    let image = inspect_image(name).await.unwrap();
+    assert_eq!(size, std::fs::metadata(&image.path).unwrap().len());
+    let res = client
+      .get(&format!("/vms/images/{name}/export"))
+      .query(&serde_json::json!({ "format": "raw", "compress": true }))
+      .unwrap()
+      .send()
+      .await
+      .unwrap();
+    test_status_code!(
+      res.status(),
+      StatusCode::BAD_REQUEST,
+      "Export a compressed raw image"
+    );
+  }
 }
@@ -0,0 +1,32 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::vm_image::VmImagePullPayload;
+
+use crate::{models::SystemState, utils};
+
+/// Pull a virtual machine image from an url or an OCI registry
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "VmImages",
+  request_body = VmImagePullPayload,
+  path = "/vms/images/{name}/pull",
+  params(
+    ("name" = String, Path, description = "The name of the vm image"),
+  ),
+  responses(
+    (status = 200, description = "Stream of the pull progress", body = nanocl_stubs::vm_image::VmImagePullStream),
+    (status = 409, description = "The vm image already exists", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/images/{name}/pull")]
+pub async fn pull_vm_image(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  web::types::Json(payload): web::types::Json<VmImagePullPayload>,
+) -> HttpResult<web::HttpResponse> {
+  let name = path.1.to_owned();
+  utils::key::validate_name(&name)?;
+  let rx = utils::vm_image_pull::pull(&name, &payload, &state).await?;
+  Ok(web::HttpResponse::Ok().streaming(rx))
+}
@@ -0,0 +1,33 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::vm::VmMigrateQuery;
+
+use crate::{models::SystemState, utils};
+
+/// Receive a virtual machine image from another node migrating a vm, used internally
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "VmImages",
+  request_body = String,
+  path = "/vms/images/{name}/receive",
+  params(
+    ("name" = String, Path, description = "The name of the vm image"),
+    ("token" = String, Query, description = "Token of the migration issued by the source node"),
+  ),
+  responses(
+    (status = 200, description = "Image have been received"),
+    (status = 403, description = "The token doesn't match a migration of the image to this node", body = crate::services::openapi::ApiError),
+    (status = 409, description = "The vm image is already on this node", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::post("/vms/images/{name}/receive")]
+pub async fn receive_vm_image(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<VmMigrateQuery>,
+  payload: web::types::Payload,
+) -> HttpResult<web::HttpResponse> {
+  utils::vm_migrate::receive(&path.1, &qs.token, payload, &state).await?;
+  Ok(web::HttpResponse::Ok().into())
+}
@@ -0,0 +1,37 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{
+  models::{SystemState, VolumeDb},
+  objects::generic::*,
+  utils,
+};
+
+/// Delete a volume that isn't mounted anymore
+#[cfg_attr(feature = "dev", utoipa::path(
+  delete,
+  tag = "Volumes",
+  path = "/volumes/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the volume"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the volume belongs default to 'global'"),
+  ),
+  responses(
+    (status = 202, description = "Volume deleted"),
+    (status = 404, description = "Volume doesn't exist", body = crate::services::openapi::ApiError),
+    (status = 409, description = "Volume still mounted by a cargo or a job", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::delete("/volumes/{name}")]
+pub async fn delete_volume(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  VolumeDb::del_obj_by_pk(&key, &(), &state).await?;
+  Ok(web::HttpResponse::Accepted().finish())
+}
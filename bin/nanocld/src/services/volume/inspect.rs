@@ -0,0 +1,36 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::generic::GenericNspQuery;
+
+use crate::{
+  models::{SystemState, VolumeDb},
+  objects::generic::*,
+  utils,
+};
+
+/// Get detailed information about a volume and the cargoes and jobs using it
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Volumes",
+  path = "/volumes/{name}/inspect",
+  params(
+    ("name" = String, Path, description = "Name of the volume"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the volume belongs default to 'global'"),
+  ),
+  responses(
+    (status = 200, description = "Volume details", body = nanocl_stubs::volume::VolumeInspect),
+    (status = 404, description = "Volume doesn't exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::get("/volumes/{name}/inspect")]
+pub async fn inspect_volume(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let volume = VolumeDb::inspect_obj_by_pk(&key, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&volume))
+}
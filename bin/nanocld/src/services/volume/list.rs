@@ -0,0 +1,38 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::generic::{GenericClause, GenericListQueryNsp};
+
+use crate::{
+  models::{SystemState, VolumeDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// List volumes with optional filter
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Volumes",
+  path = "/volumes",
+  params(
+    ("filter" = Option<String>, Query, description = "Generic filter", example = "{ \"filter\": { \"where\": { \"driver\": { \"eq\": \"local\" } } } }"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the volumes belongs default to 'global'"),
+  ),
+  responses(
+    (status = 200, description = "List of volumes", body = [nanocl_stubs::volume::Volume]),
+  ),
+))]
+#[web::get("/volumes")]
+pub async fn list_volume(
+  state: web::types::State<SystemState>,
+  qs: web::types::Query<GenericListQueryNsp>,
+) -> HttpResult<web::HttpResponse> {
+  let query = utils::query_string::parse_qs_nsp_filter(&qs)?;
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let filter = query
+    .filter
+    .unwrap_or_default()
+    .r#where("namespace_name", GenericClause::Eq(namespace));
+  let items = VolumeDb::transform_read_by(&filter, &state.inner.pool).await?;
+  Ok(web::HttpResponse::Ok().json(&items))
+}
@@ -0,0 +1,163 @@
+pub use ntex::web;
+
+pub mod count;
+pub mod create;
+pub mod delete;
+pub mod inspect;
+pub mod list;
+pub mod patch;
+
+pub use count::*;
+pub use create::*;
+pub use delete::*;
+pub use inspect::*;
+pub use list::*;
+pub use patch::*;
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_volume);
+  config.service(create_volume);
+  config.service(count_volume);
+  config.service(inspect_volume);
+  config.service(delete_volume);
+  config.service(patch_volume);
+}
+
+#[cfg(test)]
+mod test_volume {
+  use ntex::http;
+
+  use nanocl_stubs::{
+    cargo::CargoDeleteQuery,
+    cargo_spec::CargoSpecPartial,
+    volume::{Volume, VolumeInspect, VolumeMount, VolumePartial},
+  };
+
+  use crate::utils::tests::*;
+
+  const ENDPOINT: &str = "/volumes";
+
+  async fn test_create(client: &TestClient) {
+    let new_volume = VolumePartial {
+      name: String::from("test-volume"),
+      ..Default::default()
+    };
+    let mut res = client
+      .send_post(ENDPOINT, Some(&new_volume), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create volume");
+    let volume = res.json::<Volume>().await.unwrap();
+    assert_eq!(volume.key, "test-volume.global");
+    assert_eq!(volume.driver, "local");
+    let res = client
+      .send_post(ENDPOINT, Some(&new_volume), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CONFLICT,
+      "create existing volume"
+    );
+  }
+
+  async fn test_inspect(client: &TestClient) {
+    let mut res = client
+      .send_get(&format!("{ENDPOINT}/test-volume/inspect"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect volume");
+    let volume = res.json::<VolumeInspect>().await.unwrap();
+    assert!(volume.used_by.is_empty());
+  }
+
+  async fn test_list(client: &TestClient) {
+    let mut res = client.send_get(ENDPOINT, None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list volumes");
+    let volumes = res.json::<Vec<Volume>>().await.unwrap();
+    assert!(volumes.iter().any(|volume| volume.name == "test-volume"));
+  }
+
+  async fn test_delete(client: &TestClient) {
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-volume"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::ACCEPTED,
+      "delete volume"
+    );
+  }
+
+  /// A volume mounted by a cargo can't be deleted
+  async fn test_delete_used(client: &TestClient) {
+    let new_volume = VolumePartial {
+      name: String::from("test-volume-used"),
+      ..Default::default()
+    };
+    let res = client
+      .send_post(ENDPOINT, Some(&new_volume), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create volume");
+    let cargo = CargoSpecPartial {
+      name: String::from("test-volume-cargo"),
+      container: bollard_next::container::Config {
+        image: Some("ghcr.io/next-hat/nanocl-get-started:latest".to_owned()),
+        ..Default::default()
+      },
+      volumes: Some(vec![VolumeMount {
+        name: String::from("test-volume-used"),
+        path: String::from("/data"),
+        read_only: None,
+      }]),
+      ..Default::default()
+    };
+    let res = client
+      .send_post("/cargoes", Some(&cargo), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::CREATED, "create cargo");
+    let mut res = client
+      .send_get(
+        &format!("{ENDPOINT}/test-volume-used/inspect"),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "inspect volume");
+    let volume = res.json::<VolumeInspect>().await.unwrap();
+    assert_eq!(volume.used_by, vec!["test-volume-cargo.global".to_owned()]);
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-volume-used"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CONFLICT,
+      "delete used volume"
+    );
+    let res = client
+      .send_delete(
+        "/cargoes/test-volume-cargo",
+        Some(CargoDeleteQuery {
+          force: Some(true),
+          ..Default::default()
+        }),
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::ACCEPTED, "delete cargo");
+    let res = client
+      .send_delete(&format!("{ENDPOINT}/test-volume-used"), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::ACCEPTED,
+      "delete unused volume"
+    );
+  }
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    let client = system.client;
+    test_create(&client).await;
+    test_inspect(&client).await;
+    test_list(&client).await;
+    test_delete(&client).await;
+    test_delete_used(&client).await;
+  }
+}
@@ -0,0 +1,38 @@
+use ntex::web;
+
+use nanocl_error::http::HttpResult;
+use nanocl_stubs::{generic::GenericNspQuery, volume::VolumeUpdate};
+
+use crate::{
+  models::{SystemState, VolumeDb},
+  objects::generic::*,
+  utils,
+};
+
+/// Update the backup policy and the metadata of a volume
+#[cfg_attr(feature = "dev", utoipa::path(
+  patch,
+  tag = "Volumes",
+  request_body = VolumeUpdate,
+  path = "/volumes/{name}",
+  params(
+    ("name" = String, Path, description = "Name of the volume"),
+    ("namespace" = Option<String>, Query, description = "Namespace where the volume belongs default to 'global'"),
+  ),
+  responses(
+    (status = 200, description = "Volume patched", body = nanocl_stubs::volume::Volume),
+    (status = 404, description = "Volume doesn't exist", body = crate::services::openapi::ApiError),
+  ),
+))]
+#[web::patch("/volumes/{name}")]
+pub async fn patch_volume(
+  state: web::types::State<SystemState>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<VolumeUpdate>,
+  qs: web::types::Query<GenericNspQuery>,
+) -> HttpResult<web::HttpResponse> {
+  let namespace = utils::key::resolve_nsp(&qs.namespace);
+  let key = utils::key::gen_key(&namespace, &path.1);
+  let volume = VolumeDb::patch_obj_by_pk(&key, &payload, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&volume))
+}
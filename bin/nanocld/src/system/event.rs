@@ -16,7 +16,7 @@ use crate::{
   objects::generic::*,
   repositories::generic::*,
   tasks::generic::*,
-  utils,
+  utils, vars,
 };
 
 /// Remove a job after when finished and ttl is set
@@ -81,6 +81,121 @@ async fn job_ttl(actor: &EventActor, state: &SystemState) -> IoResult<()> {
   Ok(())
 }
 
+/// Render the network policies again when a policy or an instance change.
+/// Events are emitted on the policy when it's applied or when it fails.
+/// The instances are ignored when the network policies are disabled.
+fn network_policy(
+  action: &NativeEventAction,
+  actor: &EventActor,
+  state: &SystemState,
+) {
+  let is_policy = actor
+    .attributes
+    .as_ref()
+    .and_then(|attributes| attributes.get("Kind"))
+    .and_then(|kind| kind.as_str())
+    .map(|kind| kind.starts_with(vars::NETWORK_POLICY_KIND))
+    .unwrap_or_default();
+  match (&actor.kind, action) {
+    (
+      EventActorKind::Resource,
+      NativeEventAction::Create
+      | NativeEventAction::Update
+      | NativeEventAction::Destroy,
+    ) if is_policy => {}
+    (EventActorKind::Process, action)
+      if action != &NativeEventAction::Create
+        && state.inner.config.network_policies => {}
+    _ => return,
+  }
+  let state = state.clone();
+  let actor = actor.clone();
+  rt::spawn(async move {
+    let res = utils::network_policy::sync(&state).await;
+    let apply = NativeEventAction::Other("apply".to_owned());
+    match (&actor.kind, res) {
+      (EventActorKind::Resource, Ok(_)) => {
+        state.emit_normal_native_action(&actor, apply);
+      }
+      (EventActorKind::Resource, Err(err)) => {
+        state.emit_error_native_action(&actor, apply, Some(err.to_string()));
+      }
+      (_, Ok(_)) => {}
+      (_, Err(err)) => {
+        log::warn!("event::network_policy: {err}");
+        let Ok(policies) = utils::network_policy::list_resources(&state).await
+        else {
+          return;
+        };
+        for policy in policies {
+          state.emit_error_native_action(
+            &policy,
+            apply.clone(),
+            Some(err.to_string()),
+          );
+        }
+      }
+    }
+  });
+}
+
+/// Materialize the proxy and dns rules of an ingress when it's created or updated
+/// and delete them when it's destroyed.
+/// The result is saved as the status of the ingress and emitted as an event.
+fn ingress(
+  action: &NativeEventAction,
+  actor: &EventActor,
+  state: &SystemState,
+) {
+  let is_ingress = actor
+    .attributes
+    .as_ref()
+    .and_then(|attributes| attributes.get("Kind"))
+    .and_then(|kind| kind.as_str())
+    .map(|kind| kind == vars::INGRESS_KIND)
+    .unwrap_or_default();
+  if actor.kind != EventActorKind::Resource || !is_ingress {
+    return;
+  }
+  let name = actor.key.clone().unwrap_or_default();
+  let state = state.clone();
+  let actor = actor.clone();
+  match action {
+    NativeEventAction::Create | NativeEventAction::Update => {
+      rt::spawn(async move {
+        let apply = NativeEventAction::Other("apply".to_owned());
+        match utils::ingress::sync(&name, &state).await {
+          Ok(note) => {
+            state.emit_action(
+              &actor,
+              apply,
+              EventKind::Normal,
+              "state_sync",
+              Some(note),
+              None,
+            );
+          }
+          Err(err) => {
+            state.emit_error_native_action(
+              &actor,
+              apply,
+              Some(err.to_string()),
+            );
+          }
+        }
+      });
+    }
+    NativeEventAction::Destroy => {
+      rt::spawn(async move {
+        if let Err(err) = utils::ingress::remove(&name, &state).await {
+          log::warn!("event::ingress: {err}");
+        }
+      });
+    }
+    _ => {}
+  }
+}
+
 fn starting(
   key: &str,
   actor: &EventActor,
@@ -242,6 +357,8 @@ pub async fn exec_event(e: &Event, state: &SystemState) -> IoResult<()> {
     }
     _ => {}
   }
+  network_policy(&action, actor, state);
+  ingress(&action, actor, state);
   let task: Option<ObjTaskFuture> = match action {
     NativeEventAction::Starting => starting(&key, actor, state),
     NativeEventAction::Stopping => stopping(&key, actor, state),
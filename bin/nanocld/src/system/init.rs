@@ -9,7 +9,7 @@ use nanocl_stubs::config::DaemonConfig;
 
 use crate::{
   models::{NodeDb, SystemState},
-  utils,
+  utils, vars,
 };
 
 /// Create a new thread and watch for change in the run directory
@@ -130,10 +130,22 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
   NodeDb::register(&system_ptr).await?;
   utils::system::register_namespace("global", &system_ptr).await?;
   utils::system::register_namespace("system", &system_ptr).await?;
+  utils::system::register_resource_kind(
+    vars::NETWORK_POLICY_KIND,
+    "v1",
+    &system_ptr,
+  )
+  .await?;
+  utils::system::register_resource_kind(vars::INGRESS_KIND, "v1", &system_ptr)
+    .await?;
   rt::spawn(async move {
     let fut = async move {
       utils::system::sync_processes(&system_ptr).await?;
       utils::system::sync_vm_images(&system_ptr).await?;
+      if system_ptr.inner.config.network_policies {
+        utils::network_policy::sync(&system_ptr).await?;
+      }
+      utils::ingress::reconcile(&system_ptr).await?;
       Ok::<_, IoError>(())
     };
     if let Err(err) = fut.await {
@@ -143,6 +155,7 @@ pub async fn init(conf: &DaemonConfig) -> IoResult<SystemState> {
   });
   super::docker_event::analyze(&system_state);
   super::metric::spawn(&system_state);
+  utils::overlay::spawn(&system_state);
   Ok(system_state)
 }
 
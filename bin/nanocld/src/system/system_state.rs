@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use futures::channel::mpsc;
-use futures_util::{SinkExt, StreamExt};
+use futures_util::{lock::Mutex, SinkExt, StreamExt};
 use ntex::rt;
 
 use nanocl_error::io::{FromIo, IoError, IoResult};
@@ -45,6 +45,8 @@ impl SystemState {
         event_emitter_raw: RawEventEmitter::new(),
         task_manager: TaskManager::new(),
         arbiter: rt::Arbiter::new(),
+        network_policy_lock: Mutex::new(()),
+        vm_image_pulls: Default::default(),
       }),
     };
     system_state.clone().run(rx);
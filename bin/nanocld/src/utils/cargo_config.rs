@@ -0,0 +1,212 @@
+use std::{collections::HashMap, os::unix::fs::PermissionsExt};
+
+use tokio::fs;
+
+use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_stubs::{
+  cargo::Cargo,
+  cargo_spec::CargoConfig,
+  generic::{GenericClause, GenericFilter},
+};
+
+use crate::{
+  models::{SecretDb, SystemState},
+  repositories::generic::*,
+};
+
+/// Permissions of a config file when the cargo doesn't set them
+const DEFAULT_MODE: u32 = 0o644;
+/// Permissions of a config file reading the secrets when the cargo
+/// doesn't set them, only the user of the container can read it
+const SECRET_MODE: u32 = 0o600;
+
+/// Directory where the configs of a cargo are rendered
+pub fn config_dir(key: &str, state: &SystemState) -> String {
+  format!(
+    "{}/secrets/cargo/{key}/configs",
+    state.inner.config.state_dir
+  )
+}
+
+/// Read the secrets of the cargo by name to be used in the templates
+async fn read_secrets(
+  cargo: &Cargo,
+  state: &SystemState,
+) -> IoResult<HashMap<String, serde_json::Value>> {
+  let Some(secrets) = &cargo.spec.secrets else {
+    return Ok(HashMap::new());
+  };
+  let filter =
+    GenericFilter::new().r#where("key", GenericClause::In(secrets.clone()));
+  let secrets = SecretDb::transform_read_by(&filter, &state.inner.pool)
+    .await?
+    .into_iter()
+    .map(|secret| (secret.name, secret.data))
+    .collect();
+  Ok(secrets)
+}
+
+/// Object available in the templates of the configs of an instance
+fn gen_object(
+  cargo: &Cargo,
+  instance: usize,
+  secrets: &HashMap<String, serde_json::Value>,
+  state: &SystemState,
+) -> IoResult<liquid::Object> {
+  let metadata = cargo.spec.metadata.clone().unwrap_or(serde_json::json!({}));
+  let object = serde_json::json!({
+    "Cargo": {
+      "Key": cargo.spec.cargo_key,
+      "Name": cargo.spec.name,
+      "Instance": instance,
+    },
+    "Metadata": metadata,
+    "Namespace": cargo.namespace_name,
+    "Node": {
+      "Name": state.inner.config.hostname,
+      "Addr": state.inner.config.gateway,
+    },
+    "Secrets": secrets,
+  });
+  liquid::model::to_object(&object)
+    .map_err(|err| IoError::invalid_data("CargoConfig", &format!("{err}")))
+}
+
+/// Permissions of a config file, the ones rendering secrets
+/// aren't readable by the other users by default
+fn gen_mode(config: &CargoConfig) -> u32 {
+  config.mode.unwrap_or_else(|| {
+    if config.content.contains("Secrets") {
+      SECRET_MODE
+    } else {
+      DEFAULT_MODE
+    }
+  })
+}
+
+/// Render a config and replace its file, old instances keep the previous content
+/// until they are removed because the file is replaced and not truncated
+async fn write_config(
+  dir: &str,
+  config: &CargoConfig,
+  object: &liquid::Object,
+) -> IoResult<String> {
+  let content = nanocl_utils::liquid::compile(&config.content, object)
+    .map_err(|err| {
+      IoError::invalid_data("CargoConfig", &format!("{}: {err}", config.path))
+    })?;
+  let path = format!("{dir}{}", config.path);
+  if let Some((parent, _)) = path.rsplit_once('/') {
+    fs::create_dir_all(parent)
+      .await
+      .map_err(|err| err.map_err_context(|| parent))?;
+  }
+  let tmp_path = format!("{path}.tmp");
+  fs::write(&tmp_path, content)
+    .await
+    .map_err(|err| err.map_err_context(|| &tmp_path))?;
+  let mode = gen_mode(config);
+  fs::set_permissions(&tmp_path, std::fs::Permissions::from_mode(mode))
+    .await
+    .map_err(|err| err.map_err_context(|| &tmp_path))?;
+  fs::rename(&tmp_path, &path)
+    .await
+    .map_err(|err| err.map_err_context(|| &path))?;
+  Ok(path)
+}
+
+/// Render the configs of every instance of a cargo
+/// and return the binds to mount them by instance
+pub async fn render(
+  cargo: &Cargo,
+  number: usize,
+  state: &SystemState,
+) -> IoResult<Vec<Vec<String>>> {
+  let Some(configs) = &cargo.spec.configs else {
+    return Ok(vec![Vec::new(); number]);
+  };
+  let secrets = read_secrets(cargo, state).await?;
+  let dir = config_dir(&cargo.spec.cargo_key, state);
+  let mut binds = Vec::new();
+  for instance in 0..number {
+    let object = gen_object(cargo, instance, &secrets, state)?;
+    let instance_dir = format!("{dir}/{instance}");
+    let mut instance_binds = Vec::new();
+    for config in configs {
+      let path = write_config(&instance_dir, config, &object).await?;
+      instance_binds.push(format!("{path}:{}:ro", config.path));
+    }
+    binds.push(instance_binds);
+  }
+  Ok(binds)
+}
+
+/// Remove the rendered configs of a cargo
+pub async fn delete(key: &str, state: &SystemState) {
+  let _ = fs::remove_dir_all(config_dir(key, state)).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::cargo_spec::CargoSpecPartial;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn render_config() {
+    let dir = std::env::temp_dir()
+      .join(format!("nanocl-cargo-config-{}", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let config = CargoConfig {
+      path: "/etc/app/app.conf".to_owned(),
+      mode: Some(0o600),
+      content: "listen ${{ Node.Addr }}:{{ Metadata.Port }}".to_owned(),
+    };
+    let object = liquid::object!({
+      "Node": { "Addr": "10.0.0.1" },
+      "Metadata": { "Port": 8080 },
+    });
+    let path = write_config(dir, &config, &object).await.unwrap();
+    assert_eq!(path, format!("{dir}/etc/app/app.conf"));
+    let content = fs::read_to_string(&path).await.unwrap();
+    assert_eq!(content, "listen 10.0.0.1:8080");
+    let mode = fs::metadata(&path).await.unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let config = CargoConfig {
+      content: "{{ Missing.Value }}".to_owned(),
+      ..config
+    };
+    assert!(write_config(dir, &config, &object).await.is_err());
+    let _ = fs::remove_dir_all(dir).await;
+  }
+
+  #[test]
+  fn config_mode() {
+    let config = CargoConfig {
+      path: "/etc/app/app.conf".to_owned(),
+      mode: None,
+      content: "listen {{ Metadata.Port }}".to_owned(),
+    };
+    assert_eq!(gen_mode(&config), 0o644);
+    let secret = CargoConfig {
+      content: "password {{ Secrets.db.Password }}".to_owned(),
+      ..config.clone()
+    };
+    assert_eq!(gen_mode(&secret), 0o600);
+    let secret = CargoConfig {
+      mode: Some(0o640),
+      ..secret
+    };
+    assert_eq!(gen_mode(&secret), 0o640);
+    let mut spec = CargoSpecPartial {
+      configs: Some(vec![CargoConfig {
+        mode: Some(0o4755),
+        ..config
+      }]),
+      ..Default::default()
+    };
+    assert!(spec.validate_configs().is_err());
+    spec.configs.as_mut().unwrap()[0].mode = Some(0o755);
+    assert!(spec.validate_configs().is_ok());
+  }
+}
@@ -0,0 +1,215 @@
+use std::os::unix::fs::PermissionsExt;
+
+use tokio::{fs, io::AsyncWriteExt, process::Command};
+
+use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_stubs::{vm::Vm, vm_spec::VmCloudInit};
+
+use crate::{
+  models::{SecretDb, SystemState},
+  repositories::generic::*,
+};
+
+/// Kind of the secrets holding cloud-init documents
+pub const SECRET_KIND: &str = "nanocl.io/cloud-init";
+
+/// Merge the inline documents with the ones of the secret,
+/// the inline documents take precedence
+pub fn merge(
+  cloud_init: &VmCloudInit,
+  secret: Option<VmCloudInit>,
+) -> VmCloudInit {
+  let secret = secret.unwrap_or_default();
+  VmCloudInit {
+    user_data: cloud_init.user_data.clone().or(secret.user_data),
+    meta_data: cloud_init.meta_data.clone().or(secret.meta_data),
+    network_config: cloud_init.network_config.clone().or(secret.network_config),
+    vendor_data: cloud_init.vendor_data.clone().or(secret.vendor_data),
+    secret: None,
+  }
+}
+
+/// Default meta-data of a vm, the instance id is stable across the updates
+/// so cloud-init only runs the per instance modules once
+pub fn gen_meta_data(vm: &Vm) -> String {
+  let hostname = vm.spec.hostname.clone().unwrap_or(vm.spec.name.clone());
+  format!(
+    "instance-id: {}\nlocal-hostname: {hostname}\n",
+    vm.spec.vm_key
+  )
+}
+
+/// Path of the NoCloud seed of a vm, it's stored next to the images
+/// to be available inside the runtime container
+pub fn seed_path(vm_key: &str, state: &SystemState) -> String {
+  format!(
+    "{}/vms/images/{vm_key}.cidata.iso",
+    state.inner.config.state_dir
+  )
+}
+
+/// Read the documents of a `nanocl.io/cloud-init` secret
+async fn read_secret(name: &str, state: &SystemState) -> IoResult<VmCloudInit> {
+  let secret = SecretDb::transform_read_by_pk(name, &state.inner.pool).await?;
+  if secret.kind != SECRET_KIND {
+    return Err(IoError::invalid_input(
+      "CloudInit",
+      &format!("secret {name} isn't a {SECRET_KIND} secret"),
+    ));
+  }
+  let cloud_init = serde_json::from_value::<VmCloudInit>(secret.data)
+    .map_err(|err| err.map_err_context(|| "CloudInit"))?;
+  Ok(cloud_init)
+}
+
+/// Directory holding the documents of a vm while its seed is built
+fn documents_dir(vm_key: &str, state: &SystemState) -> String {
+  format!("{}/vms/cloud-init/{vm_key}", state.inner.config.state_dir)
+}
+
+/// Create the documents directory readable only by nanocld,
+/// a leftover of a previous build is removed first
+async fn create_documents_dir(dir: &str) -> IoResult<()> {
+  let _ = fs::remove_dir_all(dir).await;
+  if let Some(parent) = std::path::Path::new(dir).parent() {
+    fs::create_dir_all(parent)
+      .await
+      .map_err(|err| err.map_err_context(|| dir))?;
+  }
+  fs::DirBuilder::new()
+    .mode(0o700)
+    .create(dir)
+    .await
+    .map_err(|err| err.map_err_context(|| dir))?;
+  Ok(())
+}
+
+/// Write a document in the cloud-init directory of a vm and return its path.
+/// The documents may hold credentials so they're only readable by nanocld.
+async fn write_document(
+  dir: &str,
+  name: &str,
+  content: &str,
+) -> IoResult<String> {
+  let path = format!("{dir}/{name}");
+  let mut file = fs::OpenOptions::new()
+    .write(true)
+    .create(true)
+    .truncate(true)
+    .mode(0o600)
+    .open(&path)
+    .await
+    .map_err(|err| err.map_err_context(|| &path))?;
+  file
+    .write_all(content.as_bytes())
+    .await
+    .map_err(|err| err.map_err_context(|| &path))?;
+  Ok(path)
+}
+
+/// Build the seed iso with `cloud-localds` from the documents written in `dir`
+async fn build_seed(
+  dir: &str,
+  seed: &str,
+  cloud_init: &VmCloudInit,
+  vm: &Vm,
+) -> IoResult<()> {
+  let user_data = cloud_init
+    .user_data
+    .clone()
+    .unwrap_or("#cloud-config\n".into());
+  let meta_data = cloud_init.meta_data.clone().unwrap_or(gen_meta_data(vm));
+  let mut cmd = Command::new("cloud-localds");
+  if let Some(network_config) = &cloud_init.network_config {
+    cmd.arg("-N");
+    cmd.arg(write_document(dir, "network-config", network_config).await?);
+  }
+  if let Some(vendor_data) = &cloud_init.vendor_data {
+    cmd.arg("-V");
+    cmd.arg(write_document(dir, "vendor-data", vendor_data).await?);
+  }
+  cmd.arg(seed);
+  cmd.arg(write_document(dir, "user-data", &user_data).await?);
+  cmd.arg(write_document(dir, "meta-data", &meta_data).await?);
+  let output = cmd
+    .output()
+    .await
+    .map_err(|err| err.map_err_context(|| "cloud-localds"))?;
+  if !output.status.success() {
+    return Err(IoError::interrupted(
+      "CloudInit",
+      &format!(
+        "Unable to create the seed: {}",
+        String::from_utf8_lossy(&output.stderr)
+      ),
+    ));
+  }
+  fs::set_permissions(seed, std::fs::Permissions::from_mode(0o600))
+    .await
+    .map_err(|err| err.map_err_context(|| seed))?;
+  Ok(())
+}
+
+/// Render the cloud-init documents of a vm into a NoCloud seed iso
+/// using `cloud-localds`, returns the path of the seed when the vm has a cloud-init section
+pub async fn create_seed(
+  vm: &Vm,
+  state: &SystemState,
+) -> IoResult<Option<String>> {
+  let Some(cloud_init) = &vm.spec.cloud_init else {
+    return Ok(None);
+  };
+  let secret = match &cloud_init.secret {
+    Some(name) => Some(read_secret(name, state).await?),
+    None => None,
+  };
+  let cloud_init = merge(cloud_init, secret);
+  let dir = documents_dir(&vm.spec.vm_key, state);
+  create_documents_dir(&dir).await?;
+  let seed = seed_path(&vm.spec.vm_key, state);
+  let res = build_seed(&dir, &seed, &cloud_init, vm).await;
+  // The documents are only needed to build the seed
+  let _ = fs::remove_dir_all(&dir).await;
+  res?;
+  Ok(Some(seed))
+}
+
+/// Remove the seed and the documents of a vm
+pub async fn delete_seed(vm_key: &str, state: &SystemState) {
+  let _ = fs::remove_file(seed_path(vm_key, state)).await;
+  let _ = fs::remove_dir_all(documents_dir(vm_key, state)).await;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn documents() {
+    let cloud_init = VmCloudInit {
+      user_data: Some("#cloud-config\nhostname: inline\n".to_owned()),
+      secret: Some("vm-init".to_owned()),
+      ..Default::default()
+    };
+    let secret = VmCloudInit {
+      user_data: Some("#cloud-config\nhostname: secret\n".to_owned()),
+      network_config: Some("version: 2\n".to_owned()),
+      ..Default::default()
+    };
+    let merged = merge(&cloud_init, Some(secret));
+    assert_eq!(
+      merged.user_data.as_deref(),
+      Some("#cloud-config\nhostname: inline\n")
+    );
+    assert_eq!(merged.network_config.as_deref(), Some("version: 2\n"));
+    assert_eq!(merged.meta_data, None);
+    assert_eq!(merged.secret, None);
+    let mut vm = Vm::default();
+    vm.spec.name = "my-vm".to_owned();
+    vm.spec.vm_key = "my-vm.global".to_owned();
+    assert_eq!(
+      gen_meta_data(&vm),
+      "instance-id: my-vm.global\nlocal-hostname: my-vm\n"
+    );
+  }
+}
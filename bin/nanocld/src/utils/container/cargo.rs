@@ -8,21 +8,33 @@ use bollard_next::{
   },
   secret::{HostConfig, RestartPolicy, RestartPolicyNameEnum},
 };
-use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_error::{
+  http::HttpResult,
+  io::{FromIo, IoError, IoResult},
+};
 use nanocl_stubs::{
   cargo::Cargo,
-  cargo_spec::ReplicationMode,
-  generic::{GenericClause, GenericFilter},
+  cargo_spec::{CargoPort, ReplicationMode},
+  generic::{GenericClause, GenericFilter, NetworkKind},
   process::{Process, ProcessKind},
+  proxy::{
+    ProxyRule, ProxyRuleStream, ResourceProxyRule, StreamTarget, UpstreamTarget,
+  },
   system::{NativeEventAction, ObjPsStatusKind},
 };
 
 use crate::{
-  models::{CargoDb, ObjPsStatusDb, ProcessDb, SystemState},
+  models::{CargoDb, NamespaceDb, ObjPsStatusDb, ProcessDb, SystemState},
   repositories::generic::*,
-  utils,
+  utils, vars,
 };
 
+/// Attempts to sync the ports of a cargo when ncproxy was unreachable
+const SYNC_PORTS_RETRIES: usize = 10;
+/// Delay between two attempts to sync the ports of a cargo
+const SYNC_PORTS_INTERVAL: std::time::Duration =
+  std::time::Duration::from_secs(6);
+
 fn create_cargo_env(
   cargo: &Cargo,
   secret_envs: Vec<String>,
@@ -53,6 +65,8 @@ async fn create_init_container(
     .clone()
     .unwrap_or(cargo.spec.container.image.clone().unwrap());
   let host_config = init_container.host_config.unwrap_or_default();
+  let network =
+    NamespaceDb::get_network(&cargo.namespace_name, &state.inner.pool).await?;
   init_container.image = Some(image.clone());
   let secret_dir = utils::secret::create_tls_secrets(
     &cargo.spec.cargo_key,
@@ -66,9 +80,7 @@ async fn create_init_container(
   binds.push(format!("{}:/opt/nanocl.io/secrets", secret_dir));
   init_container.host_config = Some(HostConfig {
     binds: Some(binds),
-    network_mode: Some(
-      host_config.network_mode.unwrap_or("nanoclbr0".to_owned()),
-    ),
+    network_mode: Some(host_config.network_mode.unwrap_or(network)),
     ..host_config
   });
   super::image::download(
@@ -159,7 +171,8 @@ pub async fn create(
   state: &SystemState,
 ) -> IoResult<Vec<Process>> {
   let data = serde_json::to_string(&cargo)?;
-  let new_data = super::generic::inject_data(&data, state).await?;
+  let new_data =
+    super::generic::inject_data(&data, &cargo.namespace_name, state).await?;
   let cargo = &serde_json::from_str::<Cargo>(&new_data)?;
   super::image::download(
     &cargo.spec.container.image.clone().unwrap_or_default(),
@@ -178,12 +191,21 @@ pub async fn create(
     state,
   )
   .await?;
+  let config_binds = utils::cargo_config::render(cargo, number, state).await?;
+  let volume_binds =
+    utils::volume::gen_binds(&cargo.spec.volumes, &cargo.namespace_name, state)
+      .await?;
+  let network =
+    NamespaceDb::get_network(&cargo.namespace_name, &state.inner.pool).await?;
   let instances = (0..number)
     .collect::<Vec<usize>>()
     .into_iter()
     .map(move |current| {
       let env_secrets = env_secrets.clone();
+      let config_binds = config_binds[current].clone();
+      let volume_binds = volume_binds.clone();
       let secret_dir = secret_dir.clone();
+      let network = network.clone();
       async move {
         let ordinal_index = if current > 0 {
           current.to_string()
@@ -229,6 +251,10 @@ pub async fn create(
         // mount the secret directory to the container
         let mut binds = host_config.binds.clone().unwrap_or_default();
         binds.push(format!("{}:/opt/nanocl.io/secrets", secret_dir));
+        // mount the rendered configs of the instance
+        binds.extend(config_binds);
+        // mount the named volumes
+        binds.extend(volume_binds);
         let new_process = bollard_next::container::Config {
           attach_stderr: Some(true),
           attach_stdout: Some(true),
@@ -238,20 +264,22 @@ pub async fn create(
           env: Some(env),
           host_config: Some(HostConfig {
             restart_policy,
-            network_mode: Some("nanoclbr0".to_owned()),
+            network_mode: Some(network),
             binds: Some(binds),
             ..host_config
           }),
           ..container
         };
-        super::process::create(
+        let process = super::process::create(
           &ProcessKind::Cargo,
           &name,
           &cargo.spec.cargo_key,
           &new_process,
           state,
         )
-        .await
+        .await?;
+        utils::overlay::connect(&process.key, state).await?;
+        Ok(process)
       }
     })
     .collect::<FuturesUnordered<_>>()
@@ -262,10 +290,113 @@ pub async fn create(
   Ok(instances)
 }
 
+/// Name of the `ncproxy.io/rule` resource publishing the ports of a cargo
+fn ports_resource_name(key: &str) -> String {
+  format!("{key}.ports")
+}
+
+/// Proxy rule publishing the ports of a cargo on the nodes
+fn gen_ports_rule(
+  key: &str,
+  ports: &Option<Vec<CargoPort>>,
+) -> ResourceProxyRule {
+  let rules = ports
+    .iter()
+    .flatten()
+    .map(|port| {
+      ProxyRule::Stream(ProxyRuleStream {
+        network: port.network.clone().unwrap_or(NetworkKind::All),
+        protocol: port.get_protocol(),
+        port: port.get_host_port(),
+        ssl: None,
+        target: StreamTarget::Upstream(UpstreamTarget {
+          key: format!("{key}.c"),
+          port: port.container_port,
+          path: None,
+          disable_logging: None,
+          ssl: None,
+        }),
+      })
+    })
+    .collect();
+  ResourceProxyRule { rules }
+}
+
+/// Owner of the resources managed for a cargo
+fn resource_owner(key: &str) -> utils::resource::ResourceOwner {
+  utils::resource::ResourceOwner {
+    label: "io.nanocl.c",
+    key,
+  }
+}
+
+/// Create, update or delete the `ncproxy.io/rule` resource owned by the cargo
+/// to match the ports it publishes
+pub async fn sync_ports(
+  key: &str,
+  ports: &Option<Vec<CargoPort>>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let rule = gen_ports_rule(key, ports);
+  let data = if rule.rules.is_empty() {
+    None
+  } else {
+    Some(utils::resource::to_data(&rule)?)
+  };
+  utils::resource::sync_owned(
+    &ports_resource_name(key),
+    vars::PROXY_RULE_KIND,
+    &resource_owner(key),
+    data,
+    state,
+  )
+  .await
+}
+
+/// Sync the ports of a cargo, a rejected rule like a port already published
+/// is an error while an unreachable or failing ncproxy is logged
+/// and the sync is retried in background.
+pub async fn ensure_ports(
+  key: &str,
+  ports: &Option<Vec<CargoPort>>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let err = match sync_ports(key, ports, state).await {
+    Ok(_) => return Ok(()),
+    Err(err) if err.status.is_client_error() => return Err(err),
+    Err(err) => err,
+  };
+  let key = key.to_owned();
+  log::warn!("cargo::ensure_ports: {key}: {err}, retrying in background");
+  let state = state.clone();
+  rt::spawn(async move {
+    for _ in 0..SYNC_PORTS_RETRIES {
+      ntex::time::sleep(SYNC_PORTS_INTERVAL).await;
+      // The cargo is read again since it may be updated or deleted meanwhile
+      let Ok(cargo) =
+        CargoDb::transform_read_by_pk(&key, &state.inner.pool).await
+      else {
+        return;
+      };
+      match sync_ports(&key, &cargo.spec.ports, &state).await {
+        Ok(_) => return,
+        Err(err) if err.status.is_client_error() => {
+          log::error!("cargo::ensure_ports: {key}: {err}");
+          return;
+        }
+        Err(err) => log::warn!("cargo::ensure_ports: {key}: {err}"),
+      }
+    }
+    log::error!("cargo::ensure_ports: {key}: unable to sync the ports");
+  });
+  Ok(())
+}
+
 /// Start cargo instances
 ///
 pub async fn start(key: &str, state: &SystemState) -> IoResult<()> {
   let cargo = CargoDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  ensure_ports(&cargo.spec.cargo_key, &cargo.spec.ports, state).await?;
   let filter = GenericFilter::new().r#where(
     "data",
     GenericClause::Contains(serde_json::json!({
@@ -336,6 +467,7 @@ pub async fn start(key: &str, state: &SystemState) -> IoResult<()> {
 ///
 pub async fn update(key: &str, state: &SystemState) -> IoResult<()> {
   let cargo = CargoDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  ensure_ports(&cargo.spec.cargo_key, &cargo.spec.ports, state).await?;
   let processes =
     ProcessDb::read_by_kind_key(key, None, &state.inner.pool).await?;
   // rename old instances to flag them for deletion
@@ -479,6 +611,18 @@ pub async fn delete(key: &str, state: &SystemState) -> IoResult<()> {
       .await;
   }
   let cargo = CargoDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  if let Err(err) = utils::resource::sync_owned(
+    &ports_resource_name(key),
+    vars::PROXY_RULE_KIND,
+    &resource_owner(key),
+    None,
+    state,
+  )
+  .await
+  {
+    log::warn!("Unable to delete the ports of cargo {key}: {err}");
+  }
+  utils::cargo_config::delete(key, state).await;
   CargoDb::clear_by_pk(key, &state.inner.pool).await?;
   state
     .emit_normal_native_action_sync(&cargo, NativeEventAction::Destroy)
@@ -7,8 +7,8 @@ use nanocl_stubs::{
 
 use crate::{
   models::{
-    CargoDb, JobDb, JobUpdateDb, ObjPsStatusDb, ObjPsStatusUpdate, SystemState,
-    VmDb,
+    CargoDb, JobDb, JobUpdateDb, NamespaceDb, ObjPsStatusDb, ObjPsStatusUpdate,
+    SystemState, VmDb,
   },
   repositories::generic::*,
 };
@@ -149,21 +149,23 @@ pub async fn emit_stopping(
 }
 
 /// Inject internal data into the payload
-/// eg: $$INTERNAL_GATEWAY
+/// eg: $$INTERNAL_GATEWAY the gateway of the network of the namespace
 ///
 pub async fn inject_data(
   payload: &str,
+  namespace: &str,
   state: &SystemState,
 ) -> IoResult<String> {
+  let name = NamespaceDb::get_network(namespace, &state.inner.pool).await?;
   let network_gateway = state
     .inner
     .docker_api
-    .inspect_network("nanoclbr0", None::<InspectNetworkOptions<String>>)
+    .inspect_network(&name, None::<InspectNetworkOptions<String>>)
     .await
     .map_err(|err| {
       IoError::interrupted(
         "Network",
-        &format!("Unable to inspect network nanoclbr0 {err}"),
+        &format!("Unable to inspect network {name} {err}"),
       )
     })?;
   let ipam = network_gateway.ipam.unwrap_or_default();
@@ -171,7 +173,7 @@ pub async fn inject_data(
   let Some(network) = ipam_config.first() else {
     return Err(IoError::invalid_data(
       "Network",
-      "No network found for nanoclbr0",
+      &format!("No network found for {name}"),
     ));
   };
   let gateway_addr = network.gateway.clone().unwrap_or_default();
@@ -16,7 +16,7 @@ use crate::{
 
 /// Get the docker credentials to authenticate with the registry from the secret
 ///
-async fn get_credentials(
+pub async fn get_credentials(
   secret: Option<String>,
   state: &SystemState,
 ) -> IoResult<Option<DockerCredentials>> {
@@ -12,7 +12,7 @@ use nanocl_stubs::{
 };
 
 use crate::{
-  models::{JobDb, ObjPsStatusDb, ProcessDb, SystemState},
+  models::{JobDb, NamespaceDb, ObjPsStatusDb, ProcessDb, SystemState},
   repositories::generic::*,
   utils,
 };
@@ -49,10 +49,10 @@ async fn create_instance(
   let host_config = container.host_config.unwrap_or_default();
   let mut binds = host_config.binds.clone().unwrap_or_default();
   binds.push(format!("{}/:/opt/nanocl.io/secrets", secret_dir));
+  binds.extend(utils::volume::gen_binds(&job.volumes, "global", state).await?);
+  let network = NamespaceDb::get_network("global", &state.inner.pool).await?;
   container.host_config = Some(HostConfig {
-    network_mode: Some(
-      host_config.network_mode.unwrap_or("nanoclbr0".to_owned()),
-    ),
+    network_mode: Some(host_config.network_mode.unwrap_or(network)),
     binds: Some(binds),
     ..host_config
   });
@@ -15,6 +15,7 @@ use nanocl_stubs::{
 use crate::{
   models::{ObjPsStatusDb, ProcessDb, SystemState},
   repositories::generic::*,
+  utils,
 };
 
 /// Create a process (container) based on the kind and the item
@@ -188,6 +189,12 @@ pub async fn stop_instances(
   let processes =
     ProcessDb::read_by_kind_key(kind_pk, None, &state.inner.pool).await?;
   log::debug!("stop_process_by_kind_pk: {kind_pk}");
+  // Give the guest a chance to power off cleanly before killing qemu
+  if *kind == ProcessKind::Vm {
+    if let Err(err) = utils::vm_control::shutdown(kind_pk, state).await {
+      log::warn!("Unable to power off vm {kind_pk}: {err}");
+    }
+  }
   for process in processes {
     state
       .inner
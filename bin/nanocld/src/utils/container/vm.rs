@@ -2,26 +2,89 @@ use std::collections::HashMap;
 
 use bollard_next::secret::{DeviceMapping, HostConfig};
 
-use nanocl_error::io::IoResult;
+use nanocl_error::io::{FromIo, IoResult};
 use nanocl_stubs::{
   generic::ImagePullPolicy,
   process::{Process, ProcessKind},
   system::NativeEventAction,
   vm::Vm,
+  vm_spec::{VmDataDisk, VmDiskBus, VmHostConfig},
 };
 
 use crate::{
-  models::{ProcessDb, SystemState, VmDb, VmImageDb},
+  models::{NamespaceDb, ProcessDb, SystemState, VmDb, VmImageDb},
   repositories::generic::*,
   utils, vars,
 };
 
+/// Number of memory modules that can be hotplugged in a VM
+const MEMORY_SLOTS: u64 = 8;
+
+/// Generate the qemu arguments attaching the data disks of a VM.
+/// The scsi disks share a single virtio-scsi controller.
+fn gen_disk_args(disks: &[VmDataDisk], images: &[VmImageDb]) -> Vec<String> {
+  let mut args = Vec::new();
+  let mut has_scsi = false;
+  for (i, (disk, image)) in disks.iter().zip(images).enumerate() {
+    let bus = disk.bus.clone().unwrap_or_default();
+    let mut drive = format!("file={},format={}", image.path, image.format);
+    match bus {
+      VmDiskBus::Scsi => drive.push_str(&format!(",if=none,id=disk{i}")),
+      _ => drive.push_str(&format!(",if={bus}")),
+    }
+    if let Some(cache) = &disk.cache {
+      drive.push_str(&format!(",cache={cache}"));
+    }
+    if disk.read_only.unwrap_or_default() {
+      drive.push_str(",readonly=on");
+    }
+    if bus == VmDiskBus::Scsi && !has_scsi {
+      has_scsi = true;
+      args.push("-device".into());
+      args.push("virtio-scsi-pci,id=scsi0".into());
+    }
+    args.push("-drive".into());
+    args.push(drive);
+    if bus == VmDiskBus::Scsi {
+      args.push("-device".into());
+      args.push(format!("scsi-hd,drive=disk{i},bus=scsi0.0"));
+    }
+  }
+  args
+}
+
+/// Generate the cpu topology of a vm, leaving room for hotplugged cpus
+fn gen_smp_arg(host_config: &VmHostConfig) -> String {
+  let cpu = host_config.cpu.max(1);
+  match host_config.max_cpu {
+    Some(max_cpu) if max_cpu > cpu => format!("{cpu},maxcpus={max_cpu}"),
+    _ => cpu.to_string(),
+  }
+}
+
+/// Generate the memory of a vm, leaving slots for hotplugged modules
+fn gen_memory_arg(host_config: &VmHostConfig) -> String {
+  let memory = if host_config.memory > 0 {
+    host_config.memory
+  } else {
+    512
+  };
+  match host_config.max_memory {
+    Some(max_memory) if max_memory > memory => {
+      format!("{memory}M,slots={MEMORY_SLOTS},maxmem={max_memory}M")
+    }
+    _ => format!("{memory}M"),
+  }
+}
+
 /// Create a VM instance
 ///
+/// An incoming VM waits paused for its state to be migrated from another node.
 pub async fn create_instance(
   vm: &Vm,
   image: &VmImageDb,
   disable_keygen: bool,
+  incoming: bool,
   state: &SystemState,
 ) -> IoResult<Process> {
   let mut labels: HashMap<String, String> = HashMap::new();
@@ -30,7 +93,24 @@ pub async fn create_instance(
   labels.insert("io.nanocl.n".to_owned(), vm.namespace_name.clone());
   let mut args: Vec<String> =
     vec!["-hda".into(), image.path.clone(), "--nographic".into()];
+  let disks = vm.spec.disks.clone().unwrap_or_default();
+  let images =
+    utils::vm_image::sync_disks(&vm.spec.vm_key, &disks, None, state).await?;
+  args.extend(gen_disk_args(&disks, &images));
+  let run_dir = utils::qmp::run_dir(state);
+  tokio::fs::create_dir_all(&run_dir)
+    .await
+    .map_err(|err| err.map_err_context(|| &run_dir))?;
+  args.push("-qmp".into());
+  args.push(utils::qmp::gen_arg(&vm.spec.vm_key, state));
+  if incoming {
+    args.extend(["-incoming".into(), "defer".into(), "-S".into()]);
+  }
   let host_config = vm.spec.host_config.clone();
+  if host_config.vnc.unwrap_or_default() {
+    args.push("-vnc".into());
+    args.push(utils::vnc::gen_arg(&vm.spec.vm_key, state));
+  }
   let kvm = host_config.kvm.unwrap_or_default();
   let mut devices = vec![DeviceMapping {
     path_on_host: Some("/dev/net/tun".into()),
@@ -47,19 +127,10 @@ pub async fn create_instance(
     });
     log::debug!("KVM enabled /dev/kvm mapped");
   }
-  let cpu = host_config.cpu;
-  let cpu = if cpu > 0 { cpu.to_string() } else { "1".into() };
-  let cpu = cpu.clone();
   args.push("-smp".into());
-  args.push(cpu.clone());
-  let memory = host_config.memory;
-  let memory = if memory > 0 {
-    format!("{memory}M")
-  } else {
-    "512M".into()
-  };
+  args.push(gen_smp_arg(&host_config));
   args.push("-m".into());
-  args.push(memory);
+  args.push(gen_memory_arg(&host_config));
   let mut envs: Vec<String> = Vec::new();
   let net_iface = vm
     .spec
@@ -76,14 +147,22 @@ pub async fn create_instance(
   envs.push(format!("DEFAULT_INTERFACE={link_net_iface}"));
   envs.push(format!("FROM_NETWORK={net_iface}"));
   envs.push(format!("DELETE_SSH_KEY={disable_keygen}"));
-  if let Some(user) = &vm.spec.user {
-    envs.push(format!("USER={user}"));
-  }
-  if let Some(password) = &vm.spec.password {
-    envs.push(format!("PASSWORD={password}"));
-  }
-  if let Some(ssh_key) = &vm.spec.ssh_key {
-    envs.push(format!("SSH_KEY={ssh_key}"));
+  match utils::cloud_init::create_seed(vm, state).await? {
+    Some(seed) => {
+      args.push("-cdrom".into());
+      args.push(seed);
+    }
+    None => {
+      if let Some(user) = &vm.spec.user {
+        envs.push(format!("USER={user}"));
+      }
+      if let Some(password) = &vm.spec.password {
+        envs.push(format!("PASSWORD={password}"));
+      }
+      if let Some(ssh_key) = &vm.spec.ssh_key {
+        envs.push(format!("SSH_KEY={ssh_key}"));
+      }
+    }
   }
   let image = match &vm.spec.host_config.runtime {
     Some(runtime) => runtime.to_owned(),
@@ -97,6 +176,9 @@ pub async fn create_instance(
     state,
   )
   .await?;
+  let network =
+    NamespaceDb::get_network(&vm.namespace_name, &state.inner.pool).await?;
+  let runtime_network = vm.spec.host_config.runtime_network.clone();
   let spec = bollard_next::container::Config {
     image: Some(image),
     tty: Some(true),
@@ -109,14 +191,11 @@ pub async fn create_instance(
     attach_stdout: Some(true),
     open_stdin: Some(true),
     host_config: Some(HostConfig {
-      network_mode: Some(
-        vm.spec
-          .host_config
-          .runtime_network
-          .clone()
-          .unwrap_or("nanoclbr0".to_owned()),
-      ),
-      binds: Some(vec![format!("{img_path}:{img_path}")]),
+      network_mode: Some(runtime_network.clone().unwrap_or(network)),
+      binds: Some(vec![
+        format!("{img_path}:{img_path}"),
+        format!("{run_dir}:{run_dir}"),
+      ]),
       devices: Some(devices),
       cap_add: Some(vec!["NET_ADMIN".into()]),
       ..Default::default()
@@ -132,6 +211,10 @@ pub async fn create_instance(
     state,
   )
   .await?;
+  // A custom runtime network is left as is, it may not be a bridge
+  if runtime_network.is_none() {
+    utils::overlay::connect(&process.key, state).await?;
+  }
   Ok(process)
 }
 
@@ -145,7 +228,7 @@ pub async fn start(key: &str, state: &SystemState) -> IoResult<()> {
     ProcessDb::read_by_kind_key(&vm.spec.vm_key, None, &state.inner.pool)
       .await?;
   if processes.is_empty() {
-    create_instance(&vm, &image, true, state).await?;
+    create_instance(&vm, &image, true, false, state).await?;
   }
   super::process::start_instances(&vm.spec.vm_key, &ProcessKind::Vm, state)
     .await?;
@@ -167,6 +250,14 @@ pub async fn delete(key: &str, state: &SystemState) -> IoResult<()> {
   )
   .await?;
   utils::vm_image::delete_by_pk(&vm.spec.disk.image, state).await?;
+  utils::vm_image::sync_disks(&vm.spec.vm_key, &[], None, state).await?;
+  utils::cloud_init::delete_seed(&vm.spec.vm_key, state).await;
+  let _ =
+    tokio::fs::remove_file(utils::qmp::socket_path(&vm.spec.vm_key, state))
+      .await;
+  let _ =
+    tokio::fs::remove_file(utils::vnc::socket_path(&vm.spec.vm_key, state))
+      .await;
   VmDb::clear_by_pk(&vm.spec.vm_key, &state.inner.pool).await?;
   state
     .emit_normal_native_action_sync(&vm, NativeEventAction::Destroy)
@@ -181,8 +272,86 @@ pub async fn update(key: &str, state: &SystemState) -> IoResult<()> {
   let container_name = format!("{}.v", &vm.spec.vm_key);
   let image =
     VmImageDb::read_by_pk(&vm.spec.disk.image, &state.inner.pool).await?;
+  // Grow the disks of a running vm live, qemu-img can't resize them
+  // while qemu holds their lock
+  if let Ok(mut qmp) = utils::qmp::connect_vm(&vm, state).await {
+    let disks = vm.spec.disks.clone().unwrap_or_default();
+    utils::vm_image::sync_disks(&vm.spec.vm_key, &disks, Some(&mut qmp), state)
+      .await?;
+  }
   super::process::delete_instances(&[container_name], state).await?;
-  create_instance(&vm, &image, false, state).await?;
+  create_instance(&vm, &image, false, false, state).await?;
   super::process::start_instances(key, &ProcessKind::Vm, state).await?;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::vm_spec::VmDiskCache;
+
+  use super::*;
+
+  #[test]
+  fn disk_args() {
+    let disks = vec![
+      VmDataDisk {
+        name: "data".to_owned(),
+        cache: Some(VmDiskCache::Writeback),
+        ..Default::default()
+      },
+      VmDataDisk {
+        name: "logs".to_owned(),
+        bus: Some(VmDiskBus::Scsi),
+        read_only: Some(true),
+        ..Default::default()
+      },
+      VmDataDisk {
+        name: "legacy".to_owned(),
+        bus: Some(VmDiskBus::Ide),
+        ..Default::default()
+      },
+    ];
+    let images = disks
+      .iter()
+      .map(|disk| VmImageDb {
+        name: format!("my-vm.global.disk.{}", disk.name),
+        node_name: "node".to_owned(),
+        created_at: chrono::Utc::now().naive_utc(),
+        kind: "Volume".to_owned(),
+        path: format!("/images/{}.img", disk.name),
+        format: "qcow2".to_owned(),
+        size_actual: 0,
+        size_virtual: 0,
+        parent: None,
+      })
+      .collect::<Vec<_>>();
+    assert_eq!(
+      gen_disk_args(&disks, &images),
+      vec![
+        "-drive",
+        "file=/images/data.img,format=qcow2,if=virtio,cache=writeback",
+        "-device",
+        "virtio-scsi-pci,id=scsi0",
+        "-drive",
+        "file=/images/logs.img,format=qcow2,if=none,id=disk1,readonly=on",
+        "-device",
+        "scsi-hd,drive=disk1,bus=scsi0.0",
+        "-drive",
+        "file=/images/legacy.img,format=qcow2,if=ide",
+      ]
+    );
+  }
+
+  #[test]
+  fn hotplug_args() {
+    let mut host_config = VmHostConfig::default();
+    assert_eq!(gen_smp_arg(&host_config), "1");
+    assert_eq!(gen_memory_arg(&host_config), "512M");
+    host_config.cpu = 2;
+    host_config.max_cpu = Some(4);
+    host_config.memory = 1024;
+    host_config.max_memory = Some(4096);
+    assert_eq!(gen_smp_arg(&host_config), "2,maxcpus=4");
+    assert_eq!(gen_memory_arg(&host_config), "1024M,slots=8,maxmem=4096M");
+  }
+}
@@ -112,6 +112,57 @@ impl CtrlClient {
     self.res_json(&mut res).await
   }
 
+  /// Call validate rule method on controller
+  /// Controllers that doesn't implement validation are ignored
+  pub async fn validate_rule(
+    &self,
+    version: &str,
+    name: &str,
+    data: &serde_json::Value,
+  ) -> Result<(), HttpClientError> {
+    let url = self.format_url(&format!("/{version}/rules/{name}/validate"));
+    log::debug!("CtrlClient::validate_rule url: {}", url);
+    let mut res = self
+      .client
+      .post(url)
+      .send_json(data)
+      .await
+      .map_err(|err| err.map_err_context(|| self.name.to_owned()))?;
+    let status = res.status();
+    if status == StatusCode::NOT_FOUND {
+      log::debug!("CtrlClient::validate_rule {}: not supported", self.name);
+      return Ok(());
+    }
+    self.is_api_error(&mut res, &status).await?;
+    Ok(())
+  }
+
+  /// Read the json returned by a route of the controller
+  pub async fn get_json<Q, T>(
+    &self,
+    version: &str,
+    path: &str,
+    query: &Q,
+  ) -> Result<T, HttpClientError>
+  where
+    Q: serde::Serialize,
+    T: serde::de::DeserializeOwned,
+  {
+    let url = self.format_url(&format!("/{version}{path}"));
+    log::debug!("CtrlClient::get_json url: {}", url);
+    let mut res = self
+      .client
+      .get(url)
+      .query(query)
+      .map_err(|err| err.map_err_context(|| self.name.to_owned()))?
+      .send()
+      .await
+      .map_err(|err| err.map_err_context(|| self.name.to_owned()))?;
+    let status = res.status();
+    self.is_api_error(&mut res, &status).await?;
+    self.res_json(&mut res).await
+  }
+
   /// Call delete rule method on controller
   pub async fn delete_rule(
     &self,
@@ -0,0 +1,329 @@
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoResult},
+};
+use nanocl_stubs::{
+  dns::{DnsEntry, ResourceDnsRule},
+  generic::{GenericClause, GenericFilter},
+  ingress::{IngressTlsMode, ResourceIngress},
+  proxy::{
+    HttpTarget, LocationTarget, ProxyHttpLocation, ProxyRule, ProxyRuleHttp,
+    ProxySsl, ResourceProxyRule, UpstreamTarget, UrlRedirect,
+  },
+};
+
+use crate::{
+  models::{ResourceDb, ResourceStatusDb, SystemState},
+  repositories::generic::*,
+  utils::resource::{self, ResourceOwner},
+  vars,
+};
+
+/// Metadata label holding the ingress owning a resource
+const OWNER_LABEL: &str = "io.nanocl.ingress";
+
+/// Name of the `ncproxy.io/rule` resource of an ingress
+fn proxy_name(name: &str) -> String {
+  format!("{name}.proxy")
+}
+
+/// Name of the `ncdns.io/rule` resource of an ingress
+fn dns_name(name: &str) -> String {
+  format!("{name}.dns")
+}
+
+/// Create an empty location, only the path and the target are set
+fn new_location(path: &str, target: LocationTarget) -> ProxyHttpLocation {
+  ProxyHttpLocation {
+    path: path.to_owned(),
+    target,
+    limit_req: None,
+    allowed_ips: None,
+    headers: None,
+    version: None,
+    strip_prefix: None,
+    rewrites: None,
+    response_headers: None,
+    timeouts: None,
+    client_max_body_size: None,
+    websocket: None,
+    cors: None,
+    basic_auth: None,
+  }
+}
+
+/// Generate the proxy rules serving the paths of an ingress.
+/// With tls a second rule listens for http depending on the tls mode.
+pub fn gen_proxy_rule(ingress: &ResourceIngress) -> ResourceProxyRule {
+  let locations = ingress
+    .paths
+    .iter()
+    .map(|path| {
+      let target = LocationTarget::Upstream(UpstreamTarget {
+        key: format!("{}.c", path.cargo),
+        port: path.port,
+        path: None,
+        disable_logging: None,
+        ssl: None,
+      });
+      ProxyHttpLocation {
+        strip_prefix: path.strip_prefix,
+        ..new_location(&path.path, target)
+      }
+    })
+    .collect::<Vec<_>>();
+  let http_rule = |locations, ssl| {
+    ProxyRule::Http(ProxyRuleHttp {
+      domain: Some(ingress.host.clone()),
+      port: None,
+      network: ingress.network.clone(),
+      limit_req_zone: None,
+      locations,
+      ssl,
+      includes: None,
+    })
+  };
+  let Some(tls) = &ingress.tls else {
+    return ResourceProxyRule {
+      rules: vec![http_rule(locations, None)],
+    };
+  };
+  let ssl = Some(ProxySsl::Secret(tls.secret.clone()));
+  let mut rules = vec![http_rule(locations.clone(), ssl)];
+  match tls.mode.clone().unwrap_or_default() {
+    IngressTlsMode::Redirect => {
+      let target = LocationTarget::Http(HttpTarget {
+        url: format!("https://{}$request_uri", ingress.host),
+        redirect: Some(UrlRedirect::MovedPermanently),
+        tls: None,
+        keepalive: None,
+        health: None,
+      });
+      rules.push(http_rule(vec![new_location("/", target)], None));
+    }
+    IngressTlsMode::Both => rules.push(http_rule(locations, None)),
+    IngressTlsMode::Strict => {}
+  }
+  ResourceProxyRule { rules }
+}
+
+/// Generate the dns rule resolving the host of an ingress
+pub fn gen_dns_rule(ingress: &ResourceIngress) -> Option<ResourceDnsRule> {
+  let network = ingress.dns.clone()?;
+  Some(ResourceDnsRule {
+    network,
+    namespace: None,
+    entries: vec![DnsEntry {
+      name: ingress.host.clone(),
+      ip_address: ingress.network.clone(),
+    }],
+    records: None,
+  })
+}
+
+/// Validate the data of an ingress resource
+pub fn validate(data: &serde_json::Value) -> HttpResult<()> {
+  let ingress = serde_json::from_value::<ResourceIngress>(data.clone())
+    .map_err(|err| HttpError::bad_request(format!("Invalid ingress {err}")))?;
+  ingress
+    .validate()
+    .map_err(|err| HttpError::bad_request(format!("Invalid ingress {err}")))?;
+  Ok(())
+}
+
+/// Create or update the proxy and dns rules owned by an ingress.
+/// Returns a summary of the resources applied.
+pub async fn apply(name: &str, state: &SystemState) -> IoResult<String> {
+  let resource =
+    ResourceDb::transform_read_by_pk(name, &state.inner.pool).await?;
+  let ingress = serde_json::from_value::<ResourceIngress>(resource.spec.data)
+    .map_err(|err| err.map_err_context(|| "Ingress"))?;
+  let owner = ResourceOwner {
+    label: OWNER_LABEL,
+    key: name,
+  };
+  let proxy = proxy_name(name);
+  resource::sync_owned(
+    &proxy,
+    vars::PROXY_RULE_KIND,
+    &owner,
+    Some(resource::to_data(&gen_proxy_rule(&ingress))?),
+    state,
+  )
+  .await?;
+  let dns = dns_name(name);
+  let dns_rule = gen_dns_rule(&ingress)
+    .map(|rule| resource::to_data(&rule))
+    .transpose()?;
+  let has_dns = dns_rule.is_some();
+  resource::sync_owned(&dns, vars::DNS_RULE_KIND, &owner, dns_rule, state)
+    .await?;
+  if has_dns {
+    return Ok(format!("{proxy} and {dns} applied"));
+  }
+  Ok(format!("{proxy} applied"))
+}
+
+/// Delete the proxy and dns rules owned by an ingress
+pub async fn remove(name: &str, state: &SystemState) -> IoResult<()> {
+  let owner = ResourceOwner {
+    label: OWNER_LABEL,
+    key: name,
+  };
+  resource::sync_owned(
+    &proxy_name(name),
+    vars::PROXY_RULE_KIND,
+    &owner,
+    None,
+    state,
+  )
+  .await?;
+  resource::sync_owned(
+    &dns_name(name),
+    vars::DNS_RULE_KIND,
+    &owner,
+    None,
+    state,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Apply an ingress and save the result as its status,
+/// apart from its spec so the metadata of the user isn't altered
+pub async fn sync(name: &str, state: &SystemState) -> IoResult<String> {
+  let res = apply(name, state).await;
+  let (status, message) = match &res {
+    Ok(note) => ("Applied", note.clone()),
+    Err(err) => ("Failed", err.to_string()),
+  };
+  let status = ResourceStatusDb {
+    resource_key: name.to_owned(),
+    status: status.to_owned(),
+    message,
+    updated_at: chrono::Utc::now().naive_utc(),
+  };
+  if let Err(err) = ResourceStatusDb::save(status, &state.inner.pool).await {
+    log::warn!("ingress::sync: {name}: {err}");
+  }
+  res
+}
+
+/// Apply every ingress and delete the rules of the ingresses destroyed
+/// while nanocld wasn't running, it's called at startup
+pub async fn reconcile(state: &SystemState) -> IoResult<()> {
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(vars::INGRESS_KIND.to_owned()));
+  let ingresses =
+    ResourceDb::transform_read_all(&filter, &state.inner.pool).await?;
+  for ingress in &ingresses {
+    let name = &ingress.spec.resource_key;
+    if let Err(err) = sync(name, state).await {
+      log::warn!("ingress::reconcile: {name}: {err}");
+    }
+  }
+  let filter = GenericFilter::new()
+    .r#where("metadata", GenericClause::HasKey(OWNER_LABEL.to_owned()));
+  for resource in
+    ResourceDb::transform_read_all(&filter, &state.inner.pool).await?
+  {
+    let Some(owner) = resource
+      .spec
+      .metadata
+      .as_ref()
+      .and_then(|metadata| metadata.get(OWNER_LABEL))
+      .and_then(|owner| owner.as_str())
+    else {
+      continue;
+    };
+    if ingresses
+      .iter()
+      .any(|ingress| ingress.spec.resource_key == owner)
+    {
+      continue;
+    }
+    let owner = ResourceOwner {
+      label: OWNER_LABEL,
+      key: owner,
+    };
+    let name = &resource.spec.resource_key;
+    if let Err(err) =
+      resource::sync_owned(name, &resource.kind, &owner, None, state).await
+    {
+      log::warn!("ingress::reconcile: {name}: {err}");
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rules() {
+    let ingress =
+      serde_json::from_value::<ResourceIngress>(serde_json::json!({
+        "Host": "example.com",
+        "Network": "Public",
+        "Paths": [
+          { "Path": "/", "Cargo": "front.global", "Port": 9000 },
+          {
+            "Path": "/api",
+            "Cargo": "api.global",
+            "Port": 8080,
+            "StripPrefix": true
+          }
+        ],
+        "Tls": { "Secret": "example.com" },
+        "Dns": "Internal"
+      }))
+      .unwrap();
+    ingress.validate().unwrap();
+    validate(&serde_json::to_value(&ingress).unwrap()).unwrap();
+    assert!(validate(&serde_json::json!({ "Host": "example.com" })).is_err());
+    let proxy = serde_json::to_value(gen_proxy_rule(&ingress)).unwrap();
+    let expected = serde_json::json!({
+      "Rules": [
+        {
+          "Domain": "example.com",
+          "Port": null,
+          "Network": "Public",
+          "Locations": [
+            {
+              "Path": "/",
+              "Target": { "Key": "front.global.c", "Port": 9000 }
+            },
+            {
+              "Path": "/api",
+              "Target": { "Key": "api.global.c", "Port": 8080 },
+              "StripPrefix": true
+            }
+          ],
+          "Ssl": "example.com"
+        },
+        {
+          "Domain": "example.com",
+          "Port": null,
+          "Network": "Public",
+          "Locations": [
+            {
+              "Path": "/",
+              "Target": {
+                "Url": "https://example.com$request_uri",
+                "Redirect": "MovedPermanently"
+              }
+            }
+          ]
+        }
+      ]
+    });
+    assert_eq!(proxy, expected);
+    let dns = serde_json::to_value(gen_dns_rule(&ingress)).unwrap();
+    let expected = serde_json::json!({
+      "Network": "Internal",
+      "Entries": [{ "Name": "example.com", "IpAddress": "Public" }]
+    });
+    assert_eq!(dns, expected);
+  }
+}
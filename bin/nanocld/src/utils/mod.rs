@@ -2,16 +2,29 @@ pub mod key;
 pub mod stream;
 pub mod ws;
 
+pub mod cargo_config;
+pub mod cloud_init;
 pub mod container;
 pub mod cron;
 pub mod ctrl_client;
 pub mod exec;
+pub mod ingress;
+pub mod network_policy;
+pub mod overlay;
+pub mod qmp;
 pub mod query_string;
+pub mod resource;
 pub mod secret;
 pub mod server;
 pub mod store;
 pub mod system;
+pub mod vm_control;
 pub mod vm_image;
+pub mod vm_image_pull;
+pub mod vm_migrate;
+pub mod vm_snapshot;
+pub mod vnc;
+pub mod volume;
 
 #[cfg(test)]
 pub mod tests {
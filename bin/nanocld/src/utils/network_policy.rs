@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, HashMap};
+
+use tokio::fs;
+
+use bollard_next::{
+  container::ListContainersOptions, network::InspectNetworkOptions,
+};
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoError, IoResult},
+};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  network_policy::{NetworkPolicyRule, ResourceNetworkPolicy},
+  resource::Resource,
+};
+
+use crate::{
+  models::{NamespaceDb, ResourceDb, SystemState},
+  repositories::generic::*,
+  utils, vars,
+};
+
+/// Chain jumped from `DOCKER-USER` holding every rule managed by nanocld
+const CHAIN: &str = "NANOCL-POLICY";
+/// Chain checking the traffic allowed into the isolated namespaces
+const INGRESS_CHAIN: &str = "NANOCL-POLICY-IN";
+/// Chain checking the traffic allowed out of the isolated namespaces
+const EGRESS_CHAIN: &str = "NANOCL-POLICY-OUT";
+/// Namespace of the nanocl components, it's never isolated
+const SYSTEM_NAMESPACE: &str = "system";
+
+/// Rules allowed for a namespace, `None` when the direction isn't isolated
+#[derive(Default)]
+struct NamespaceRules<'a> {
+  ingress: Option<Vec<&'a NetworkPolicyRule>>,
+  egress: Option<Vec<&'a NetworkPolicyRule>>,
+}
+
+/// Merge the policies targeting the same namespace
+fn merge_policies(
+  policies: &[ResourceNetworkPolicy],
+) -> BTreeMap<&str, NamespaceRules> {
+  let mut namespaces = BTreeMap::<&str, NamespaceRules>::new();
+  for policy in policies {
+    let rules = namespaces.entry(&policy.namespace).or_default();
+    if let Some(ingress) = &policy.ingress {
+      rules.ingress.get_or_insert_with(Vec::new).extend(ingress);
+    }
+    if let Some(egress) = &policy.egress {
+      rules.egress.get_or_insert_with(Vec::new).extend(egress);
+    }
+  }
+  namespaces
+}
+
+/// Addresses of the peers selected by a rule
+fn gen_peers(
+  rule: &NetworkPolicyRule,
+  instances: &BTreeMap<String, Vec<String>>,
+  subnets: &[String],
+) -> Vec<String> {
+  if rule.namespaces.iter().any(|namespace| namespace == "*") {
+    return subnets.to_vec();
+  }
+  rule
+    .namespaces
+    .iter()
+    .filter_map(|namespace| instances.get(namespace))
+    .flatten()
+    .cloned()
+    .collect()
+}
+
+/// Match of the ports allowed by a rule, an empty match allows every port
+fn gen_ports(rule: &NetworkPolicyRule) -> Vec<String> {
+  let Some(ports) = &rule.ports else {
+    return vec![String::new()];
+  };
+  ports
+    .iter()
+    .flat_map(|port| match &port.protocol {
+      Some(protocol) => vec![format!(" -p {protocol} --dport {}", port.port)],
+      None => vec![
+        format!(" -p tcp --dport {}", port.port),
+        format!(" -p udp --dport {}", port.port),
+      ],
+    })
+    .collect()
+}
+
+/// Generate the rules of a direction for an isolated namespace.
+/// The instances of the namespace are matched as destination for the ingress
+/// and as source for the egress.
+fn gen_direction(
+  chain: &str,
+  namespace: &str,
+  rules: &[&NetworkPolicyRule],
+  instances: &BTreeMap<String, Vec<String>>,
+  subnets: &[String],
+) -> Vec<String> {
+  let fmt_rule = |addr: &str, peer: &str, ports: &str, target: &str| {
+    if chain == INGRESS_CHAIN {
+      format!("-A {chain} -s {peer} -d {addr}{ports} -j {target}")
+    } else {
+      format!("-A {chain} -s {addr} -d {peer}{ports} -j {target}")
+    }
+  };
+  let trusted = [namespace, SYSTEM_NAMESPACE]
+    .iter()
+    .filter_map(|namespace| instances.get(*namespace))
+    .flatten()
+    .collect::<Vec<_>>();
+  let mut lines = Vec::new();
+  for addr in instances.get(namespace).into_iter().flatten() {
+    for peer in &trusted {
+      lines.push(fmt_rule(addr, peer, "", "RETURN"));
+    }
+    for rule in rules {
+      let ports = gen_ports(rule);
+      for peer in gen_peers(rule, instances, subnets) {
+        for port in &ports {
+          lines.push(fmt_rule(addr, &peer, port, "RETURN"));
+        }
+      }
+    }
+    for subnet in subnets {
+      lines.push(fmt_rule(addr, subnet, "", "DROP"));
+    }
+  }
+  lines
+}
+
+/// Generate the `iptables-restore` input of the policies.
+/// `instances` are the addresses of the running instances by namespace
+/// and `subnets` the subnets of the networks of the namespaces.
+/// When `overlay` is set the traffic coming from the other nodes
+/// is accepted once it went through the policies.
+pub fn gen_rules(
+  policies: &[ResourceNetworkPolicy],
+  instances: &BTreeMap<String, Vec<String>>,
+  subnets: &[String],
+  overlay: bool,
+) -> String {
+  let mut lines = vec![
+    "*filter".to_owned(),
+    format!(":{CHAIN} - [0:0]"),
+    format!(":{INGRESS_CHAIN} - [0:0]"),
+    format!(":{EGRESS_CHAIN} - [0:0]"),
+    format!("-A {CHAIN} -m conntrack --ctstate RELATED,ESTABLISHED -j RETURN"),
+    format!("-A {CHAIN} -j {EGRESS_CHAIN}"),
+    format!("-A {CHAIN} -j {INGRESS_CHAIN}"),
+  ];
+  for (namespace, rules) in merge_policies(policies) {
+    if let Some(ingress) = &rules.ingress {
+      lines.extend(gen_direction(
+        INGRESS_CHAIN,
+        namespace,
+        ingress,
+        instances,
+        subnets,
+      ));
+    }
+    if let Some(egress) = &rules.egress {
+      lines.extend(gen_direction(
+        EGRESS_CHAIN,
+        namespace,
+        egress,
+        instances,
+        subnets,
+      ));
+    }
+  }
+  if overlay {
+    lines.push(format!(
+      "-A {CHAIN} -i {} -o {} -j ACCEPT",
+      utils::overlay::VXLAN_IFACE,
+      utils::overlay::NETWORK,
+    ));
+  }
+  lines.push("COMMIT".to_owned());
+  lines.join("\n") + "\n"
+}
+
+/// List the network policies resources
+pub async fn list_resources(state: &SystemState) -> IoResult<Vec<Resource>> {
+  let filter = GenericFilter::new().r#where(
+    "kind",
+    GenericClause::Eq(vars::NETWORK_POLICY_KIND.to_owned()),
+  );
+  ResourceDb::transform_read_by(&filter, &state.inner.pool).await
+}
+
+/// Addresses of the running instances grouped by namespace,
+/// each instance is reached on the network of its namespace
+/// and on the overlay network for the ones running on the other nodes
+async fn list_instances(
+  state: &SystemState,
+) -> IoResult<BTreeMap<String, Vec<String>>> {
+  let options = Some(ListContainersOptions::<String> {
+    filters: HashMap::from([(
+      "label".to_owned(),
+      vec!["io.nanocl.n".to_owned()],
+    )]),
+    ..Default::default()
+  });
+  let containers = state
+    .inner
+    .docker_api
+    .list_containers(options)
+    .await
+    .map_err(|err| err.map_err_context(|| "NetworkPolicy"))?;
+  let mut networks = HashMap::<String, String>::new();
+  let mut instances = BTreeMap::<String, Vec<String>>::new();
+  for container in containers {
+    let labels = container.labels.unwrap_or_default();
+    let Some(namespace) = labels.get("io.nanocl.n") else {
+      continue;
+    };
+    if !networks.contains_key(namespace) {
+      let network =
+        NamespaceDb::get_network(namespace, &state.inner.pool).await?;
+      networks.insert(namespace.clone(), network);
+    }
+    let settings = container
+      .network_settings
+      .and_then(|settings| settings.networks)
+      .unwrap_or_default();
+    let addrs = [networks[namespace].as_str(), utils::overlay::NETWORK]
+      .iter()
+      .filter_map(|network| settings.get(*network))
+      .filter_map(|network| network.ip_address.clone())
+      .filter(|addr| !addr.is_empty())
+      .collect::<Vec<_>>();
+    instances
+      .entry(namespace.clone())
+      .or_default()
+      .extend(addrs);
+  }
+  for (namespace, addr) in utils::overlay::list_remote_instances(state).await? {
+    instances.entry(namespace).or_default().push(addr);
+  }
+  instances.retain(|_, addrs| !addrs.is_empty());
+  for addrs in instances.values_mut() {
+    addrs.sort();
+  }
+  Ok(instances)
+}
+
+/// Subnet of a docker network
+async fn get_subnet(name: &str, state: &SystemState) -> IoResult<String> {
+  let network = state
+    .inner
+    .docker_api
+    .inspect_network(name, None::<InspectNetworkOptions<String>>)
+    .await
+    .map_err(|err| err.map_err_context(|| "NetworkPolicy"))?;
+  network
+    .ipam
+    .and_then(|ipam| ipam.config)
+    .and_then(|config| config.into_iter().find_map(|config| config.subnet))
+    .ok_or(IoError::invalid_data(
+      "NetworkPolicy",
+      &format!("No subnet found for {name}"),
+    ))
+}
+
+/// Subnets of `nanoclbr0`, of the networks of the namespaces
+/// with running instances and the pool of the overlay
+async fn list_subnets(
+  instances: &BTreeMap<String, Vec<String>>,
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mut networks = vec!["nanoclbr0".to_owned()];
+  for namespace in instances.keys() {
+    let network =
+      NamespaceDb::get_network(namespace, &state.inner.pool).await?;
+    if !networks.contains(&network) {
+      networks.push(network);
+    }
+  }
+  let mut subnets = Vec::new();
+  for network in networks {
+    subnets.push(get_subnet(&network, state).await?);
+  }
+  if let Some(pool) = &state.inner.config.overlay_subnet {
+    subnets.push(utils::overlay::parse_pool(pool)?.to_string());
+  }
+  Ok(subnets)
+}
+
+/// Validate the data of a network policy resource
+pub fn validate(data: &serde_json::Value) -> HttpResult<()> {
+  let policy = serde_json::from_value::<ResourceNetworkPolicy>(data.clone())
+    .map_err(|err| {
+      HttpError::bad_request(format!("Invalid network policy {err}"))
+    })?;
+  policy.validate().map_err(|err| {
+    HttpError::bad_request(format!("Invalid network policy {err}"))
+  })?;
+  Ok(())
+}
+
+/// Render the policies with the current instances and replace the rules
+/// of the host firewall.
+/// The syncs are serialized so the rules are rendered from the latest state.
+/// The traffic between the instances goes through the `FORWARD` chain
+/// so the rules are jumped from `DOCKER-USER` to be evaluated before the docker ones.
+pub async fn sync(state: &SystemState) -> IoResult<()> {
+  if !state.inner.config.network_policies {
+    return Err(IoError::invalid_input(
+      "NetworkPolicy",
+      "network policies are disabled, start nanocld with --network-policies",
+    ));
+  }
+  let _lock = state.inner.network_policy_lock.lock().await;
+  let policies = list_resources(state)
+    .await?
+    .into_iter()
+    .map(|resource| {
+      serde_json::from_value::<ResourceNetworkPolicy>(resource.spec.data)
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| err.map_err_context(|| "NetworkPolicy"))?;
+  let instances = list_instances(state).await?;
+  let subnets = list_subnets(&instances, state).await?;
+  let overlay = state.inner.config.overlay_subnet.is_some();
+  let rules = gen_rules(&policies, &instances, &subnets, overlay);
+  let path = format!("{}/network-policy.rules", state.inner.config.state_dir);
+  fs::write(&path, &rules)
+    .await
+    .map_err(|err| err.map_err_context(|| &path))?;
+  if !utils::system::exec_host_netns(&[
+    "iptables-restore",
+    "-w",
+    "--noflush",
+    &path,
+  ])
+  .await?
+  {
+    return Err(IoError::interrupted(
+      "NetworkPolicy",
+      "Unable to restore the rules with iptables-restore",
+    ));
+  }
+  if utils::system::exec_host_netns(&[
+    "iptables",
+    "-w",
+    "-C",
+    "DOCKER-USER",
+    "-j",
+    CHAIN,
+  ])
+  .await?
+  {
+    return Ok(());
+  }
+  if !utils::system::exec_host_netns(&[
+    "iptables",
+    "-w",
+    "-I",
+    "DOCKER-USER",
+    "-j",
+    CHAIN,
+  ])
+  .await?
+  {
+    return Err(IoError::interrupted(
+      "NetworkPolicy",
+      "Unable to jump to the rules from DOCKER-USER",
+    ));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rules() {
+    let policy =
+      serde_json::from_value::<ResourceNetworkPolicy>(serde_json::json!({
+        "Namespace": "back",
+        "Ingress": [
+          {
+            "Namespaces": ["front"],
+            "Ports": [{ "Port": 5432, "Protocol": "Tcp" }, { "Port": 53 }]
+          }
+        ],
+        "Egress": []
+      }))
+      .unwrap();
+    policy.validate().unwrap();
+    let instances = BTreeMap::from([
+      ("back".to_owned(), vec!["10.0.0.2".to_owned()]),
+      ("front".to_owned(), vec!["10.0.0.3".to_owned()]),
+      ("system".to_owned(), vec!["10.0.0.4".to_owned()]),
+    ]);
+    let subnets = ["10.0.0.0/16".to_owned(), "10.1.0.0/24".to_owned()];
+    let rules = gen_rules(&[policy], &instances, &subnets, true);
+    let expected = "*filter
+:NANOCL-POLICY - [0:0]
+:NANOCL-POLICY-IN - [0:0]
+:NANOCL-POLICY-OUT - [0:0]
+-A NANOCL-POLICY -m conntrack --ctstate RELATED,ESTABLISHED -j RETURN
+-A NANOCL-POLICY -j NANOCL-POLICY-OUT
+-A NANOCL-POLICY -j NANOCL-POLICY-IN
+-A NANOCL-POLICY-IN -s 10.0.0.2 -d 10.0.0.2 -j RETURN
+-A NANOCL-POLICY-IN -s 10.0.0.4 -d 10.0.0.2 -j RETURN
+-A NANOCL-POLICY-IN -s 10.0.0.3 -d 10.0.0.2 -p tcp --dport 5432 -j RETURN
+-A NANOCL-POLICY-IN -s 10.0.0.3 -d 10.0.0.2 -p tcp --dport 53 -j RETURN
+-A NANOCL-POLICY-IN -s 10.0.0.3 -d 10.0.0.2 -p udp --dport 53 -j RETURN
+-A NANOCL-POLICY-IN -s 10.0.0.0/16 -d 10.0.0.2 -j DROP
+-A NANOCL-POLICY-IN -s 10.1.0.0/24 -d 10.0.0.2 -j DROP
+-A NANOCL-POLICY-OUT -s 10.0.0.2 -d 10.0.0.2 -j RETURN
+-A NANOCL-POLICY-OUT -s 10.0.0.2 -d 10.0.0.4 -j RETURN
+-A NANOCL-POLICY-OUT -s 10.0.0.2 -d 10.0.0.0/16 -j DROP
+-A NANOCL-POLICY-OUT -s 10.0.0.2 -d 10.1.0.0/24 -j DROP
+-A NANOCL-POLICY -i nanoclvx0 -o nanoclov0 -j ACCEPT
+COMMIT
+";
+    assert_eq!(rules, expected);
+  }
+}
@@ -0,0 +1,653 @@
+use std::{collections::HashMap, net::Ipv4Addr};
+
+use ipnet::Ipv4Net;
+use ntex::rt;
+
+use bollard_next::{
+  network::{
+    ConnectNetworkOptions, CreateNetworkOptions, InspectNetworkOptions,
+  },
+  service::{Ipam, IpamConfig},
+};
+use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_stubs::generic::{GenericClause, GenericFilter};
+
+use crate::{
+  models::{NodeDb, OverlaySubnetDb, ProcessDb, SystemState},
+  repositories::generic::*,
+  utils,
+};
+
+/// Docker network of the instances reachable from the other nodes
+pub const NETWORK: &str = "nanoclov0";
+/// VXLAN interface tunneling the traffic between the nodes
+pub const VXLAN_IFACE: &str = "nanoclvx0";
+/// VXLAN network identifier of the overlay
+const VXLAN_ID: &str = "4210";
+/// UDP port of the VXLAN tunnel
+const VXLAN_PORT: &str = "4789";
+/// Seconds between two syncs of the overlay with the registered nodes
+const SYNC_INTERVAL: u64 = 30;
+/// Nodes fitting in the overlay since their tunnel addresses share a /24
+const MAX_NODES: usize = 254;
+/// Attempts to allocate a subnet when another node took the same one
+const ALLOCATE_RETRIES: usize = 5;
+
+/// A command to run on the host to setup the overlay
+#[derive(Debug, PartialEq)]
+pub struct OverlayCommand {
+  pub args: Vec<String>,
+  /// Failures are ignored when false eg: the setting already exists
+  pub required: bool,
+}
+
+impl OverlayCommand {
+  fn new(args: &[&str], required: bool) -> Self {
+    Self {
+      args: args.iter().map(|arg| arg.to_string()).collect(),
+      required,
+    }
+  }
+}
+
+/// Parse the address pool of the overlay.
+/// The first /24 holds the tunnel addresses and the next ones are given to the nodes.
+pub fn parse_pool(pool: &str) -> IoResult<Ipv4Net> {
+  let pool = pool.parse::<Ipv4Net>().map_err(|err| {
+    IoError::invalid_input("Overlay", &format!("{pool}: {err}"))
+  })?;
+  if pool.prefix_len() > 23 {
+    return Err(IoError::invalid_input(
+      "Overlay",
+      &format!("{pool}: prefix must be at most /23"),
+    ));
+  }
+  Ok(pool.trunc())
+}
+
+/// Subnet given to a node before they were stored in `overlay_subnets`,
+/// it's kept when still free so the node doesn't change of subnet
+fn get_metadata_subnet(node: &NodeDb) -> Option<Ipv4Net> {
+  node
+    .metadata
+    .as_ref()?
+    .get("Overlay")?
+    .get("Subnet")?
+    .as_str()?
+    .parse()
+    .ok()
+}
+
+/// Subnets given to the nodes by node name
+fn map_subnets(subnets: &[OverlaySubnetDb]) -> HashMap<String, Ipv4Net> {
+  subnets
+    .iter()
+    .filter_map(|row| {
+      let subnet = row.subnet.parse::<Ipv4Net>().ok()?;
+      Some((row.node_name.clone(), subnet))
+    })
+    .collect()
+}
+
+/// Index of a node subnet inside the pool, 0 is the tunnel subnet
+fn get_index(pool: &Ipv4Net, subnet: &Ipv4Net) -> Option<u32> {
+  if !pool.contains(subnet) {
+    return None;
+  }
+  let offset = u32::from(subnet.network()) - u32::from(pool.network());
+  Some(offset >> 8)
+}
+
+/// Address of a node on the VXLAN interface
+fn get_tunnel_addr(pool: &Ipv4Net, index: u32) -> Ipv4Addr {
+  Ipv4Addr::from(u32::from(pool.network()) + index)
+}
+
+/// Pick the first subnet of the pool not given to another node.
+/// Only 254 nodes fit since their tunnel addresses share the first /24.
+pub fn allocate_subnet(pool: &Ipv4Net, used: &[Ipv4Net]) -> IoResult<Ipv4Net> {
+  let used = used
+    .iter()
+    .filter_map(|subnet| get_index(pool, subnet))
+    .collect::<Vec<_>>();
+  pool
+    .subnets(24)
+    .map_err(|err| IoError::invalid_input("Overlay", &err.to_string()))?
+    .enumerate()
+    .skip(1)
+    .take(MAX_NODES)
+    .find(|(index, _)| !used.contains(&(*index as u32)))
+    .map(|(_, subnet)| subnet)
+    .ok_or(IoError::invalid_data(
+      "Overlay",
+      "No subnet left in the pool",
+    ))
+}
+
+/// Address and subnet of the remote nodes with a subnet in the pool
+fn list_remotes(
+  pool: &Ipv4Net,
+  local: &NodeDb,
+  nodes: &[NodeDb],
+  subnets: &HashMap<String, Ipv4Net>,
+) -> Vec<(String, Ipv4Net, u32)> {
+  nodes
+    .iter()
+    .filter(|node| node.name != local.name)
+    .filter_map(|node| {
+      let subnet = subnets.get(&node.name)?;
+      let index = get_index(pool, subnet)?;
+      Some((node.ip_address.addr().to_string(), *subnet, index))
+    })
+    .collect()
+}
+
+/// Generate the commands creating the tunnel of the local node
+/// and routing the subnets of the remote nodes through it
+pub fn gen_commands(
+  pool: &Ipv4Net,
+  local: &NodeDb,
+  nodes: &[NodeDb],
+  subnets: &HashMap<String, Ipv4Net>,
+) -> Vec<OverlayCommand> {
+  let Some(index) = subnets
+    .get(&local.name)
+    .and_then(|subnet| get_index(pool, subnet))
+  else {
+    return Vec::new();
+  };
+  let local_ip = local.ip_address.addr().to_string();
+  let tunnel_addr = format!("{}/24", get_tunnel_addr(pool, index));
+  let mut commands = vec![
+    OverlayCommand::new(
+      &[
+        "ip",
+        "link",
+        "add",
+        VXLAN_IFACE,
+        "type",
+        "vxlan",
+        "id",
+        VXLAN_ID,
+        "dstport",
+        VXLAN_PORT,
+        "local",
+        &local_ip,
+      ],
+      false,
+    ),
+    OverlayCommand::new(
+      &["ip", "addr", "replace", &tunnel_addr, "dev", VXLAN_IFACE],
+      true,
+    ),
+    OverlayCommand::new(&["ip", "link", "set", VXLAN_IFACE, "up"], true),
+  ];
+  for (remote_ip, subnet, index) in list_remotes(pool, local, nodes, subnets) {
+    let gateway = get_tunnel_addr(pool, index).to_string();
+    commands.push(OverlayCommand::new(
+      &[
+        "bridge",
+        "fdb",
+        "append",
+        "00:00:00:00:00:00",
+        "dev",
+        VXLAN_IFACE,
+        "dst",
+        &remote_ip,
+      ],
+      false,
+    ));
+    commands.push(OverlayCommand::new(
+      &[
+        "ip",
+        "route",
+        "replace",
+        &subnet.to_string(),
+        "via",
+        &gateway,
+        "dev",
+        VXLAN_IFACE,
+        "onlink",
+      ],
+      true,
+    ));
+  }
+  commands
+}
+
+/// Generate the commands removing the routes and the forwarding entries
+/// of the nodes that left the cluster or changed of address.
+/// `routes` and `fdb` are the outputs of `ip route show dev nanoclvx0`
+/// and `bridge fdb show dev nanoclvx0`.
+pub fn gen_cleanup_commands(
+  pool: &Ipv4Net,
+  local: &NodeDb,
+  nodes: &[NodeDb],
+  subnets: &HashMap<String, Ipv4Net>,
+  routes: &str,
+  fdb: &str,
+) -> Vec<OverlayCommand> {
+  let remotes = list_remotes(pool, local, nodes, subnets);
+  let mut commands = Vec::new();
+  for line in routes.lines() {
+    let mut fields = line.split_whitespace();
+    let Some(dst) = fields.next() else {
+      continue;
+    };
+    // The route of the tunnel subnet is owned by the kernel
+    if line.contains("proto kernel") {
+      continue;
+    }
+    if remotes
+      .iter()
+      .any(|(_, subnet, _)| subnet.to_string() == dst)
+    {
+      continue;
+    }
+    commands.push(OverlayCommand::new(
+      &["ip", "route", "del", dst, "dev", VXLAN_IFACE],
+      false,
+    ));
+  }
+  for line in fdb.lines() {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let [mac, "dst", remote_ip, ..] = fields.as_slice() else {
+      continue;
+    };
+    if *mac != "00:00:00:00:00:00"
+      || remotes.iter().any(|(ip, _, _)| ip == remote_ip)
+    {
+      continue;
+    }
+    commands.push(OverlayCommand::new(
+      &[
+        "bridge",
+        "fdb",
+        "del",
+        mac,
+        "dev",
+        VXLAN_IFACE,
+        "dst",
+        remote_ip,
+      ],
+      false,
+    ));
+  }
+  commands
+}
+
+/// Subnets given to the nodes
+async fn list_subnets(state: &SystemState) -> IoResult<Vec<OverlaySubnetDb>> {
+  let filter = GenericFilter::new().limit(MAX_NODES);
+  OverlaySubnetDb::read_by(&filter, &state.inner.pool).await
+}
+
+/// Give a subnet to the local node if it doesn't have one yet.
+/// The subnet is reserved by inserting a row unique by subnet and by node,
+/// when another node reserved it first the allocation is retried.
+async fn ensure_node_subnet(
+  pool: &Ipv4Net,
+  local: &NodeDb,
+  state: &SystemState,
+) -> IoResult<()> {
+  for _ in 0..ALLOCATE_RETRIES {
+    let subnets = map_subnets(&list_subnets(state).await?);
+    if subnets.contains_key(&local.name) {
+      return Ok(());
+    }
+    let used = subnets.values().cloned().collect::<Vec<_>>();
+    let subnet = match get_metadata_subnet(local).filter(|subnet| {
+      get_index(pool, subnet).is_some() && !used.contains(subnet)
+    }) {
+      Some(subnet) => subnet,
+      None => allocate_subnet(pool, &used)?,
+    };
+    let item = OverlaySubnetDb {
+      subnet: subnet.to_string(),
+      node_name: local.name.clone(),
+      created_at: chrono::Utc::now().naive_utc(),
+    };
+    match OverlaySubnetDb::create_from(item, &state.inner.pool).await {
+      Ok(_) => return Ok(()),
+      Err(err) if err.inner.kind() == std::io::ErrorKind::AlreadyExists => {
+        log::debug!("overlay::ensure_node_subnet: {subnet} is taken: {err}");
+      }
+      Err(err) => return Err(err),
+    }
+  }
+  Err(IoError::interrupted(
+    "Overlay",
+    "Unable to allocate a subnet, too many concurrent allocations",
+  ))
+}
+
+/// Create the docker network of the local subnet if it doesn't exists
+async fn ensure_network(subnet: &Ipv4Net, state: &SystemState) -> IoResult<()> {
+  let docker_api = &state.inner.docker_api;
+  if let Ok(network) = docker_api
+    .inspect_network(NETWORK, None::<InspectNetworkOptions<String>>)
+    .await
+  {
+    let current = network
+      .ipam
+      .and_then(|ipam| ipam.config)
+      .and_then(|config| config.into_iter().find_map(|config| config.subnet))
+      .unwrap_or_default();
+    if current != subnet.to_string() {
+      log::warn!(
+        "overlay::ensure_network: {NETWORK} subnet is {current} instead of {subnet}"
+      );
+    }
+    return Ok(());
+  }
+  let gateway = subnet.hosts().next().map(|addr| addr.to_string());
+  docker_api
+    .create_network(CreateNetworkOptions {
+      name: NETWORK.to_owned(),
+      check_duplicate: true,
+      driver: "bridge".to_owned(),
+      attachable: true,
+      ipam: Ipam {
+        config: Some(vec![IpamConfig {
+          subnet: Some(subnet.to_string()),
+          gateway,
+          ..Default::default()
+        }]),
+        ..Default::default()
+      },
+      options: HashMap::from([(
+        "com.docker.network.bridge.name".to_owned(),
+        NETWORK.to_owned(),
+      )]),
+      labels: HashMap::from([("io.nanocl".to_owned(), "enabled".to_owned())]),
+      ..Default::default()
+    })
+    .await
+    .map_err(|err| err.map_err_context(|| NETWORK))?;
+  Ok(())
+}
+
+/// Allow the traffic coming from the tunnel into the overlay network.
+/// With the network policies enabled the traffic is accepted at the end
+/// of their chain instead so it's filtered like the local one.
+async fn ensure_forward(state: &SystemState) -> IoResult<()> {
+  let rule = [
+    "DOCKER-USER",
+    "-i",
+    VXLAN_IFACE,
+    "-o",
+    NETWORK,
+    "-j",
+    "ACCEPT",
+  ];
+  let check = [&["iptables", "-w", "-C"][..], &rule[..]].concat();
+  let exists = utils::system::exec_host_netns(&check).await?;
+  if state.inner.config.network_policies {
+    if exists {
+      let delete = [&["iptables", "-w", "-D"][..], &rule[..]].concat();
+      utils::system::exec_host_netns(&delete).await?;
+    }
+    return Ok(());
+  }
+  if exists {
+    return Ok(());
+  }
+  let insert = [&["iptables", "-w", "-I"][..], &rule[..]].concat();
+  if !utils::system::exec_host_netns(&insert).await? {
+    return Err(IoError::interrupted(
+      "Overlay",
+      "Unable to allow the tunnel traffic in DOCKER-USER",
+    ));
+  }
+  Ok(())
+}
+
+/// Keep the instance addresses on the traffic leaving through the tunnel.
+/// Docker masquerades everything going out of the bridge, so the peers would
+/// only see the tunnel address and their network policies couldn't match
+/// the instances, the overlay pool is excluded from it.
+async fn ensure_nat(subnet: &Ipv4Net, pool: &Ipv4Net) -> IoResult<()> {
+  let subnet = subnet.to_string();
+  let pool = pool.to_string();
+  let rule = ["POSTROUTING", "-s", &subnet, "-d", &pool, "-j", "RETURN"];
+  let check = [&["iptables", "-w", "-t", "nat", "-C"][..], &rule[..]].concat();
+  if utils::system::exec_host_netns(&check).await? {
+    return Ok(());
+  }
+  let insert = [&["iptables", "-w", "-t", "nat", "-I"][..], &rule[..]].concat();
+  if !utils::system::exec_host_netns(&insert).await? {
+    return Err(IoError::interrupted(
+      "Overlay",
+      "Unable to exclude the overlay from the masquerade",
+    ));
+  }
+  Ok(())
+}
+
+/// Setup the overlay of the local node with the nodes registered in the store
+pub async fn sync(state: &SystemState) -> IoResult<()> {
+  let Some(pool) = &state.inner.config.overlay_subnet else {
+    return Ok(());
+  };
+  let pool = parse_pool(pool)?;
+  let filter = GenericFilter::new().limit(MAX_NODES);
+  let nodes = NodeDb::read_by(&filter, &state.inner.pool).await?;
+  let Some(local) = nodes
+    .iter()
+    .find(|node| node.name == state.inner.config.hostname)
+  else {
+    return Err(IoError::not_found("Node", &state.inner.config.hostname));
+  };
+  ensure_node_subnet(&pool, local, state).await?;
+  let subnets = map_subnets(&list_subnets(state).await?);
+  if let Some(subnet) = subnets.get(&local.name) {
+    ensure_network(subnet, state).await?;
+    ensure_nat(subnet, &pool).await?;
+  }
+  run_commands(gen_commands(&pool, local, &nodes, &subnets)).await?;
+  let routes = utils::system::exec_host_netns_output(&[
+    "ip",
+    "route",
+    "show",
+    "dev",
+    VXLAN_IFACE,
+  ])
+  .await?;
+  let fdb = utils::system::exec_host_netns_output(&[
+    "bridge",
+    "fdb",
+    "show",
+    "dev",
+    VXLAN_IFACE,
+  ])
+  .await?;
+  run_commands(gen_cleanup_commands(
+    &pool, local, &nodes, &subnets, &routes, &fdb,
+  ))
+  .await?;
+  ensure_forward(state).await?;
+  Ok(())
+}
+
+/// Run the commands on the host, stopping at the first required one failing
+async fn run_commands(commands: Vec<OverlayCommand>) -> IoResult<()> {
+  for command in commands {
+    let args = command
+      .args
+      .iter()
+      .map(|arg| arg.as_str())
+      .collect::<Vec<_>>();
+    if !utils::system::exec_host_netns(&args).await? && command.required {
+      return Err(IoError::interrupted(
+        "Overlay",
+        &format!("Unable to run {}", command.args.join(" ")),
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Overlay addresses of the instances running on the other nodes
+/// with the namespace they belong to
+pub async fn list_remote_instances(
+  state: &SystemState,
+) -> IoResult<Vec<(String, String)>> {
+  if state.inner.config.overlay_subnet.is_none() {
+    return Ok(Vec::new());
+  }
+  let filter = GenericFilter::new().r#where(
+    "node_name",
+    GenericClause::Ne(state.inner.config.hostname.clone()),
+  );
+  let processes =
+    ProcessDb::transform_read_all(&filter, &state.inner.pool).await?;
+  let mut instances = Vec::new();
+  for process in &processes {
+    let namespace = process
+      .data
+      .config
+      .as_ref()
+      .and_then(|config| config.labels.as_ref())
+      .and_then(|labels| labels.get("io.nanocl.n"));
+    let addr = process
+      .data
+      .network_settings
+      .as_ref()
+      .and_then(|settings| settings.networks.as_ref())
+      .and_then(|networks| networks.get(NETWORK))
+      .and_then(|network| network.ip_address.clone())
+      .filter(|addr| !addr.is_empty());
+    if let (Some(namespace), Some(addr)) = (namespace, addr) {
+      instances.push((namespace.clone(), addr));
+    }
+  }
+  Ok(instances)
+}
+
+/// Keep the overlay in sync with the nodes joining the cluster
+pub fn spawn(state: &SystemState) {
+  if state.inner.config.overlay_subnet.is_none() {
+    return;
+  }
+  let state = state.clone();
+  rt::spawn(async move {
+    loop {
+      if let Err(err) = sync(&state).await {
+        log::warn!("overlay::spawn: {err}");
+      }
+      ntex::time::sleep(std::time::Duration::from_secs(SYNC_INTERVAL)).await;
+    }
+  });
+}
+
+/// Connect a process to the overlay network when it's enabled
+pub async fn connect(key: &str, state: &SystemState) -> IoResult<()> {
+  if state.inner.config.overlay_subnet.is_none() {
+    return Ok(());
+  }
+  state
+    .inner
+    .docker_api
+    .connect_network(
+      NETWORK,
+      ConnectNetworkOptions {
+        container: key.to_owned(),
+        ..Default::default()
+      },
+    )
+    .await
+    .map_err(|err| err.map_err_context(|| NETWORK))?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gen_node(name: &str, ip_address: &str) -> NodeDb {
+    NodeDb {
+      name: name.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+      ip_address: ipnet::IpNet::from(
+        ip_address.parse::<std::net::IpAddr>().unwrap(),
+      ),
+      endpoint: format!("http://{ip_address}:8585"),
+      version: "0.16.0".to_owned(),
+      metadata: None,
+    }
+  }
+
+  fn gen_subnet(node_name: &str, subnet: &str) -> OverlaySubnetDb {
+    OverlaySubnetDb {
+      subnet: subnet.to_owned(),
+      node_name: node_name.to_owned(),
+      created_at: chrono::Utc::now().naive_utc(),
+    }
+  }
+
+  #[test]
+  fn subnets() {
+    let pool = parse_pool("10.210.0.0/16").unwrap();
+    assert!(parse_pool("10.210.0.0/24").is_err());
+    let nodes = vec![
+      gen_node("node1", "192.168.1.10"),
+      gen_node("node2", "192.168.1.11"),
+      gen_node("node3", "192.168.1.12"),
+    ];
+    let subnets = map_subnets(&[
+      gen_subnet("node1", "10.210.1.0/24"),
+      gen_subnet("node2", "10.210.3.0/24"),
+    ]);
+    let used = subnets.values().cloned().collect::<Vec<_>>();
+    let subnet = allocate_subnet(&pool, &used).unwrap();
+    assert_eq!(subnet.to_string(), "10.210.2.0/24");
+    let commands = gen_commands(&pool, &nodes[0], &nodes, &subnets)
+      .into_iter()
+      .map(|command| command.args.join(" "))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      commands,
+      vec![
+        "ip link add nanoclvx0 type vxlan id 4210 dstport 4789 local 192.168.1.10",
+        "ip addr replace 10.210.0.1/24 dev nanoclvx0",
+        "ip link set nanoclvx0 up",
+        "bridge fdb append 00:00:00:00:00:00 dev nanoclvx0 dst 192.168.1.11",
+        "ip route replace 10.210.3.0/24 via 10.210.0.3 dev nanoclvx0 onlink",
+      ]
+    );
+    assert!(gen_commands(&pool, &nodes[2], &nodes, &subnets).is_empty());
+  }
+
+  #[test]
+  fn cleanup() {
+    let pool = parse_pool("10.210.0.0/16").unwrap();
+    let nodes = vec![
+      gen_node("node1", "192.168.1.10"),
+      gen_node("node2", "192.168.1.11"),
+    ];
+    let subnets = map_subnets(&[
+      gen_subnet("node1", "10.210.1.0/24"),
+      gen_subnet("node2", "10.210.3.0/24"),
+    ]);
+    let routes = "10.210.0.0/24 proto kernel scope link src 10.210.0.1
+10.210.3.0/24 via 10.210.0.3 onlink
+10.210.4.0/24 via 10.210.0.4 onlink
+";
+    let fdb = "00:00:00:00:00:00 dst 192.168.1.11 self permanent
+00:00:00:00:00:00 dst 192.168.1.12 self permanent
+6a:2b:1c:3d:4e:5f dst 192.168.1.12 self
+";
+    let commands =
+      gen_cleanup_commands(&pool, &nodes[0], &nodes, &subnets, routes, fdb)
+        .into_iter()
+        .map(|command| command.args.join(" "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+      commands,
+      vec![
+        "ip route del 10.210.4.0/24 dev nanoclvx0",
+        "bridge fdb del 00:00:00:00:00:00 dev nanoclvx0 dst 192.168.1.12",
+      ]
+    );
+  }
+}
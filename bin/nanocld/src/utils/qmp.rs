@@ -0,0 +1,298 @@
+use std::fmt::Write;
+
+use ntex::{
+  http::StatusCode,
+  time::{self, Millis},
+};
+use tokio::{
+  io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+  net::UnixStream,
+};
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoError, IoResult},
+};
+use nanocl_stubs::vm::Vm;
+
+use crate::models::SystemState;
+
+/// Maximum time to wait for a command, saving the memory of a vm can be long
+const COMMAND_TIMEOUT: Millis = Millis::from_secs(300);
+/// Maximum time to connect and receive the greeting, the socket only accepts
+/// one client at a time so a busy vm would block the caller forever
+const CONNECT_TIMEOUT: Millis = Millis::from_secs(5);
+/// Maximum length of the path of a unix socket, `sun_path` minus the null byte
+const MAX_SOCKET_PATH: usize = 107;
+
+/// Directory of the QMP sockets, bind mounted in the vm runtime
+pub fn run_dir(state: &SystemState) -> String {
+  format!("{}/vms/run", state.inner.config.state_dir)
+}
+
+/// Path of the QMP socket of a vm, a key too long to fit in the path
+/// of a unix socket is replaced by its hash
+pub fn socket_path(vm_key: &str, state: &SystemState) -> String {
+  gen_socket_path(&run_dir(state), vm_key, "qmp")
+}
+
+/// Path of a unix socket of a vm in the run directory,
+/// a key too long to fit in `sun_path` is replaced by its hash
+pub fn gen_socket_path(run_dir: &str, vm_key: &str, ext: &str) -> String {
+  let path = format!("{run_dir}/{vm_key}.{ext}");
+  if path.len() <= MAX_SOCKET_PATH {
+    return path;
+  }
+  let hash = openssl::sha::sha256(vm_key.as_bytes())
+    .iter()
+    .take(16)
+    .fold(String::new(), |mut hash, byte| {
+      let _ = write!(hash, "{byte:02x}");
+      hash
+    });
+  format!("{run_dir}/{hash}.{ext}")
+}
+
+/// Qemu argument exposing the QMP socket of a vm
+pub fn gen_arg(vm_key: &str, state: &SystemState) -> String {
+  format!("unix:{},server=on,wait=off", socket_path(vm_key, state))
+}
+
+/// Connect to the QMP socket of a vm, it must be running.
+/// A vm not answering in time is reported as unavailable.
+pub async fn connect_vm(vm: &Vm, state: &SystemState) -> HttpResult<QmpClient> {
+  let path = socket_path(&vm.spec.vm_key, state);
+  QmpClient::connect(&path).await.map_err(|err| {
+    if err.inner.kind() == std::io::ErrorKind::TimedOut {
+      return HttpError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        format!("Vm {} isn't answering: {err}", vm.spec.vm_key),
+      );
+    }
+    HttpError::conflict(format!("Vm {} must be running: {err}", vm.spec.vm_key))
+  })
+}
+
+/// A connection to the QMP socket of a running vm
+pub struct QmpClient {
+  reader: BufReader<UnixStream>,
+}
+
+impl QmpClient {
+  /// Connect to the socket and negotiate the capabilities
+  pub async fn connect(path: &str) -> IoResult<Self> {
+    let connect = async {
+      let stream = UnixStream::connect(path)
+        .await
+        .map_err(|err| err.map_err_context(|| path))?;
+      let mut client = Self {
+        reader: BufReader::new(stream),
+      };
+      // The server greets with its version before accepting commands
+      client.read().await?;
+      Ok::<_, IoError>(client)
+    };
+    let mut client =
+      time::timeout(CONNECT_TIMEOUT, connect)
+        .await
+        .map_err(|_| {
+          IoError::with_context(
+            path,
+            std::io::Error::new(
+              std::io::ErrorKind::TimedOut,
+              "no greeting, the monitor may be used by another client",
+            ),
+          )
+        })??;
+    client.execute("qmp_capabilities", None).await?;
+    Ok(client)
+  }
+
+  /// Read the next message ignoring the asynchronous events
+  async fn read(&mut self) -> IoResult<serde_json::Value> {
+    loop {
+      let mut line = String::new();
+      let n = self
+        .reader
+        .read_line(&mut line)
+        .await
+        .map_err(|err| err.map_err_context(|| "Qmp"))?;
+      if n == 0 {
+        return Err(IoError::interrupted("Qmp", "connection closed"));
+      }
+      let message = serde_json::from_str::<serde_json::Value>(&line)
+        .map_err(|err| err.map_err_context(|| "Qmp"))?;
+      if message.get("event").is_none() {
+        return Ok(message);
+      }
+    }
+  }
+
+  /// Execute a command and return its result
+  pub async fn execute(
+    &mut self,
+    command: &str,
+    arguments: Option<serde_json::Value>,
+  ) -> IoResult<serde_json::Value> {
+    let mut request = serde_json::json!({ "execute": command });
+    if let Some(arguments) = arguments {
+      request["arguments"] = arguments;
+    }
+    let request = format!("{request}\r\n");
+    self
+      .reader
+      .get_mut()
+      .write_all(request.as_bytes())
+      .await
+      .map_err(|err| err.map_err_context(|| "Qmp"))?;
+    let response =
+      time::timeout(COMMAND_TIMEOUT, self.read())
+        .await
+        .map_err(|_| {
+          IoError::interrupted("Qmp", &format!("{command} timed out"))
+        })??;
+    if let Some(error) = response.get("error") {
+      let desc = error
+        .get("desc")
+        .and_then(|desc| desc.as_str())
+        .unwrap_or("unknown error");
+      return Err(IoError::interrupted("Qmp", &format!("{command}: {desc}")));
+    }
+    Ok(response.get("return").cloned().unwrap_or_default())
+  }
+
+  /// Execute a human monitor command, they report their errors as output
+  pub async fn execute_hmp(&mut self, command_line: &str) -> IoResult<()> {
+    let output = self
+      .execute(
+        "human-monitor-command",
+        Some(serde_json::json!({ "command-line": command_line })),
+      )
+      .await?;
+    let output = output.as_str().unwrap_or_default().trim();
+    if !output.is_empty() {
+      return Err(IoError::interrupted(
+        "Qmp",
+        &format!("{command_line}: {output}"),
+      ));
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use tokio::net::UnixListener;
+
+  use super::*;
+
+  /// Fake a qmp server answering each command with the next reply
+  /// and return the commands it received
+  pub fn serve(
+    name: &str,
+    replies: Vec<&'static str>,
+  ) -> (String, ntex::rt::JoinHandle<Vec<serde_json::Value>>) {
+    let path = std::env::temp_dir()
+      .join(format!("nanocl-{name}-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let server = ntex::rt::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      let mut stream = BufReader::new(stream);
+      stream
+        .get_mut()
+        .write_all(b"{\"QMP\": {\"version\": {}, \"capabilities\": []}}\r\n")
+        .await
+        .unwrap();
+      let mut commands = Vec::new();
+      for reply in std::iter::once(r#"{"return": {}}"#).chain(replies) {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await.unwrap() == 0 {
+          break;
+        }
+        commands
+          .push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+        stream
+          .get_mut()
+          .write_all(format!("{reply}\r\n").as_bytes())
+          .await
+          .unwrap();
+      }
+      commands
+    });
+    (path.to_string_lossy().into_owned(), server)
+  }
+
+  #[test]
+  fn long_socket_path() {
+    let run_dir = "/var/lib/nanocl/vms/run";
+    let path = gen_socket_path(run_dir, "my-vm.global", "qmp");
+    assert_eq!(path, "/var/lib/nanocl/vms/run/my-vm.global.qmp");
+    let path = gen_socket_path(run_dir, "my-vm.global", "vnc");
+    assert_eq!(path, "/var/lib/nanocl/vms/run/my-vm.global.vnc");
+    let key = format!("{}.global", "a".repeat(100));
+    for ext in ["qmp", "vnc"] {
+      let path = gen_socket_path(run_dir, &key, ext);
+      assert!(path.len() <= MAX_SOCKET_PATH);
+      assert!(path.ends_with(&format!(".{ext}")));
+      assert_eq!(path, gen_socket_path(run_dir, &key, ext));
+    }
+    // Only the key decides when it's hashed, both sockets stay side by side
+    let key = format!(
+      "{}.global",
+      "a".repeat(MAX_SOCKET_PATH - run_dir.len() - 12)
+    );
+    assert_eq!(
+      gen_socket_path(run_dir, &key, "qmp").replace(".qmp", ".vnc"),
+      gen_socket_path(run_dir, &key, "vnc")
+    );
+  }
+
+  #[ntex::test]
+  async fn protocol() {
+    let path = std::env::temp_dir()
+      .join(format!("nanocl-qmp-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    let server = ntex::rt::spawn(async move {
+      let (stream, _) = listener.accept().await.unwrap();
+      let mut stream = BufReader::new(stream);
+      let replies = [
+        r#"{"QMP": {"version": {}, "capabilities": []}}"#,
+        r#"{"return": {}}"#,
+        r#"{"event": "STOP", "timestamp": {}}"#,
+        r#"{"return": ""}"#,
+        r#"{"return": "Error: Snapshot 'missing' does not exist\r\n"}"#,
+      ];
+      stream
+        .get_mut()
+        .write_all(format!("{}\r\n", replies[0]).as_bytes())
+        .await
+        .unwrap();
+      let mut commands = Vec::new();
+      for reply in [&replies[1..2], &replies[2..4], &replies[4..]] {
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        commands
+          .push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+        for message in reply {
+          stream
+            .get_mut()
+            .write_all(format!("{message}\r\n").as_bytes())
+            .await
+            .unwrap();
+        }
+      }
+      commands
+    });
+    let mut client = QmpClient::connect(path.to_str().unwrap()).await.unwrap();
+    client.execute_hmp("savevm before").await.unwrap();
+    let err = client.execute_hmp("loadvm missing").await.unwrap_err();
+    assert!(err.to_string().contains("does not exist"));
+    let commands = server.await.unwrap();
+    assert_eq!(commands[0]["execute"], "qmp_capabilities");
+    assert_eq!(commands[1]["execute"], "human-monitor-command");
+    assert_eq!(commands[1]["arguments"]["command-line"], "savevm before");
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -0,0 +1,143 @@
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoError, IoResult},
+};
+use nanocl_stubs::{
+  resource::{Resource, ResourcePartial},
+  resource_kind::ResourceKind,
+};
+
+use crate::{
+  models::{ResourceDb, SpecDb, SystemState},
+  objects::generic::*,
+  repositories::generic::*,
+  utils, vars,
+};
+
+/// Validate the data of the resource kinds implemented by nanocld,
+/// the other kinds are validated by their schema and controller
+pub fn validate_data(kind: &str, data: &serde_json::Value) -> HttpResult<()> {
+  match kind {
+    vars::NETWORK_POLICY_KIND => utils::network_policy::validate(data),
+    vars::INGRESS_KIND => utils::ingress::validate(data),
+    _ => Ok(()),
+  }
+}
+
+/// Read a route of the controller of a resource kind at its current version,
+/// so the clients don't need to reach the controllers directly
+pub async fn get_from_controller<Q, T>(
+  kind: &str,
+  path: &str,
+  query: &Q,
+  state: &SystemState,
+) -> HttpResult<T>
+where
+  Q: serde::Serialize,
+  T: serde::de::DeserializeOwned,
+{
+  let (name, version) = ResourceDb::parse_kind(kind, &state.inner.pool).await?;
+  let kind: ResourceKind =
+    SpecDb::get_version(&name, &version, &state.inner.pool)
+      .await?
+      .try_into()?;
+  let Some(url) = &kind.data.url else {
+    return Err(HttpError::not_found(format!(
+      "Resource kind {name}: no controller"
+    )));
+  };
+  let ctrl_client = utils::ctrl_client::CtrlClient::new(&name, url);
+  let res = ctrl_client.get_json(&version, path, query).await?;
+  Ok(res)
+}
+
+/// Owner of a resource managed by nanocld eg: a cargo publishing its ports.
+/// The owner is saved in the metadata of the resource as `{ label: key }`.
+pub struct ResourceOwner<'a> {
+  pub label: &'a str,
+  pub key: &'a str,
+}
+
+/// Read a resource managed by nanocld on behalf of an owner.
+/// An error is returned when a resource with the same name isn't owned by it.
+pub async fn read_owned(
+  name: &str,
+  owner: &ResourceOwner<'_>,
+  state: &SystemState,
+) -> IoResult<Option<Resource>> {
+  let resource =
+    match ResourceDb::transform_read_by_pk(name, &state.inner.pool).await {
+      Ok(resource) => resource,
+      Err(err) if err.inner.kind() == std::io::ErrorKind::NotFound => {
+        return Ok(None);
+      }
+      Err(err) => return Err(err),
+    };
+  let key = resource
+    .spec
+    .metadata
+    .as_ref()
+    .and_then(|metadata| metadata.get(owner.label))
+    .and_then(|key| key.as_str());
+  if key != Some(owner.key) {
+    return Err(IoError::invalid_input(
+      "Resource",
+      &format!("{name} already exists and isn't owned by {}", owner.key),
+    ));
+  }
+  Ok(Some(resource))
+}
+
+/// Create, update or delete a resource managed on behalf of an owner
+/// so it matches `data`, the resource is deleted when `data` is `None`.
+/// The error keeps the status returned by the controller of the kind.
+pub async fn sync_owned(
+  name: &str,
+  kind: &str,
+  owner: &ResourceOwner<'_>,
+  data: Option<serde_json::Value>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let current = read_owned(name, owner, state).await?;
+  let map_err = |err: HttpError| {
+    HttpError::new(err.status, format!("Resource {name}: {}", err.msg))
+  };
+  let Some(data) = data else {
+    if current.is_some() {
+      ResourceDb::del_obj_by_pk(name, &(), state)
+        .await
+        .map_err(map_err)?;
+    }
+    return Ok(());
+  };
+  let resource = ResourcePartial {
+    name: name.to_owned(),
+    kind: kind.to_owned(),
+    data,
+    metadata: Some(serde_json::json!({ owner.label: owner.key })),
+  };
+  match current {
+    Some(current) if current.spec.data == resource.data => {}
+    Some(_) => {
+      ResourceDb::put_obj_by_pk(name, &resource, state)
+        .await
+        .map_err(map_err)?;
+    }
+    None => {
+      ResourceDb::create_obj(&resource, state)
+        .await
+        .map_err(map_err)?;
+    }
+  }
+  Ok(())
+}
+
+/// Serialize the data of a managed resource
+pub fn to_data<T>(data: &T) -> IoResult<serde_json::Value>
+where
+  T: serde::Serialize,
+{
+  let data = serde_json::to_value(data)
+    .map_err(|err| err.map_err_context(|| "Resource"))?;
+  Ok(data)
+}
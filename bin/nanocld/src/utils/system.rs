@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use tokio::process::Command;
+
 use nanocl_error::io::{FromIo, IoError, IoResult};
 
 use bollard_next::{
@@ -13,19 +15,61 @@ use nanocl_stubs::{
   generic::{GenericClause, GenericFilter},
   namespace::NamespacePartial,
   process::ProcessPartial,
+  resource_kind::{ResourceKindPartial, ResourceKindSpec},
   system::ObjPsStatusKind,
 };
 
 use crate::{
   models::{
     CargoDb, CargoObjCreateIn, NamespaceDb, ObjPsStatusDb, ObjPsStatusUpdate,
-    ProcessDb, ProcessUpdateDb, SystemState, VmImageDb,
+    ProcessDb, ProcessUpdateDb, ResourceKindDb, SpecDb, SystemState, VmImageDb,
   },
   objects::generic::ObjCreate,
   repositories::generic::*,
   utils, vars,
 };
 
+/// Network namespace of the host where docker forwards the traffic
+const HOST_NETNS: &str = "/proc/1/ns/net";
+
+/// Run a command inside the network namespace of the host
+/// and return whether it succeeded
+pub async fn exec_host_netns(args: &[&str]) -> IoResult<bool> {
+  let output = Command::new("nsenter")
+    .arg(format!("--net={HOST_NETNS}"))
+    .args(args)
+    .output()
+    .await
+    .map_err(|err| err.map_err_context(|| "HostNetns"))?;
+  if !output.status.success() {
+    log::debug!(
+      "system::exec_host_netns: {args:?} {}",
+      String::from_utf8_lossy(&output.stderr)
+    );
+  }
+  Ok(output.status.success())
+}
+
+/// Execute a command in the network namespace of the host and return its output
+pub async fn exec_host_netns_output(args: &[&str]) -> IoResult<String> {
+  let output = Command::new("nsenter")
+    .arg(format!("--net={HOST_NETNS}"))
+    .args(args)
+    .output()
+    .await
+    .map_err(|err| err.map_err_context(|| "HostNetns"))?;
+  if !output.status.success() {
+    return Err(IoError::interrupted(
+      "HostNetns",
+      &format!(
+        "{args:?} {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+      ),
+    ));
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// Will determine if the instance is registered by nanocl
 /// and sync his data with our store accordingly
 pub async fn sync_process(
@@ -110,6 +154,31 @@ pub async fn register_namespace(
   Ok(())
 }
 
+/// Register a resource kind handled by the daemon itself if it doesn't exists
+pub async fn register_resource_kind(
+  name: &str,
+  version: &str,
+  state: &SystemState,
+) -> IoResult<()> {
+  if SpecDb::get_version(name, version, &state.inner.pool)
+    .await
+    .is_ok()
+  {
+    return Ok(());
+  }
+  let new_kind = ResourceKindPartial {
+    name: name.to_owned(),
+    version: version.to_owned(),
+    metadata: None,
+    data: ResourceKindSpec {
+      schema: None,
+      url: None,
+    },
+  };
+  ResourceKindDb::create_from_spec(&new_kind, &state.inner.pool).await?;
+  Ok(())
+}
+
 /// Sync the cargo status with the container status.
 /// We use it at startup to be sure that the cargo status is up to date.
 async fn sync_cargo_status(
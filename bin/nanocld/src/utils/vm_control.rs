@@ -0,0 +1,540 @@
+use bollard_next::{
+  container::{LogOutput, WaitContainerOptions},
+  exec::{CreateExecOptions, StartExecResults},
+};
+use futures::StreamExt;
+use ntex::time::{self, Millis};
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::IoResult,
+};
+use nanocl_stubs::{
+  process::ProcessKind,
+  system::NativeEventAction,
+  vm::{Vm, VmHotplug, VmNic},
+};
+
+use crate::{
+  models::{ProcessDb, SystemState, VmDb},
+  repositories::generic::*,
+  utils::{self, qmp::QmpClient},
+};
+
+/// Seconds to wait for a guest to power off when the vm doesn't set it
+const DEFAULT_SHUTDOWN_TIMEOUT: u64 = 60;
+/// Longest wait for a guest to power off, a longer timeout is clamped
+const MAX_SHUTDOWN_TIMEOUT: u64 = 3600;
+
+/// Read the vm targeted by a process action, other kinds have no control channel
+pub async fn read_vm(
+  kind: &str,
+  name: &str,
+  namespace: &Option<String>,
+  state: &SystemState,
+) -> HttpResult<Vm> {
+  let kind: ProcessKind = kind.parse().map_err(HttpError::bad_request)?;
+  if kind != ProcessKind::Vm {
+    return Err(HttpError::bad_request(format!(
+      "Process kind {kind} doesn't support this action"
+    )));
+  }
+  let key = utils::key::gen_kind_key(&kind, name, namespace);
+  let vm = VmDb::transform_read_by_pk(&key, &state.inner.pool).await?;
+  Ok(vm)
+}
+
+/// Ask the guest to power off through ACPI and wait for qemu to exit.
+/// The caller is expected to stop the remaining processes after the timeout.
+pub async fn shutdown(vm_key: &str, state: &SystemState) -> IoResult<()> {
+  let path = utils::qmp::socket_path(vm_key, state);
+  let Ok(mut qmp) = QmpClient::connect(&path).await else {
+    log::debug!("vm_control::shutdown: {vm_key} is not running");
+    return Ok(());
+  };
+  let vm = VmDb::transform_read_by_pk(vm_key, &state.inner.pool).await?;
+  let timeout = vm
+    .spec
+    .host_config
+    .shutdown_timeout
+    .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT)
+    .min(MAX_SHUTDOWN_TIMEOUT);
+  // A paused guest can't handle the ACPI event
+  let status = qmp.execute("query-status", None).await?;
+  if status["status"] == "paused" {
+    qmp.execute("cont", None).await?;
+  }
+  qmp.execute("system_powerdown", None).await?;
+  let processes =
+    ProcessDb::read_by_kind_key(vm_key, None, &state.inner.pool).await?;
+  for process in processes {
+    let mut stream = state.inner.docker_api.wait_container(
+      &process.key,
+      Some(WaitContainerOptions {
+        condition: "not-running",
+      }),
+    );
+    let wait = time::timeout(Millis::from_secs(timeout as u32), stream.next());
+    if wait.await.is_err() {
+      log::warn!("Vm {vm_key} didn't power off after {timeout}s, stopping it");
+      break;
+    }
+  }
+  Ok(())
+}
+
+/// Execute a command on a running vm and notify the system
+async fn execute(
+  vm: &Vm,
+  command: &str,
+  action: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let mut qmp = utils::qmp::connect_vm(vm, state).await?;
+  log::debug!("vm_control::{action}: {}", vm.spec.vm_key);
+  qmp.execute(command, None).await?;
+  utils::container::generic::emit(
+    &vm.spec.vm_key,
+    &ProcessKind::Vm,
+    NativeEventAction::Other(action.to_owned()),
+    state,
+  )
+  .await?;
+  Ok(())
+}
+
+/// Freeze the cpus of a running vm
+pub async fn pause(vm: &Vm, state: &SystemState) -> HttpResult<()> {
+  execute(vm, "stop", "pause", state).await
+}
+
+/// Resume the cpus of a paused vm
+pub async fn resume(vm: &Vm, state: &SystemState) -> HttpResult<()> {
+  execute(vm, "cont", "resume", state).await
+}
+
+/// Reset a running vm like the reset button of a computer
+pub async fn reset(vm: &Vm, state: &SystemState) -> HttpResult<()> {
+  execute(vm, "system_reset", "reset", state).await
+}
+
+/// Generate the `device_add` arguments to plug `count` cpus
+/// from the reply of `query-hotpluggable-cpus`
+fn gen_cpu_devices(
+  cpus: &serde_json::Value,
+  count: u64,
+) -> HttpResult<Vec<serde_json::Value>> {
+  let cpus = cpus.as_array().cloned().unwrap_or_default();
+  // Qemu lists the slots from the last one
+  let devices = cpus
+    .iter()
+    .rev()
+    .enumerate()
+    .filter(|(_, cpu)| cpu.get("qom-path").is_none())
+    .take(count as usize)
+    .map(|(i, cpu)| {
+      let mut device = cpu["props"].clone();
+      device["driver"] = cpu["type"].clone();
+      device["id"] = format!("cpu{i}").into();
+      device
+    })
+    .collect::<Vec<_>>();
+  if devices.len() < count as usize {
+    return Err(HttpError::bad_request(format!(
+      "Only {} cpu can be added",
+      devices.len()
+    )));
+  }
+  Ok(devices)
+}
+
+/// Check the id of a network card, it's also the name of its tap interface
+fn validate_nic(nic: &VmNic) -> HttpResult<()> {
+  let valid = !nic.id.is_empty()
+    && nic.id.len() <= 15
+    && nic
+      .id
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !valid {
+    return Err(HttpError::bad_request(format!(
+      "Invalid network card id {}, expected at most 15 alphanumeric characters",
+      nic.id
+    )));
+  }
+  Ok(())
+}
+
+/// Plug cpus in a running vm
+async fn hotplug_cpu(
+  vm: &Vm,
+  count: u64,
+  qmp: &mut QmpClient,
+) -> HttpResult<()> {
+  if vm.spec.host_config.max_cpu.is_none() {
+    return Err(HttpError::bad_request(format!(
+      "Vm {} must be created with a max cpu to hotplug cpus",
+      vm.spec.vm_key
+    )));
+  }
+  let cpus = qmp.execute("query-hotpluggable-cpus", None).await?;
+  for device in gen_cpu_devices(&cpus, count)? {
+    qmp.execute("device_add", Some(device)).await?;
+  }
+  Ok(())
+}
+
+/// Plug a memory module in a running vm
+async fn hotplug_memory(
+  vm: &Vm,
+  size: u64,
+  qmp: &mut QmpClient,
+) -> HttpResult<()> {
+  let Some(max_memory) = vm.spec.host_config.max_memory else {
+    return Err(HttpError::bad_request(format!(
+      "Vm {} must be created with a max memory to hotplug memory",
+      vm.spec.vm_key
+    )));
+  };
+  let too_large = || {
+    HttpError::bad_request(format!(
+      "Vm {} can only grow to {max_memory} MB",
+      vm.spec.vm_key
+    ))
+  };
+  let size = size.checked_mul(1024 * 1024).ok_or_else(too_large)?;
+  let max_memory_bytes =
+    max_memory.checked_mul(1024 * 1024).ok_or_else(too_large)?;
+  let summary = qmp.execute("query-memory-size-summary", None).await?;
+  let current = summary["base-memory"].as_u64().unwrap_or_default()
+    + summary["plugged-memory"].as_u64().unwrap_or_default();
+  match current.checked_add(size) {
+    Some(total) if size > 0 && total <= max_memory_bytes => {}
+    _ => return Err(too_large()),
+  }
+  let modules = qmp.execute("query-memory-devices", None).await?;
+  let (memdev, dimm) = gen_memory_ids(&modules);
+  qmp
+    .execute(
+      "object-add",
+      Some(serde_json::json!({
+        "qom-type": "memory-backend-ram",
+        "id": memdev,
+        "size": size,
+      })),
+    )
+    .await?;
+  let res = qmp
+    .execute(
+      "device_add",
+      Some(serde_json::json!({
+        "driver": "pc-dimm",
+        "id": dimm,
+        "memdev": memdev,
+      })),
+    )
+    .await;
+  if let Err(err) = res {
+    // Don't leave the backend behind, its id would be taken for the next plug
+    let args = serde_json::json!({ "id": memdev });
+    if let Err(err) = qmp.execute("object-del", Some(args)).await {
+      log::warn!("vm_control::hotplug_memory: {err}");
+    }
+    return Err(err.into());
+  }
+  Ok(())
+}
+
+/// Generate the ids of the backend and the dimm of a new memory module,
+/// the first ones not used by the plugged modules
+fn gen_memory_ids(modules: &serde_json::Value) -> (String, String) {
+  let used = modules
+    .as_array()
+    .into_iter()
+    .flatten()
+    .flat_map(|module| {
+      let data = &module["data"];
+      let memdev = data["memdev"].as_str().unwrap_or_default();
+      [
+        data["id"].as_str().unwrap_or_default().to_owned(),
+        memdev.trim_start_matches("/objects/").to_owned(),
+      ]
+    })
+    .collect::<Vec<_>>();
+  let i = (0..)
+    .find(|i| {
+      !used.contains(&format!("mem{i}")) && !used.contains(&format!("dimm{i}"))
+    })
+    .unwrap_or_default();
+  (format!("mem{i}"), format!("dimm{i}"))
+}
+
+/// Run a command in the runtime container of a vm and return its output
+async fn exec_runtime(
+  vm: &Vm,
+  cmd: &[&str],
+  state: &SystemState,
+) -> HttpResult<String> {
+  let name = format!("{}.v", vm.spec.vm_key);
+  let exec = state
+    .inner
+    .docker_api
+    .create_exec(
+      &name,
+      CreateExecOptions {
+        cmd: Some(cmd.iter().map(|arg| arg.to_string()).collect()),
+        attach_stdout: Some(true),
+        attach_stderr: Some(true),
+        ..Default::default()
+      },
+    )
+    .await?;
+  let mut output = String::new();
+  if let StartExecResults::Attached {
+    output: mut stream, ..
+  } = state.inner.docker_api.start_exec(&exec.id, None).await?
+  {
+    while let Some(log) = stream.next().await {
+      match log? {
+        LogOutput::StdOut { message } | LogOutput::StdErr { message } => {
+          output.push_str(&String::from_utf8_lossy(&message));
+        }
+        _ => {}
+      }
+    }
+  }
+  let inspect = state.inner.docker_api.inspect_exec(&exec.id).await?;
+  if inspect.exit_code.unwrap_or_default() != 0 {
+    return Err(HttpError::internal_server_error(format!(
+      "Vm {} failed to run {cmd:?}: {}",
+      vm.spec.vm_key,
+      output.trim()
+    )));
+  }
+  Ok(output)
+}
+
+/// Parse the bridge an interface is attached to from `ip -o link show`
+fn parse_master(link: &str) -> Option<String> {
+  let mut words = link.split_whitespace();
+  words.find(|word| *word == "master")?;
+  words.next().map(|master| master.to_owned())
+}
+
+/// Find the bridge the runtime container links the vm network with
+async fn read_runtime_bridge(
+  vm: &Vm,
+  state: &SystemState,
+) -> HttpResult<String> {
+  let iface = vm
+    .spec
+    .host_config
+    .link_net_iface
+    .clone()
+    .unwrap_or("eth0".into());
+  let link =
+    exec_runtime(vm, &["ip", "-o", "link", "show", "dev", &iface], state)
+      .await?;
+  parse_master(&link).ok_or_else(|| {
+    HttpError::conflict(format!(
+      "Vm {} network {iface} isn't attached to a bridge",
+      vm.spec.vm_key
+    ))
+  })
+}
+
+/// Plug a network card in a running vm backed by a new tap interface
+/// attached to the bridge of its runtime network
+async fn hotplug_nic(
+  vm: &Vm,
+  nic: &VmNic,
+  qmp: &mut QmpClient,
+  state: &SystemState,
+) -> HttpResult<()> {
+  validate_nic(nic)?;
+  let bridge = read_runtime_bridge(vm, state).await?;
+  let netdev = format!("net-{}", nic.id);
+  qmp
+    .execute(
+      "netdev_add",
+      Some(serde_json::json!({
+        "type": "tap",
+        "id": netdev,
+        "ifname": nic.id,
+        "script": "no",
+        "downscript": "no",
+      })),
+    )
+    .await?;
+  let attach = exec_runtime(
+    vm,
+    &["ip", "link", "set", &nic.id, "master", &bridge, "up"],
+    state,
+  )
+  .await;
+  if let Err(err) = attach {
+    let args = serde_json::json!({ "id": netdev });
+    if let Err(err) = qmp.execute("netdev_del", Some(args)).await {
+      log::warn!("vm_control::hotplug_nic: {err}");
+    }
+    return Err(err);
+  }
+  let mut device = serde_json::json!({
+    "driver": "virtio-net-pci",
+    "id": nic.id,
+    "netdev": netdev,
+  });
+  if let Some(mac) = &nic.mac {
+    device["mac"] = mac.clone().into();
+  }
+  qmp.execute("device_add", Some(device)).await?;
+  Ok(())
+}
+
+/// Plug a device in a running vm, it's lost when the vm is restarted
+pub async fn hotplug(
+  vm: &Vm,
+  device: &VmHotplug,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let mut qmp = utils::qmp::connect_vm(vm, state).await?;
+  log::debug!("vm_control::hotplug: {} {device:?}", vm.spec.vm_key);
+  match device {
+    VmHotplug::Cpu(count) => hotplug_cpu(vm, *count, &mut qmp).await?,
+    VmHotplug::Memory(size) => hotplug_memory(vm, *size, &mut qmp).await?,
+    VmHotplug::Nic(nic) => hotplug_nic(vm, nic, &mut qmp, state).await?,
+  }
+  utils::container::generic::emit(
+    &vm.spec.vm_key,
+    &ProcessKind::Vm,
+    NativeEventAction::Other("hotplug".to_owned()),
+    state,
+  )
+  .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use nanocl_stubs::vm_spec::VmHostConfig;
+
+  use crate::utils::qmp::tests::serve;
+
+  use super::*;
+
+  #[ntex::test]
+  async fn memory_hotplug() {
+    let mut vm = Vm::default();
+    vm.spec.vm_key = "my-vm.global".to_owned();
+    vm.spec.host_config = VmHostConfig {
+      memory: 512,
+      max_memory: Some(1024),
+      ..Default::default()
+    };
+    let summary = r#"{"return": {"base-memory": 536870912}}"#;
+    let (path, server) = serve(
+      "memory-hotplug",
+      vec![
+        summary,
+        summary,
+        r#"{"return": []}"#,
+        r#"{"return": {}}"#,
+        r#"{"return": {}}"#,
+        summary,
+        r#"{"return": [{"type": "dimm", "data": {"id": "dimm0", "memdev": "/objects/mem0"}}]}"#,
+        r#"{"return": {}}"#,
+        r#"{"error": {"class": "GenericError", "desc": "no slot"}}"#,
+        r#"{"return": {}}"#,
+      ],
+    );
+    let mut qmp = QmpClient::connect(&path).await.unwrap();
+    let err = hotplug_memory(&vm, u64::MAX, &mut qmp).await.unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::BAD_REQUEST);
+    let err = hotplug_memory(&vm, 1024, &mut qmp).await.unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::BAD_REQUEST);
+    hotplug_memory(&vm, 512, &mut qmp).await.unwrap();
+    assert!(hotplug_memory(&vm, 256, &mut qmp).await.is_err());
+    drop(qmp);
+    let commands = server.await.unwrap();
+    assert_eq!(commands[4]["arguments"]["id"], "mem0");
+    assert_eq!(commands[5]["arguments"]["id"], "dimm0");
+    assert_eq!(commands[8]["arguments"]["id"], "mem1");
+    assert_eq!(commands[9]["arguments"]["id"], "dimm1");
+    assert_eq!(commands[10]["arguments"]["id"], "mem1");
+    let commands = commands
+      .iter()
+      .map(|command| command["execute"].as_str().unwrap())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      commands,
+      vec![
+        "qmp_capabilities",
+        "query-memory-size-summary",
+        "query-memory-size-summary",
+        "query-memory-devices",
+        "object-add",
+        "device_add",
+        "query-memory-size-summary",
+        "query-memory-devices",
+        "object-add",
+        "device_add",
+        "object-del",
+      ]
+    );
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[test]
+  fn runtime_bridge() {
+    let link = "2: eth0@if7: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500 \
+      qdisc noqueue master br0 state UP mode DEFAULT";
+    assert_eq!(parse_master(link), Some("br0".to_owned()));
+    let link = "2: eth0@if7: <BROADCAST,MULTICAST,UP,LOWER_UP> mtu 1500";
+    assert_eq!(parse_master(link), None);
+  }
+
+  #[test]
+  fn cpu_devices() {
+    let cpus = serde_json::json!([
+      {
+        "props": { "core-id": 0, "thread-id": 0, "socket-id": 2 },
+        "vcpus-count": 1,
+        "type": "qemu64-x86_64-cpu"
+      },
+      {
+        "props": { "core-id": 0, "thread-id": 0, "socket-id": 1 },
+        "vcpus-count": 1,
+        "type": "qemu64-x86_64-cpu"
+      },
+      {
+        "props": { "core-id": 0, "thread-id": 0, "socket-id": 0 },
+        "vcpus-count": 1,
+        "qom-path": "/machine/unattached/device[0]",
+        "type": "qemu64-x86_64-cpu"
+      }
+    ]);
+    let devices = gen_cpu_devices(&cpus, 1).unwrap();
+    assert_eq!(
+      devices,
+      vec![serde_json::json!({
+        "core-id": 0,
+        "thread-id": 0,
+        "socket-id": 1,
+        "driver": "qemu64-x86_64-cpu",
+        "id": "cpu1",
+      })]
+    );
+    assert_eq!(gen_cpu_devices(&cpus, 2).unwrap().len(), 2);
+    assert!(gen_cpu_devices(&cpus, 3).is_err());
+    let nic = VmNic {
+      id: "eth1".to_owned(),
+      mac: None,
+    };
+    assert!(validate_nic(&nic).is_ok());
+    let nic = VmNic {
+      id: "a-very-long-interface".to_owned(),
+      mac: None,
+    };
+    assert!(validate_nic(&nic).is_err());
+  }
+}
@@ -1,16 +1,24 @@
 use std::process::Stdio;
 
+use futures::{stream, Stream};
 use ntex::{channel::mpsc::Receiver, rt, util::Bytes, web};
 use tokio::{fs, io::AsyncReadExt, process::Command};
 
 use nanocl_error::http::{HttpError, HttpResult};
 
-use nanocl_stubs::vm_image::{VmImageCloneStream, VmImageResizePayload};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  vm_image::{
+    VmImageCloneStream, VmImageExportFormat, VmImageExportQuery,
+    VmImageResizePayload,
+  },
+  vm_spec::VmDataDisk,
+};
 
 use crate::{
   models::{Pool, QemuImgInfo, SystemState, VmImageDb, VmImageUpdateDb},
   repositories::generic::*,
-  utils,
+  utils::qmp::QmpClient,
 };
 
 /// Delete a vm image from the database and the filesystem
@@ -30,10 +38,11 @@ pub async fn delete_by_pk(pk: &str, state: &SystemState) -> HttpResult<()> {
   Ok(())
 }
 
-/// Get the info of a vm image using qemu-img info command and parse the output
+/// Get the info of a vm image using qemu-img info command and parse the output,
+/// the image can be in use by a running vm
 pub async fn get_info(path: &str) -> HttpResult<QemuImgInfo> {
   let output = Command::new("qemu-img")
-    .args(["info", "--output=json", path])
+    .args(["info", "-U", "--output=json", path])
     .output()
     .await
     .map_err(|err| {
@@ -127,6 +136,192 @@ pub async fn create_snap(
   Ok(snap_image)
 }
 
+/// Read a vm image that can be used to create a snapshot
+pub async fn read_base(name: &str, pool: &Pool) -> HttpResult<VmImageDb> {
+  let image = VmImageDb::read_by_pk(name, pool).await?;
+  if image.kind.as_str() != "Base" {
+    return Err(HttpError::bad_request(format!("Image {name} is not a base image please convert the snapshot into a base image first")));
+  }
+  Ok(image)
+}
+
+/// Create a blank qcow2 vm image of the given size in GB as a `Volume`.
+/// Stored in the state directory and added to the database.
+/// It will be used as a data disk of a VM.
+pub async fn create_volume(
+  name: &str,
+  size: u64,
+  state: &SystemState,
+) -> HttpResult<VmImageDb> {
+  if VmImageDb::read_by_pk(name, &state.inner.pool).await.is_ok() {
+    return Err(HttpError::conflict(format!("Vm image {name} already used")));
+  }
+  let volume_path =
+    format!("{}/vms/images/{}.img", state.inner.config.state_dir, name);
+  let size = format!("{size}G");
+  let output = Command::new("qemu-img")
+    .args(["create", "-f", "qcow2", &volume_path, &size])
+    .output()
+    .await
+    .map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Failed to create volume {name}: {err}"
+      ))
+    })?;
+  output.status.success().then_some(()).ok_or(
+    HttpError::internal_server_error(format!(
+      "Failed to create volume {name}: {output:#?}"
+    )),
+  )?;
+  let img_info = get_info(&volume_path).await?;
+  let volume = VmImageDb {
+    name: name.to_owned(),
+    node_name: state.inner.config.hostname.clone(),
+    created_at: chrono::Utc::now().naive_utc(),
+    kind: "Volume".into(),
+    path: volume_path,
+    format: img_info.format,
+    size_actual: img_info.actual_size,
+    size_virtual: img_info.virtual_size,
+    parent: None,
+  };
+  let volume = VmImageDb::create_from(volume, &state.inner.pool).await?;
+  Ok(volume)
+}
+
+/// Name of the vm image backing a data disk of a VM
+pub fn disk_name(vm_key: &str, disk: &str) -> String {
+  format!("{vm_key}.disk.{disk}")
+}
+
+/// Escape the wildcards of a `LIKE` pattern so they match literally
+fn escape_like(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('%', "\\%")
+    .replace('_', "\\_")
+}
+
+/// Size in bytes of a disk given in GB
+fn disk_bytes(name: &str, size: u64) -> HttpResult<u64> {
+  size
+    .checked_mul(1 << 30)
+    .ok_or(HttpError::bad_request(format!(
+      "Disk {name} size {size}G is too big"
+    )))
+}
+
+/// Grow the block device of a running VM backed by a file through QMP
+async fn block_resize(
+  path: &str,
+  bytes: u64,
+  qmp: &mut QmpClient,
+) -> HttpResult<()> {
+  let blocks = qmp.execute("query-block", None).await?;
+  let Some(device) = blocks
+    .as_array()
+    .into_iter()
+    .flatten()
+    .find(|block| block["inserted"]["file"] == path)
+    .and_then(|block| block["device"].as_str())
+  else {
+    return Err(HttpError::conflict(format!(
+      "Disk {path} isn't attached to the vm"
+    )));
+  };
+  qmp
+    .execute(
+      "block_resize",
+      Some(serde_json::json!({ "device": device, "size": bytes })),
+    )
+    .await?;
+  Ok(())
+}
+
+/// Grow a disk of a running VM and save its new size
+async fn resize_live(
+  image: &VmImageDb,
+  bytes: u64,
+  qmp: &mut QmpClient,
+  pool: &Pool,
+) -> HttpResult<VmImageDb> {
+  block_resize(&image.path, bytes, qmp).await?;
+  let img_info = get_info(&image.path).await?;
+  let res = VmImageDb::update_pk(
+    &image.name,
+    VmImageUpdateDb {
+      size_actual: Some(img_info.actual_size),
+      size_virtual: Some(img_info.virtual_size),
+      ..Default::default()
+    },
+    pool,
+  )
+  .await?;
+  Ok(res)
+}
+
+/// Create the missing data disks of a VM, grow the ones with a bigger size
+/// and delete the ones removed from its spec.
+/// The disks of a running VM are grown live with `block_resize` through its
+/// `qmp` client since qemu holds their lock, the ones of a stopped VM
+/// with `qemu-img resize`. A removed disk is only deleted once the VM
+/// is stopped. Returns the vm images in the order of the disks.
+pub async fn sync_disks(
+  vm_key: &str,
+  disks: &[VmDataDisk],
+  mut qmp: Option<&mut QmpClient>,
+  state: &SystemState,
+) -> HttpResult<Vec<VmImageDb>> {
+  let pool = &state.inner.pool;
+  let mut images = Vec::new();
+  for disk in disks {
+    let name = disk_name(vm_key, &disk.name);
+    let size = disk.size.unwrap_or(20);
+    let bytes = disk_bytes(&name, size)?;
+    let image = match VmImageDb::read_by_pk(&name, pool).await {
+      // Only grow the disk, shrinking it could corrupt its file system
+      Ok(image) if bytes > image.size_virtual.max(0) as u64 => {
+        log::debug!("Resizing disk {name} to {size}G");
+        match qmp.as_deref_mut() {
+          Some(qmp) => resize_live(&image, bytes, qmp, pool).await?,
+          None => {
+            let payload = VmImageResizePayload {
+              size,
+              shrink: false,
+            };
+            resize(&image, &payload, pool).await?
+          }
+        }
+      }
+      Ok(image) => image,
+      Err(_) => match &disk.image {
+        Some(base) => {
+          let base = read_base(base, pool).await?;
+          create_snap(&name, size, &base, state).await?
+        }
+        None => create_volume(&name, size, state).await?,
+      },
+    };
+    images.push(image);
+  }
+  if qmp.is_some() {
+    return Ok(images);
+  }
+  let filter = GenericFilter::new()
+    .r#where(
+      "name",
+      GenericClause::Like(format!("{}%", escape_like(&disk_name(vm_key, "")))),
+    )
+    .r#where("kind", GenericClause::Ne("Base".into()));
+  for image in VmImageDb::read_by(&filter, pool).await? {
+    if !images.iter().any(|used| used.name == image.name) {
+      log::debug!("Deleting disk {}", image.name);
+      delete_by_pk(&image.name, state).await?;
+    }
+  }
+  Ok(images)
+}
+
 /// Clone a vm image snapshot from a `Snapshot` vm image.
 /// The snapshot is created using qemu-img create command using the `Snapshot` image.
 /// The created clone is a qcow2 image. Stored in the state directory and added to the database.
@@ -257,6 +452,146 @@ pub async fn clone(
   Ok(rx)
 }
 
+/// Read the chain of images a snapshot is based on using their `parent` links,
+/// starting with the image itself and ending with its `Base` image
+pub async fn read_chain(
+  image: &VmImageDb,
+  pool: &Pool,
+) -> HttpResult<Vec<VmImageDb>> {
+  let mut chain = vec![image.clone()];
+  while let Some(parent) = chain.last().and_then(|image| image.parent.clone()) {
+    if chain.iter().any(|image| image.name == parent) {
+      return Err(HttpError::internal_server_error(format!(
+        "Vm image {} has a cyclic parent chain",
+        image.name
+      )));
+    }
+    let parent = VmImageDb::read_by_pk(&parent, pool).await.map_err(|_| {
+      HttpError::not_found(format!(
+        "Vm image {parent} the parent of {} doesn't exist",
+        chain
+          .last()
+          .map(|image| image.name.as_str())
+          .unwrap_or_default()
+      ))
+    })?;
+    chain.push(parent);
+  }
+  Ok(chain)
+}
+
+/// Export a vm image as a stream of bytes.
+/// A snapshot is flattened with its parents into a standalone image
+/// using qemu-img convert, as a base image is streamed as it is
+/// when it's already in the wanted format and no compression is asked.
+/// The file is read as the client consumes the stream.
+pub async fn export(
+  image: &VmImageDb,
+  query: &VmImageExportQuery,
+  state: &SystemState,
+) -> HttpResult<impl Stream<Item = HttpResult<Bytes>>> {
+  let format = query.format.clone().unwrap_or_default();
+  let compress = query.compress.unwrap_or_default();
+  if compress && format != VmImageExportFormat::Qcow2 {
+    return Err(HttpError::bad_request(
+      "Compression is only supported by the qcow2 format",
+    ));
+  }
+  let chain = read_chain(image, &state.inner.pool).await?;
+  log::debug!(
+    "Exporting vm image {} from {}",
+    image.name,
+    chain
+      .iter()
+      .map(|image| image.name.as_str())
+      .collect::<Vec<_>>()
+      .join(" <- ")
+  );
+  // qemu-img needs every file of the chain to flatten the image
+  for image in &chain {
+    if fs::metadata(&image.path).await.is_err() {
+      return Err(HttpError::internal_server_error(format!(
+        "Vm image {} file {} is missing",
+        image.name, image.path
+      )));
+    }
+  }
+  let is_native =
+    image.parent.is_none() && image.format == format.to_string() && !compress;
+  let path = if is_native {
+    image.path.clone()
+  } else {
+    let export_dir = format!("{}/vms/exports", state.inner.config.state_dir);
+    fs::create_dir_all(&export_dir).await.map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to create {export_dir}: {err}"
+      ))
+    })?;
+    let path = format!(
+      "{export_dir}/{}.{}.{format}",
+      image.name,
+      uuid::Uuid::new_v4()
+    );
+    let format = format.to_string();
+    let mut args = vec!["convert", "-O", &format];
+    if compress {
+      args.push("-c");
+    }
+    args.push(&image.path);
+    args.push(&path);
+    let output =
+      Command::new("qemu-img")
+        .args(args)
+        .output()
+        .await
+        .map_err(|err| {
+          HttpError::internal_server_error(format!(
+            "Failed to export {}: {err}",
+            image.name
+          ))
+        })?;
+    if !output.status.success() {
+      let _ = fs::remove_file(&path).await;
+      return Err(HttpError::internal_server_error(format!(
+        "Failed to export {}: {}",
+        image.name,
+        String::from_utf8_lossy(&output.stderr)
+      )));
+    }
+    path
+  };
+  let file = fs::File::open(&path).await.map_err(|err| {
+    HttpError::internal_server_error(format!("Unable to read {path}: {err}"))
+  })?;
+  // The converted file is only needed by the opened descriptor
+  if !is_native {
+    let _ = fs::remove_file(&path).await;
+  }
+  let name = image.name.clone();
+  let buf = vec![0; 1 << 16];
+  let stream = stream::unfold(Some((file, buf)), move |state| {
+    let name = name.clone();
+    async move {
+      let (mut file, mut buf) = state?;
+      match file.read(&mut buf).await {
+        Ok(0) => None,
+        Ok(n) => {
+          let bytes = Bytes::copy_from_slice(&buf[..n]);
+          Some((Ok(bytes), Some((file, buf))))
+        }
+        Err(err) => {
+          log::warn!("Unable to export vm image {name}: {err}");
+          let err = HttpError::internal_server_error(format!(
+            "Unable to read vm image {name}: {err}"
+          ));
+          Some((Err(err), None))
+        }
+      }
+    }
+  });
+  Ok(stream)
+}
+
 /// Resize a vm image to a new size
 pub async fn resize(
   image: &VmImageDb,
@@ -291,8 +626,9 @@ pub async fn resize(
   let res = VmImageDb::update_pk(
     &image.name,
     VmImageUpdateDb {
-      size_actual: img_info.actual_size,
-      size_virtual: img_info.virtual_size,
+      size_actual: Some(img_info.actual_size),
+      size_virtual: Some(img_info.virtual_size),
+      ..Default::default()
     },
     pool,
   )
@@ -310,14 +646,57 @@ pub async fn resize_by_name(
   resize(&image, payload, pool).await
 }
 
-/// Create a vm image from a file as a `Base` image
+/// Formats of the imported images converted to qcow2,
+/// snapshots can only be created from qcow2 images
+pub const CONVERTED_FORMATS: [&str; 3] = ["raw", "vmdk", "vhdx"];
+
+/// Convert an image to qcow2 in place using qemu-img convert command
+pub async fn convert_to_qcow2(filepath: &str) -> HttpResult<()> {
+  let tmp_path = format!("{filepath}.qcow2");
+  let output = Command::new("qemu-img")
+    .args(["convert", "-O", "qcow2", filepath, &tmp_path])
+    .output()
+    .await
+    .map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Failed to convert {filepath} to qcow2: {err}"
+      ))
+    })?;
+  if !output.status.success() {
+    let _ = fs::remove_file(&tmp_path).await;
+    return Err(HttpError::internal_server_error(format!(
+      "Failed to convert {filepath} to qcow2: {}",
+      String::from_utf8_lossy(&output.stderr)
+    )));
+  }
+  fs::rename(&tmp_path, filepath).await.map_err(|err| {
+    HttpError::internal_server_error(format!(
+      "Failed to convert {filepath} to qcow2: {err}"
+    ))
+  })?;
+  Ok(())
+}
+
+/// Get the info of an imported image, converting it to qcow2 when needed
+async fn get_import_info(filepath: &str) -> HttpResult<QemuImgInfo> {
+  let img_info = get_info(filepath).await?;
+  if !CONVERTED_FORMATS.contains(&img_info.format.as_str()) {
+    return Ok(img_info);
+  }
+  log::debug!("Converting {filepath} from {} to qcow2", img_info.format);
+  convert_to_qcow2(filepath).await?;
+  get_info(filepath).await
+}
+
+/// Create a vm image from a file as a `Base` image.
+/// Raw, vmdk and vhdx files are converted to qcow2.
 pub async fn create(
   name: &str,
   filepath: &str,
   state: &SystemState,
 ) -> HttpResult<VmImageDb> {
   // Get image info
-  let img_info = match utils::vm_image::get_info(filepath).await {
+  let img_info = match get_import_info(filepath).await {
     Err(err) => {
       let fp2 = filepath.to_owned();
       let _ = web::block(move || std::fs::remove_file(fp2)).await;
@@ -339,3 +718,48 @@ pub async fn create(
   let image = VmImageDb::create_from(vm_image, &state.inner.pool).await?;
   Ok(image)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn disk_pattern() {
+    assert_eq!(
+      escape_like(&disk_name("my_vm.global", "")),
+      "my\\_vm.global.disk."
+    );
+    assert_eq!(escape_like("100%\\"), "100\\%\\\\");
+    assert_eq!(disk_bytes("data", 2).unwrap(), 2 << 30);
+    assert!(disk_bytes("data", u64::MAX).is_err());
+  }
+
+  #[ntex::test]
+  async fn live_resize() {
+    let (path, server) = crate::utils::qmp::tests::serve(
+      "live-resize",
+      vec![
+        r#"{"return": [{"device": "disk0", "inserted": {"file": "/images/data.img"}}]}"#,
+        r#"{"return": {}}"#,
+        r#"{"return": []}"#,
+      ],
+    );
+    let mut qmp = QmpClient::connect(&path).await.unwrap();
+    block_resize("/images/data.img", 2 << 30, &mut qmp)
+      .await
+      .unwrap();
+    let err = block_resize("/images/logs.img", 2 << 30, &mut qmp)
+      .await
+      .unwrap_err();
+    assert_eq!(err.status, ntex::http::StatusCode::CONFLICT);
+    drop(qmp);
+    let commands = server.await.unwrap();
+    assert_eq!(commands[1]["execute"], "query-block");
+    assert_eq!(commands[2]["execute"], "block_resize");
+    assert_eq!(commands[2]["arguments"]["device"], "disk0");
+    assert_eq!(commands[2]["arguments"]["size"], 2u64 << 30);
+    assert_eq!(commands[3]["execute"], "query-block");
+    assert_eq!(commands.len(), 4);
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -0,0 +1,740 @@
+use std::{fmt::Write, process::Stdio};
+
+use bollard_next::auth::DockerCredentials;
+use futures::StreamExt;
+use ntex::{
+  channel::mpsc::{self, Receiver, Sender},
+  http::{
+    client::{ClientResponse, Connector},
+    header, Client, StatusCode,
+  },
+  rt,
+  time::Millis,
+  util::Bytes,
+};
+use serde::Deserialize;
+use tokio::{
+  fs,
+  io::{AsyncReadExt, AsyncWriteExt},
+  process::Command,
+};
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::vm_image::{
+  VmImagePullPayload, VmImagePullProgress, VmImagePullStream, VmImageSource,
+};
+
+use crate::{
+  models::{SystemState, VmImageDb},
+  repositories::generic::*,
+  utils,
+};
+
+/// Media types accepted for the manifest of an OCI artifact
+const OCI_MANIFEST_TYPES: &str = "application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.v2+json";
+
+/// Minimum number of bytes downloaded between two progress messages
+const PROGRESS_STEP: u64 = 1 << 20;
+
+/// Maximum number of redirects followed by a request
+const MAX_REDIRECTS: usize = 10;
+
+/// Reference of an OCI artifact as `[registry/]repository[:tag|@digest]`
+#[derive(Debug, PartialEq)]
+struct OciReference {
+  registry: String,
+  repository: String,
+  reference: String,
+}
+
+impl OciReference {
+  /// Parse a reference, the registry default to the docker hub
+  fn parse(reference: &str) -> HttpResult<Self> {
+    let invalid =
+      || HttpError::bad_request(format!("Invalid OCI reference {reference}"));
+    let (registry, rest) = match reference.split_once('/') {
+      Some((registry, rest))
+        if registry.contains(['.', ':']) || registry == "localhost" =>
+      {
+        (registry.to_owned(), rest)
+      }
+      _ => ("registry-1.docker.io".to_owned(), reference),
+    };
+    let (repository, reference) = match rest.split_once('@') {
+      Some((repository, digest)) => (repository, digest),
+      None => match rest.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+        _ => (rest, "latest"),
+      },
+    };
+    if repository.is_empty() || reference.is_empty() {
+      return Err(invalid());
+    }
+    let repository =
+      if registry == "registry-1.docker.io" && !repository.contains('/') {
+        format!("library/{repository}")
+      } else {
+        repository.to_owned()
+      };
+    Ok(Self {
+      registry,
+      repository,
+      reference: reference.to_owned(),
+    })
+  }
+
+  /// Base url of the repository, plain http is only used for a local registry
+  fn base_url(&self) -> String {
+    let is_local = self.registry.starts_with("localhost")
+      || self.registry.starts_with("127.0.0.1");
+    let scheme = if is_local { "http" } else { "https" };
+    format!("{scheme}://{}/v2/{}", self.registry, self.repository)
+  }
+}
+
+/// Descriptor of a layer in an OCI manifest
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OciDescriptor {
+  media_type: Option<String>,
+  digest: String,
+  size: u64,
+}
+
+/// Blob of the layer of an OCI artifact to download
+struct OciBlob {
+  url: String,
+  headers: Vec<(String, String)>,
+  digest: String,
+  media_type: Option<String>,
+}
+
+/// Compression of an OCI layer given by the suffix of its media type
+#[derive(Debug, PartialEq)]
+enum LayerCompression {
+  Gzip,
+  Zstd,
+}
+
+impl LayerCompression {
+  /// Compression of a media type eg: `application/vnd.oci.image.layer.v1.tar+gzip`
+  fn from_media_type(media_type: &str) -> Option<Self> {
+    if media_type.ends_with("+gzip") || media_type.ends_with(".gzip") {
+      Some(Self::Gzip)
+    } else if media_type.ends_with("+zstd") || media_type.ends_with(".zstd") {
+      Some(Self::Zstd)
+    } else {
+      None
+    }
+  }
+
+  /// Program decompressing the layer with `-dc`
+  fn program(&self) -> &'static str {
+    match self {
+      Self::Gzip => "gzip",
+      Self::Zstd => "zstd",
+    }
+  }
+}
+
+/// Manifest of an OCI artifact, only the layers are used
+#[derive(Deserialize)]
+struct OciManifest {
+  layers: Vec<OciDescriptor>,
+}
+
+/// Token returned by the authentication server of a registry
+#[derive(Deserialize)]
+struct OciToken {
+  token: Option<String>,
+  access_token: Option<String>,
+}
+
+/// Parse a checksum as `sha256:<hex>` and return the lowercase hex digest
+fn parse_checksum(checksum: &str) -> HttpResult<String> {
+  let hex = checksum.strip_prefix("sha256:").unwrap_or(checksum);
+  if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Err(HttpError::bad_request(format!(
+      "Invalid checksum {checksum} expected sha256:<hex>"
+    )));
+  }
+  Ok(hex.to_lowercase())
+}
+
+/// Parse a `www-authenticate` bearer challenge into its realm and params
+fn parse_challenge(challenge: &str) -> Option<(String, Vec<(String, String)>)> {
+  let params = challenge.strip_prefix("Bearer ")?;
+  let mut realm = None;
+  let mut query = Vec::new();
+  let mut rest = params.trim();
+  while let Some((key, value)) = rest.split_once('=') {
+    let key = key.trim().trim_start_matches(',').trim().to_owned();
+    let (value, next) = match value.strip_prefix('"') {
+      Some(value) => value.split_once('"')?,
+      None => value.split_once(',').unwrap_or((value, "")),
+    };
+    if key == "realm" {
+      realm = Some(value.to_owned());
+    } else {
+      query.push((key, value.to_owned()));
+    }
+    rest = next;
+  }
+  Some((realm?, query))
+}
+
+/// Send a message to the client pulling an image
+fn send(tx: &Sender<HttpResult<Bytes>>, stream: &VmImagePullStream) {
+  let Ok(stream) = serde_json::to_string(stream) else {
+    return;
+  };
+  let _ = tx.send(Ok(Bytes::from(format!("{stream}\r\n"))));
+}
+
+/// Send a get request following the redirects,
+/// the headers are dropped when redirected to another host
+async fn send_get(
+  client: &Client,
+  url: &str,
+  headers: &[(String, String)],
+) -> HttpResult<ClientResponse> {
+  let mut url = url::Url::parse(url).map_err(|err| {
+    HttpError::bad_request(format!("Invalid url {url}: {err}"))
+  })?;
+  let mut headers = headers.to_vec();
+  for _ in 0..MAX_REDIRECTS {
+    let mut req = client.get(url.as_str()).no_decompress();
+    for (key, value) in &headers {
+      req = req.header(key.as_str(), value.as_str());
+    }
+    let res = req.send().await.map_err(|err| {
+      HttpError::bad_gateway(format!("Unable to get {url}: {err}"))
+    })?;
+    if !res.status().is_redirection() {
+      return Ok(res);
+    }
+    let location = res
+      .headers()
+      .get(header::LOCATION)
+      .and_then(|location| location.to_str().ok())
+      .and_then(|location| url.join(location).ok())
+      .ok_or(HttpError::bad_gateway(format!(
+        "Invalid redirection from {url}"
+      )))?;
+    if location.host_str() != url.host_str() {
+      headers.clear();
+    }
+    url = location;
+  }
+  Err(HttpError::bad_gateway(format!(
+    "Too many redirections for {url}"
+  )))
+}
+
+/// Read the body of a small response
+async fn read_body(res: &mut ClientResponse, url: &str) -> HttpResult<Bytes> {
+  res.body().limit(4 << 20).await.map_err(|err| {
+    HttpError::bad_gateway(format!("Unable to read {url}: {err}"))
+  })
+}
+
+/// Value of a basic `authorization` header from registry credentials
+fn basic_auth(credentials: &DockerCredentials) -> Option<String> {
+  if let Some(auth) = &credentials.auth {
+    return Some(format!("Basic {auth}"));
+  }
+  let username = credentials.username.as_deref()?;
+  let password = credentials.password.as_deref().unwrap_or_default();
+  let auth =
+    openssl::base64::encode_block(format!("{username}:{password}").as_bytes());
+  Some(format!("Basic {auth}"))
+}
+
+/// Get a token from the authentication server of a registry,
+/// the credentials are sent when given otherwise the token is anonymous
+async fn fetch_token(
+  client: &Client,
+  challenge: &str,
+  credentials: Option<&DockerCredentials>,
+) -> HttpResult<String> {
+  let (realm, query) = parse_challenge(challenge).ok_or(
+    HttpError::bad_gateway(format!("Unsupported challenge {challenge}")),
+  )?;
+  let url = url::Url::parse_with_params(&realm, &query).map_err(|err| {
+    HttpError::bad_gateway(format!("Invalid realm {realm}: {err}"))
+  })?;
+  let headers = credentials
+    .and_then(basic_auth)
+    .map(|auth| vec![("authorization".to_owned(), auth)])
+    .unwrap_or_default();
+  let mut res = send_get(client, url.as_str(), &headers).await?;
+  if !res.status().is_success() {
+    return Err(HttpError::bad_gateway(format!(
+      "Unable to authenticate to {realm}: {}",
+      res.status()
+    )));
+  }
+  let body = read_body(&mut res, &realm).await?;
+  let token = serde_json::from_slice::<OciToken>(&body).map_err(|err| {
+    HttpError::bad_gateway(format!("Invalid token from {realm}: {err}"))
+  })?;
+  token
+    .token
+    .or(token.access_token)
+    .ok_or(HttpError::bad_gateway(format!("No token from {realm}")))
+}
+
+/// Authorization answering the challenge of a registry
+async fn authorize(
+  client: &Client,
+  challenge: &str,
+  credentials: Option<&DockerCredentials>,
+) -> HttpResult<String> {
+  if challenge.starts_with("Basic") {
+    return credentials
+      .and_then(basic_auth)
+      .ok_or(HttpError::bad_request(
+        "The registry requires credentials, set an image pull secret",
+      ));
+  }
+  if let Some(token) =
+    credentials.and_then(|credentials| credentials.registrytoken.clone())
+  {
+    return Ok(format!("Bearer {token}"));
+  }
+  let token = fetch_token(client, challenge, credentials).await?;
+  Ok(format!("Bearer {token}"))
+}
+
+/// Resolve an OCI artifact into the blob of its biggest layer
+async fn resolve_oci(
+  client: &Client,
+  reference: &str,
+  credentials: Option<&DockerCredentials>,
+) -> HttpResult<OciBlob> {
+  let oci = OciReference::parse(reference)?;
+  let url = format!("{}/manifests/{}", oci.base_url(), oci.reference);
+  let mut headers = vec![("accept".to_owned(), OCI_MANIFEST_TYPES.to_owned())];
+  let mut res = send_get(client, &url, &headers).await?;
+  if res.status() == StatusCode::UNAUTHORIZED {
+    let challenge = res
+      .headers()
+      .get(header::WWW_AUTHENTICATE)
+      .and_then(|challenge| challenge.to_str().ok())
+      .unwrap_or_default()
+      .to_owned();
+    let authorization = authorize(client, &challenge, credentials).await?;
+    headers.push(("authorization".to_owned(), authorization));
+    res = send_get(client, &url, &headers).await?;
+  }
+  if !res.status().is_success() {
+    return Err(HttpError::bad_gateway(format!(
+      "Unable to get the manifest of {reference}: {}",
+      res.status()
+    )));
+  }
+  let body = read_body(&mut res, &url).await?;
+  let manifest =
+    serde_json::from_slice::<OciManifest>(&body).map_err(|err| {
+      HttpError::bad_request(format!(
+        "{reference} isn't a single artifact manifest: {err}"
+      ))
+    })?;
+  let layer = manifest
+    .layers
+    .into_iter()
+    .max_by_key(|layer| layer.size)
+    .ok_or(HttpError::bad_request(format!("{reference} has no layer")))?;
+  headers.retain(|(key, _)| key != "accept");
+  Ok(OciBlob {
+    url: format!("{}/blobs/{}", oci.base_url(), layer.digest),
+    headers,
+    digest: layer.digest,
+    media_type: layer.media_type,
+  })
+}
+
+/// Path of the validator of a partial download, it's an etag or a date
+fn validator_path(path: &str) -> String {
+  format!("{path}.validator")
+}
+
+/// Validator of a response usable in `if-range`, a weak etag can't be used
+fn get_validator(res: &ClientResponse) -> Option<String> {
+  let get = |name| {
+    res
+      .headers()
+      .get(name)
+      .and_then(|value| value.to_str().ok())
+      .map(|value| value.to_owned())
+  };
+  get(header::ETAG)
+    .filter(|etag| !etag.starts_with("W/"))
+    .or_else(|| get(header::LAST_MODIFIED))
+}
+
+/// Download a file, resuming a previous partial download of the same path.
+/// The download is resumed with `if-range` so the server sends the whole file
+/// again when it changed since, without a validator it restarts from scratch.
+async fn download(
+  client: &Client,
+  url: &str,
+  headers: &[(String, String)],
+  path: &str,
+  tx: &Sender<HttpResult<Bytes>>,
+) -> HttpResult<()> {
+  let validator = fs::read_to_string(validator_path(path)).await.ok();
+  let offset = match &validator {
+    Some(_) => fs::metadata(path).await.map(|m| m.len()).unwrap_or(0),
+    None => 0,
+  };
+  let mut headers = headers.to_vec();
+  if let (Some(validator), true) = (&validator, offset > 0) {
+    log::debug!("Resuming the download of {url} at {offset}");
+    headers.push(("range".to_owned(), format!("bytes={offset}-")));
+    headers.push(("if-range".to_owned(), validator.clone()));
+  }
+  let mut res = send_get(client, url, &headers).await?;
+  let status = res.status();
+  if status == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+    return Ok(());
+  }
+  if !status.is_success() {
+    return Err(HttpError::bad_gateway(format!(
+      "Unable to download {url}: {status}"
+    )));
+  }
+  let map_err = |err: std::io::Error| {
+    HttpError::internal_server_error(format!("Unable to write {path}: {err}"))
+  };
+  let is_resumed = status == StatusCode::PARTIAL_CONTENT && offset > 0;
+  let mut file = if is_resumed {
+    fs::OpenOptions::new()
+      .append(true)
+      .open(path)
+      .await
+      .map_err(map_err)?
+  } else {
+    if offset > 0 {
+      log::debug!("{url} changed since the last download, restarting");
+    }
+    match get_validator(&res) {
+      Some(validator) => fs::write(validator_path(path), validator)
+        .await
+        .map_err(map_err)?,
+      None => {
+        let _ = fs::remove_file(validator_path(path)).await;
+      }
+    }
+    fs::File::create(path).await.map_err(map_err)?
+  };
+  let mut current = if is_resumed { offset } else { 0 };
+  let total = res
+    .headers()
+    .get(header::CONTENT_LENGTH)
+    .and_then(|len| len.to_str().ok())
+    .and_then(|len| len.parse::<u64>().ok())
+    .map(|len| len + current);
+  let mut sent = current;
+  while let Some(bytes) = res.next().await {
+    let bytes = bytes.map_err(|err| {
+      HttpError::bad_gateway(format!("Unable to download {url}: {err}"))
+    })?;
+    file.write_all(&bytes).await.map_err(map_err)?;
+    current += bytes.len() as u64;
+    if current - sent >= PROGRESS_STEP {
+      sent = current;
+      send(
+        tx,
+        &VmImagePullStream::Progress(VmImagePullProgress { current, total }),
+      );
+    }
+  }
+  file.flush().await.map_err(map_err)?;
+  send(
+    tx,
+    &VmImagePullStream::Progress(VmImagePullProgress { current, total }),
+  );
+  Ok(())
+}
+
+/// Compute the sha256 of a file as lowercase hex
+async fn sha256(path: &str) -> HttpResult<String> {
+  let map_err = |err: std::io::Error| {
+    HttpError::internal_server_error(format!("Unable to read {path}: {err}"))
+  };
+  let mut file = fs::File::open(path).await.map_err(map_err)?;
+  let mut hasher = openssl::sha::Sha256::new();
+  let mut buf = vec![0; 1 << 16];
+  loop {
+    let n = file.read(&mut buf).await.map_err(map_err)?;
+    if n == 0 {
+      break;
+    }
+    hasher.update(&buf[..n]);
+  }
+  let hex = hasher.finish().iter().fold(String::new(), |mut hex, byte| {
+    let _ = write!(hex, "{byte:02x}");
+    hex
+  });
+  Ok(hex)
+}
+
+/// Decompress a layer into `output` with gzip or zstd
+async fn decompress(
+  input: &str,
+  output: &str,
+  compression: &LayerCompression,
+) -> HttpResult<()> {
+  let program = compression.program();
+  let file = std::fs::File::create(output).map_err(|err| {
+    HttpError::internal_server_error(format!("Unable to write {output}: {err}"))
+  })?;
+  let output = Command::new(program)
+    .args(["-dc", input])
+    .stdout(file)
+    .stderr(Stdio::piped())
+    .output()
+    .await
+    .map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to run {program}: {err}"
+      ))
+    })?;
+  if !output.status.success() {
+    return Err(HttpError::bad_request(format!(
+      "Unable to decompress {input} with {program}: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+  Ok(())
+}
+
+/// A pull in progress, the name is released when it's dropped
+struct PullGuard {
+  name: String,
+  state: SystemState,
+}
+
+impl PullGuard {
+  /// Register the pull of an image, a concurrent pull of the same name
+  /// is rejected since they would write the same partial download
+  fn new(name: &str, state: &SystemState) -> HttpResult<Self> {
+    let mut pulls = state
+      .inner
+      .vm_image_pulls
+      .lock()
+      .map_err(|err| HttpError::internal_server_error(err.to_string()))?;
+    if !pulls.insert(name.to_owned()) {
+      return Err(HttpError::conflict(format!(
+        "Vm image {name} is already being pulled"
+      )));
+    }
+    Ok(Self {
+      name: name.to_owned(),
+      state: state.clone(),
+    })
+  }
+}
+
+impl Drop for PullGuard {
+  fn drop(&mut self) {
+    if let Ok(mut pulls) = self.state.inner.vm_image_pulls.lock() {
+      pulls.remove(&self.name);
+    }
+  }
+}
+
+/// Download, verify and convert an image then add it as a `Base` image
+async fn pull_image(
+  name: &str,
+  payload: &VmImagePullPayload,
+  state: &SystemState,
+  tx: &Sender<HttpResult<Bytes>>,
+) -> HttpResult<()> {
+  let client = Client::build()
+    .connector(Connector::default().timeout(Millis::from_secs(10)).finish())
+    .timeout(Millis::from_secs(30))
+    .finish();
+  let credentials = utils::container::image::get_credentials(
+    payload.image_pull_secret.clone(),
+    state,
+  )
+  .await?;
+  let mut checksum = payload.checksum.clone();
+  let mut compression = None;
+  let (url, headers) = match &payload.source {
+    VmImageSource::Url(url) => (url.clone(), Vec::new()),
+    VmImageSource::Oci(reference) => {
+      let blob = resolve_oci(&client, reference, credentials.as_ref()).await?;
+      checksum = checksum.or(Some(blob.digest));
+      compression = blob
+        .media_type
+        .as_deref()
+        .and_then(LayerCompression::from_media_type);
+      (blob.url, blob.headers)
+    }
+  };
+  let images_dir = format!("{}/vms/images", state.inner.config.state_dir);
+  let part_path = format!("{images_dir}/{name}.img.part");
+  download(&client, &url, &headers, &part_path, tx).await?;
+  if let Some(checksum) = checksum {
+    let expected = parse_checksum(&checksum)?;
+    let actual = sha256(&part_path).await?;
+    if actual != expected {
+      let _ = fs::remove_file(&part_path).await;
+      let _ = fs::remove_file(validator_path(&part_path)).await;
+      return Err(HttpError::bad_request(format!(
+        "Checksum mismatch for {name} expected sha256:{expected} got sha256:{actual}"
+      )));
+    }
+  }
+  let mut download_path = part_path.clone();
+  if let Some(compression) = &compression {
+    download_path = format!("{images_dir}/{name}.img.layer");
+    let res = decompress(&part_path, &download_path, compression).await;
+    if res.is_err() {
+      let _ = fs::remove_file(&download_path).await;
+    }
+    res?;
+    let _ = fs::remove_file(&part_path).await;
+  }
+  let _ = fs::remove_file(validator_path(&part_path)).await;
+  let info = utils::vm_image::get_info(&download_path).await?;
+  if utils::vm_image::CONVERTED_FORMATS.contains(&info.format.as_str()) {
+    send(tx, &VmImagePullStream::Converting(info.format));
+  }
+  let filepath = format!("{images_dir}/{name}.img");
+  fs::rename(&download_path, &filepath).await.map_err(|err| {
+    HttpError::internal_server_error(format!(
+      "Unable to move {download_path}: {err}"
+    ))
+  })?;
+  let image = utils::vm_image::create(name, &filepath, state).await?;
+  send(tx, &VmImagePullStream::Done(image.into()));
+  Ok(())
+}
+
+/// Pull a vm image from an url or an OCI registry as a `Base` image.
+/// The progress is streamed back and an interrupted download is resumed
+/// when the same image is pulled again, only one pull of a name runs at once.
+pub async fn pull(
+  name: &str,
+  payload: &VmImagePullPayload,
+  state: &SystemState,
+) -> HttpResult<Receiver<HttpResult<Bytes>>> {
+  if VmImageDb::read_by_pk(name, &state.inner.pool).await.is_ok() {
+    return Err(HttpError::conflict(format!("Vm image {name} already used")));
+  }
+  if let Some(checksum) = &payload.checksum {
+    parse_checksum(checksum)?;
+  }
+  match &payload.source {
+    VmImageSource::Url(url)
+      if !url.starts_with("http://") && !url.starts_with("https://") =>
+    {
+      return Err(HttpError::bad_request(format!(
+        "Invalid url {url} expected http(s)://"
+      )));
+    }
+    VmImageSource::Oci(reference) => {
+      OciReference::parse(reference)?;
+    }
+    _ => {}
+  }
+  let guard = PullGuard::new(name, state)?;
+  let (tx, rx) = mpsc::channel::<HttpResult<Bytes>>();
+  let name = name.to_owned();
+  let payload = payload.clone();
+  let state = state.clone();
+  rt::spawn(async move {
+    if let Err(err) = pull_image(&name, &payload, &state, &tx).await {
+      log::warn!("Unable to pull vm image {name}: {err}");
+      let _ = tx.send(Err(err));
+    }
+    drop(guard);
+  });
+  Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn oci_reference() {
+    let oci = OciReference::parse("ubuntu").unwrap();
+    assert_eq!(oci.registry, "registry-1.docker.io");
+    assert_eq!(oci.repository, "library/ubuntu");
+    assert_eq!(oci.reference, "latest");
+    let oci = OciReference::parse("ghcr.io/org/images/ubuntu:24.04").unwrap();
+    assert_eq!(oci.repository, "org/images/ubuntu");
+    assert_eq!(oci.reference, "24.04");
+    assert_eq!(oci.base_url(), "https://ghcr.io/v2/org/images/ubuntu");
+    let oci = OciReference::parse("localhost:5000/ubuntu@sha256:abc").unwrap();
+    assert_eq!(oci.reference, "sha256:abc");
+    assert_eq!(oci.base_url(), "http://localhost:5000/v2/ubuntu");
+    assert!(OciReference::parse("ghcr.io/:tag").is_err());
+  }
+
+  #[test]
+  fn checksum_and_challenge() {
+    let hex = "A".repeat(64);
+    assert_eq!(
+      parse_checksum(&format!("sha256:{hex}")).unwrap(),
+      "a".repeat(64)
+    );
+    assert!(parse_checksum("md5:abc").is_err());
+    let (realm, query) = parse_challenge(
+      r#"Bearer realm="https://ghcr.io/token",service="ghcr.io",scope="repository:org/ubuntu:pull,push""#,
+    )
+    .unwrap();
+    assert_eq!(realm, "https://ghcr.io/token");
+    assert_eq!(
+      query,
+      vec![
+        ("service".to_owned(), "ghcr.io".to_owned()),
+        (
+          "scope".to_owned(),
+          "repository:org/ubuntu:pull,push".to_owned()
+        ),
+      ]
+    );
+    assert!(parse_challenge("Basic realm=\"registry\"").is_none());
+    let credentials = DockerCredentials {
+      username: Some("user".to_owned()),
+      password: Some("pass".to_owned()),
+      ..Default::default()
+    };
+    assert_eq!(
+      basic_auth(&credentials).as_deref(),
+      Some("Basic dXNlcjpwYXNz")
+    );
+  }
+
+  #[test]
+  fn layer_compression() {
+    assert_eq!(
+      LayerCompression::from_media_type(
+        "application/vnd.oci.image.layer.v1.tar+gzip"
+      ),
+      Some(LayerCompression::Gzip)
+    );
+    assert_eq!(
+      LayerCompression::from_media_type(
+        "application/vnd.docker.image.rootfs.diff.tar.gzip"
+      ),
+      Some(LayerCompression::Gzip)
+    );
+    assert_eq!(
+      LayerCompression::from_media_type(
+        "application/vnd.oci.image.layer.v1.tar+zstd"
+      ),
+      Some(LayerCompression::Zstd)
+    );
+    assert_eq!(
+      LayerCompression::from_media_type("application/vnd.acme.disk.qcow2"),
+      None
+    );
+  }
+}
@@ -0,0 +1,921 @@
+use std::{fmt::Write, time::Duration};
+
+use bollard_next::container::InspectContainerOptions;
+use futures::{stream, Stream, StreamExt};
+use ntex::{
+  channel::mpsc::{self, Receiver, Sender},
+  rt,
+  util::Bytes,
+};
+use rand::{thread_rng, Rng};
+use tokio::{
+  fs,
+  io::{AsyncReadExt, AsyncWriteExt},
+  process::Command,
+};
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::{
+  generic::{GenericClause, GenericFilter},
+  process::ProcessKind,
+  system::{NativeEventAction, ObjPsStatusKind},
+  vm::{
+    Vm, VmMigrateIncoming, VmMigrateMemory, VmMigratePayload, VmMigrateQuery,
+    VmMigrateStream, VmMigrateTransfer,
+  },
+};
+use nanocld_client::{ConnectOpts, NanocldClient};
+
+use crate::{
+  models::{
+    NodeDb, ProcessDb, SystemState, VmDb, VmImageDb, VmImageUpdateDb,
+    VmMigrationDb,
+  },
+  repositories::generic::*,
+  utils::{self, qmp::QmpClient},
+  vars,
+};
+
+/// Port of the migration stream in the runtime of the target node
+const MIGRATE_PORT: u16 = 4444;
+/// Port of the NBD server exporting the disks in the runtime of the target node
+const NBD_PORT: u16 = 10809;
+/// Port of the daemon of a node advertising only its address
+const NODE_PORT: u16 = 8585;
+/// Delay between two polls of the qemu jobs
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Attempts to reach the QMP socket of the incoming vm
+const QMP_ATTEMPTS: usize = 20;
+/// Size of the chunks of the images sent to the target node
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Id of the qemu object holding the pre shared key of a migration
+const TLS_CREDS: &str = "migrate-tls";
+/// Length of the token of a migration
+const TOKEN_LENGTH: usize = 64;
+
+fn send(tx: &Sender<HttpResult<Bytes>>, stream: &VmMigrateStream) {
+  let Ok(stream) = serde_json::to_string(stream) else {
+    return;
+  };
+  let _ = tx.send(Ok(Bytes::from(format!("{stream}\r\n"))));
+}
+
+/// Client of the daemon of another node
+fn node_client(node: &NodeDb) -> HttpResult<NanocldClient> {
+  let url = if node.endpoint.starts_with("http://")
+    || node.endpoint.starts_with("https://")
+  {
+    node.endpoint.clone()
+  } else {
+    format!("http://{}:{NODE_PORT}", node.endpoint)
+  };
+  let client = NanocldClient::connect_to(&ConnectOpts {
+    url,
+    version: Some(format!("v{}", vars::VERSION)),
+    ..Default::default()
+  })?;
+  Ok(client)
+}
+
+/// Filter the processes of the local node, the other nodes manage their own
+fn local_processes(state: &SystemState) -> GenericFilter {
+  GenericFilter::new().r#where(
+    "node_name",
+    GenericClause::Eq(state.inner.config.hostname.clone()),
+  )
+}
+
+/// Generate a random pre shared key in the hex format expected by qemu
+fn gen_psk() -> String {
+  let key: [u8; 32] = thread_rng().gen();
+  key.iter().fold(String::new(), |mut psk, byte| {
+    let _ = write!(psk, "{byte:02x}");
+    psk
+  })
+}
+
+/// Directory of the pre shared key of a migration,
+/// the runtime of the vm sees it at the same path
+fn tls_dir(vm_key: &str, state: &SystemState) -> String {
+  format!("{}/{vm_key}.tls", utils::qmp::run_dir(state))
+}
+
+/// Write the pre shared key of a migration readable by the daemon only
+async fn write_psk(
+  migration: &VmMigrationDb,
+  state: &SystemState,
+) -> HttpResult<String> {
+  let dir = tls_dir(&migration.vm_key, state);
+  let map_err = |err: std::io::Error| {
+    HttpError::internal_server_error(format!("Unable to write {dir}: {err}"))
+  };
+  let _ = fs::remove_dir_all(&dir).await;
+  fs::DirBuilder::new()
+    .mode(0o700)
+    .create(&dir)
+    .await
+    .map_err(map_err)?;
+  let mut file = fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .mode(0o600)
+    .open(format!("{dir}/keys.psk"))
+    .await
+    .map_err(map_err)?;
+  file
+    .write_all(format!("qemu:{}\n", migration.psk).as_bytes())
+    .await
+    .map_err(map_err)?;
+  file.flush().await.map_err(map_err)?;
+  Ok(dir)
+}
+
+/// Remove the pre shared key of a migration once it's over
+async fn remove_psk(vm_key: &str, state: &SystemState) {
+  let _ = fs::remove_dir_all(tls_dir(vm_key, state)).await;
+}
+
+/// Load the pre shared key of a migration in qemu,
+/// the memory and the disks of the vm are encrypted with it
+async fn add_tls_creds(
+  endpoint: &str,
+  dir: &str,
+  qmp: &mut QmpClient,
+) -> HttpResult<()> {
+  qmp
+    .execute(
+      "object-add",
+      Some(serde_json::json!({
+        "qom-type": "tls-creds-psk",
+        "id": TLS_CREDS,
+        "endpoint": endpoint,
+        "dir": dir,
+      })),
+    )
+    .await?;
+  qmp
+    .execute(
+      "migrate-set-parameters",
+      Some(serde_json::json!({ "tls-creds": TLS_CREDS })),
+    )
+    .await?;
+  Ok(())
+}
+
+/// Read the migration of a vm to this node issued with the given token
+async fn read_migration(
+  vm_key: &str,
+  token: &str,
+  state: &SystemState,
+) -> HttpResult<VmMigrationDb> {
+  let migration = VmMigrationDb::read_by_pk(vm_key, &state.inner.pool)
+    .await
+    .ok()
+    .filter(|migration| {
+      migration.token == token
+        && migration.node_name == state.inner.config.hostname
+    })
+    .ok_or(HttpError::forbidden(format!(
+      "No migration of vm {vm_key} to this node matches the token"
+    )))?;
+  Ok(migration)
+}
+
+/// Images owned by a vm, its main disk followed by its data disks
+async fn read_disks(
+  vm: &Vm,
+  state: &SystemState,
+) -> HttpResult<Vec<VmImageDb>> {
+  let pool = &state.inner.pool;
+  let mut disks = vec![VmImageDb::read_by_pk(&vm.spec.disk.image, pool).await?];
+  for disk in vm.spec.disks.as_deref().unwrap_or_default() {
+    let name = utils::vm_image::disk_name(&vm.spec.vm_key, &disk.name);
+    disks.push(VmImageDb::read_by_pk(&name, pool).await?);
+  }
+  Ok(disks)
+}
+
+/// Images the disks are based on, they can be shared with other vms
+/// so they are copied to the target node but stay on this one.
+/// The parents come first.
+async fn read_backing(
+  disks: &[VmImageDb],
+  state: &SystemState,
+) -> HttpResult<Vec<VmImageDb>> {
+  let mut backing: Vec<VmImageDb> = Vec::new();
+  for disk in disks {
+    let chain = utils::vm_image::read_chain(disk, &state.inner.pool).await?;
+    for image in chain.into_iter().skip(1).rev() {
+      if !backing.iter().any(|item| item.name == image.name) {
+        backing.push(image);
+      }
+    }
+  }
+  Ok(backing)
+}
+
+/// Find the block node of a disk in the reply of `query-block`,
+/// the drives are matched by their file
+fn find_block_node(blocks: &serde_json::Value, path: &str) -> Option<String> {
+  blocks
+    .as_array()?
+    .iter()
+    .find(|block| block["inserted"]["file"] == path)?["inserted"]["node-name"]
+    .as_str()
+    .map(|node| node.to_owned())
+}
+
+/// Block nodes of the disks of a running vm
+async fn read_block_nodes(
+  disks: &[VmImageDb],
+  qmp: &mut QmpClient,
+) -> HttpResult<Vec<String>> {
+  let blocks = qmp.execute("query-block", None).await?;
+  disks
+    .iter()
+    .map(|disk| {
+      find_block_node(&blocks, &disk.path).ok_or(
+        HttpError::internal_server_error(format!(
+          "Disk {} isn't attached to the vm",
+          disk.name
+        )),
+      )
+    })
+    .collect()
+}
+
+/// Stream a vm image to the target node
+async fn send_image(
+  client: &NanocldClient,
+  image: &VmImageDb,
+  migration: &VmMigrationDb,
+  tx: &Sender<HttpResult<Bytes>>,
+) -> HttpResult<()> {
+  let map_err = |err: std::io::Error| {
+    HttpError::internal_server_error(format!(
+      "Unable to read {}: {err}",
+      image.path
+    ))
+  };
+  let file = fs::File::open(&image.path).await.map_err(map_err)?;
+  let total = file.metadata().await.map_err(map_err)?.len();
+  let name = image.name.clone();
+  let progress = tx.clone();
+  let body = stream::unfold(Some((file, 0)), move |file| {
+    let name = name.clone();
+    let progress = progress.clone();
+    async move {
+      let (mut file, current) = file?;
+      let mut buf = vec![0; CHUNK_SIZE];
+      let n = match file.read(&mut buf).await {
+        Ok(0) => return None,
+        Ok(n) => n,
+        Err(err) => return Some((Err(err), None)),
+      };
+      buf.truncate(n);
+      let next = current + n as u64;
+      // Only notify each percent
+      if next * 100 / total.max(1) != current * 100 / total.max(1) {
+        let transfer = VmMigrateTransfer {
+          image: name,
+          current: next,
+          total,
+        };
+        send(&progress, &VmMigrateStream::Transfer(transfer));
+      }
+      Some((Ok(Bytes::from(buf)), Some((file, next))))
+    }
+  });
+  client
+    .send_post_stream(
+      &format!("/vms/images/{}/receive", image.name),
+      Box::pin(body),
+      Some(VmMigrateQuery {
+        namespace: None,
+        token: migration.token.clone(),
+      }),
+    )
+    .await?;
+  Ok(())
+}
+
+/// Write a vm image sent by another node at the path it has in the store.
+/// The image must belong to a vm migrating to this node with the given token.
+pub async fn receive<S, E>(
+  name: &str,
+  token: &str,
+  mut payload: S,
+  state: &SystemState,
+) -> HttpResult<()>
+where
+  S: Stream<Item = Result<Bytes, E>> + Unpin,
+  E: std::fmt::Display,
+{
+  let filter =
+    GenericFilter::new().r#where("token", GenericClause::Eq(token.to_owned()));
+  let migration = VmMigrationDb::read_one_by(&filter, &state.inner.pool)
+    .await
+    .ok()
+    .filter(|migration| migration.node_name == state.inner.config.hostname)
+    .ok_or(HttpError::forbidden("Invalid migration token"))?;
+  let vm =
+    VmDb::transform_read_by_pk(&migration.vm_key, &state.inner.pool).await?;
+  let disks = read_disks(&vm, state).await?;
+  let backing = read_backing(&disks, state).await?;
+  let Some(image) = disks
+    .into_iter()
+    .chain(backing)
+    .find(|image| image.name == name)
+  else {
+    return Err(HttpError::forbidden(format!(
+      "Vm image {name} doesn't belong to vm {}",
+      migration.vm_key
+    )));
+  };
+  if image.node_name == state.inner.config.hostname {
+    return Err(HttpError::conflict(format!(
+      "Vm image {name} is already on this node"
+    )));
+  }
+  let part_path = format!("{}.part", image.path);
+  let map_err = |err: std::io::Error| {
+    HttpError::internal_server_error(format!(
+      "Unable to write {part_path}: {err}"
+    ))
+  };
+  let mut file = fs::File::create(&part_path).await.map_err(map_err)?;
+  while let Some(bytes) = payload.next().await {
+    let bytes = bytes.map_err(|err| {
+      HttpError::internal_server_error(format!(
+        "Unable to receive vm image {name}: {err}"
+      ))
+    })?;
+    file.write_all(&bytes).await.map_err(map_err)?;
+  }
+  file.flush().await.map_err(map_err)?;
+  fs::rename(&part_path, &image.path).await.map_err(map_err)?;
+  Ok(())
+}
+
+/// Create the disks of an incoming vm empty on top of their backing images,
+/// they are filled by the source node through NBD
+async fn create_overlays(
+  disks: &[VmImageDb],
+  state: &SystemState,
+) -> HttpResult<()> {
+  for disk in disks {
+    if fs::metadata(&disk.path).await.is_ok() {
+      return Err(HttpError::conflict(format!(
+        "Vm image {} already exists on this node",
+        disk.name
+      )));
+    }
+    let mut args = vec!["create".to_owned(), "-f".into(), "qcow2".into()];
+    if let Some(parent) = &disk.parent {
+      let parent = VmImageDb::read_by_pk(parent, &state.inner.pool).await?;
+      args.extend(["-b".into(), parent.path, "-F".into(), parent.format]);
+    }
+    args.extend([disk.path.clone(), disk.size_virtual.to_string()]);
+    let output = Command::new("qemu-img")
+      .args(&args)
+      .output()
+      .await
+      .map_err(|err| {
+        HttpError::internal_server_error(format!(
+          "Unable to create {}: {err}",
+          disk.name
+        ))
+      })?;
+    if !output.status.success() {
+      return Err(HttpError::internal_server_error(format!(
+        "Unable to create {}: {}",
+        disk.name,
+        String::from_utf8_lossy(&output.stderr)
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Address of the runtime of a vm on the overlay network
+async fn read_overlay_address(
+  vm: &Vm,
+  key: &str,
+  state: &SystemState,
+) -> HttpResult<String> {
+  let inspect = state
+    .inner
+    .docker_api
+    .inspect_container(key, None::<InspectContainerOptions>)
+    .await?;
+  let address = inspect
+    .network_settings
+    .and_then(|settings| settings.networks)
+    .and_then(|networks| networks.get(utils::overlay::NETWORK).cloned())
+    .and_then(|network| network.ip_address)
+    .filter(|address| !address.is_empty())
+    .ok_or(HttpError::internal_server_error(format!(
+      "Incoming vm {} isn't on the overlay network",
+      vm.spec.vm_key
+    )))?;
+  Ok(address)
+}
+
+/// Start the runtime of a vm waiting for its memory and exporting its disks.
+/// The migration only listens on the overlay network between the nodes
+/// and is encrypted with the pre shared key of the migration.
+pub async fn incoming(
+  vm: &Vm,
+  token: &str,
+  state: &SystemState,
+) -> HttpResult<VmMigrateIncoming> {
+  if state.inner.config.overlay_subnet.is_none() {
+    return Err(HttpError::bad_request(
+      "The overlay network must be enabled to migrate a running vm",
+    ));
+  }
+  let migration = read_migration(&vm.spec.vm_key, token, state).await?;
+  let disks = read_disks(vm, state).await?;
+  create_overlays(&disks, state).await?;
+  let process =
+    utils::container::vm::create_instance(vm, &disks[0], true, true, state)
+      .await?;
+  // The runtime is connected by `create_instance` unless it has its own network
+  if vm.spec.host_config.runtime_network.is_some() {
+    utils::overlay::connect(&process.key, state).await?;
+  }
+  state
+    .inner
+    .docker_api
+    .start_container::<String>(&process.key, None)
+    .await?;
+  let address = read_overlay_address(vm, &process.key, state).await?;
+  let path = utils::qmp::socket_path(&vm.spec.vm_key, state);
+  let mut qmp = None;
+  for _ in 0..QMP_ATTEMPTS {
+    if let Ok(client) = QmpClient::connect(&path).await {
+      qmp = Some(client);
+      break;
+    }
+    ntex::time::sleep(POLL_INTERVAL).await;
+  }
+  let Some(mut qmp) = qmp else {
+    return Err(HttpError::internal_server_error(format!(
+      "Incoming vm {} didn't start",
+      vm.spec.vm_key
+    )));
+  };
+  let dir = write_psk(&migration, state).await?;
+  add_tls_creds("server", &dir, &mut qmp).await?;
+  qmp
+    .execute(
+      "migrate-incoming",
+      Some(
+        serde_json::json!({ "uri": format!("tcp:{address}:{MIGRATE_PORT}") }),
+      ),
+    )
+    .await?;
+  qmp
+    .execute(
+      "nbd-server-start",
+      Some(serde_json::json!({
+        "addr": {
+          "type": "inet",
+          "data": { "host": address, "port": NBD_PORT.to_string() },
+        },
+        "tls-creds": TLS_CREDS,
+      })),
+    )
+    .await?;
+  let nodes = read_block_nodes(&disks, &mut qmp).await?;
+  for (i, (disk, node)) in disks.iter().zip(nodes).enumerate() {
+    qmp
+      .execute(
+        "block-export-add",
+        Some(serde_json::json!({
+          "type": "nbd",
+          "id": format!("migrate{i}"),
+          "node-name": node,
+          "name": disk.name,
+          "writable": true,
+        })),
+      )
+      .await?;
+  }
+  Ok(VmMigrateIncoming { address })
+}
+
+/// Resume an incoming vm once its disks are in sync
+pub async fn finish(
+  vm: &Vm,
+  token: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  read_migration(&vm.spec.vm_key, token, state).await?;
+  let mut qmp = utils::qmp::connect_vm(vm, state).await?;
+  let exports = qmp.execute("query-block-exports", None).await?;
+  for export in exports.as_array().cloned().unwrap_or_default() {
+    qmp
+      .execute(
+        "block-export-del",
+        Some(serde_json::json!({ "id": export["id"] })),
+      )
+      .await?;
+  }
+  qmp.execute("nbd-server-stop", None).await?;
+  qmp.execute("cont", None).await?;
+  remove_psk(&vm.spec.vm_key, state).await;
+  Ok(())
+}
+
+/// Remove an incoming vm and its disks after a failed migration
+pub async fn abort(
+  vm: &Vm,
+  token: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  read_migration(&vm.spec.vm_key, token, state).await?;
+  let processes = ProcessDb::read_by_kind_key(
+    &vm.spec.vm_key,
+    Some(local_processes(state)),
+    &state.inner.pool,
+  )
+  .await?;
+  let keys = processes.into_iter().map(|p| p.key).collect::<Vec<_>>();
+  utils::container::process::delete_instances(&keys, state).await?;
+  for disk in read_disks(vm, state).await? {
+    // Never remove the disks of a vm living on this node
+    if disk.node_name != state.inner.config.hostname {
+      let _ = fs::remove_file(&disk.path).await;
+    }
+  }
+  remove_psk(&vm.spec.vm_key, state).await;
+  Ok(())
+}
+
+/// Wait for the mirrors of the disks to be ready to switch over
+async fn wait_mirrors(
+  disks: &[VmImageDb],
+  qmp: &mut QmpClient,
+  tx: &Sender<HttpResult<Bytes>>,
+) -> HttpResult<()> {
+  loop {
+    let jobs = qmp.execute("query-block-jobs", None).await?;
+    let jobs = jobs.as_array().cloned().unwrap_or_default();
+    let mut ready = true;
+    for (i, disk) in disks.iter().enumerate() {
+      let id = format!("migrate{i}");
+      let Some(job) = jobs.iter().find(|job| job["device"] == id) else {
+        return Err(HttpError::internal_server_error(format!(
+          "Mirror of {} failed",
+          disk.name
+        )));
+      };
+      let transfer = VmMigrateTransfer {
+        image: disk.name.clone(),
+        current: job["offset"].as_u64().unwrap_or_default(),
+        total: job["len"].as_u64().unwrap_or_default(),
+      };
+      send(tx, &VmMigrateStream::Transfer(transfer));
+      ready &= job["ready"].as_bool().unwrap_or_default();
+    }
+    if ready {
+      return Ok(());
+    }
+    ntex::time::sleep(POLL_INTERVAL).await;
+  }
+}
+
+/// Wait for the memory of the vm to be copied
+async fn wait_migration(
+  qmp: &mut QmpClient,
+  tx: &Sender<HttpResult<Bytes>>,
+) -> HttpResult<()> {
+  loop {
+    let info = qmp.execute("query-migrate", None).await?;
+    match info["status"].as_str().unwrap_or_default() {
+      "completed" => return Ok(()),
+      "failed" | "cancelled" => {
+        return Err(HttpError::internal_server_error(format!(
+          "Migration failed: {}",
+          info["error-desc"].as_str().unwrap_or("cancelled")
+        )));
+      }
+      _ => {}
+    }
+    let ram = &info["ram"];
+    let memory = VmMigrateMemory {
+      transferred: ram["transferred"].as_u64().unwrap_or_default(),
+      remaining: ram["remaining"].as_u64().unwrap_or_default(),
+      total: ram["total"].as_u64().unwrap_or_default(),
+    };
+    send(tx, &VmMigrateStream::Memory(memory));
+    ntex::time::sleep(POLL_INTERVAL).await;
+  }
+}
+
+/// Copy the disks and the memory of a running vm to the incoming one
+/// through a NBD block device and a migration encrypted with the pre shared key
+async fn migrate_live(
+  vm: &Vm,
+  disks: &[VmImageDb],
+  migration: &VmMigrationDb,
+  client: &NanocldClient,
+  mut qmp: QmpClient,
+  tx: &Sender<HttpResult<Bytes>>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let url = format!("/vms/{}/migrate/incoming", vm.spec.name);
+  let query = VmMigrateQuery {
+    namespace: Some(vm.namespace_name.clone()),
+    token: migration.token.clone(),
+  };
+  let res = client
+    .send_post(&url, None::<String>, Some(query.clone()))
+    .await?;
+  let incoming = NanocldClient::res_json::<VmMigrateIncoming>(res).await?;
+  let address = incoming.address;
+  let res = async {
+    let dir = write_psk(migration, state).await?;
+    add_tls_creds("client", &dir, &mut qmp).await?;
+    let nodes = read_block_nodes(disks, &mut qmp).await?;
+    for (i, (disk, node)) in disks.iter().zip(nodes).enumerate() {
+      qmp
+        .execute(
+          "blockdev-add",
+          Some(serde_json::json!({
+            "driver": "nbd",
+            "node-name": format!("migrate-target{i}"),
+            "server": {
+              "type": "inet",
+              "host": address,
+              "port": NBD_PORT.to_string(),
+            },
+            "export": disk.name,
+            "tls-creds": TLS_CREDS,
+          })),
+        )
+        .await?;
+      // The backing images are already on the target node
+      qmp
+        .execute(
+          "blockdev-mirror",
+          Some(serde_json::json!({
+            "job-id": format!("migrate{i}"),
+            "device": node,
+            "target": format!("migrate-target{i}"),
+            "sync": "top",
+          })),
+        )
+        .await?;
+    }
+    wait_mirrors(disks, &mut qmp, tx).await?;
+    qmp
+      .execute(
+        "migrate",
+        Some(
+          serde_json::json!({ "uri": format!("tcp:{address}:{MIGRATE_PORT}") }),
+        ),
+      )
+      .await?;
+    wait_migration(&mut qmp, tx).await?;
+    // The vm is paused, cancelling a ready mirror leaves its target in sync
+    for i in 0..disks.len() {
+      qmp
+        .execute(
+          "block-job-cancel",
+          Some(serde_json::json!({ "device": format!("migrate{i}") })),
+        )
+        .await?;
+    }
+    loop {
+      let jobs = qmp.execute("query-block-jobs", None).await?;
+      if jobs.as_array().map(|jobs| jobs.is_empty()).unwrap_or(true) {
+        break;
+      }
+      ntex::time::sleep(POLL_INTERVAL).await;
+    }
+    client
+      .send_post(
+        &format!("/vms/{}/migrate/finish", vm.spec.name),
+        None::<String>,
+        Some(query.clone()),
+      )
+      .await?;
+    Ok::<_, HttpError>(())
+  }
+  .await;
+  if let Err(err) = res {
+    log::warn!("Rolling back the migration of {}: {err}", vm.spec.vm_key);
+    let _ = qmp.execute("migrate_cancel", None).await;
+    for i in 0..disks.len() {
+      let _ = qmp
+        .execute(
+          "block-job-cancel",
+          Some(serde_json::json!({ "device": format!("migrate{i}"), "force": true })),
+        )
+        .await;
+      let _ = qmp
+        .execute(
+          "blockdev-del",
+          Some(
+            serde_json::json!({ "node-name": format!("migrate-target{i}") }),
+          ),
+        )
+        .await;
+    }
+    let _ = qmp
+      .execute(
+        "migrate-set-parameters",
+        Some(serde_json::json!({ "tls-creds": "" })),
+      )
+      .await;
+    let _ = qmp
+      .execute("object-del", Some(serde_json::json!({ "id": TLS_CREDS })))
+      .await;
+    let _ = qmp.execute("cont", None).await;
+    let _ = client.send_delete(&url, Some(query)).await;
+    return Err(err);
+  }
+  Ok(())
+}
+
+/// Move a vm to another node, its images records now point to the node
+async fn migrate_vm(
+  vm: &Vm,
+  node: &NodeDb,
+  migration: &VmMigrationDb,
+  tx: &Sender<HttpResult<Bytes>>,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let client = node_client(node)?;
+  let disks = read_disks(vm, state).await?;
+  utils::container::generic::emit(
+    &vm.spec.vm_key,
+    &ProcessKind::Vm,
+    NativeEventAction::Other("migrating".to_owned()),
+    state,
+  )
+  .await?;
+  for image in read_backing(&disks, state).await? {
+    send_image(&client, &image, migration, tx).await?;
+  }
+  let path = utils::qmp::socket_path(&vm.spec.vm_key, state);
+  let live = match QmpClient::connect(&path).await {
+    Ok(qmp) => {
+      migrate_live(vm, &disks, migration, &client, qmp, tx, state).await?;
+      true
+    }
+    // A stopped vm only needs its disks
+    Err(_) => {
+      for disk in &disks {
+        send_image(&client, disk, migration, tx).await?;
+      }
+      false
+    }
+  };
+  let processes = ProcessDb::read_by_kind_key(
+    &vm.spec.vm_key,
+    Some(local_processes(state)),
+    &state.inner.pool,
+  )
+  .await?;
+  let keys = processes.into_iter().map(|p| p.key).collect::<Vec<_>>();
+  utils::container::process::delete_instances(&keys, state).await?;
+  for disk in &disks {
+    VmImageDb::update_pk(
+      &disk.name,
+      VmImageUpdateDb {
+        node_name: Some(node.name.clone()),
+        ..Default::default()
+      },
+      &state.inner.pool,
+    )
+    .await?;
+    if let Err(err) = fs::remove_file(&disk.path).await {
+      log::warn!("Unable to remove {}: {err}", disk.path);
+    }
+  }
+  // A vm wanted running but not running here is started on the target node
+  if !live && vm.status.wanted == ObjPsStatusKind::Start {
+    client
+      .start_process("vm", &vm.spec.name, Some(&vm.namespace_name))
+      .await?;
+  }
+  utils::container::generic::emit(
+    &vm.spec.vm_key,
+    &ProcessKind::Vm,
+    NativeEventAction::Other("migrate".to_owned()),
+    state,
+  )
+  .await?;
+  send(tx, &VmMigrateStream::Done(node.name.clone()));
+  Ok(())
+}
+
+/// Migrate a vm to another node.
+/// A running vm keeps running while its disks and its memory are copied,
+/// it requires the overlay network between the nodes.
+/// The target node only accepts the vm with the token issued for the migration.
+/// The nodes are expected to share the same state directory layout.
+pub async fn migrate(
+  vm: &Vm,
+  payload: &VmMigratePayload,
+  state: &SystemState,
+) -> HttpResult<Receiver<HttpResult<Bytes>>> {
+  let hostname = &state.inner.config.hostname;
+  if &payload.node == hostname {
+    return Err(HttpError::bad_request(format!(
+      "Vm {} is already on node {hostname}",
+      vm.spec.vm_key
+    )));
+  }
+  let image =
+    VmImageDb::read_by_pk(&vm.spec.disk.image, &state.inner.pool).await?;
+  if &image.node_name != hostname {
+    return Err(HttpError::bad_request(format!(
+      "Vm {} is on node {}, migrate it from there",
+      vm.spec.vm_key, image.node_name
+    )));
+  }
+  let path = utils::qmp::socket_path(&vm.spec.vm_key, state);
+  if state.inner.config.overlay_subnet.is_none()
+    && QmpClient::connect(&path).await.is_ok()
+  {
+    return Err(HttpError::bad_request(
+      "The overlay network must be enabled to migrate a running vm",
+    ));
+  }
+  let node = NodeDb::read_by_pk(&payload.node, &state.inner.pool).await?;
+  let migration = VmMigrationDb {
+    vm_key: vm.spec.vm_key.clone(),
+    node_name: node.name.clone(),
+    token: utils::key::generate_short_id(TOKEN_LENGTH),
+    psk: gen_psk(),
+    created_at: chrono::Utc::now().naive_utc(),
+  };
+  let migration = VmMigrationDb::create_from(migration, &state.inner.pool)
+    .await
+    .map_err(|_| {
+      HttpError::conflict(format!(
+        "Vm {} is already being migrated",
+        vm.spec.vm_key
+      ))
+    })?;
+  let (tx, rx) = mpsc::channel::<HttpResult<Bytes>>();
+  let vm = vm.clone();
+  let state = state.clone();
+  rt::spawn(async move {
+    if let Err(err) = migrate_vm(&vm, &node, &migration, &tx, &state).await {
+      log::warn!("Unable to migrate vm {}: {err}", vm.spec.vm_key);
+      let _ = tx.send(Err(err));
+    }
+    remove_psk(&vm.spec.vm_key, &state).await;
+    if let Err(err) =
+      VmMigrationDb::del_by_pk(&vm.spec.vm_key, &state.inner.pool).await
+    {
+      log::warn!(
+        "Unable to remove the migration of {}: {err}",
+        vm.spec.vm_key
+      );
+    }
+  });
+  Ok(rx)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn block_node() {
+    let blocks = serde_json::json!([
+      {
+        "device": "ide0-hd0",
+        "inserted": { "file": "/images/main.img", "node-name": "#block143" }
+      },
+      { "device": "ide1-cd0" },
+      {
+        "device": "",
+        "inserted": { "file": "/images/data.img", "node-name": "#block512" }
+      }
+    ]);
+    assert_eq!(
+      find_block_node(&blocks, "/images/data.img").as_deref(),
+      Some("#block512")
+    );
+    assert_eq!(
+      find_block_node(&blocks, "/images/main.img").as_deref(),
+      Some("#block143")
+    );
+    assert!(find_block_node(&blocks, "/images/other.img").is_none());
+  }
+
+  #[test]
+  fn psk() {
+    let psk = gen_psk();
+    assert_eq!(psk.len(), 64);
+    assert!(psk.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(psk, gen_psk());
+  }
+}
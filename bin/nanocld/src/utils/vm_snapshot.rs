@@ -0,0 +1,119 @@
+use tokio::process::Command;
+
+use nanocl_error::http::{HttpError, HttpResult};
+use nanocl_stubs::vm::{Vm, VmSnapshot, VmSnapshotPartial};
+
+use crate::{
+  models::{SystemState, VmImageDb},
+  repositories::generic::*,
+  utils::{self, qmp::QmpClient},
+};
+
+/// Read a snapshot of a vm by name
+async fn read(
+  vm: &Vm,
+  name: &str,
+  state: &SystemState,
+) -> HttpResult<VmSnapshot> {
+  list(vm, state)
+    .await?
+    .into_iter()
+    .find(|snapshot| snapshot.name == name)
+    .ok_or(HttpError::not_found(format!(
+      "Snapshot {name} of vm {} not found",
+      vm.spec.vm_key
+    )))
+}
+
+/// List the snapshots of a vm, they are stored in its main disk
+pub async fn list(vm: &Vm, state: &SystemState) -> HttpResult<Vec<VmSnapshot>> {
+  let image =
+    VmImageDb::read_by_pk(&vm.spec.disk.image, &state.inner.pool).await?;
+  let info = utils::vm_image::get_info(&image.path).await?;
+  let snapshots = info
+    .snapshots
+    .unwrap_or_default()
+    .into_iter()
+    .map(VmSnapshot::from)
+    .collect();
+  Ok(snapshots)
+}
+
+/// Save the disks and the memory of a running vm
+pub async fn create(
+  vm: &Vm,
+  partial: &VmSnapshotPartial,
+  state: &SystemState,
+) -> HttpResult<VmSnapshot> {
+  let name = &partial.name;
+  if name.is_empty() {
+    return Err(HttpError::bad_request("Snapshot name cannot be empty"));
+  }
+  utils::key::validate_name(name)?;
+  if read(vm, name, state).await.is_ok() {
+    return Err(HttpError::conflict(format!(
+      "Snapshot {name} of vm {} already exists",
+      vm.spec.vm_key
+    )));
+  }
+  let mut qmp = utils::qmp::connect_vm(vm, state).await?;
+  log::debug!("Saving vm {} as {name}", vm.spec.vm_key);
+  qmp.execute_hmp(&format!("savevm {name}")).await?;
+  read(vm, name, state).await
+}
+
+/// Restore the disks and the memory of a running vm
+pub async fn restore(
+  vm: &Vm,
+  name: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  read(vm, name, state).await?;
+  let mut qmp = utils::qmp::connect_vm(vm, state).await?;
+  log::debug!("Restoring vm {} from {name}", vm.spec.vm_key);
+  qmp.execute_hmp(&format!("loadvm {name}")).await?;
+  Ok(())
+}
+
+/// Delete a snapshot of a vm, from its disks directly when it's stopped
+pub async fn delete(
+  vm: &Vm,
+  name: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  read(vm, name, state).await?;
+  let path = utils::qmp::socket_path(&vm.spec.vm_key, state);
+  if let Ok(mut qmp) = QmpClient::connect(&path).await {
+    qmp.execute_hmp(&format!("delvm {name}")).await?;
+    return Ok(());
+  }
+  let mut images = vec![vm.spec.disk.image.clone()];
+  for disk in vm.spec.disks.as_deref().unwrap_or_default() {
+    images.push(utils::vm_image::disk_name(&vm.spec.vm_key, &disk.name));
+  }
+  for (i, image) in images.iter().enumerate() {
+    let image = VmImageDb::read_by_pk(image, &state.inner.pool).await?;
+    let output = Command::new("qemu-img")
+      .args(["snapshot", "-d", name, &image.path])
+      .output()
+      .await
+      .map_err(|err| {
+        HttpError::internal_server_error(format!(
+          "Failed to delete snapshot {name}: {err}"
+        ))
+      })?;
+    if output.status.success() {
+      continue;
+    }
+    let err = String::from_utf8_lossy(&output.stderr);
+    // Data disks attached after the snapshot don't have it
+    if i > 0 {
+      log::warn!("Unable to delete snapshot {name} of {}: {err}", image.name);
+      continue;
+    }
+    return Err(HttpError::internal_server_error(format!(
+      "Failed to delete snapshot {name}: {err}"
+    )));
+  }
+  Ok(())
+}
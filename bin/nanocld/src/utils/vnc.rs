@@ -0,0 +1,11 @@
+use crate::{models::SystemState, utils};
+
+/// Path of the VNC socket of a vm, next to its QMP socket
+pub fn socket_path(vm_key: &str, state: &SystemState) -> String {
+  utils::qmp::gen_socket_path(&utils::qmp::run_dir(state), vm_key, "vnc")
+}
+
+/// Qemu argument exposing the display of a vm on its VNC socket
+pub fn gen_arg(vm_key: &str, state: &SystemState) -> String {
+  format!("unix:{}", socket_path(vm_key, state))
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use bollard_next::{
+  container::Config,
+  secret::HostConfig,
+  volume::{CreateVolumeOptions, RemoveVolumeOptions},
+};
+use tokio::fs;
+
+use nanocl_error::{
+  http::{HttpError, HttpResult},
+  io::{FromIo, IoError, IoResult},
+};
+use nanocl_stubs::{
+  generic::GenericFilter,
+  job::JobPartial,
+  volume::{Volume, VolumeMount},
+};
+
+use crate::{
+  models::{CargoDb, JobDb, SystemState, VolumeDb},
+  objects::generic::*,
+  repositories::generic::*,
+  utils, vars,
+};
+
+/// Number of archives kept when the backup policy doesn't set it
+const DEFAULT_RETENTION: usize = 7;
+
+/// Key of the volume targeted by a mount,
+/// a name without namespace belongs to the namespace of the mount
+pub fn resolve_key(name: &str, namespace: &str) -> String {
+  if name.contains('.') {
+    return name.to_owned();
+  }
+  utils::key::gen_key(namespace, name)
+}
+
+/// Ensure the mounts target existing volumes at distinct absolute paths
+pub async fn validate_mounts(
+  mounts: &Option<Vec<VolumeMount>>,
+  namespace: &str,
+  state: &SystemState,
+) -> HttpResult<()> {
+  let mounts = mounts.as_deref().unwrap_or_default();
+  for (i, mount) in mounts.iter().enumerate() {
+    if !mount.path.starts_with('/') {
+      return Err(HttpError::bad_request(format!(
+        "Volume {}: {} isn't an absolute path",
+        mount.name, mount.path
+      )));
+    }
+    if mounts[..i].iter().any(|other| other.path == mount.path) {
+      return Err(HttpError::bad_request(format!(
+        "Volume {}: {} is already mounted",
+        mount.name, mount.path
+      )));
+    }
+    let key = resolve_key(&mount.name, namespace);
+    if VolumeDb::read_by_pk(&key, &state.inner.pool).await.is_err() {
+      return Err(HttpError::bad_request(format!(
+        "Volume {key} doesn't exist"
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Generate the binds of the mounts, the volumes must be stored on this node
+pub async fn gen_binds(
+  mounts: &Option<Vec<VolumeMount>>,
+  namespace: &str,
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mut binds = Vec::new();
+  for mount in mounts.as_deref().unwrap_or_default() {
+    let key = resolve_key(&mount.name, namespace);
+    let volume = VolumeDb::read_by_pk(&key, &state.inner.pool).await?;
+    if volume.node_name != state.inner.config.hostname {
+      return Err(IoError::invalid_input(
+        "Volume",
+        &format!("{key} is stored on node {}", volume.node_name),
+      ));
+    }
+    let mode = if mount.read_only.unwrap_or_default() {
+      ":ro"
+    } else {
+      ""
+    };
+    binds.push(format!("{key}:{}{mode}", mount.path));
+  }
+  Ok(binds)
+}
+
+/// Ensure a volume is stored on this node, its docker volume and its backups
+/// can only be managed by the node storing it
+pub fn ensure_local(volume: &Volume, state: &SystemState) -> HttpResult<()> {
+  if volume.node_name != state.inner.config.hostname {
+    return Err(HttpError::conflict(format!(
+      "Volume {}: stored on node {}",
+      volume.key, volume.node_name
+    )));
+  }
+  Ok(())
+}
+
+/// Keys of the cargoes and names of the jobs mounting a volume.
+/// The backup job binds the volume directly instead of mounting it
+/// so it isn't counted and doesn't prevent the deletion.
+pub async fn used_by(
+  volume: &Volume,
+  state: &SystemState,
+) -> IoResult<Vec<String>> {
+  let mounts_volume = |mounts: &Option<Vec<VolumeMount>>, namespace: &str| {
+    mounts
+      .iter()
+      .flatten()
+      .any(|mount| resolve_key(&mount.name, namespace) == volume.key)
+  };
+  let filter = GenericFilter::new();
+  let cargoes = CargoDb::transform_read_all(&filter, &state.inner.pool).await?;
+  let jobs = JobDb::transform_read_all(&filter, &state.inner.pool).await?;
+  let used_by = cargoes
+    .into_iter()
+    .filter(|cargo| mounts_volume(&cargo.spec.volumes, &cargo.namespace_name))
+    .map(|cargo| cargo.spec.cargo_key)
+    .chain(
+      jobs
+        .into_iter()
+        .filter(|job| mounts_volume(&job.volumes, "global"))
+        .map(|job| job.name),
+    )
+    .collect();
+  Ok(used_by)
+}
+
+/// Create the docker volume
+pub async fn create(volume: &Volume, state: &SystemState) -> IoResult<()> {
+  let mut labels = volume.labels.clone().unwrap_or_default();
+  labels.insert("io.nanocl".to_owned(), "enabled".to_owned());
+  labels.insert("io.nanocl.v".to_owned(), volume.key.clone());
+  labels.insert("io.nanocl.n".to_owned(), volume.namespace_name.clone());
+  let mut driver_opts = HashMap::new();
+  // The local driver only supports a size on some filesystems, it stays a hint
+  if let (Some(size), true) = (volume.size, volume.driver != "local") {
+    driver_opts.insert("size".to_owned(), format!("{size}M"));
+  }
+  state
+    .inner
+    .docker_api
+    .create_volume(CreateVolumeOptions {
+      name: volume.key.clone(),
+      driver: volume.driver.clone(),
+      driver_opts,
+      labels,
+    })
+    .await
+    .map_err(|err| err.map_err_context(|| "Volume"))?;
+  Ok(())
+}
+
+/// Remove the docker volume, it's ignored when it doesn't exist anymore.
+/// The volume must be stored on this node, see `ensure_local`.
+pub async fn remove(key: &str, state: &SystemState) -> IoResult<()> {
+  let res = state
+    .inner
+    .docker_api
+    .remove_volume(key, None::<RemoveVolumeOptions>)
+    .await;
+  match res {
+    Err(bollard_next::errors::Error::DockerResponseServerError {
+      status_code: 404,
+      ..
+    })
+    | Ok(_) => Ok(()),
+    Err(err) => Err(*err.map_err_context(|| "Volume")),
+  }
+}
+
+/// Path of the docker volume on its node
+pub async fn mountpoint(key: &str, state: &SystemState) -> Option<String> {
+  let volume = state.inner.docker_api.inspect_volume(key).await.ok()?;
+  Some(volume.mountpoint)
+}
+
+/// Name of the job archiving a volume
+pub fn backup_job_name(key: &str) -> String {
+  format!("{key}.backup")
+}
+
+/// Directory of the archives of a volume on its node
+pub fn backup_dir(key: &str, state: &SystemState) -> String {
+  format!("{}/backups/volumes/{key}", state.inner.config.state_dir)
+}
+
+/// Generate the job archiving a volume and removing the oldest archives
+fn gen_backup_job(volume: &Volume, state: &SystemState) -> Option<JobPartial> {
+  let backup = volume.backup.as_ref()?;
+  let retention = backup.retention.unwrap_or(DEFAULT_RETENTION);
+  let script = format!(
+    "tar -czf /backups/$(date +%Y%m%d%H%M%S).tar.gz -C /volume . \
+    && ls -1t /backups/*.tar.gz | tail -n +{} | xargs -r rm -f",
+    retention + 1
+  );
+  let binds = vec![
+    format!("{}:/volume:ro", volume.key),
+    format!("{}:/backups", backup_dir(&volume.key, state)),
+  ];
+  Some(JobPartial {
+    name: backup_job_name(&volume.key),
+    schedule: Some(backup.schedule.clone()),
+    metadata: Some(serde_json::json!({
+      "Volume": volume.key,
+      "Node": volume.node_name,
+    })),
+    containers: vec![Config {
+      image: Some(vars::VOLUME_BACKUP_IMAGE.to_owned()),
+      cmd: Some(vec!["sh".to_owned(), "-c".to_owned(), script]),
+      host_config: Some(HostConfig {
+        binds: Some(binds),
+        ..Default::default()
+      }),
+      ..Default::default()
+    }],
+    ..Default::default()
+  })
+}
+
+/// Replace the backup job of a volume to match its backup policy.
+/// The job is pinned to the node storing the volume: its cron rule is
+/// registered in the crontab of this node and starts it through this daemon.
+pub async fn sync_backup(
+  volume: &Volume,
+  state: &SystemState,
+) -> HttpResult<()> {
+  ensure_local(volume, state)?;
+  let name = backup_job_name(&volume.key);
+  if JobDb::read_by_pk(&name, &state.inner.pool).await.is_ok() {
+    utils::container::job::delete(&name, state).await?;
+  }
+  let Some(job) = gen_backup_job(volume, state) else {
+    return Ok(());
+  };
+  let dir = backup_dir(&volume.key, state);
+  fs::create_dir_all(&dir)
+    .await
+    .map_err(|err| err.map_err_context(|| &dir))?;
+  JobDb::create_obj(&job, state).await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mount_key() {
+    assert_eq!(resolve_key("data", "global"), "data.global");
+    assert_eq!(resolve_key("data.other", "global"), "data.other");
+    let mount = "data:/var/lib/data:ro".parse::<VolumeMount>().unwrap();
+    assert_eq!(mount.name, "data");
+    assert_eq!(mount.path, "/var/lib/data");
+    assert_eq!(mount.read_only, Some(true));
+    assert!("data".parse::<VolumeMount>().is_err());
+    assert!("data:relative".parse::<VolumeMount>().is_err());
+    assert!("data:/data:rx".parse::<VolumeMount>().is_err());
+  }
+}
@@ -15,5 +15,15 @@ pub const COMMIT_ID: &str = env!("GIT_HASH");
 pub const CHANNEL: &str = env!("CHANNEL");
 /// Controller
 pub const CONTROLLER_NAME: &str = "nanocl.io/core";
+/// Kind of the resources isolating the namespaces
+pub const NETWORK_POLICY_KIND: &str = "nanocl.io/network-policy";
+/// Kind of the resources publishing the cargoes on a host
+pub const INGRESS_KIND: &str = "nanocl.io/ingress";
+/// Kind of the resources publishing the ports of the cargoes
+pub const PROXY_RULE_KIND: &str = "ncproxy.io/rule";
+/// Kind of the resources resolving the hosts of the ingresses
+pub const DNS_RULE_KIND: &str = "ncdns.io/rule";
 /// Default Virtual Machine runtime
 pub const VM_RUNTIME: &str = "ghcr.io/next-hat/nanocl-qemu:8.0.2.0";
+/// Image archiving the volumes with a backup policy
+pub const VOLUME_BACKUP_IMAGE: &str = "busybox:1.36";
@@ -0,0 +1,113 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use nanocl_error::io::{IoError, IoResult};
+
+use nanocld_client::stubs::dns::DnsServedRecord;
+use nanocld_client::NanocldClient;
+
+use crate::{
+  dnsmasq::{self, Dnsmasq},
+  nameserver::{self, Nameserver},
+  utils,
+  zone::Zone,
+};
+
+/// Server answering the dns queries of the zones
+#[derive(Clone)]
+pub(crate) enum Backend {
+  /// Zones are written as dnsmasq configs and the `ndns` cargo is restarted
+  Dnsmasq(Dnsmasq),
+  /// Zones are served in process and replaced without any restart
+  Embedded(Nameserver),
+}
+
+impl Backend {
+  /// Create or replace a zone, an empty zone is removed
+  pub(crate) async fn apply_zone(
+    &self,
+    name: &str,
+    zone: &Zone,
+    client: &NanocldClient,
+  ) -> IoResult<()> {
+    if zone.entries.is_empty() {
+      return self.remove_zone(name, client).await;
+    }
+    match self {
+      Backend::Dnsmasq(_) if zone.namespace.is_some() => {
+        return Err(IoError::invalid_input(
+          "Zone",
+          "namespace scoped rules require the embedded backend",
+        ));
+      }
+      Backend::Dnsmasq(dnsmasq) => {
+        dnsmasq.zones.write()?.insert(name.to_owned(), zone.clone());
+        let content = dnsmasq::gen_zone_config(zone);
+        let current = dnsmasq.read_config(name).await.unwrap_or_default();
+        if content == current {
+          return Ok(());
+        }
+        dnsmasq.write_config(name, &content).await?;
+        utils::reload_service(client).await?;
+      }
+      Backend::Embedded(nameserver) => {
+        nameserver.apply_zone(name, zone).await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Remove a zone if it exists
+  pub(crate) async fn remove_zone(
+    &self,
+    name: &str,
+    client: &NanocldClient,
+  ) -> IoResult<()> {
+    match self {
+      Backend::Dnsmasq(dnsmasq) => {
+        dnsmasq.zones.write()?.remove(name);
+        if dnsmasq.read_config(name).await.is_err() {
+          return Ok(());
+        }
+        dnsmasq.remove_config(name).await?;
+        utils::reload_service(client).await?;
+      }
+      Backend::Embedded(nameserver) => {
+        nameserver.remove_zone(name)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Names of the existing zones
+  pub(crate) async fn list_zones(&self) -> IoResult<Vec<String>> {
+    match self {
+      Backend::Dnsmasq(dnsmasq) => dnsmasq.list_configs().await,
+      Backend::Embedded(nameserver) => nameserver.list_zones(),
+    }
+  }
+
+  /// Records answered on the address to the clients of the namespace
+  pub(crate) fn list_records(
+    &self,
+    listen_address: &str,
+    namespace: Option<&str>,
+  ) -> IoResult<Vec<DnsServedRecord>> {
+    let zones = match self {
+      Backend::Dnsmasq(dnsmasq) => dnsmasq.zones.read()?,
+      Backend::Embedded(nameserver) => nameserver.zones.read()?,
+    };
+    Ok(nameserver::list_records(&zones, listen_address, namespace))
+  }
+
+  /// Update the namespaces of the instances addresses,
+  /// dnsmasq can't answer differently by client so they are ignored
+  pub(crate) fn set_clients(
+    &self,
+    clients: HashMap<IpAddr, String>,
+  ) -> IoResult<()> {
+    match self {
+      Backend::Dnsmasq(_) => Ok(()),
+      Backend::Embedded(nameserver) => nameserver.set_clients(clients),
+    }
+  }
+}
@@ -1,4 +1,14 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+
+/// Server answering the dns queries
+#[derive(Clone, Debug, Default, ValueEnum)]
+pub(crate) enum BackendKind {
+  /// Serve the records in process
+  #[default]
+  Embedded,
+  /// Write the records as dnsmasq configs for the `ndns` cargo
+  Dnsmasq,
+}
 
 /// Nanocl Controller Daemon DNS
 #[derive(Debug, Parser)]
@@ -12,4 +22,10 @@ pub(crate) struct Cli {
   /// Server address to listen on (default: unix:///run/nanocl/dns.sock)
   #[clap(long, default_value = "unix:///run/nanocl/dns.sock")]
   pub(crate) host: String,
+  /// Server answering the dns queries
+  #[clap(long, value_enum, default_value_t = BackendKind::Embedded)]
+  pub(crate) backend: BackendKind,
+  /// Port the embedded dns server listen on
+  #[clap(long, default_value = "53")]
+  pub(crate) dns_port: u16,
 }
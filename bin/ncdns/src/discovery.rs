@@ -0,0 +1,272 @@
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  net::IpAddr,
+};
+
+use nanocl_error::io::IoResult;
+
+use nanocld_client::{
+  stubs::{
+    dns::{DnsRecord, DnsSrvRecord},
+    process::{Process, ProcessKind},
+  },
+  NanocldClient,
+};
+
+use crate::{
+  backend::Backend,
+  utils,
+  zone::{Zone, ZoneEntry},
+};
+
+/// Domain under which cargoes and vms are published
+pub(crate) const DOMAIN: &str = "nanocl.internal";
+/// Name of the zone holding the discovered records
+const ZONE_NAME: &str = "discovery";
+/// Key of the cargo running dnsmasq, restarting it must not trigger a new sync
+const SELF_KEY: &str = "ndns.system";
+
+/// Addresses and exposed ports of the instances of a cargo or a vm
+#[derive(Default)]
+struct Service {
+  addresses: BTreeSet<String>,
+  ports: BTreeSet<(u16, String)>,
+}
+
+/// Whether a process is a running cargo or vm instance
+fn is_instance(process: &Process) -> bool {
+  if matches!(process.kind, ProcessKind::Job)
+    || process.name.starts_with("tmp-")
+    || process.kind_key == SELF_KEY
+  {
+    return false;
+  }
+  process
+    .data
+    .state
+    .as_ref()
+    .and_then(|state| state.running)
+    .unwrap_or_default()
+}
+
+/// Address of a running cargo or vm instance reachable by the other instances
+fn instance_address(process: &Process) -> Option<String> {
+  if !is_instance(process) {
+    return None;
+  }
+  process.network_address()
+}
+
+/// Namespace of the running instances by address
+/// the key of cargoes and vms ends with their namespace eg: `web.global`,
+/// every network of an instance is mapped since it can query from any of them
+pub(crate) fn gen_clients(processes: &[Process]) -> HashMap<IpAddr, String> {
+  let mut clients = HashMap::new();
+  for process in processes.iter().filter(|process| is_instance(process)) {
+    let Some((_, namespace)) = process.kind_key.rsplit_once('.') else {
+      continue;
+    };
+    let networks = process
+      .data
+      .network_settings
+      .as_ref()
+      .and_then(|settings| settings.networks.as_ref());
+    let addresses = networks
+      .into_iter()
+      .flat_map(|networks| networks.values())
+      .filter_map(|network| network.ip_address.as_ref()?.parse().ok());
+    for address in addresses {
+      clients.insert(address, namespace.to_owned());
+    }
+  }
+  clients
+}
+
+/// Generate the zone of the running cargo and vm instances
+/// - a host for every instance address
+/// - a srv record for every exposed port
+pub(crate) fn gen_zone(processes: &[Process], listen_address: &str) -> Zone {
+  let mut services = BTreeMap::<String, Service>::new();
+  for process in processes {
+    let Some(ip_address) = instance_address(process) else {
+      continue;
+    };
+    let service = services.entry(process.kind_key.clone()).or_default();
+    service.addresses.insert(ip_address);
+    let exposed_ports = process
+      .data
+      .config
+      .as_ref()
+      .and_then(|config| config.exposed_ports.as_ref());
+    for exposed_port in exposed_ports.into_iter().flat_map(|ports| ports.keys())
+    {
+      let (port, protocol) = exposed_port
+        .split_once('/')
+        .unwrap_or((exposed_port, "tcp"));
+      let Ok(port) = port.parse::<u16>() else {
+        continue;
+      };
+      service.ports.insert((port, protocol.to_owned()));
+    }
+  }
+  let mut zone = Zone {
+    listen_address: listen_address.to_owned(),
+    namespace: None,
+    entries: Vec::new(),
+  };
+  for (key, service) in services {
+    let domain = format!("{key}.{DOMAIN}");
+    for address in &service.addresses {
+      let Ok(address) = address.parse() else {
+        continue;
+      };
+      zone.entries.push(ZoneEntry::Host {
+        name: domain.clone(),
+        address,
+      });
+    }
+    for (port, protocol) in &service.ports {
+      zone
+        .entries
+        .push(ZoneEntry::Record(DnsRecord::Srv(DnsSrvRecord {
+          name: format!("_{port}._{protocol}.{domain}"),
+          target: domain.clone(),
+          port: *port,
+          priority: None,
+          weight: None,
+        })));
+    }
+  }
+  zone
+}
+
+/// Publish the records of the running instances
+pub(crate) async fn sync(
+  backend: &Backend,
+  client: &NanocldClient,
+) -> IoResult<()> {
+  let processes = client.list_process(None).await?;
+  let listen_address = utils::get_bridge_addr(client).await?;
+  let zone = gen_zone(&processes, &listen_address);
+  backend.set_clients(gen_clients(&processes))?;
+  log::debug!("discovery::sync: {} records", zone.entries.len());
+  backend.apply_zone(ZONE_NAME, &zone, client).await
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use nanocld_client::bollard_next::service::{
+    ContainerConfig, ContainerInspectResponse, ContainerState,
+    EndpointSettings, HostConfig, NetworkSettings,
+  };
+
+  use super::*;
+  use crate::dnsmasq;
+
+  fn gen_process(name: &str, kind_key: &str, ip_address: &str) -> Process {
+    Process {
+      key: name.to_owned(),
+      created_at: Default::default(),
+      updated_at: Default::default(),
+      name: name.to_owned(),
+      kind: ProcessKind::Cargo,
+      node_name: "test".to_owned(),
+      kind_key: kind_key.to_owned(),
+      data: ContainerInspectResponse {
+        state: Some(ContainerState {
+          running: Some(true),
+          ..Default::default()
+        }),
+        config: Some(ContainerConfig {
+          exposed_ports: Some(HashMap::from([(
+            "80/tcp".to_owned(),
+            HashMap::new(),
+          )])),
+          ..Default::default()
+        }),
+        network_settings: Some(NetworkSettings {
+          networks: Some(HashMap::from([(
+            "nanoclbr0".to_owned(),
+            EndpointSettings {
+              ip_address: Some(ip_address.to_owned()),
+              ..Default::default()
+            },
+          )])),
+          ..Default::default()
+        }),
+        ..Default::default()
+      },
+    }
+  }
+
+  #[test]
+  fn records() {
+    let processes = vec![
+      gen_process("web-1", "web.global", "10.0.0.2"),
+      gen_process("web-2", "web.global", "10.0.0.3"),
+      gen_process("tmp-web", "web.global", "10.0.0.4"),
+      gen_process("ndns", SELF_KEY, "10.0.0.5"),
+    ];
+    let clients = gen_clients(&processes);
+    assert_eq!(clients.len(), 2);
+    assert_eq!(clients[&"10.0.0.2".parse().unwrap()], "global");
+    let zone = gen_zone(&processes, "10.0.0.1");
+    assert_eq!(
+      dnsmasq::gen_zone_config(&zone),
+      "bind-dynamic
+listen-address=10.0.0.1
+host-record=web.global.nanocl.internal,10.0.0.2
+host-record=web.global.nanocl.internal,10.0.0.3
+srv-host=_80._tcp.web.global.nanocl.internal,web.global.nanocl.internal,80,0,0
+"
+    );
+  }
+
+  #[test]
+  fn namespace_network() {
+    let mut process = gen_process("api-1", "api.back", "10.0.0.2");
+    process.data.host_config = Some(HostConfig {
+      network_mode: Some("back".to_owned()),
+      ..Default::default()
+    });
+    process.data.network_settings = Some(NetworkSettings {
+      networks: Some(HashMap::from([(
+        "back".to_owned(),
+        EndpointSettings {
+          ip_address: Some("10.1.0.2".to_owned()),
+          ..Default::default()
+        },
+      )])),
+      ..Default::default()
+    });
+    let clients = gen_clients(&[process]);
+    assert_eq!(clients[&"10.1.0.2".parse().unwrap()], "back");
+  }
+
+  #[test]
+  fn overlay_network() {
+    let mut process = gen_process("api-1", "api.global", "10.0.0.2");
+    if let Some(networks) = process
+      .data
+      .network_settings
+      .as_mut()
+      .and_then(|settings| settings.networks.as_mut())
+    {
+      networks.insert(
+        "nanoclov0".to_owned(),
+        EndpointSettings {
+          ip_address: Some("10.210.1.2".to_owned()),
+          ..Default::default()
+        },
+      );
+    }
+    let processes = vec![process];
+    let clients = gen_clients(&processes);
+    assert_eq!(clients.len(), 2);
+    let zone = gen_zone(&processes, "10.0.0.1");
+    assert!(dnsmasq::gen_zone_config(&zone)
+      .contains("host-record=api.global.nanocl.internal,10.210.1.2\n"));
+  }
+}
@@ -1,13 +1,23 @@
-use std::fs;
+use std::{
+  collections::HashMap,
+  fs,
+  sync::{Arc, RwLock},
+};
 
 use nanocl_error::io::{FromIo, IoResult};
 
+use nanocld_client::stubs::dns::DnsRecord;
+
+use crate::zone::{Zone, ZoneEntry};
+
 /// Dnsmasq configuration manager
 #[derive(Clone)]
 pub struct Dnsmasq {
   pub(crate) config_dir: String,
   pub(crate) config_path: String,
   pub(crate) dns: Vec<String>,
+  /// Zones written as configs to list the records dnsmasq answers
+  pub(crate) zones: Arc<RwLock<HashMap<String, Zone>>>,
 }
 
 impl Dnsmasq {
@@ -17,6 +27,7 @@ impl Dnsmasq {
       config_dir: config_path.to_owned(),
       config_path: format!("{}/dnsmasq.conf", &config_path.to_owned()),
       dns: Vec::new(),
+      zones: Arc::new(RwLock::new(HashMap::new())),
     }
   }
 
@@ -27,6 +38,7 @@ impl Dnsmasq {
       config_dir: self.config_dir.to_owned(),
       config_path: self.config_path.to_owned(),
       dns: self.dns.to_owned(),
+      zones: self.zones.clone(),
     }
   }
 
@@ -63,7 +75,6 @@ no-poll
 no-hosts
 proxy-dnssec
 except-interface=lo
-interface=nanoclbr0
 conf-dir={}/dnsmasq.d,*.conf
 ",
       &self.config_dir
@@ -143,6 +154,22 @@ conf-dir={}/dnsmasq.d,*.conf
     Ok(content)
   }
 
+  /// List the names of the domain records files
+  pub(crate) async fn list_configs(&self) -> IoResult<Vec<String>> {
+    let dir_path = format!("{}/dnsmasq.d", &self.config_dir);
+    let mut entries = tokio::fs::read_dir(&dir_path).await.map_err(|err| {
+      err.map_err_context(|| format!("unable to read directory {dir_path}"))
+    })?;
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+      let file_name = entry.file_name().to_string_lossy().to_string();
+      if let Some(name) = file_name.strip_suffix(".conf") {
+        names.push(name.to_owned());
+      }
+    }
+    Ok(names)
+  }
+
   /// Remove domain records file for dnsmasq
   pub(crate) async fn remove_config(&self, name: &str) -> IoResult<()> {
     let file_path = format!("{}/dnsmasq.d/{name}.conf", &self.config_dir);
@@ -154,3 +181,62 @@ conf-dir={}/dnsmasq.d,*.conf
     Ok(())
   }
 }
+
+/// Render the dnsmasq directive of a record
+fn gen_record(record: &DnsRecord) -> String {
+  match record {
+    DnsRecord::Aaaa(record) => {
+      let name = record.name.trim_start_matches("*.");
+      format!("address=/{name}/{}", record.address)
+    }
+    DnsRecord::Cname(record) => {
+      format!("cname={},{}", record.name, record.target)
+    }
+    DnsRecord::Txt(record) => {
+      let values = record
+        .values
+        .iter()
+        .map(|value| format!("\"{value}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+      format!("txt-record={},{values}", record.name)
+    }
+    DnsRecord::Srv(record) => format!(
+      "srv-host={},{},{},{},{}",
+      record.name,
+      record.target,
+      record.port,
+      record.priority.unwrap_or_default(),
+      record.weight.unwrap_or_default()
+    ),
+    DnsRecord::Mx(record) => format!(
+      "mx-host={},{},{}",
+      record.name,
+      record.exchange,
+      record.preference.unwrap_or(1)
+    ),
+    DnsRecord::Ptr(record) => {
+      format!("ptr-record={},{}", record.name, record.target)
+    }
+  }
+}
+
+/// Render the dnsmasq config of a zone
+pub(crate) fn gen_zone_config(zone: &Zone) -> String {
+  let mut content =
+    format!("bind-dynamic\nlisten-address={}\n", zone.listen_address);
+  for entry in &zone.entries {
+    let directive = match entry {
+      // dnsmasq address directives already match every subdomain
+      ZoneEntry::Address { name, address } => {
+        format!("address=/{}/{address}", name.trim_start_matches("*."))
+      }
+      ZoneEntry::Host { name, address } => {
+        format!("host-record={name},{address}")
+      }
+      ZoneEntry::Record(record) => gen_record(record),
+    };
+    content += &format!("{directive}\n");
+  }
+  content
+}
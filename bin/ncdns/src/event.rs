@@ -1,25 +1,59 @@
+use std::str::FromStr;
+
+use futures::StreamExt;
 use ntex::rt;
 
 use nanocl_error::io::IoResult;
 
 use nanocl_utils::versioning;
 
-use nanocld_client::stubs::resource_kind::{
-  ResourceKindPartial, ResourceKindSpec,
+use nanocld_client::stubs::{
+  dns::ResourceDnsRule,
+  resource_kind::{ResourceKindPartial, ResourceKindSpec},
+  system::{Event, EventActorKind, NativeEventAction},
 };
 
 use nanocld_client::NanocldClient;
 
-use crate::vars;
+use crate::{backend::Backend, discovery, utils, vars};
+
+/// Publish the records of cargoes and vms when their instances change
+async fn on_event(
+  event: &Event,
+  backend: &Backend,
+  client: &NanocldClient,
+) -> IoResult<()> {
+  let Some(actor) = &event.actor else {
+    return Ok(());
+  };
+  // Custom actions of the other controllers aren't handled
+  let Ok(action) = NativeEventAction::from_str(&event.action) else {
+    return Ok(());
+  };
+  match (&actor.kind, action) {
+    (
+      EventActorKind::Process | EventActorKind::Cargo | EventActorKind::Vm,
+      NativeEventAction::Start
+      | NativeEventAction::Restart
+      | NativeEventAction::Update
+      | NativeEventAction::Stop
+      | NativeEventAction::Die
+      | NativeEventAction::Destroy,
+    ) => discovery::sync(backend, client).await,
+    _ => Ok(()),
+  }
+}
 
 async fn ensure_self_config(client: &NanocldClient) -> IoResult<()> {
   let formatted_version = versioning::format_version(vars::VERSION);
   let resource_kind = ResourceKindPartial {
-    name: "ncdns.io/rule".to_owned(),
+    name: vars::RULE_KEY.to_owned(),
     version: format!("v{formatted_version}"),
     metadata: None,
     data: ResourceKindSpec {
-      schema: None,
+      schema: Some(serde_json::to_value(schemars::schema_for!(
+        ResourceDnsRule
+      ))?),
       url: Some("unix:///run/nanocl/dns.sock".to_owned()),
     },
   };
@@ -34,17 +68,36 @@ async fn ensure_self_config(client: &NanocldClient) -> IoResult<()> {
   Ok(())
 }
 
-async fn r#loop(client: &NanocldClient) {
+async fn r#loop(backend: &Backend, client: &NanocldClient) {
   loop {
     log::info!("event::loop: subscribing to nanocld events");
     match client.watch_events(None).await {
       Err(err) => {
         log::warn!("event::loop: {err}");
       }
-      Ok(_) => {
-        log::info!("event::loop: subscribed to nanocld events");
-        if ensure_self_config(client).await.is_ok() {
-          break;
+      Ok(mut stream) => {
+        if let Err(err) = ensure_self_config(client).await {
+          log::warn!("event::loop: {err}");
+        } else {
+          log::info!("event::loop: subscribed to nanocld events");
+          if let Err(err) = utils::sync_rules(backend, client).await {
+            log::warn!("event::loop: {err}");
+          }
+          if let Err(err) = discovery::sync(backend, client).await {
+            log::warn!("event::loop: {err}");
+          }
+          while let Some(event) = stream.next().await {
+            let event = match event {
+              Err(err) => {
+                log::warn!("event::loop: {err}");
+                continue;
+              }
+              Ok(event) => event,
+            };
+            if let Err(err) = on_event(&event, backend, client).await {
+              log::warn!("event::loop: {err}");
+            }
+          }
         }
       }
     }
@@ -54,11 +107,12 @@ async fn r#loop(client: &NanocldClient) {
 }
 
 /// Spawn new thread with event loop to watch for nanocld events
-pub(crate) fn spawn(client: &NanocldClient) {
+pub(crate) fn spawn(backend: &Backend, client: &NanocldClient) {
+  let backend = backend.clone();
   let client = client.clone();
   rt::Arbiter::new().exec_fn(move || {
     ntex::rt::spawn(async move {
-      r#loop(&client).await;
+      r#loop(&backend, &client).await;
       rt::Arbiter::current().stop();
     });
   });
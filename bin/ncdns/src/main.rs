@@ -3,24 +3,36 @@ use clap::Parser;
 use nanocl_error::io::IoResult;
 use nanocl_utils::logger;
 
+mod backend;
 mod cli;
+mod discovery;
 mod dnsmasq;
 mod event;
+mod nameserver;
 mod server;
 mod services;
 mod utils;
 mod vars;
+mod zone;
 
 use nanocld_client::NanocldClient;
 
-use cli::Cli;
+use backend::Backend;
+use cli::{BackendKind, Cli};
 use dnsmasq::Dnsmasq;
+use nameserver::Nameserver;
 
 async fn run(cli: &Cli) -> IoResult<()> {
-  // Spawn a new thread to listen events from nanocld
-  let dnsmasq = Dnsmasq::new(&cli.state_dir)
-    .with_dns(cli.dns.clone())
-    .ensure()?;
+  let backend = match cli.backend {
+    BackendKind::Dnsmasq => Backend::Dnsmasq(
+      Dnsmasq::new(&cli.state_dir)
+        .with_dns(cli.dns.clone())
+        .ensure()?,
+    ),
+    BackendKind::Embedded => {
+      Backend::Embedded(Nameserver::new(&cli.dns, cli.dns_port)?)
+    }
+  };
   #[allow(unused)]
   let mut client = NanocldClient::connect_with_unix_default();
   #[cfg(any(feature = "dev", feature = "test"))]
@@ -31,8 +43,14 @@ async fn run(cli: &Cli) -> IoResult<()> {
       ..Default::default()
     })?;
   }
-  event::spawn(&client);
-  let server = server::gen(&cli.host, &dnsmasq, &client)?;
+  // Load the existing rules before serving, the event loop syncs them again
+  // once subscribed in case nanocld isn't reachable yet
+  if let Err(err) = utils::sync_rules(&backend, &client).await {
+    log::warn!("run: {err}");
+  }
+  // Spawn a new thread to listen events from nanocld
+  event::spawn(&backend, &client);
+  let server = server::gen(&cli.host, &backend, &client)?;
   server.await?;
   Ok(())
 }
@@ -0,0 +1,677 @@
+use std::{
+  collections::{HashMap, HashSet},
+  net::{IpAddr, SocketAddr},
+  sync::{Arc, Mutex, RwLock},
+  time::Duration,
+};
+
+use hickory_proto::{
+  error::ProtoError,
+  op::{Message, MessageType, ResponseCode},
+  rr::{
+    rdata::{A, AAAA, CNAME, MX, PTR, SRV, TXT},
+    Name, RData, Record, RecordType,
+  },
+};
+use ntex::rt;
+use tokio::{
+  io::{AsyncReadExt, AsyncWriteExt},
+  net::{TcpListener, TcpStream, UdpSocket},
+};
+
+use nanocl_error::io::{FromIo, IoError, IoResult};
+
+use nanocld_client::stubs::dns::{DnsRecord, DnsServedRecord};
+
+use crate::zone::{Zone, ZoneEntry};
+
+/// Time to live of the records we are authoritative for
+const TTL: u32 = 10;
+/// Maximum number of aliases followed when resolving a cname
+const MAX_CNAME_DEPTH: usize = 8;
+/// Time to wait for an upstream server to answer
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Authoritative and forwarding dns server
+/// The zones are kept in memory and replaced atomically
+#[derive(Clone)]
+pub(crate) struct Nameserver {
+  port: u16,
+  upstreams: Arc<Vec<SocketAddr>>,
+  pub(crate) zones: Arc<RwLock<HashMap<String, Zone>>>,
+  /// Namespace of the instances by address to select their zones
+  clients: Arc<RwLock<HashMap<IpAddr, String>>>,
+  listeners: Arc<Mutex<HashSet<IpAddr>>>,
+}
+
+/// Convert a dns protocol error
+fn proto_error(err: ProtoError) -> IoError {
+  IoError::invalid_data("Dns", &err.to_string())
+}
+
+/// Parse a domain name of a record
+fn parse_name(name: &str) -> IoResult<Name> {
+  Name::from_ascii(name).map_err(proto_error)
+}
+
+/// Normalize a domain name to compare it
+fn normalize(name: &str) -> String {
+  name.trim_end_matches('.').to_lowercase()
+}
+
+/// Return true if the name is the domain or one of its subdomains
+fn is_within(name: &str, domain: &str) -> bool {
+  name == domain || name.ends_with(&format!(".{domain}"))
+}
+
+/// Return true if the name of the record matches the queried name
+fn matches(entry: &ZoneEntry, name: &str) -> bool {
+  match entry {
+    ZoneEntry::Address { name: domain, .. } => {
+      is_within(name, &normalize(domain.trim_start_matches("*.")))
+    }
+    ZoneEntry::Host { name: host, .. } => name == normalize(host),
+    ZoneEntry::Record(DnsRecord::Aaaa(record)) => {
+      is_within(name, &normalize(record.name.trim_start_matches("*.")))
+    }
+    ZoneEntry::Record(DnsRecord::Cname(record)) => {
+      match record.name.strip_prefix("*.") {
+        Some(domain) => name.ends_with(&format!(".{}", normalize(domain))),
+        None => name == normalize(&record.name),
+      }
+    }
+    ZoneEntry::Record(record) => name == normalize(record.name()),
+  }
+}
+
+/// Name, type and value of a zone entry as answered
+fn describe(entry: &ZoneEntry) -> (String, &'static str, String) {
+  match entry {
+    ZoneEntry::Address { name, address }
+    | ZoneEntry::Host { name, address } => {
+      let kind = if address.is_ipv4() { "A" } else { "AAAA" };
+      (name.clone(), kind, address.to_string())
+    }
+    ZoneEntry::Record(record) => {
+      let (kind, value) = match record {
+        DnsRecord::Aaaa(record) => ("AAAA", record.address.to_string()),
+        DnsRecord::Cname(record) => ("CNAME", record.target.clone()),
+        DnsRecord::Txt(record) => {
+          ("TXT", format!("\"{}\"", record.values.join("\" \"")))
+        }
+        DnsRecord::Srv(record) => (
+          "SRV",
+          format!(
+            "{} {} {} {}",
+            record.priority.unwrap_or_default(),
+            record.weight.unwrap_or_default(),
+            record.port,
+            record.target
+          ),
+        ),
+        DnsRecord::Mx(record) => (
+          "MX",
+          format!("{} {}", record.preference.unwrap_or(1), record.exchange),
+        ),
+        DnsRecord::Ptr(record) => ("PTR", record.target.clone()),
+      };
+      (record.name().to_owned(), kind, value)
+    }
+  }
+}
+
+/// List the records answered on the address to the clients of the namespace,
+/// the global records are omitted when a record of the namespace answers their name
+pub(crate) fn list_records(
+  zones: &HashMap<String, Zone>,
+  listen_address: &str,
+  namespace: Option<&str>,
+) -> Vec<DnsServedRecord> {
+  let entries = |view: Option<&str>| {
+    zones
+      .iter()
+      .filter(move |(_, zone)| {
+        zone.listen_address == listen_address
+          && zone.namespace.as_deref() == view
+      })
+      .flat_map(|(name, zone)| {
+        zone.entries.iter().map(move |entry| (name, zone, entry))
+      })
+      .collect::<Vec<_>>()
+  };
+  let scoped = match namespace {
+    Some(namespace) => entries(Some(namespace)),
+    None => Vec::new(),
+  };
+  let global = entries(None).into_iter().filter(|(_, _, entry)| {
+    let (name, _, _) = describe(entry);
+    let name = normalize(name.trim_start_matches("*."));
+    !scoped.iter().any(|(_, _, scoped)| matches(scoped, &name))
+  });
+  let mut records = scoped
+    .iter()
+    .copied()
+    .chain(global)
+    .map(|(zone_name, zone, entry)| {
+      let (name, kind, value) = describe(entry);
+      DnsServedRecord {
+        name,
+        kind: kind.to_owned(),
+        value,
+        namespace: zone.namespace.clone(),
+        zone: zone_name.clone(),
+      }
+    })
+    .collect::<Vec<_>>();
+  records.sort_by(|a, b| a.name.cmp(&b.name).then(a.kind.cmp(&b.kind)));
+  records
+}
+
+/// Convert a zone entry into the rdata answering the query type
+fn to_rdata(
+  entry: &ZoneEntry,
+  query_type: RecordType,
+) -> IoResult<Option<RData>> {
+  let any = query_type == RecordType::ANY;
+  let rdata = match entry {
+    ZoneEntry::Address { address, .. } | ZoneEntry::Host { address, .. } => {
+      match address {
+        IpAddr::V4(ip) if any || query_type == RecordType::A => {
+          Some(RData::A(A(*ip)))
+        }
+        IpAddr::V6(ip) if any || query_type == RecordType::AAAA => {
+          Some(RData::AAAA(AAAA(*ip)))
+        }
+        _ => None,
+      }
+    }
+    ZoneEntry::Record(record) => match record {
+      DnsRecord::Aaaa(record) if any || query_type == RecordType::AAAA => {
+        Some(RData::AAAA(AAAA(record.address)))
+      }
+      DnsRecord::Cname(record) => {
+        Some(RData::CNAME(CNAME(parse_name(&record.target)?)))
+      }
+      DnsRecord::Txt(record) if any || query_type == RecordType::TXT => {
+        Some(RData::TXT(TXT::new(record.values.clone())))
+      }
+      DnsRecord::Srv(record) if any || query_type == RecordType::SRV => {
+        Some(RData::SRV(SRV::new(
+          record.priority.unwrap_or_default(),
+          record.weight.unwrap_or_default(),
+          record.port,
+          parse_name(&record.target)?,
+        )))
+      }
+      DnsRecord::Mx(record) if any || query_type == RecordType::MX => {
+        Some(RData::MX(MX::new(
+          record.preference.unwrap_or(1),
+          parse_name(&record.exchange)?,
+        )))
+      }
+      DnsRecord::Ptr(record) if any || query_type == RecordType::PTR => {
+        Some(RData::PTR(PTR(parse_name(&record.target)?)))
+      }
+      _ => None,
+    },
+  };
+  Ok(rdata)
+}
+
+/// Collect the answers of the name from the entries,
+/// return false if the name isn't defined by the entries
+fn collect_answers(
+  entries: &[&ZoneEntry],
+  owner: &Name,
+  query_type: RecordType,
+  answers: &mut Vec<Record>,
+  depth: usize,
+) -> IoResult<bool> {
+  let name = normalize(&owner.to_ascii());
+  let matching = entries
+    .iter()
+    .filter(|entry| matches(entry, &name))
+    .collect::<Vec<_>>();
+  if matching.is_empty() {
+    return Ok(false);
+  }
+  for entry in matching {
+    let Some(rdata) = to_rdata(entry, query_type)? else {
+      continue;
+    };
+    // Follow the alias when the query isn't about the cname itself
+    if let RData::CNAME(CNAME(target)) = &rdata {
+      answers.push(Record::from_rdata(owner.clone(), TTL, rdata.clone()));
+      if query_type != RecordType::CNAME && depth < MAX_CNAME_DEPTH {
+        collect_answers(entries, target, query_type, answers, depth + 1)?;
+      }
+      continue;
+    }
+    answers.push(Record::from_rdata(owner.clone(), TTL, rdata));
+  }
+  Ok(true)
+}
+
+impl Nameserver {
+  /// Create a new nameserver forwarding unknown names to the upstreams
+  pub(crate) fn new(upstreams: &[String], port: u16) -> IoResult<Self> {
+    let upstreams = upstreams
+      .iter()
+      .map(|upstream| {
+        upstream
+          .parse::<SocketAddr>()
+          .or_else(|_| {
+            upstream.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53))
+          })
+          .map_err(|_| {
+            IoError::invalid_input("Dns", &format!("invalid server {upstream}"))
+          })
+      })
+      .collect::<IoResult<Vec<_>>>()?;
+    Ok(Self {
+      port,
+      upstreams: Arc::new(upstreams),
+      zones: Arc::new(RwLock::new(HashMap::new())),
+      clients: Arc::new(RwLock::new(HashMap::new())),
+      listeners: Arc::new(Mutex::new(HashSet::new())),
+    })
+  }
+
+  /// Replace the zone with the given name,
+  /// listening on its address if it's not already the case
+  pub(crate) async fn apply_zone(
+    &self,
+    name: &str,
+    zone: &Zone,
+  ) -> IoResult<()> {
+    let ip = zone.listen_address.parse::<IpAddr>().map_err(|_| {
+      IoError::invalid_input(
+        "Zone",
+        &format!("invalid listen address {}", zone.listen_address),
+      )
+    })?;
+    self.listen(ip).await?;
+    self.zones.write()?.insert(name.to_owned(), zone.clone());
+    Ok(())
+  }
+
+  /// Remove the zone with the given name
+  pub(crate) fn remove_zone(&self, name: &str) -> IoResult<()> {
+    self.zones.write()?.remove(name);
+    Ok(())
+  }
+
+  /// Names of the zones being served
+  pub(crate) fn list_zones(&self) -> IoResult<Vec<String>> {
+    Ok(self.zones.read()?.keys().cloned().collect())
+  }
+
+  /// Replace the namespaces of the instances addresses
+  pub(crate) fn set_clients(
+    &self,
+    clients: HashMap<IpAddr, String>,
+  ) -> IoResult<()> {
+    *self.clients.write()? = clients;
+    Ok(())
+  }
+
+  /// Bind the udp and tcp sockets on the address unless they already are
+  async fn listen(&self, ip: IpAddr) -> IoResult<()> {
+    if self.listeners.lock()?.contains(&ip) {
+      return Ok(());
+    }
+    let addr = SocketAddr::new(ip, self.port);
+    let udp = UdpSocket::bind(addr).await.map_err(|err| {
+      err.map_err_context(|| format!("Unable to bind {addr}"))
+    })?;
+    let tcp = TcpListener::bind(addr).await.map_err(|err| {
+      err.map_err_context(|| format!("Unable to bind {addr}"))
+    })?;
+    self.listeners.lock()?.insert(ip);
+    log::info!("nameserver::listen: {addr}");
+    let nameserver = self.clone();
+    rt::spawn(async move {
+      nameserver.serve_udp(ip, udp).await;
+    });
+    let nameserver = self.clone();
+    rt::spawn(async move {
+      nameserver.serve_tcp(ip, tcp).await;
+    });
+    Ok(())
+  }
+
+  async fn serve_udp(&self, ip: IpAddr, socket: UdpSocket) {
+    let socket = Arc::new(socket);
+    let mut buf = vec![0; 4096];
+    loop {
+      let (len, peer) = match socket.recv_from(&mut buf).await {
+        Err(err) => {
+          log::warn!("nameserver::serve_udp: {err}");
+          continue;
+        }
+        Ok(res) => res,
+      };
+      let packet = buf[..len].to_vec();
+      let nameserver = self.clone();
+      let socket = Arc::clone(&socket);
+      rt::spawn(async move {
+        match nameserver.handle(ip, peer.ip(), &packet, false).await {
+          Err(err) => log::warn!("nameserver::serve_udp: {peer} {err}"),
+          Ok(response) => {
+            if let Err(err) = socket.send_to(&response, peer).await {
+              log::warn!("nameserver::serve_udp: {peer} {err}");
+            }
+          }
+        }
+      });
+    }
+  }
+
+  async fn serve_tcp(&self, ip: IpAddr, listener: TcpListener) {
+    loop {
+      let (stream, peer) = match listener.accept().await {
+        Err(err) => {
+          log::warn!("nameserver::serve_tcp: {err}");
+          continue;
+        }
+        Ok(res) => res,
+      };
+      let nameserver = self.clone();
+      rt::spawn(async move {
+        if let Err(err) = nameserver.handle_tcp(ip, peer.ip(), stream).await {
+          log::warn!("nameserver::serve_tcp: {peer} {err}");
+        }
+      });
+    }
+  }
+
+  /// Answer the length prefixed queries of a tcp connection
+  async fn handle_tcp(
+    &self,
+    ip: IpAddr,
+    peer: IpAddr,
+    mut stream: TcpStream,
+  ) -> IoResult<()> {
+    loop {
+      let len = match stream.read_u16().await {
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+          return Ok(());
+        }
+        Err(err) => return Err(err.into()),
+        Ok(len) => len as usize,
+      };
+      let mut packet = vec![0; len];
+      stream.read_exact(&mut packet).await?;
+      let response = self.handle(ip, peer, &packet, true).await?;
+      stream.write_u16(response.len() as u16).await?;
+      stream.write_all(&response).await?;
+    }
+  }
+
+  /// Answer a query received on the given address
+  pub(crate) async fn handle(
+    &self,
+    ip: IpAddr,
+    peer: IpAddr,
+    packet: &[u8],
+    tcp: bool,
+  ) -> IoResult<Vec<u8>> {
+    let request = Message::from_vec(packet).map_err(proto_error)?;
+    if let Some(response) = self.resolve(ip, peer, &request)? {
+      return response.to_vec().map_err(proto_error);
+    }
+    match self.forward(packet, tcp).await {
+      Ok(response) => Ok(response),
+      Err(err) => {
+        log::warn!("nameserver::handle: {err}");
+        let response = Message::error_msg(
+          request.id(),
+          request.op_code(),
+          ResponseCode::ServFail,
+        );
+        response.to_vec().map_err(proto_error)
+      }
+    }
+  }
+
+  /// Build the answer of the query from the zones served on the address,
+  /// the zones of the namespace of the client take precedence over the global ones
+  /// return none when the name must be forwarded
+  pub(crate) fn resolve(
+    &self,
+    ip: IpAddr,
+    peer: IpAddr,
+    request: &Message,
+  ) -> IoResult<Option<Message>> {
+    let Some(query) = request.queries().first() else {
+      return Ok(None);
+    };
+    let namespace = self.clients.read()?.get(&peer).cloned();
+    let zones = self.zones.read()?;
+    let listen_address = ip.to_string();
+    let mut views = vec![None];
+    if namespace.is_some() {
+      views.insert(0, namespace);
+    }
+    let mut answers = Vec::new();
+    let mut found = false;
+    for view in &views {
+      let entries = zones
+        .values()
+        .filter(|zone| {
+          zone.listen_address == listen_address && &zone.namespace == view
+        })
+        .flat_map(|zone| zone.entries.iter())
+        .collect::<Vec<_>>();
+      if collect_answers(
+        &entries,
+        query.name(),
+        query.query_type(),
+        &mut answers,
+        0,
+      )? {
+        found = true;
+        break;
+      }
+    }
+    if !found {
+      return Ok(None);
+    }
+    let mut response = Message::new();
+    response
+      .set_id(request.id())
+      .set_message_type(MessageType::Response)
+      .set_op_code(request.op_code())
+      .set_authoritative(true)
+      .set_recursion_desired(request.recursion_desired())
+      .set_recursion_available(true)
+      .set_response_code(ResponseCode::NoError)
+      .add_query(query.clone())
+      .add_answers(answers);
+    Ok(Some(response))
+  }
+
+  /// Send the query to the upstream servers until one of them answer
+  async fn forward(&self, packet: &[u8], tcp: bool) -> IoResult<Vec<u8>> {
+    for upstream in self.upstreams.iter() {
+      let res = tokio::time::timeout(FORWARD_TIMEOUT, async {
+        if tcp {
+          let mut stream = TcpStream::connect(upstream).await?;
+          stream.write_u16(packet.len() as u16).await?;
+          stream.write_all(packet).await?;
+          let len = stream.read_u16().await? as usize;
+          let mut response = vec![0; len];
+          stream.read_exact(&mut response).await?;
+          return Ok::<_, std::io::Error>(response);
+        }
+        let bind_addr = match upstream {
+          SocketAddr::V4(_) => "0.0.0.0:0",
+          SocketAddr::V6(_) => "[::]:0",
+        };
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(upstream).await?;
+        socket.send(packet).await?;
+        let mut response = vec![0; 4096];
+        let len = socket.recv(&mut response).await?;
+        response.truncate(len);
+        Ok(response)
+      })
+      .await;
+      match res {
+        Ok(Ok(response)) => return Ok(response),
+        Ok(Err(err)) => log::warn!("nameserver::forward: {upstream} {err}"),
+        Err(_) => log::warn!("nameserver::forward: {upstream} timeout"),
+      }
+    }
+    Err(IoError::interrupted("Dns", "no upstream server answered"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use hickory_proto::op::Query;
+  use nanocld_client::stubs::dns::{DnsCnameRecord, DnsTxtRecord};
+
+  use super::*;
+
+  fn gen_query(name: &str, query_type: RecordType) -> Message {
+    let mut message = Message::new();
+    message
+      .set_id(42)
+      .add_query(Query::query(Name::from_ascii(name).unwrap(), query_type));
+    message
+  }
+
+  #[ntex::test]
+  async fn resolve() {
+    let nameserver = Nameserver::new(&["1.1.1.1".to_owned()], 53).unwrap();
+    let zone = Zone {
+      listen_address: "127.0.0.1".to_owned(),
+      namespace: None,
+      entries: vec![
+        ZoneEntry::Address {
+          name: "*.test.com".to_owned(),
+          address: "10.0.0.2".parse().unwrap(),
+        },
+        ZoneEntry::Record(DnsRecord::Cname(DnsCnameRecord {
+          name: "www.example.com".to_owned(),
+          target: "app.test.com".to_owned(),
+        })),
+        ZoneEntry::Record(DnsRecord::Txt(DnsTxtRecord {
+          name: "test.com".to_owned(),
+          values: vec!["hello".to_owned()],
+        })),
+      ],
+    };
+    // Only update the zones to avoid binding a socket
+    nameserver
+      .zones
+      .write()
+      .unwrap()
+      .insert("test".to_owned(), zone);
+    let local = "127.0.0.1".parse().unwrap();
+    let query = gen_query("app.test.com.", RecordType::A);
+    let response = nameserver.resolve(local, local, &query).unwrap().unwrap();
+    assert_eq!(response.id(), 42);
+    assert_eq!(response.answers().len(), 1);
+    let query = gen_query("www.example.com.", RecordType::A);
+    let response = nameserver.resolve(local, local, &query).unwrap().unwrap();
+    assert_eq!(response.answers().len(), 2);
+    let query = gen_query("test.com.", RecordType::TXT);
+    let response = nameserver.resolve(local, local, &query).unwrap().unwrap();
+    assert_eq!(response.answers().len(), 1);
+    let query = gen_query("test.com.", RecordType::MX);
+    let response = nameserver.resolve(local, local, &query).unwrap().unwrap();
+    assert!(response.answers().is_empty());
+    let query = gen_query("google.com.", RecordType::A);
+    assert!(nameserver.resolve(local, local, &query).unwrap().is_none());
+    let other = "10.0.0.1".parse().unwrap();
+    let query = gen_query("app.test.com.", RecordType::A);
+    assert!(nameserver.resolve(other, local, &query).unwrap().is_none());
+    nameserver.remove_zone("test").unwrap();
+    assert!(nameserver.resolve(local, local, &query).unwrap().is_none());
+  }
+
+  #[ntex::test]
+  async fn resolve_namespace() {
+    let nameserver = Nameserver::new(&["1.1.1.1".to_owned()], 53).unwrap();
+    let gen_zone = |namespace: Option<&str>, address: &str| Zone {
+      listen_address: "10.0.0.1".to_owned(),
+      namespace: namespace.map(ToOwned::to_owned),
+      entries: vec![ZoneEntry::Address {
+        name: "db.test.com".to_owned(),
+        address: address.parse().unwrap(),
+      }],
+    };
+    {
+      let mut zones = nameserver.zones.write().unwrap();
+      zones.insert("Internal".to_owned(), gen_zone(None, "10.0.0.2"));
+      zones
+        .insert("Internal.dev".to_owned(), gen_zone(Some("dev"), "10.0.0.3"));
+      zones.insert(
+        "Internal.prod".to_owned(),
+        gen_zone(Some("prod"), "10.0.0.4"),
+      );
+    }
+    let dev = "10.0.0.10".parse().unwrap();
+    let other = "10.0.0.11".parse().unwrap();
+    nameserver
+      .set_clients(HashMap::from([(dev, "dev".to_owned())]))
+      .unwrap();
+    let local = "10.0.0.1".parse().unwrap();
+    let query = gen_query("db.test.com.", RecordType::A);
+    let response = nameserver.resolve(local, dev, &query).unwrap().unwrap();
+    assert_eq!(
+      response.answers()[0].data(),
+      Some(&RData::A(A("10.0.0.3".parse().unwrap())))
+    );
+    let response = nameserver.resolve(local, other, &query).unwrap().unwrap();
+    assert_eq!(
+      response.answers()[0].data(),
+      Some(&RData::A(A("10.0.0.2".parse().unwrap())))
+    );
+  }
+
+  #[test]
+  fn list_namespace_records() {
+    let gen_zone = |namespace: Option<&str>, name: &str, address: &str| Zone {
+      listen_address: "10.0.0.1".to_owned(),
+      namespace: namespace.map(ToOwned::to_owned),
+      entries: vec![ZoneEntry::Address {
+        name: name.to_owned(),
+        address: address.parse().unwrap(),
+      }],
+    };
+    let zones = HashMap::from([
+      (
+        "Internal".to_owned(),
+        gen_zone(None, "db.test.com", "10.0.0.2"),
+      ),
+      (
+        "discovery".to_owned(),
+        gen_zone(None, "api.test.com", "10.0.0.5"),
+      ),
+      (
+        "Internal.dev".to_owned(),
+        gen_zone(Some("dev"), "*.test.com", "10.0.0.3"),
+      ),
+      (
+        "Public".to_owned(),
+        Zone {
+          listen_address: "10.0.1.1".to_owned(),
+          ..gen_zone(None, "web.test.com", "10.0.0.6")
+        },
+      ),
+    ]);
+    let records = list_records(&zones, "10.0.0.1", None);
+    let names = records.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["api.test.com", "db.test.com"]);
+    let records = list_records(&zones, "10.0.0.1", Some("dev"));
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].name, "*.test.com");
+    assert_eq!(records[0].kind, "A");
+    assert_eq!(records[0].value, "10.0.0.3");
+    assert_eq!(records[0].namespace.as_deref(), Some("dev"));
+    assert_eq!(records[0].zone, "Internal.dev");
+  }
+}
@@ -9,6 +9,7 @@ use crate::vars;
 #[cfg(feature = "dev")]
 mod openapi;
 
+mod record;
 mod rule;
 
 pub async fn unhandled() -> Result<web::HttpResponse, HttpError> {
@@ -42,6 +43,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(
     web::scope("/{version}")
       .wrap(versioning)
-      .configure(rule::ntex_config),
+      .configure(rule::ntex_config)
+      .configure(record::ntex_config),
   );
 }
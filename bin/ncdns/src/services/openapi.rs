@@ -1,8 +1,11 @@
 use utoipa::OpenApi;
 
-use nanocld_client::stubs::dns::{DnsEntry, ResourceDnsRule};
+use nanocld_client::stubs::dns::{
+  DnsAaaaRecord, DnsCnameRecord, DnsEntry, DnsMxRecord, DnsPtrRecord,
+  DnsRecord, DnsServedRecord, DnsSrvRecord, DnsTxtRecord, ResourceDnsRule,
+};
 
-use super::rule;
+use super::{record, rule};
 
 /// Helper to generate the versioned OpenAPI documentation
 struct VersionModifier;
@@ -32,13 +35,23 @@ impl utoipa::Modify for VersionModifier {
   paths(
     rule::apply_rule,
     rule::remove_rule,
+    record::list_records,
   ),
   components(schemas(
     ResourceDnsRule,
     DnsEntry,
+    DnsRecord,
+    DnsAaaaRecord,
+    DnsCnameRecord,
+    DnsTxtRecord,
+    DnsSrvRecord,
+    DnsMxRecord,
+    DnsPtrRecord,
+    DnsServedRecord,
   )),
   tags(
     (name = "Rules", description = "Rules management endpoints."),
+    (name = "Records", description = "Served records endpoints."),
   ),
   modifiers(&VersionModifier),
 )]
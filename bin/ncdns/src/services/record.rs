@@ -0,0 +1,39 @@
+use ntex::web;
+
+use nanocl_error::http::HttpError;
+
+use nanocld_client::stubs::dns::{DnsRecordQuery, DnsServedRecord};
+use nanocld_client::stubs::generic::NetworkKind;
+use nanocld_client::NanocldClient;
+
+use crate::{backend::Backend, utils};
+
+/// List the records answered to the clients of a network and namespace
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Records",
+  path = "/records",
+  params(
+    ("network" = Option<String>, Query, description = "Network of the clients default to Internal"),
+    ("namespace" = Option<String>, Query, description = "Namespace of the clients"),
+  ),
+  responses(
+    (status = 200, description = "The served records", body = [DnsServedRecord]),
+  ),
+))]
+#[web::get("/records")]
+pub(crate) async fn list_records(
+  client: web::types::State<NanocldClient>,
+  backend: web::types::State<Backend>,
+  qs: web::types::Query<DnsRecordQuery>,
+) -> Result<web::HttpResponse, HttpError> {
+  let network = qs.network.clone().unwrap_or(NetworkKind::Internal);
+  let listen_address = utils::get_network_addr(&network, &client).await?;
+  let records: Vec<DnsServedRecord> =
+    backend.list_records(&listen_address, qs.namespace.as_deref())?;
+  Ok(web::HttpResponse::Ok().json(&records))
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_records);
+}
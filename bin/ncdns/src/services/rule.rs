@@ -5,7 +5,7 @@ use nanocl_error::http::HttpError;
 use nanocld_client::stubs::dns::ResourceDnsRule;
 use nanocld_client::NanocldClient;
 
-use crate::{dnsmasq, utils};
+use crate::{backend::Backend, utils};
 
 /// Create/Update a new DnsRule
 #[cfg_attr(feature = "dev", utoipa::path(
@@ -24,12 +24,11 @@ use crate::{dnsmasq, utils};
 pub(crate) async fn apply_rule(
   // To follow the ressource service convention, we have to use a tuple
   client: web::types::State<NanocldClient>,
-  dnsmasq: web::types::State<dnsmasq::Dnsmasq>,
+  backend: web::types::State<Backend>,
   path: web::types::Path<(String, String)>,
   payload: web::types::Json<ResourceDnsRule>,
 ) -> Result<web::HttpResponse, HttpError> {
-  utils::update_entries(&path.1, &payload, &dnsmasq, &client).await?;
-  utils::reload_service(&client).await?;
+  utils::update_entries(&path.1, &payload, &backend, &client).await?;
   Ok(web::HttpResponse::Ok().json(&payload.into_inner()))
 }
 
@@ -48,7 +47,7 @@ pub(crate) async fn apply_rule(
 #[web::delete("/rules/{name}")]
 pub(crate) async fn remove_rule(
   client: web::types::State<NanocldClient>,
-  dnsmasq: web::types::State<dnsmasq::Dnsmasq>,
+  backend: web::types::State<Backend>,
   path: web::types::Path<(String, String)>,
 ) -> Result<web::HttpResponse, HttpError> {
   let rule = client.inspect_resource(&path.1).await?;
@@ -56,8 +55,7 @@ pub(crate) async fn remove_rule(
     .map_err(|err| {
       HttpError::bad_request(format!("Unable to serialize the DnsRule: {err}"))
     })?;
-  utils::remove_entries(&dns_rule, &dnsmasq, &client).await?;
-  utils::reload_service(&client).await?;
+  utils::remove_entries(&path.1, &dns_rule, &backend, &client).await?;
   Ok(web::HttpResponse::Ok().finish())
 }
 
@@ -84,6 +82,48 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "basic");
   }
 
+  #[ntex::test]
+  async fn records() {
+    let data =
+      std::fs::read_to_string("tests/resource_dns_records.yml").unwrap();
+    let payload = serde_yaml::from_str::<ResourceDnsRule>(&data).unwrap();
+    let client = gen_default_test_client();
+    let res = client
+      .send_put("/rules/test-records", Some(payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "records");
+  }
+
+  #[ntex::test]
+  async fn namespace() {
+    let data =
+      std::fs::read_to_string("tests/resource_dns_namespace.yml").unwrap();
+    let payload = serde_yaml::from_str::<ResourceDnsRule>(&data).unwrap();
+    let client = gen_default_test_client();
+    let res = client
+      .send_put("/rules/test-namespace", Some(payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "namespace");
+  }
+
+  #[ntex::test]
+  async fn apply_invalid_record() {
+    let client = gen_default_test_client();
+    let payload = serde_json::json!({
+      "Network": "Internal",
+      "Entries": [],
+      "Records": [{ "Txt": { "Name": "*.test.com", "Values": ["test"] } }],
+    });
+    let res = client
+      .send_put("/rules/test-invalid", Some(payload), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "apply invalid record"
+    );
+  }
+
   #[ntex::test]
   async fn apply_empty_rule() {
     let client = gen_default_test_client();
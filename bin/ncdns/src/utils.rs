@@ -1,3 +1,5 @@
+use std::{collections::BTreeMap, net::IpAddr};
+
 use nanocl_error::io::{FromIo, IoError, IoResult};
 
 use nanocld_client::stubs::dns::ResourceDnsRule;
@@ -6,7 +8,11 @@ use nanocld_client::stubs::generic::{
 };
 use nanocld_client::NanocldClient;
 
-use crate::dnsmasq::Dnsmasq;
+use crate::{
+  backend::Backend,
+  vars,
+  zone::{Zone, ZoneEntry},
+};
 
 /// Get public address of host
 async fn get_host_addr(client: &NanocldClient) -> IoResult<String> {
@@ -18,7 +24,9 @@ async fn get_host_addr(client: &NanocldClient) -> IoResult<String> {
 }
 
 /// Get address of nanoclbr0 network
-async fn get_bridge_addr(client: &NanocldClient) -> IoResult<String> {
+pub(crate) async fn get_bridge_addr(
+  client: &NanocldClient,
+) -> IoResult<String> {
   let info = client
     .info()
     .await
@@ -35,7 +43,7 @@ async fn get_bridge_addr(client: &NanocldClient) -> IoResult<String> {
 }
 
 /// Get network address of given network
-async fn get_network_addr(
+pub(crate) async fn get_network_addr(
   network: &NetworkKind,
   client: &NanocldClient,
 ) -> IoResult<String> {
@@ -53,8 +61,7 @@ async fn get_network_addr(
   Ok(addr)
 }
 
-/// Reload the dns service
-/// TODO: use a better way to reload the service, we may have to move from dnsmasq to something else
+/// Restart the `ndns` cargo running dnsmasq to reload its configs
 pub(crate) async fn reload_service(client: &NanocldClient) -> IoResult<()> {
   client
     .restart_process("cargo", "ndns", Some("system"))
@@ -62,14 +69,118 @@ pub(crate) async fn reload_service(client: &NanocldClient) -> IoResult<()> {
   Ok(())
 }
 
-pub(crate) async fn update_entries(
+/// Generate the zone entries of the entries and records of a rule
+async fn gen_zone_entries(
+  dns_rule: &ResourceDnsRule,
+  client: &NanocldClient,
+) -> IoResult<Vec<ZoneEntry>> {
+  let mut entries = Vec::new();
+  for entry in &dns_rule.entries {
+    let ip_address = match &entry.ip_address {
+      NetworkKind::Other(ip) => ip.to_string(),
+      network => get_network_addr(network, client).await?,
+    };
+    let address = ip_address.parse::<IpAddr>().map_err(|_| {
+      IoError::invalid_data(
+        "Network",
+        &format!("invalid address {ip_address} for {}", entry.name),
+      )
+    })?;
+    entries.push(ZoneEntry::Address {
+      name: entry.name.clone(),
+      address,
+    });
+  }
+  for record in dns_rule.records.as_deref().unwrap_or_default() {
+    entries.push(ZoneEntry::Record(record.clone()));
+  }
+  Ok(entries)
+}
+
+/// Name of the zone of a network and namespace
+fn gen_zone_name(network: &str, namespace: Option<&str>) -> String {
+  match namespace {
+    Some(namespace) => format!("{network}.{namespace}"),
+    None => network.to_owned(),
+  }
+}
+
+/// List every dns rule matching the filter
+async fn list_rules(
+  filter: &GenericFilter,
+  client: &NanocldClient,
+) -> IoResult<Vec<ResourceDnsRule>> {
+  let filter = filter
+    .clone()
+    .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()));
+  let resources = client.list_all_resource(&filter).await.map_err(|err| {
+    err.map_err_context(|| "Unable to list resources from nanocl daemon")
+  })?;
+  let mut rules = Vec::new();
+  for resource in resources {
+    let dns_rule = serde_json::from_value::<ResourceDnsRule>(
+      resource.spec.data,
+    )
+    .map_err(|err| err.map_err_context(|| "Unable to serialize the DnsRule"))?;
+    rules.push(dns_rule);
+  }
+  Ok(rules)
+}
+
+/// Write the zones of a network, one per namespace
+/// with the entries of every given rule
+async fn write_network_zones(
+  network: &NetworkKind,
+  rules: &[ResourceDnsRule],
+  backend: &Backend,
+  client: &NanocldClient,
+) -> IoResult<()> {
+  log::debug!("utils::write_network_zones: {} rules", rules.len());
+  let mut zones = BTreeMap::<Option<String>, Zone>::new();
+  for dns_rule in rules {
+    zones
+      .entry(dns_rule.namespace.clone())
+      .or_default()
+      .entries
+      .extend(gen_zone_entries(dns_rule, client).await?);
+  }
+  let listen_address = if zones.is_empty() {
+    String::default()
+  } else {
+    get_network_addr(network, client).await?
+  };
+  let network = network.to_string();
+  let mut names = Vec::new();
+  for (namespace, mut zone) in zones {
+    let name = gen_zone_name(&network, namespace.as_deref());
+    zone.listen_address.clone_from(&listen_address);
+    zone.namespace = namespace;
+    log::debug!("utils::write_network_zones: {name} {zone:?}");
+    backend.apply_zone(&name, &zone, client).await?;
+    names.push(name);
+  }
+  // Remove the zones of the namespaces without any rule left
+  let prefix = format!("{network}.");
+  for name in backend.list_zones().await? {
+    if (name == network || name.starts_with(&prefix)) && !names.contains(&name)
+    {
+      backend.remove_zone(&name, client).await?;
+    }
+  }
+  Ok(())
+}
+
+/// Write the zones of the network of the rule
+/// with the entries of every other rule of the same network
+/// The rule itself is omitted when `dns_rule` is only used to know the network
+async fn write_network_config(
   key: &str,
   dns_rule: &ResourceDnsRule,
-  dnsmasq: &Dnsmasq,
+  include_rule: bool,
+  backend: &Backend,
   client: &NanocldClient,
 ) -> IoResult<()> {
   let filter = GenericFilter::new()
-    .r#where("kind", GenericClause::Eq("DnsRule".to_owned()))
     .r#where("key", GenericClause::Ne(key.to_owned()))
     .r#where(
       "data",
@@ -77,77 +188,56 @@ pub(crate) async fn update_entries(
         serde_json::json!({ "Network": dns_rule.network }),
       ),
     );
-  let resources = client.list_resource(Some(&filter)).await.map_err(|err| {
-    err.map_err_context(|| "Unable to list resources from nanocl daemon")
-  })?;
-  log::debug!("utils::update_entries: {} resources", resources.len());
-  let mut entries = dns_rule.entries.clone();
-  for resource in resources {
-    let mut dns_rule = serde_json::from_value::<ResourceDnsRule>(
-      resource.spec.data,
-    )
-    .map_err(|err| err.map_err_context(|| "Unable to serialize the DnsRule"))?;
-    entries.append(&mut dns_rule.entries);
+  let mut rules = list_rules(&filter, client).await?;
+  if include_rule {
+    rules.push(dns_rule.clone());
   }
-  let listen_address = get_network_addr(&dns_rule.network, client).await?;
-  let mut file_content =
-    format!("bind-dynamic\nlisten-address={listen_address}\n");
-  for entry in &entries {
-    let ip_address = match &entry.ip_address {
-      NetworkKind::Local => "127.0.0.1".to_owned(),
-      NetworkKind::Public => get_host_addr(client).await?,
-      NetworkKind::Internal => get_bridge_addr(client).await?,
-      NetworkKind::Other(ip) => ip.to_string(),
-      NetworkKind::All => {
-        return Err(IoError::invalid_input(
-          "Network",
-          "All network is not supported",
-        ))
-      }
-    };
-    let entry = &format!("address=/{}/{}", entry.name, ip_address);
-    file_content += &format!("{entry}\n");
-    log::debug!("utils::update_entries: {entry}");
+  write_network_zones(&dns_rule.network, &rules, backend, client).await
+}
+
+/// Load every existing dns rule into the backend,
+/// it's called before serving so the records survive a restart
+pub(crate) async fn sync_rules(
+  backend: &Backend,
+  client: &NanocldClient,
+) -> IoResult<()> {
+  let rules = list_rules(&GenericFilter::new(), client).await?;
+  let mut networks =
+    BTreeMap::<String, (NetworkKind, Vec<ResourceDnsRule>)>::new();
+  for dns_rule in rules {
+    networks
+      .entry(dns_rule.network.to_string())
+      .or_insert_with(|| (dns_rule.network.clone(), Vec::new()))
+      .1
+      .push(dns_rule);
+  }
+  log::info!("utils::sync_rules: {} networks", networks.len());
+  for (network, rules) in networks.values() {
+    if let Err(err) = write_network_zones(network, rules, backend, client).await
+    {
+      log::warn!("utils::sync_rules: {network} {err}");
+    }
   }
-  dnsmasq
-    .write_config(&dns_rule.network.to_string(), &file_content)
-    .await?;
   Ok(())
 }
 
+pub(crate) async fn update_entries(
+  key: &str,
+  dns_rule: &ResourceDnsRule,
+  backend: &Backend,
+  client: &NanocldClient,
+) -> IoResult<()> {
+  dns_rule.validate()?;
+  write_network_config(key, dns_rule, true, backend, client).await
+}
+
 pub(crate) async fn remove_entries(
+  key: &str,
   dns_rule: &ResourceDnsRule,
-  dnsmasq: &Dnsmasq,
+  backend: &Backend,
   client: &NanocldClient,
 ) -> IoResult<()> {
-  let content = dnsmasq.read_config(&dns_rule.network.to_string()).await?;
-  log::debug!("{}", content);
-  let mut file_content = String::new();
-  let lines = content.lines();
-  let listen_address = get_network_addr(&dns_rule.network, client).await?;
-  let empty_entries = format!("listen-address={listen_address}\n");
-  for line in lines {
-    let mut found = false;
-    for entry in &dns_rule.entries {
-      if line.starts_with(&format!("address=/{}/", entry.name)) {
-        found = true;
-        log::debug!("Found {}", line);
-        break;
-      }
-    }
-    if !found {
-      file_content.push_str(&format!("{line}\n"));
-    }
-  }
-  log::debug!("{}", file_content);
-  if file_content == empty_entries {
-    dnsmasq.remove_config(&dns_rule.network.to_string()).await?;
-    return Ok(());
-  }
-  dnsmasq
-    .write_config(&dns_rule.network.to_string(), &file_content)
-    .await?;
-  Ok(())
+  write_network_config(key, dns_rule, false, backend, client).await
 }
 
 #[cfg(test)]
@@ -155,7 +245,7 @@ pub mod tests {
   pub use nanocl_utils::ntex::test_client::*;
   use nanocld_client::{ConnectOpts, NanocldClient};
 
-  use crate::{dnsmasq, services, vars};
+  use crate::{backend::Backend, nameserver::Nameserver, services, vars};
 
   // Before a test
   pub fn before() {
@@ -166,10 +256,10 @@ pub mod tests {
   // Generate a test server
   pub fn gen_default_test_client() -> TestClient {
     before();
-    let dnsmasq = dnsmasq::Dnsmasq::new("/tmp/dnsmasq");
-    dnsmasq
-      .ensure()
-      .expect("Expect to setup minimal dnsmasq config");
+    let backend = Backend::Embedded(
+      Nameserver::new(&["1.1.1.1".to_owned()], 5353)
+        .expect("Expect to create the nameserver"),
+    );
     let client = NanocldClient::connect_to(&ConnectOpts {
       url: "http://nanocl.internal:8585".into(),
       ..Default::default()
@@ -178,7 +268,7 @@ pub mod tests {
     // Create test server
     let srv = ntex::web::test::server(move || {
       ntex::web::App::new()
-        .state(dnsmasq.clone())
+        .state(backend.clone())
         .state(client.clone())
         .configure(services::ntex_config)
     });
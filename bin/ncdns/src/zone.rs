@@ -0,0 +1,23 @@
+use std::net::IpAddr;
+
+use nanocld_client::stubs::dns::DnsRecord;
+
+/// A record served by a zone
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum ZoneEntry {
+  /// Address resolved for the name and every subdomain
+  Address { name: String, address: IpAddr },
+  /// Address resolved only for the name, a name can have multiple hosts
+  Host { name: String, address: IpAddr },
+  /// Any other record type
+  Record(DnsRecord),
+}
+
+/// Records served on a listen address
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Zone {
+  pub(crate) listen_address: String,
+  /// Only answer the instances of this namespace when set
+  pub(crate) namespace: Option<String>,
+  pub(crate) entries: Vec<ZoneEntry>,
+}
@@ -8,6 +8,9 @@ pub struct Cli {
   /// Path to state directory
   #[clap(long)]
   pub state_dir: String,
+  /// Address to expose the prometheus metrics on eg: tcp://0.0.0.0:9113
+  #[clap(long)]
+  pub metrics_host: Option<String>,
 }
 
 #[cfg(test)]
@@ -28,6 +31,15 @@ mod tests {
     let args = Cli::parse_from(["ncproxy", "--state-dir", "/test/state"]);
     assert_eq!(args.nginx_dir, "/etc/nginx");
     assert_eq!(args.state_dir, "/test/state");
+    assert_eq!(args.metrics_host, None);
+    let args = Cli::parse_from([
+      "ncproxy",
+      "--state-dir",
+      "/test/state",
+      "--metrics-host",
+      "tcp://0.0.0.0:9113",
+    ]);
+    assert_eq!(args.metrics_host.as_deref(), Some("tcp://0.0.0.0:9113"));
     let _ = Cli::try_parse();
   }
 }
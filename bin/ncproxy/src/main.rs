@@ -39,6 +39,12 @@ async fn main() -> std::io::Result<()> {
     Err(err) => err.print_and_exit(),
     Ok(state) => state,
   };
+  if let Some(host) = &cli.metrics_host {
+    // The server keep running in the background until the program stop
+    if let Err(err) = utils::server::gen_metrics(host, &state) {
+      err.print_and_exit();
+    }
+  }
   match utils::server::gen(&state) {
     Err(err) => err.print_and_exit(),
     Ok(srv) => srv.await,
@@ -0,0 +1,89 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, RwLock},
+};
+
+use nanocl_error::io::{IoError, IoResult};
+
+use nanocld_client::stubs::proxy::ProxyListener;
+
+/// Index of the listeners claimed by every proxy rule resources.
+/// It's used to reject rules that would overwrite each other in nginx.
+#[derive(Clone, Default)]
+pub struct ListenerIndex(Arc<RwLock<HashMap<String, Vec<ProxyListener>>>>);
+
+impl ListenerIndex {
+  /// Ensure the listeners of the resource `name` doesn't conflict
+  /// with the listeners of other resources
+  pub fn check(&self, name: &str, listeners: &[ProxyListener]) -> IoResult<()> {
+    let index = self.0.read()?;
+    for (owner, owned) in index.iter() {
+      if owner == name {
+        continue;
+      }
+      for listener in listeners {
+        let Some(conflict) =
+          owned.iter().find(|owned| owned.conflicts_with(listener))
+        else {
+          continue;
+        };
+        let domain = conflict
+          .domain
+          .as_ref()
+          .map(|domain| format!(" for domain {domain}"))
+          .unwrap_or_default();
+        return Err(IoError::with_context(
+          format!("Rule {name}"),
+          std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!(
+              "port {} on network {}{domain} is already used by resource {owner}",
+              conflict.port, conflict.network,
+            ),
+          ),
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Set the listeners owned by the resource `name`
+  pub fn insert(
+    &self,
+    name: &str,
+    listeners: Vec<ProxyListener>,
+  ) -> IoResult<()> {
+    let mut index = self.0.write()?;
+    index.insert(name.to_owned(), listeners);
+    Ok(())
+  }
+
+  /// Replace the whole index, the listeners of resources that
+  /// aren't in `index` anymore are dropped
+  pub fn replace(
+    &self,
+    index: HashMap<String, Vec<ProxyListener>>,
+  ) -> IoResult<()> {
+    *self.0.write()? = index;
+    Ok(())
+  }
+
+  /// Remove the listeners owned by the resource `name`
+  pub fn remove(&self, name: &str) -> IoResult<()> {
+    let mut index = self.0.write()?;
+    index.remove(name);
+    Ok(())
+  }
+
+  /// List every listeners sorted by port
+  pub fn list(&self) -> IoResult<Vec<ProxyListener>> {
+    let index = self.0.read()?;
+    let mut listeners = index.values().flatten().cloned().collect::<Vec<_>>();
+    listeners.sort_by(|a, b| {
+      a.port
+        .cmp(&b.port)
+        .then_with(|| a.resource.cmp(&b.resource))
+    });
+    Ok(listeners)
+  }
+}
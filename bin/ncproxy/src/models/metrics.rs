@@ -0,0 +1,189 @@
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  sync::{Arc, RwLock},
+};
+
+use nanocl_error::io::IoResult;
+
+use nanocld_client::stubs::metric::{HttpMetric, StreamMetric};
+
+/// Upper bounds in seconds of the latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+  0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// A cumulative histogram with fixed latency buckets
+#[derive(Default)]
+struct Histogram {
+  buckets: [u64; LATENCY_BUCKETS.len()],
+  sum: f64,
+  count: u64,
+}
+
+impl Histogram {
+  fn observe(&mut self, value: f64) {
+    for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+      if value <= *bound {
+        self.buckets[i] += 1;
+      }
+    }
+    self.sum += value;
+    self.count += 1;
+  }
+
+  fn render(&self, name: &str, labels: &str, out: &mut String) {
+    for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+      let _ = writeln!(
+        out,
+        "{name}_bucket{{{labels},le=\"{bound}\"}} {}",
+        self.buckets[i]
+      );
+    }
+    let _ =
+      writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+    let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+    let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+  }
+}
+
+/// Aggregated values of a proxy rule and upstream
+#[derive(Default)]
+struct Series {
+  /// Number of requests or sessions by status class eg: 2xx
+  statuses: BTreeMap<String, u64>,
+  latency: Histogram,
+  bytes_sent: u64,
+  bytes_received: u64,
+}
+
+/// Labels identifying a series: rule, upstream and protocol
+type SeriesKey = (String, String, String);
+
+#[derive(Default)]
+struct MetricsInner {
+  http: BTreeMap<SeriesKey, Series>,
+  stream: BTreeMap<SeriesKey, Series>,
+}
+
+/// Aggregate the nginx access logs per proxy rule and upstream
+/// to expose them in the prometheus text format
+#[derive(Clone, Default)]
+pub struct MetricsRegistry(Arc<RwLock<MetricsInner>>);
+
+/// Escape a prometheus label value
+fn escape_label(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+fn status_class(status: i64) -> String {
+  match status {
+    100..=599 => format!("{}xx", status / 100),
+    _ => "unknown".to_owned(),
+  }
+}
+
+fn series_labels(key: &SeriesKey) -> String {
+  let (rule, upstream, protocol) = key;
+  format!(
+    "rule=\"{}\",upstream=\"{}\",protocol=\"{}\"",
+    escape_label(rule),
+    escape_label(upstream),
+    escape_label(protocol)
+  )
+}
+
+fn render_series(
+  prefix: &str,
+  unit: &str,
+  series: &BTreeMap<SeriesKey, Series>,
+  out: &mut String,
+) {
+  let name = format!("ncproxy_{prefix}_{unit}s_total");
+  let _ = writeln!(out, "# HELP {name} Number of {unit}s by status class");
+  let _ = writeln!(out, "# TYPE {name} counter");
+  for (key, value) in series {
+    let labels = series_labels(key);
+    for (status, count) in &value.statuses {
+      let _ = writeln!(out, "{name}{{{labels},status=\"{status}\"}} {count}");
+    }
+  }
+  let name = format!("ncproxy_{prefix}_{unit}_duration_seconds");
+  let _ = writeln!(out, "# HELP {name} Duration of {unit}s in seconds");
+  let _ = writeln!(out, "# TYPE {name} histogram");
+  for (key, value) in series {
+    value.latency.render(&name, &series_labels(key), out);
+  }
+  let name = format!("ncproxy_{prefix}_sent_bytes_total");
+  let _ = writeln!(out, "# HELP {name} Bytes sent to clients");
+  let _ = writeln!(out, "# TYPE {name} counter");
+  for (key, value) in series {
+    let _ =
+      writeln!(out, "{name}{{{}}} {}", series_labels(key), value.bytes_sent);
+  }
+  let name = format!("ncproxy_{prefix}_received_bytes_total");
+  let _ = writeln!(out, "# HELP {name} Bytes received from clients");
+  let _ = writeln!(out, "# TYPE {name} counter");
+  for (key, value) in series {
+    let _ = writeln!(
+      out,
+      "{name}{{{}}} {}",
+      series_labels(key),
+      value.bytes_received
+    );
+  }
+}
+
+impl MetricsRegistry {
+  /// Record a line of the http access log
+  pub fn record_http(&self, metric: &HttpMetric) -> IoResult<()> {
+    let key = (
+      metric.proxy_rule.clone().unwrap_or_default(),
+      metric.upstream_addr.clone().unwrap_or_default(),
+      metric.server_protocol.clone(),
+    );
+    let mut inner = self.0.write()?;
+    let series = inner.http.entry(key).or_default();
+    *series
+      .statuses
+      .entry(status_class(metric.status))
+      .or_default() += 1;
+    series.latency.observe(metric.request_time);
+    series.bytes_sent += metric.bytes_sent.max(0) as u64;
+    series.bytes_received += metric.request_length.max(0) as u64;
+    Ok(())
+  }
+
+  /// Record a line of the stream access log
+  pub fn record_stream(&self, metric: &StreamMetric) -> IoResult<()> {
+    let key = (
+      metric.proxy_rule.clone().unwrap_or_default(),
+      metric.upstream_addr.clone(),
+      metric.protocol.clone().unwrap_or_default(),
+    );
+    let mut inner = self.0.write()?;
+    let series = inner.stream.entry(key).or_default();
+    *series
+      .statuses
+      .entry(status_class(metric.status))
+      .or_default() += 1;
+    series
+      .latency
+      .observe(metric.session_time.parse::<f64>().unwrap_or_default());
+    series.bytes_sent += metric.bytes_sent.max(0) as u64;
+    series.bytes_received += metric.bytes_received.max(0) as u64;
+    Ok(())
+  }
+
+  /// Render the metrics using the prometheus text format
+  pub fn render(&self) -> IoResult<String> {
+    let inner = self.0.read()?;
+    let mut out = String::new();
+    render_series("http", "request", &inner.http, &mut out);
+    render_series("stream", "session", &inner.stream, &mut out);
+    Ok(out)
+  }
+}
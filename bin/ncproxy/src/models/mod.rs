@@ -1,7 +1,11 @@
+mod listener;
+mod metrics;
 mod store;
 mod system;
 mod template;
 
+pub use listener::*;
+pub use metrics::*;
 pub use store::*;
 pub use system::*;
 pub use template::*;
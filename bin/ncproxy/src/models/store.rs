@@ -60,20 +60,28 @@ impl Store {
     }
   }
 
-  fn gen_path(&self, name: &str, kind: &NginxRuleKind) -> (String, String) {
+  fn gen_dir(&self, kind: &NginxRuleKind) -> (String, String) {
     let dir = &self.dir;
     match kind {
       NginxRuleKind::Site => (
-        format!("{dir}/sites-available/{name}.conf"),
-        format!("{dir}/sites-enabled/{name}.conf"),
+        format!("{dir}/sites-available"),
+        format!("{dir}/sites-enabled"),
       ),
       NginxRuleKind::Stream => (
-        format!("{dir}/streams-available/{name}.conf"),
-        format!("{dir}/streams-enabled/{name}.conf"),
+        format!("{dir}/streams-available"),
+        format!("{dir}/streams-enabled"),
       ),
     }
   }
 
+  fn gen_path(&self, name: &str, kind: &NginxRuleKind) -> (String, String) {
+    let (available, enabled) = self.gen_dir(kind);
+    (
+      format!("{available}/{name}.conf"),
+      format!("{enabled}/{name}.conf"),
+    )
+  }
+
   pub async fn write_conf_file(
     &self,
     name: &str,
@@ -90,6 +98,34 @@ impl Store {
     Ok(())
   }
 
+  /// Copy every enabled config files into the target store
+  /// except the ones of the given rule name
+  pub async fn copy_enabled(
+    &self,
+    target: &Store,
+    exclude: &str,
+  ) -> IoResult<()> {
+    for kind in [NginxRuleKind::Site, NginxRuleKind::Stream] {
+      let (_, enabled_dir) = self.gen_dir(&kind);
+      let mut entries =
+        tokio::fs::read_dir(&enabled_dir).await.map_err(|err| {
+          err.map_err_context(|| format!("Unable to read {enabled_dir}"))
+        })?;
+      while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(name) = file_name.strip_suffix(".conf") else {
+          continue;
+        };
+        if name == exclude {
+          continue;
+        }
+        let data = tokio::fs::read_to_string(entry.path()).await?;
+        target.write_conf_file(name, &data, &kind).await?;
+      }
+    }
+    Ok(())
+  }
+
   pub async fn delete_conf_file(&self, name: &str, kind: &NginxRuleKind) {
     let path = self.gen_path(name, kind);
     let _ = tokio::fs::remove_file(&path.0).await;
@@ -9,12 +9,14 @@ use nanocld_client::NanocldClient;
 
 use crate::utils;
 
-use super::Store;
+use super::{ListenerIndex, MetricsRegistry, Store};
 
 /// Shared state of the program
 #[derive(Clone)]
 pub struct SystemState {
   pub store: Store,
+  pub listeners: ListenerIndex,
+  pub metrics: MetricsRegistry,
   pub client: NanocldClient,
   pub event_emitter: EventEmitter,
   pub nginx_dir: String,
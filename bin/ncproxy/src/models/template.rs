@@ -1,8 +1,43 @@
-use nanocld_client::stubs::proxy::{LimitReq, ProxySslConfig};
+use nanocld_client::stubs::proxy::{
+  LimitReq, ProxyHttpTimeouts, ProxySslConfig,
+};
 use serde::{Deserialize, Serialize};
 
 use nanocl_error::io::{IoError, IoResult};
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RewriteTemplate {
+  pub pattern: String,
+  pub replacement: String,
+  pub flag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorsTemplate {
+  /// Origin to send back as is, set when any origin is allowed
+  pub origin: Option<String>,
+  /// Regex matching the allowed origins
+  pub origin_regex: Option<String>,
+  pub methods: String,
+  pub headers: Option<String>,
+  pub expose_headers: Option<String>,
+  pub allow_credentials: bool,
+  pub max_age: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BasicAuthTemplate {
+  pub realm: String,
+  pub user_file: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpstreamTlsTemplate {
+  pub server_name: String,
+  pub verify: bool,
+  pub trusted_certificate: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LocationTemplate {
   pub path: String,
@@ -14,6 +49,17 @@ pub struct LocationTemplate {
   pub version: Option<f64>,
   pub headers: Option<Vec<String>>,
   pub ssl: Option<ProxySslConfig>,
+  pub rewrites: Vec<RewriteTemplate>,
+  pub response_headers: Vec<String>,
+  pub timeouts: Option<ProxyHttpTimeouts>,
+  pub client_max_body_size: Option<String>,
+  pub websocket: bool,
+  pub cors: Option<CorsTemplate>,
+  pub basic_auth: Option<BasicAuthTemplate>,
+  /// Host header sent to the upstream, default to the requested host
+  pub host_header: Option<String>,
+  pub upstream_tls: Option<UpstreamTlsTemplate>,
+  pub keepalive: bool,
 }
 
 pub struct Template<'a> {
@@ -55,6 +101,10 @@ pub const UPSTREAM_TEMPLATE: &Template = &Template {
   data: include_str!("templates/upstream.conf"),
 };
 
+pub const EXTERNAL_UPSTREAM_TEMPLATE: &Template = &Template {
+  data: include_str!("templates/external_upstream.conf"),
+};
+
 pub const UNIX_UPSTREAM_TEMPLATE: &Template = &Template {
   data: include_str!("templates/unix_upstream.conf"),
 };
@@ -0,0 +1,93 @@
+use ntex::web;
+
+use nanocl_error::http::HttpError;
+
+use crate::models::SystemStateRef;
+
+/// Get the aggregated proxy metrics in the prometheus text format
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Metrics",
+  path = "/metrics",
+  responses(
+    (status = 200, description = "The metrics in the prometheus text format", body = String),
+  ),
+))]
+#[web::get("/metrics")]
+pub async fn get_metrics(
+  state: web::types::State<SystemStateRef>,
+) -> Result<web::HttpResponse, HttpError> {
+  let metrics = state.metrics.render()?;
+  Ok(
+    web::HttpResponse::Ok()
+      .content_type("text/plain; version=0.0.4")
+      .body(metrics),
+  )
+}
+
+pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(get_metrics);
+}
+
+#[cfg(test)]
+mod tests {
+  use ntex::http;
+
+  use nanocld_client::stubs::metric::HttpMetric;
+
+  use crate::utils::tests::*;
+
+  #[ntex::test]
+  async fn basic() {
+    let system = gen_default_test_system().await;
+    // A line of the nginx access log as written by the http log format
+    let metric = serde_json::from_value::<HttpMetric>(serde_json::json!({
+      "date_gmt": "2024-06-12T10:00:00+00:00",
+      "uri": "/",
+      "host": "test.nanocl.internal",
+      "remote_addr": "10.0.0.1",
+      "realip_remote_addr": "10.0.0.1",
+      "server_protocol": "HTTP/1.1",
+      "request_method": "GET",
+      "bytes_sent": "512",
+      "content_length": "0",
+      "status": "404",
+      "request_time": "0.02",
+      "body_bytes_sent": "256",
+      "proxy_host": "",
+      "upstream_addr": "10.0.0.2:80",
+      "query_string": "",
+      "request_body": "",
+      "content_type": "",
+      "http_user_agent": "",
+      "http_referrer": "",
+      "http_accept_language": "",
+      "request_length": "128",
+      "proxy_rule": "metrics-test-rule",
+    }))
+    .unwrap();
+    system.state.metrics.record_http(&metric).unwrap();
+    let mut res = system.client.send_get("/metrics", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "get metrics");
+    let content_type = res.headers().get("content-type").unwrap();
+    assert_eq!(content_type, "text/plain; version=0.0.4");
+    let body = res.body().await.unwrap();
+    let body = String::from_utf8_lossy(&body);
+    let labels = "rule=\"metrics-test-rule\",upstream=\"10.0.0.2:80\",protocol=\"HTTP/1.1\"";
+    for line in [
+      "# TYPE ncproxy_http_requests_total counter".to_owned(),
+      format!("ncproxy_http_requests_total{{{labels},status=\"4xx\"}} 1"),
+      format!(
+        "ncproxy_http_request_duration_seconds_bucket{{{labels},le=\"0.025\"}} 1"
+      ),
+      format!(
+        "ncproxy_http_request_duration_seconds_bucket{{{labels},le=\"0.01\"}} 0"
+      ),
+      format!("ncproxy_http_request_duration_seconds_count{{{labels}}} 1"),
+      format!("ncproxy_http_sent_bytes_total{{{labels}}} 512"),
+      format!("ncproxy_http_received_bytes_total{{{labels}}} 128"),
+    ] {
+      assert!(body.lines().any(|l| l == line), "missing {line} in {body}");
+    }
+  }
+}
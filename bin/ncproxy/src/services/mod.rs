@@ -9,12 +9,18 @@ use crate::vars;
 #[cfg(feature = "dev")]
 mod openapi;
 
+mod metrics;
 mod rule;
 
 pub async fn unhandled() -> Result<web::HttpResponse, HttpError> {
   Err(HttpError::not_found("Route or method unhandled"))
 }
 
+/// Services exposed on the standalone metrics listener
+pub fn metrics_config(config: &mut web::ServiceConfig) {
+  metrics::ntex_config(config);
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
   #[cfg(feature = "dev")]
   {
@@ -39,6 +45,7 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
   config.service(
     web::scope("/{version}")
       .wrap(versioning)
-      .configure(rule::ntex_config),
+      .configure(rule::ntex_config)
+      .configure(metrics::ntex_config),
   );
 }
@@ -1,13 +1,15 @@
 use utoipa::OpenApi;
 
 use nanocld_client::stubs::proxy::{
-  HttpTarget, LocationTarget, ProxyHttpLocation, ProxyRule, ProxyRuleHttp,
-  ProxyRuleStream, ProxySsl, ProxySslConfig, ProxyStreamProtocol,
+  HttpTarget, LocationTarget, ProxyBasicAuth, ProxyHttpCors, ProxyHttpLocation,
+  ProxyHttpRewrite, ProxyHttpTimeouts, ProxyListener, ProxyListenerProtocol,
+  ProxyRewriteFlag, ProxyRule, ProxyRuleHttp, ProxyRuleStream, ProxySsl,
+  ProxySslConfig, ProxyStreamProtocol, ProxyUpstreamHealth, ProxyUpstreamTls,
   ResourceProxyRule, StreamTarget, UnixTarget, UpstreamTarget, UriTarget,
   UrlRedirect,
 };
 
-use super::rule;
+use super::{metrics, rule};
 
 /// Helper to generate the versioned OpenAPI documentation
 struct VersionModifier;
@@ -35,8 +37,11 @@ impl utoipa::Modify for VersionModifier {
 #[derive(OpenApi)]
 #[openapi(
   paths(
+    rule::list_listeners,
     rule::apply_rule,
+    rule::validate_rule,
     rule::remove_rule,
+    metrics::get_metrics,
   ),
   components(schemas(
     ResourceProxyRule,
@@ -44,6 +49,15 @@ impl utoipa::Modify for VersionModifier {
     ProxyRuleHttp,
     ProxyRuleStream,
     ProxyHttpLocation,
+    ProxyHttpRewrite,
+    ProxyRewriteFlag,
+    ProxyHttpTimeouts,
+    ProxyHttpCors,
+    ProxyBasicAuth,
+    ProxyUpstreamHealth,
+    ProxyUpstreamTls,
+    ProxyListener,
+    ProxyListenerProtocol,
     ProxySsl,
     ProxySslConfig,
     ProxyStreamProtocol,
@@ -57,6 +71,7 @@ impl utoipa::Modify for VersionModifier {
   )),
   tags(
     (name = "Rules", description = "Rules management endpoints."),
+    (name = "Metrics", description = "Metrics endpoints."),
   ),
   modifiers(&VersionModifier),
 )]
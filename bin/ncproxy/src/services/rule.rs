@@ -26,11 +26,40 @@ pub async fn apply_rule(
   payload: web::types::Json<ResourceProxyRule>,
 ) -> Result<web::HttpResponse, HttpError> {
   log::info!("apply_rule: {}", path.1);
+  utils::nginx::validate_rule(&path.1, &payload, &state).await?;
   utils::nginx::add_rule(&path.1, &payload, &state).await?;
+  state
+    .listeners
+    .insert(&path.1, payload.listeners(&path.1))?;
   state.event_emitter.emit_reload().await;
   Ok(web::HttpResponse::Ok().json(&payload.into_inner()))
 }
 
+/// Validate a ProxyRule against nginx without applying it
+#[cfg_attr(feature = "dev", utoipa::path(
+  post,
+  tag = "Rules",
+  path = "/rules/{name}/validate",
+  request_body = ResourceProxyRule,
+  params(
+    ("name" = String, Path, description = "Name of the rule"),
+  ),
+  responses(
+    (status = 200, description = "The rule is valid", body = ResourceProxyRule),
+    (status = 400, description = "The rule is invalid"),
+  ),
+))]
+#[web::post("/rules/{name}/validate")]
+pub async fn validate_rule(
+  state: web::types::State<SystemStateRef>,
+  path: web::types::Path<(String, String)>,
+  payload: web::types::Json<ResourceProxyRule>,
+) -> Result<web::HttpResponse, HttpError> {
+  log::info!("validate_rule: {}", path.1);
+  utils::nginx::validate_rule(&path.1, &payload, &state).await?;
+  Ok(web::HttpResponse::Ok().json(&payload.into_inner()))
+}
+
 /// Delete a ProxyRule
 #[cfg_attr(feature = "dev", utoipa::path(
   delete,
@@ -50,12 +79,32 @@ pub async fn remove_rule(
 ) -> Result<web::HttpResponse, HttpError> {
   log::info!("remove_rule: {}", path.1);
   utils::nginx::del_rule(&path.1, &state).await;
+  state.listeners.remove(&path.1)?;
   state.event_emitter.emit_reload().await;
   Ok(web::HttpResponse::Ok().finish())
 }
 
+/// List the listeners claimed by every ProxyRule
+#[cfg_attr(feature = "dev", utoipa::path(
+  get,
+  tag = "Rules",
+  path = "/rules/listeners",
+  responses(
+    (status = 200, description = "List of listeners", body = [nanocld_client::stubs::proxy::ProxyListener]),
+  ),
+))]
+#[web::get("/rules/listeners")]
+pub async fn list_listeners(
+  state: web::types::State<SystemStateRef>,
+) -> Result<web::HttpResponse, HttpError> {
+  let listeners = state.listeners.list()?;
+  Ok(web::HttpResponse::Ok().json(&listeners))
+}
+
 pub fn ntex_config(config: &mut web::ServiceConfig) {
+  config.service(list_listeners);
   config.service(apply_rule);
+  config.service(validate_rule);
   config.service(remove_rule);
 }
 
@@ -63,6 +112,8 @@ pub fn ntex_config(config: &mut web::ServiceConfig) {
 mod tests {
   use ntex::http;
 
+  use nanocld_client::stubs::proxy::ProxyListener;
+
   use crate::utils::tests::*;
 
   #[ntex::test]
@@ -83,4 +134,195 @@ mod tests {
     test_status_code!(res.status(), http::StatusCode::OK, "delete a rule");
     clean_test_cargo().await.unwrap();
   }
+
+  #[ntex::test]
+  async fn validate() {
+    let name = "ncproxy-io-test-validate";
+    let client = gen_default_test_client().await;
+    ensure_test_cargo().await.unwrap();
+    let payload = read_rule("tests/basic.yml").unwrap();
+    let res = client
+      .send_post(
+        &format!("/rules/{name}/validate"),
+        Some(&payload),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "validate a rule");
+    let payload = serde_json::json!({
+      "Rules": [{
+        "Network": "All",
+        "Locations": [{
+          "Path": "/",
+          "Target": { "Url": "https://google.com" },
+          "Headers": ["Invalid-Header"],
+        }],
+      }],
+    });
+    let res = client
+      .send_post(
+        &format!("/rules/{name}/validate"),
+        Some(&payload),
+        None::<String>,
+      )
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "validate an invalid rule"
+    );
+    clean_test_cargo().await.unwrap();
+  }
+
+  #[ntex::test]
+  async fn conflict() {
+    let name = "ncproxy-io-test-conflict";
+    let other = "ncproxy-io-test-conflict-other";
+    let client = gen_default_test_client().await;
+    ensure_test_cargo().await.unwrap();
+    let payload = read_rule("tests/basic.yml").unwrap();
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put a rule");
+    let res = client
+      .send_put(&format!("/rules/{other}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::CONFLICT,
+      "put a conflicting rule"
+    );
+    let mut res = client.send_get("/rules/listeners", None::<String>).await;
+    test_status_code!(res.status(), http::StatusCode::OK, "list listeners");
+    let listeners = res.json::<Vec<ProxyListener>>().await.unwrap();
+    assert!(listeners.iter().all(|listener| listener.resource == name));
+    let res = client
+      .send_delete(&format!("/rules/{name}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "delete a rule");
+    clean_test_cargo().await.unwrap();
+  }
+
+  #[ntex::test]
+  async fn external() {
+    let name = "ncproxy-io-test-external";
+    let client = gen_default_test_client().await;
+    let payload = read_rule("tests/external.yml").unwrap();
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "put a rule");
+    let res = client
+      .send_delete(&format!("/rules/{name}"), None::<String>)
+      .await;
+    test_status_code!(res.status(), http::StatusCode::OK, "delete a rule");
+    let payload = serde_json::json!({
+      "Rules": [{
+        "Network": "All",
+        "Protocol": "Tcp",
+        "Port": 9998,
+        "Target": { "Uri": "google.com" },
+      }],
+    });
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "put a uri without port"
+    );
+  }
+
+  #[ntex::test]
+  async fn invalid_location() {
+    let name = "ncproxy-io-test-invalid-location";
+    let client = gen_default_test_client().await;
+    let payload = serde_json::json!({
+      "Rules": [{
+        "Network": "All",
+        "Locations": [{
+          "Path": "~ ^/api",
+          "StripPrefix": true,
+          "Target": { "Url": "https://google.com" },
+        }],
+      }],
+    });
+    let res = client
+      .send_put(&format!("/rules/{name}"), Some(&payload), None::<String>)
+      .await;
+    test_status_code!(
+      res.status(),
+      http::StatusCode::BAD_REQUEST,
+      "put an invalid rule"
+    );
+  }
+
+  #[ntex::test]
+  async fn unsafe_values() {
+    let name = "ncproxy-io-test-unsafe-values";
+    let client = gen_default_test_client().await;
+    let location = serde_json::json!({
+      "Path": "/",
+      "Target": { "Url": "https://google.com" },
+    });
+    let fields = [
+      ("ResponseHeaders", serde_json::json!(["X-A b{c}"])),
+      ("Headers", serde_json::json!(["X-A b{c}"])),
+      (
+        "BasicAuth",
+        serde_json::json!({ "Secret": "s", "Realm": "a{c}" }),
+      ),
+      (
+        "Rewrites",
+        serde_json::json!([{ "Pattern": "^/a{c}", "Replacement": "/b" }]),
+      ),
+      (
+        "Rewrites",
+        serde_json::json!([{ "Pattern": "^/a", "Replacement": "/b{c}" }]),
+      ),
+      (
+        "Cors",
+        serde_json::json!({ "AllowedOrigins": ["https://a.com{c}"] }),
+      ),
+      (
+        "Cors",
+        serde_json::json!({ "AllowedOrigins": ["*"], "AllowedMethods": ["GET{c}"] }),
+      ),
+      (
+        "Cors",
+        serde_json::json!({ "AllowedOrigins": ["*"], "AllowedHeaders": ["X-A{c}"] }),
+      ),
+      (
+        "Cors",
+        serde_json::json!({ "AllowedOrigins": ["*"], "ExposeHeaders": ["X-A{c}"] }),
+      ),
+    ];
+    for c in ["\"", ";", "{", "}", "\n", "\u{7}"] {
+      for (field, value) in &fields {
+        let escaped = serde_json::to_string(c).unwrap();
+        let value = value
+          .to_string()
+          .replace("{c}", &escaped[1..escaped.len() - 1]);
+        let mut location = location.clone();
+        location[field] = serde_json::from_str(&value).unwrap();
+        let payload = serde_json::json!({
+          "Rules": [{ "Network": "All", "Locations": [location] }],
+        });
+        let res = client
+          .send_post(
+            &format!("/rules/{name}/validate"),
+            Some(&payload),
+            None::<String>,
+          )
+          .await;
+        test_status_code!(
+          res.status(),
+          http::StatusCode::BAD_REQUEST,
+          format!("validate {field} with {c:?}")
+        );
+      }
+    }
+  }
 }
@@ -158,6 +158,9 @@ async fn r#loop(state: &SystemStateRef) {
           continue;
         }
         let _ = utils::nginx::ensure_conf(state).await;
+        if let Err(err) = utils::resource::sync_listeners(state).await {
+          log::warn!("event::loop: {err}");
+        }
         log::info!("event::loop: subscribed to nanocld events");
         while let Some(event) = stream.next().await {
           let event = match event {
@@ -6,7 +6,10 @@ use nanocld_client::NanocldClient;
 
 use crate::{
   cli::Cli,
-  models::{EventEmitter, Store, SystemState, SystemStateRef},
+  models::{
+    EventEmitter, ListenerIndex, MetricsRegistry, Store, SystemState,
+    SystemStateRef,
+  },
 };
 
 use super::{event, metric};
@@ -27,6 +30,8 @@ pub async fn init(cli: &Cli) -> IoResult<SystemStateRef> {
     client,
     event_emitter,
     store: Store::new(&cli.state_dir),
+    listeners: ListenerIndex::default(),
+    metrics: MetricsRegistry::default(),
     nginx_dir: cli.nginx_dir.clone(),
   });
   event::spawn(&state);
@@ -16,10 +16,7 @@ use ntex::{http, rt};
 
 use nanocl_error::io::{FromIo, IoError, IoResult};
 
-use nanocld_client::{
-  stubs::metric::{HttpMetric, MetricPartial},
-  NanocldClient,
-};
+use nanocld_client::stubs::metric::{HttpMetric, MetricPartial, StreamMetric};
 
 use crate::models::SystemStateRef;
 
@@ -67,13 +64,14 @@ fn read(path: &Path) -> IoResult<serde_json::Value> {
 async fn create(
   kind: &str,
   path: &Path,
-  client: &NanocldClient,
+  state: &SystemStateRef,
 ) -> IoResult<()> {
   let data = read(path)?;
   log::trace!("metric::create: {kind} {data}");
   let display = match kind {
     "ncproxy.io/http" => {
       let data = serde_json::from_value::<HttpMetric>(data.clone())?;
+      state.metrics.record_http(&data)?;
       let upstream_addr = data.upstream_addr.unwrap_or("<none>".to_owned());
       let status = match http::StatusCode::from_u16(data.status as u16) {
         Err(_) => data.status.to_string(),
@@ -85,7 +83,11 @@ async fn create(
       );
       Some(display)
     }
-    "ncproxy.io/stream" => None,
+    "ncproxy.io/stream" => {
+      let data = serde_json::from_value::<StreamMetric>(data.clone())?;
+      state.metrics.record_stream(&data)?;
+      None
+    }
     _ => None,
   };
   let metric = MetricPartial {
@@ -93,7 +95,7 @@ async fn create(
     data,
     note: display,
   };
-  client.create_metric(&metric).await?;
+  state.client.create_metric(&metric).await?;
   Ok(())
 }
 
@@ -148,7 +150,7 @@ async fn watch(state: &SystemStateRef) -> IoResult<()> {
             continue;
           }
         };
-        if let Err(err) = create(kind, path, &state.client).await {
+        if let Err(err) = create(kind, path, state).await {
           log::warn!("metric::watch: {err}");
         }
       }
@@ -81,20 +81,34 @@ pub(crate) mod tests {
     Ok(rule)
   }
 
-  pub async fn gen_default_test_client() -> TestClient {
+  pub struct TestSystem {
+    pub client: TestClient,
+    pub state: crate::models::SystemStateRef,
+  }
+
+  pub async fn gen_default_test_system() -> TestSystem {
     before();
     let home = std::env::var("HOME").unwrap();
     let options = crate::cli::Cli {
       state_dir: format!("{home}/.nanocl_dev/state/proxy"),
       nginx_dir: "/etc/nginx".to_owned(),
+      metrics_host: None,
     };
     let system_state = crate::subsystem::init(&options).await.unwrap();
+    let state = Arc::clone(&system_state);
     // Create test server
     let srv = ntex::web::test::server(move || {
       ntex::web::App::new()
         .state(Arc::clone(&system_state))
         .configure(services::ntex_config)
     });
-    TestClient::new(srv, vars::VERSION)
+    TestSystem {
+      client: TestClient::new(srv, vars::VERSION),
+      state,
+    }
+  }
+
+  pub async fn gen_default_test_client() -> TestClient {
+    gen_default_test_system().await.client
   }
 }
@@ -3,7 +3,10 @@ use std::{fs, sync::Arc};
 use futures::StreamExt;
 use ntex::web;
 
-use nanocl_error::io::{IoError, IoResult};
+use nanocl_error::{
+  http_client::HttpClientError,
+  io::{IoError, IoResult},
+};
 
 use nanocld_client::{
   bollard_next::exec::{CreateExecOptions, StartExecOptions},
@@ -12,11 +15,12 @@ use nanocld_client::{
 };
 
 use crate::models::{
-  LocationTemplate, NginxRuleKind, SystemStateRef, CONF_TEMPLATE,
+  NginxRuleKind, Store, SystemState, SystemStateRef, CONF_TEMPLATE,
   HTTP_TEMPLATE, STREAM_TEMPLATE,
 };
 
-pub async fn ensure_conf(state: &SystemStateRef) -> IoResult<()> {
+/// Create the directories and the main nginx.conf for the given state
+async fn write_conf(state: &SystemStateRef) -> IoResult<()> {
   let state_ref = Arc::clone(state);
   let conf_path = format!("{}/nginx.conf", state_ref.store.dir);
   let default_conf = CONF_TEMPLATE.compile(&liquid::object!({
@@ -45,6 +49,11 @@ pub async fn ensure_conf(state: &SystemStateRef) -> IoResult<()> {
     "NginxManager: writing default conf to {conf_path}:\n{default_conf}"
   );
   std::fs::write(conf_path, default_conf)?;
+  Ok(())
+}
+
+pub async fn ensure_conf(state: &SystemStateRef) -> IoResult<()> {
+  write_conf(state).await?;
   self::test(&state.client).await?;
   Ok(())
 }
@@ -56,6 +65,14 @@ pub async fn test(client: &NanocldClient) -> IoResult<()> {
   Ok(())
 }
 
+/// Test a nginx configuration file that isn't the live one
+async fn test_conf(conf_path: &str, client: &NanocldClient) -> IoResult<()> {
+  log::info!("nginx::test_conf: starting {conf_path}");
+  exec_nginx_cmd(&format!("nginx -t -c {conf_path}"), client).await?;
+  log::info!("nginx::test_conf: done");
+  Ok(())
+}
+
 async fn exec_nginx_cmd(cmd: &str, client: &NanocldClient) -> IoResult<()> {
   let exec_options = CreateExecOptions {
     attach_stderr: Some(true),
@@ -63,12 +80,17 @@ async fn exec_nginx_cmd(cmd: &str, client: &NanocldClient) -> IoResult<()> {
     cmd: Some(cmd.split(' ').map(|e| e.into()).collect()),
     ..Default::default()
   };
+  // A failure to reach the daemon is never the fault of the configuration
+  let exec_err =
+    |err: HttpClientError| IoError::other("exec", &err.to_string());
   let start_res = client
     .create_exec("nproxy", &exec_options, Some("system"))
-    .await?;
+    .await
+    .map_err(exec_err)?;
   let mut start_stream = client
     .start_exec(&start_res.id, &StartExecOptions::default())
-    .await?;
+    .await
+    .map_err(exec_err)?;
   let mut output = String::default();
   while let Some(output_log) = start_stream.next().await {
     let Ok(output_log) = output_log else {
@@ -76,15 +98,14 @@ async fn exec_nginx_cmd(cmd: &str, client: &NanocldClient) -> IoResult<()> {
     };
     output += &output_log.data;
   }
-  let inspect_result = client.inspect_exec(&start_res.id).await?;
+  let inspect_result =
+    client.inspect_exec(&start_res.id).await.map_err(exec_err)?;
   match inspect_result.exit_code {
-    Some(code) => {
-      if code == 0 {
-        return Ok(());
-      }
-      Err(IoError::other("exec", &output))
-    }
-    None => Ok(()),
+    Some(0) | None => Ok(()),
+    // 126 and 127 are returned by the shell when nginx can't be executed
+    Some(126 | 127) => Err(IoError::other("exec", &output)),
+    // nginx rejected the configuration
+    Some(_) => Err(IoError::invalid_input("exec", &output)),
   }
 }
 
@@ -95,10 +116,72 @@ pub async fn reload(client: &NanocldClient) -> IoResult<()> {
   Ok(())
 }
 
+/// Render the rule into a scratch directory next to the live configuration
+/// and run `nginx -t` against it, the live configuration is never touched.
+pub async fn validate_rule(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
+) -> IoResult<()> {
+  rule.validate()?;
+  state.listeners.check(name, &rule.listeners(name))?;
+  let scratch_dir = format!(
+    "{}/scratch/{name}-{}",
+    state.store.dir,
+    chrono::Utc::now().timestamp_micros()
+  );
+  let scratch = Arc::new(SystemState {
+    store: Store::new(&scratch_dir),
+    ..state.as_ref().clone()
+  });
+  let res = async {
+    write_conf(&scratch).await?;
+    state.store.copy_enabled(&scratch.store, name).await?;
+    render_rule(name, rule, &scratch).await?;
+    test_conf(&format!("{scratch_dir}/nginx.conf"), &state.client).await
+  }
+  .await
+  .map_err(|err| {
+    let context = format!("Rule {name}");
+    // Only a rejection of the configuration is the fault of the rule
+    match err.inner.kind() {
+      std::io::ErrorKind::InvalidInput => {
+        IoError::invalid_input(context, err.to_string())
+      }
+      _ => IoError::other(context, err.to_string()),
+    }
+  });
+  if let Err(err) = tokio::fs::remove_dir_all(&scratch_dir).await {
+    log::warn!("nginx::validate_rule: unable to remove {scratch_dir} {err}");
+  }
+  res
+}
+
 pub async fn add_rule(
   name: &str,
   rule: &ResourceProxyRule,
   state: &SystemStateRef,
+) -> IoResult<()> {
+  render_rule(name, rule, state).await?;
+  if let Err(err) = self::test(&state.client).await {
+    let _ = del_rule(name, state).await;
+    return Err(err);
+  }
+  Ok(())
+}
+
+/// Append the upstream block to the config unless it's already defined
+fn push_upstream(conf: &mut String, key: &str, upstream: &str) {
+  if !conf.contains(&format!("upstream {key} {{")) {
+    *conf += upstream;
+  }
+}
+
+/// Write the nginx config files of the rule into the state store
+async fn render_rule(
+  name: &str,
+  rule: &ResourceProxyRule,
+  state: &SystemStateRef,
 ) -> IoResult<()> {
   let mut stream_conf = String::new();
   let mut http_conf = String::new();
@@ -112,6 +195,7 @@ pub async fn add_rule(
         )
         .await?;
         let upstream_key = match super::rule::gen_stream_upstream_key(
+          name,
           &stream_rule.target,
           state,
         )
@@ -121,7 +205,12 @@ pub async fn add_rule(
             log::warn!("{err} {:#?}", stream_rule.target);
             continue;
           }
-          Ok(upstream_key) => upstream_key,
+          Ok((upstream_key, upstream)) => {
+            if let Some(upstream) = upstream {
+              push_upstream(&mut stream_conf, &upstream_key, &upstream);
+            }
+            upstream_key
+          }
         };
         let ssl = match &stream_rule.ssl {
           Some(ssl) => match super::rule::gen_ssl_config(ssl, state).await {
@@ -197,21 +286,20 @@ pub async fn add_rule(
                 }
                 None => None,
               };
-              let location = LocationTemplate {
-                path: location.path.clone(),
-                limit_req: location.limit_req.clone(),
-                upstream_key: if ssl.is_some() {
-                  format!("https://{upstream_key}")
-                } else {
-                  format!("http://{upstream_key}")
-                },
-                redirect: None,
-                upstream_path: upstream.path.clone().unwrap_or("/".to_owned()),
-                version: location.version,
-                allowed_ips: location.allowed_ips.clone(),
-                headers: location.headers.clone(),
-                ssl,
+              let upstream_key = if ssl.is_some() {
+                format!("https://{upstream_key}")
+              } else {
+                format!("http://{upstream_key}")
               };
+              let location = super::rule::gen_location_template(
+                location,
+                upstream_key,
+                upstream.path.clone().unwrap_or("/".to_owned()),
+                None,
+                ssl,
+                state,
+              )
+              .await?;
               locations.push(location);
             }
             LocationTarget::Unix(unix) => {
@@ -221,31 +309,57 @@ pub async fn add_rule(
                 state,
               )
               .await?;
-              let location = LocationTemplate {
-                path: location.path.clone(),
-                upstream_key: format!("http://{upstream_key}"),
-                redirect: None,
-                limit_req: location.limit_req.clone(),
-                upstream_path: "/".to_owned(),
-                version: location.version,
-                allowed_ips: location.allowed_ips.clone(),
-                headers: location.headers.clone(),
-                ssl: None,
+              let location = super::rule::gen_location_template(
+                location,
+                format!("http://{upstream_key}"),
+                "/".to_owned(),
+                None,
+                None,
+                state,
+              )
+              .await?;
+              locations.push(location);
+            }
+            LocationTarget::Http(http) if http.redirect.is_none() => {
+              let upstream =
+                super::rule::gen_http_upstream(name, http, state).await?;
+              push_upstream(&mut http_conf, &upstream.key, &upstream.data);
+              let mut location = super::rule::gen_location_template(
+                location,
+                format!("{}://{}", upstream.url.scheme, upstream.key),
+                upstream.url.path.clone(),
+                None,
+                None,
+                state,
+              )
+              .await?;
+              let default_port = match upstream.url.scheme.as_str() {
+                "https" => 443,
+                _ => 80,
               };
+              location.host_header =
+                Some(if upstream.url.port == default_port {
+                  upstream.url.host.clone()
+                } else {
+                  format!("{}:{}", upstream.url.host, upstream.url.port)
+                });
+              location.upstream_tls = upstream.tls;
+              if http.keepalive.is_some() {
+                location.keepalive = true;
+                location.version = location.version.or(Some(1.1));
+              }
               locations.push(location);
             }
             LocationTarget::Http(http) => {
-              let location = LocationTemplate {
-                path: location.path.clone(),
-                upstream_key: http.url.clone(),
-                limit_req: location.limit_req.clone(),
-                version: location.version,
-                upstream_path: "/".to_owned(),
-                allowed_ips: location.allowed_ips.clone(),
-                headers: location.headers.clone(),
-                redirect: http.redirect.clone().map(|r| format!("{r}")),
-                ssl: None,
-              };
+              let location = super::rule::gen_location_template(
+                location,
+                http.url.clone(),
+                "/".to_owned(),
+                http.redirect.clone().map(|r| format!("{r}")),
+                None,
+                state,
+              )
+              .await?;
               locations.push(location);
             }
           }
@@ -276,10 +390,6 @@ pub async fn add_rule(
       .write_conf_file(name, &http_conf, &NginxRuleKind::Site)
       .await?;
   }
-  if let Err(err) = self::test(&state.client).await {
-    let _ = del_rule(name, state).await;
-    return Err(err);
-  }
   Ok(())
 }
 
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use futures::{stream::FuturesUnordered, StreamExt};
 use nanocl_error::io::{FromIo, IoError, IoResult};
 
@@ -24,9 +26,39 @@ pub async fn list_by_secret(
         serde_json::json!({ "Rules": [ { "Ssl": name }  ] }),
       ),
     );
-  let resources = client.list_resource(Some(&filter)).await.map_err(|err| {
-    err.map_err_context(|| "Unable to list resources from nanocl daemon")
-  })?;
+  let ssl_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let filter = GenericFilter::new()
+  .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()))
+  .r#where(
+    "data",
+    GenericClause::Contains(
+      serde_json::json!({ "Rules": [ { "Locations": [ { "BasicAuth": { "Secret": name } } ] } ] }),
+    ),
+  );
+  let basic_auth_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let filter = GenericFilter::new()
+  .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()))
+  .r#where(
+    "data",
+    GenericClause::Contains(
+      serde_json::json!({ "Rules": [ { "Locations": [ { "Target": { "Tls": { "CaSecret": name } } } ] } ] }),
+    ),
+  );
+  let ca_resources =
+    client.list_resource(Some(&filter)).await.map_err(|err| {
+      err.map_err_context(|| "Unable to list resources from nanocl daemon")
+    })?;
+  let resources = ssl_resources
+    .into_iter()
+    .chain(basic_auth_resources.into_iter())
+    .chain(ca_resources.into_iter())
+    .collect::<Vec<Resource>>();
   if resources.is_empty() {
     return Err(IoError::not_found(
       "Resource",
@@ -80,6 +112,32 @@ pub(crate) async fn list_by_cargo(
   Ok(resources)
 }
 
+/// List every proxy rule resources
+pub(crate) async fn list_rules(
+  client: &NanocldClient,
+) -> IoResult<Vec<Resource>> {
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()));
+  let resources = client.list_all_resource(&filter).await.map_err(|err| {
+    err.map_err_context(|| "Unable to list resources from nanocl daemon")
+  })?;
+  Ok(resources)
+}
+
+/// Rebuild the listener index from the existing proxy rule resources
+pub(crate) async fn sync_listeners(state: &SystemStateRef) -> IoResult<()> {
+  let resources = list_rules(&state.client).await?;
+  let mut index = HashMap::new();
+  for resource in resources {
+    let resource: ResourcePartial = resource.into();
+    let rule = serialize(&resource.data)?;
+    let listeners = rule.listeners(&resource.name);
+    index.insert(resource.name, listeners);
+  }
+  state.listeners.replace(index)?;
+  Ok(())
+}
+
 pub(crate) fn serialize(
   data: &serde_json::Value,
 ) -> IoResult<ResourceProxyRule> {
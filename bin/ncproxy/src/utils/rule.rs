@@ -5,16 +5,23 @@ use nanocld_client::{
     generic::NetworkKind,
     process::Process,
     proxy::{
-      ProxySsl, ProxySslConfig, StreamTarget, UnixTarget, UpstreamTarget,
+      HttpTarget, HttpTargetUrl, ProxyBasicAuth, ProxyHttpCors,
+      ProxyHttpLocation, ProxySsl, ProxySslConfig, ProxyUpstreamHealth,
+      StreamTarget, UnixTarget, UpstreamTarget, UriTarget,
     },
   },
   NanocldClient,
 };
 
 use crate::models::{
-  NginxRuleKind, SystemStateRef, UNIX_UPSTREAM_TEMPLATE, UPSTREAM_TEMPLATE,
+  BasicAuthTemplate, CorsTemplate, LocationTemplate, NginxRuleKind,
+  RewriteTemplate, SystemStateRef, UpstreamTlsTemplate,
+  EXTERNAL_UPSTREAM_TEMPLATE, UNIX_UPSTREAM_TEMPLATE, UPSTREAM_TEMPLATE,
 };
 
+/// Bundle of the system used to verify external upstreams without a CA secret
+const SYSTEM_CA_BUNDLE: &str = "/etc/ssl/certs/ca-certificates.crt";
+
 /// Get public address of host
 async fn get_host_addr(client: &NanocldClient) -> IoResult<String> {
   let info = client
@@ -55,39 +62,22 @@ fn parse_upstream_target(key: &str) -> IoResult<(String, String, String)> {
   Ok((name, namespace, kind))
 }
 
-pub async fn get_addresses(
-  processes: &[Process],
-  network: &str,
-) -> IoResult<Vec<String>> {
+pub async fn get_addresses(processes: &[Process]) -> IoResult<Vec<String>> {
   let mut addresses = vec![];
   for process in processes {
     log::debug!("get_addresses from: {}", process.name);
     if process.name.starts_with("tmp-") {
       continue;
     }
-    let networks = process
-      .data
-      .network_settings
-      .clone()
-      .unwrap_or_default()
-      .networks
-      .unwrap_or_default();
-    let network = networks.get(network);
-    let Some(network) = network else {
-      continue;
-    };
-    let Some(ip_address) = network.ip_address.clone() else {
+    let Some(ip_address) = process.network_address() else {
       continue;
     };
-    if ip_address.is_empty() {
-      continue;
-    }
     addresses.push(ip_address);
   }
   if addresses.is_empty() {
     return Err(IoError::invalid_data(
       "Process",
-      &format!("No address found for {network} are processes running ?"),
+      "No address found are processes running ?",
     ));
   }
   Ok(addresses)
@@ -166,7 +156,7 @@ pub async fn gen_upstream(
             format!("Unable to inspect cargo {target_name}")
           })
         })?;
-      let addresses = get_addresses(&cargo.instances, "nanoclbr0").await?;
+      let addresses = get_addresses(&cargo.instances).await?;
       let key = format!("{}-{}-cargo", cargo.spec.cargo_key, port);
       let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
         "key": key,
@@ -183,7 +173,7 @@ pub async fn gen_upstream(
         .map_err(|err| {
           err.map_err_context(|| format!("Unable to inspect vm {target_name}"))
         })?;
-      let addresses = get_addresses(&vm.instances, "nanoclbr0").await?;
+      let addresses = get_addresses(&vm.instances).await?;
       let key = format!("{}-{}-vm", vm.spec.vm_key, port);
       let data = UPSTREAM_TEMPLATE.compile(&liquid::object!({
         "key": key,
@@ -220,19 +210,247 @@ pub async fn gen_unix_target_key(
   Ok(upstream_key)
 }
 
+/// Generate the key of an upstream targeting an external host of the rule
+fn gen_external_key(name: &str, host: &str, port: u16) -> String {
+  let key = format!("{name}-{host}-{port}")
+    .chars()
+    .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+    .collect::<String>();
+  format!("ext-{key}")
+}
+
+/// Generate the upstream block of an external host,
+/// it's rendered inside the rule config so it's removed with it
+fn gen_external_upstream(
+  key: &str,
+  host: &str,
+  port: u16,
+  health: Option<&ProxyUpstreamHealth>,
+  keepalive: Option<u32>,
+) -> IoResult<String> {
+  EXTERNAL_UPSTREAM_TEMPLATE.compile(&liquid::object!({
+    "key": key,
+    "host": host,
+    "port": port,
+    "health": health,
+    "keepalive": keepalive,
+  }))
+}
+
+/// Generate the upstream key of a stream target,
+/// with the upstream block to render inside the rule config for uri targets
 pub async fn gen_stream_upstream_key(
+  name: &str,
   target: &StreamTarget,
   state: &SystemStateRef,
-) -> IoResult<String> {
+) -> IoResult<(String, Option<String>)> {
   match target {
     StreamTarget::Upstream(upstream) => {
-      gen_upstream(upstream, &NginxRuleKind::Stream, state).await
+      let key = gen_upstream(upstream, &NginxRuleKind::Stream, state).await?;
+      Ok((key, None))
     }
     StreamTarget::Unix(unix) => {
-      gen_unix_target_key(unix, &NginxRuleKind::Stream, state).await
+      let key =
+        gen_unix_target_key(unix, &NginxRuleKind::Stream, state).await?;
+      Ok((key, None))
     }
-    StreamTarget::Uri(_) => {
-      Err(IoError::invalid_input("StreamTarget", "uri not supported"))
+    StreamTarget::Uri(uri) => {
+      let (key, data) = gen_uri_upstream(name, uri)?;
+      Ok((key, Some(data)))
     }
   }
 }
+
+/// Generate the upstream of a stream uri target
+pub fn gen_uri_upstream(
+  name: &str,
+  uri: &UriTarget,
+) -> IoResult<(String, String)> {
+  let (host, port) = uri.host_port()?;
+  let key = gen_external_key(name, &host, port);
+  let data =
+    gen_external_upstream(&key, &host, port, uri.health.as_ref(), None)?;
+  Ok((key, data))
+}
+
+/// An external http upstream rendered inside the rule config
+pub struct HttpUpstream {
+  pub key: String,
+  pub data: String,
+  pub url: HttpTargetUrl,
+  pub tls: Option<UpstreamTlsTemplate>,
+}
+
+/// Write the CA certificate of the secret into the state directory
+async fn gen_trusted_certificate(
+  secret: &str,
+  state: &SystemStateRef,
+) -> IoResult<String> {
+  let secret = state.client.inspect_secret(secret).await?;
+  let certificate = serde_json::from_value::<String>(secret.data)
+    .map_err(|err| err.map_err_context(|| "Unable to deserialize CA"))?;
+  let path = format!("{}/secrets/{}.trusted.crt", state.store.dir, secret.name);
+  tokio::fs::write(&path, certificate).await?;
+  Ok(path)
+}
+
+/// Generate the upstream of an external http target
+pub async fn gen_http_upstream(
+  name: &str,
+  http: &HttpTarget,
+  state: &SystemStateRef,
+) -> IoResult<HttpUpstream> {
+  let url = http.parse_url()?;
+  let key = gen_external_key(name, &url.host, url.port);
+  let data = gen_external_upstream(
+    &key,
+    &url.host,
+    url.port,
+    http.health.as_ref(),
+    http.keepalive,
+  )?;
+  let tls = if url.scheme == "https" {
+    let tls = http.tls.clone().unwrap_or_default();
+    let verify = tls.verify.unwrap_or(true);
+    let trusted_certificate = match &tls.ca_secret {
+      Some(secret) => gen_trusted_certificate(secret, state).await?,
+      None => SYSTEM_CA_BUNDLE.to_owned(),
+    };
+    Some(UpstreamTlsTemplate {
+      server_name: tls.server_name.unwrap_or(url.host.clone()),
+      verify,
+      trusted_certificate,
+    })
+  } else {
+    None
+  };
+  Ok(HttpUpstream {
+    key,
+    data,
+    url,
+    tls,
+  })
+}
+
+/// Escape regex special characters so the value can be matched literally
+fn escape_regex(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    if "\\.+*?()|[]{}^$".contains(c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+fn gen_cors(cors: &ProxyHttpCors) -> CorsTemplate {
+  let any_origin = cors.allowed_origins.iter().any(|origin| origin == "*");
+  let origins = cors
+    .allowed_origins
+    .iter()
+    .map(|origin| escape_regex(origin))
+    .collect::<Vec<_>>();
+  CorsTemplate {
+    origin: any_origin.then(|| "*".to_owned()),
+    origin_regex: (!any_origin).then(|| format!("^({})$", origins.join("|"))),
+    methods: cors
+      .allowed_methods
+      .clone()
+      .unwrap_or_else(|| {
+        ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+          .map(String::from)
+          .to_vec()
+      })
+      .join(", "),
+    headers: cors.allowed_headers.as_ref().map(|h| h.join(", ")),
+    expose_headers: cors.expose_headers.as_ref().map(|h| h.join(", ")),
+    allow_credentials: cors.allow_credentials.unwrap_or_default(),
+    max_age: cors.max_age,
+  }
+}
+
+/// Write the htpasswd entries of the basic auth secret into the state directory
+pub async fn gen_basic_auth(
+  basic_auth: &ProxyBasicAuth,
+  state: &SystemStateRef,
+) -> IoResult<BasicAuthTemplate> {
+  let secret = state.client.inspect_secret(&basic_auth.secret).await?;
+  let entries = serde_json::from_value::<Vec<String>>(secret.data)
+    .map_err(|err| err.map_err_context(|| "Unable to deserialize htpasswd"))?;
+  let user_file =
+    format!("{}/secrets/{}.htpasswd", state.store.dir, secret.name);
+  tokio::fs::write(&user_file, entries.join("\n") + "\n").await?;
+  Ok(BasicAuthTemplate {
+    realm: basic_auth
+      .realm
+      .clone()
+      .unwrap_or_else(|| "Restricted".to_owned()),
+    user_file,
+  })
+}
+
+/// Generate the template data of a location with the given target
+pub async fn gen_location_template(
+  location: &ProxyHttpLocation,
+  upstream_key: String,
+  upstream_path: String,
+  redirect: Option<String>,
+  ssl: Option<ProxySslConfig>,
+  state: &SystemStateRef,
+) -> IoResult<LocationTemplate> {
+  let mut rewrites = location
+    .rewrites
+    .clone()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|rewrite| RewriteTemplate {
+      pattern: rewrite.pattern,
+      replacement: rewrite.replacement,
+      flag: rewrite.flag.map(|flag| flag.to_string()),
+    })
+    .collect::<Vec<_>>();
+  if location.strip_prefix.unwrap_or_default() && redirect.is_none() {
+    let prefix = location.path.trim_end_matches('/');
+    rewrites.push(RewriteTemplate {
+      pattern: format!("^{}/?(.*)$", escape_regex(prefix)),
+      replacement: format!("{}/$1", upstream_path.trim_end_matches('/')),
+      flag: Some("break".to_owned()),
+    });
+  }
+  let response_headers = location
+    .response_headers
+    .clone()
+    .unwrap_or_default()
+    .iter()
+    .filter_map(|header| {
+      let (name, value) = header.trim().split_once(char::is_whitespace)?;
+      Some(format!("{name}: {}", value.trim()))
+    })
+    .collect();
+  let basic_auth = match &location.basic_auth {
+    Some(basic_auth) => Some(gen_basic_auth(basic_auth, state).await?),
+    None => None,
+  };
+  Ok(LocationTemplate {
+    path: location.path.clone(),
+    upstream_key,
+    upstream_path,
+    redirect,
+    limit_req: location.limit_req.clone(),
+    allowed_ips: location.allowed_ips.clone(),
+    version: location.version,
+    headers: location.headers.clone(),
+    ssl,
+    rewrites,
+    response_headers,
+    timeouts: location.timeouts.clone(),
+    client_max_body_size: location.client_max_body_size.clone(),
+    websocket: location.websocket.unwrap_or_default(),
+    cors: location.cors.as_ref().map(gen_cors),
+    basic_auth,
+    host_header: None,
+    upstream_tls: None,
+    keepalive: false,
+  })
+}
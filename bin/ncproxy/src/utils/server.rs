@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use ntex::web;
 
-use nanocl_error::io::IoResult;
+use nanocl_error::io::{IoError, IoResult};
 
 use nanocl_utils::ntex::middlewares;
 
@@ -27,3 +27,32 @@ pub fn gen(state: &SystemStateRef) -> IoResult<ntex::server::Server> {
   server = server.workers(num_cpus::get());
   Ok(server.run())
 }
+
+/// Generate a standalone server exposing only the prometheus metrics
+/// so they can be scraped without access to the proxy api
+pub fn gen_metrics(
+  host: &str,
+  state: &SystemStateRef,
+) -> IoResult<ntex::server::Server> {
+  let state = Arc::clone(state);
+  let mut server = web::HttpServer::new(move || {
+    web::App::new()
+      .state(Arc::clone(&state))
+      .wrap(middlewares::SerializeError)
+      .configure(services::metrics_config)
+      .default_service(web::route().to(services::unhandled))
+  });
+  if let Some(addr) = host.strip_prefix("unix://") {
+    server = server.bind_uds(addr)?;
+  } else if let Some(addr) = host.strip_prefix("tcp://") {
+    server = server.bind(addr)?;
+  } else {
+    return Err(IoError::invalid_input(
+      "metrics host",
+      "invalid protocol [tcp:// | unix://] allowed",
+    ));
+  }
+  log::info!("server::gen_metrics: {host}");
+  server = server.workers(1);
+  Ok(server.run())
+}
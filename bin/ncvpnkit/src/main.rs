@@ -1,35 +1,22 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
 use futures_util::StreamExt;
 
-use nanocl_error::io::{FromIo, IoError, IoResult};
+use nanocl_error::io::{FromIo, IoResult};
 
 use vpnkitrc::stubs::*;
 
 use nanocl_utils::logger;
-use nanocld_client::stubs::resource::Resource;
 use nanocld_client::stubs::system::{Event, EventActorKind, NativeEventAction};
 use nanocld_client::stubs::{
-  generic::NetworkKind,
-  proxy::{ProxyRule, ProxyRuleStream, ProxyStreamProtocol, ResourceProxyRule},
+  generic::{GenericClause, GenericFilter, NetworkKind},
+  proxy::{ProxyListener, ProxyListenerProtocol, ResourceProxyRule},
+  resource::Resource,
 };
 use nanocld_client::NanocldClient;
 
 mod vars;
 
-/// Convert a Resource to a ProxyRule if the `Kind` is `ProxyRule`.
-fn resource_to_proxy_rule(
-  resource: &Resource,
-) -> std::io::Result<ResourceProxyRule> {
-  serde_json::from_value::<ResourceProxyRule>(resource.spec.data.clone())
-    .map_err(|err| {
-      std::io::Error::new(
-        std::io::ErrorKind::InvalidData,
-        format!("Unable to deserialize proxy rule: {err}"),
-      )
-    })
-}
-
 /// Apply vpnkit rule
 async fn apply_rule(port: &VpnKitPort, vpnkit_client: &VpnKitRc) {
   if let Some(VpnKitProtocol::UNIX) = port.proto {
@@ -84,74 +71,148 @@ async fn remove_rule(port: &VpnKitPort, vpnkit_client: &VpnKitRc) {
   }
 }
 
-/// Convert a `ProxyRuleStream` to a `VpnKitPort`
-fn rule_stream_to_vpnkit_port(rule_stream: &ProxyRuleStream) -> VpnKitPort {
+/// Convert a public `ProxyListener` to a `VpnKitPort`
+fn listener_to_vpnkit_port(listener: &ProxyListener) -> VpnKitPort {
   VpnKitPort {
-    proto: match rule_stream.protocol {
-      ProxyStreamProtocol::Tcp => Some(VpnKitProtocol::TCP),
-      ProxyStreamProtocol::Udp => Some(VpnKitProtocol::UDP),
+    proto: match listener.protocol {
+      ProxyListenerProtocol::Udp => Some(VpnKitProtocol::UDP),
+      _ => Some(VpnKitProtocol::TCP),
     },
     out_ip: Some("0.0.0.0".into()),
-    out_port: Some(rule_stream.port.into()),
+    out_port: Some(listener.port.into()),
     in_ip: Some("127.0.0.1".into()),
-    in_port: Some(rule_stream.port.into()),
+    in_port: Some(listener.port.into()),
     ..Default::default()
   }
 }
 
+/// Key identifying a forwarded port
+fn port_key(port: &VpnKitPort) -> String {
+  format!(
+    "{}:{}",
+    port.proto.clone().unwrap_or_default(),
+    port.out_port.unwrap_or_default()
+  )
+}
+
+/// Return true if the port has been exposed by us
+/// so other forwarding rules of vpnkit are never removed
+fn is_managed_port(port: &VpnKitPort) -> bool {
+  matches!(port.proto, Some(VpnKitProtocol::TCP | VpnKitProtocol::UDP))
+    && port.in_ip.as_deref() == Some("127.0.0.1")
+    && port.in_port == port.out_port
+}
+
+/// Generate the ports to expose for the proxy rules
+/// Ports 80 and 443 are always exposed for the default http rules
+fn gen_ports(rules: &[(String, ResourceProxyRule)]) -> Vec<VpnKitPort> {
+  let mut ports = BTreeMap::new();
+  for port in [80, 443] {
+    let port = VpnKitPort {
+      proto: Some(VpnKitProtocol::TCP),
+      out_ip: Some("0.0.0.0".into()),
+      out_port: Some(port),
+      in_ip: Some("127.0.0.1".into()),
+      in_port: Some(port),
+      ..Default::default()
+    };
+    ports.insert(port_key(&port), port);
+  }
+  for (key, rule) in rules {
+    for listener in rule.listeners(key) {
+      match listener.network {
+        NetworkKind::All | NetworkKind::Public => {}
+        _ => continue,
+      }
+      let port = listener_to_vpnkit_port(&listener);
+      ports.insert(port_key(&port), port);
+    }
+  }
+  ports.into_values().collect()
+}
+
+/// List every proxy rule resource
+async fn list_rules(nanocl_client: &NanocldClient) -> IoResult<Vec<Resource>> {
+  let filter = GenericFilter::new()
+    .r#where("kind", GenericClause::Eq(vars::RULE_KEY.to_owned()));
+  let resources = nanocl_client.list_all_resource(&filter).await?;
+  Ok(resources)
+}
+
+/// Expose the ports of every proxy rule
+/// and remove the ones that are not used anymore
+async fn reconcile(
+  nanocl_client: &NanocldClient,
+  vpnkit_client: &VpnKitRc,
+) -> IoResult<()> {
+  let rules = list_rules(nanocl_client)
+    .await?
+    .into_iter()
+    .filter_map(|resource| {
+      let key = resource.spec.resource_key;
+      match serde_json::from_value::<ResourceProxyRule>(resource.spec.data) {
+        Err(err) => {
+          log::warn!("Unable to deserialize proxy rule {key}: {err}");
+          None
+        }
+        Ok(rule) => Some((key, rule)),
+      }
+    })
+    .collect::<Vec<_>>();
+  let ports = gen_ports(&rules);
+  let current = vpnkit_client
+    .list()
+    .await
+    .map_err(|err| err.map_err_context(|| "Unable to list vpnkit ports"))?
+    .into_iter()
+    .filter(is_managed_port)
+    .map(|port| (port_key(&port), port))
+    .collect::<BTreeMap<_, _>>();
+  let keys = ports.iter().map(port_key).collect::<Vec<_>>();
+  for (key, port) in &current {
+    if !keys.contains(key) {
+      remove_rule(port, vpnkit_client).await;
+    }
+  }
+  for port in &ports {
+    if !current.contains_key(&port_key(port)) {
+      apply_rule(port, vpnkit_client).await;
+    }
+  }
+  Ok(())
+}
+
 /// Handle event from the nanocl daemon.
-/// It's watching for ProxyRule events and apply the rules to vpnkit.
+/// It's watching for ProxyRule events and reconcile the rules with vpnkit.
 async fn on_event(
   event: &Event,
   nanocl_client: &NanocldClient,
   vpnkit_client: &VpnKitRc,
 ) -> IoResult<()> {
   let action = NativeEventAction::from_str(&event.action)?;
-  let Some(actor) = event.actor.clone() else {
+  let Some(actor) = &event.actor else {
     return Ok(());
   };
-  let actor_kind = &actor.kind;
-  if actor_kind != &EventActorKind::Resource {
+  if actor.kind != EventActorKind::Resource {
+    return Ok(());
+  }
+  let kind = actor
+    .attributes
+    .as_ref()
+    .and_then(|attributes| attributes.get("Kind"))
+    .and_then(|kind| kind.as_str());
+  if kind != Some(vars::RULE_KEY) {
     return Ok(());
   }
   match action {
-    NativeEventAction::Create | NativeEventAction::Update => {
-      let key = actor.key.unwrap_or_default();
-      let resource = nanocl_client.inspect_resource(&key).await?;
-      let r_proxy_rule = resource_to_proxy_rule(&resource)?;
-      for rule in r_proxy_rule.rules.into_iter() {
-        if let ProxyRule::Stream(stream) = rule {
-          match stream.network {
-            NetworkKind::All | NetworkKind::Public => {}
-            _ => continue,
-          }
-          let port = rule_stream_to_vpnkit_port(&stream);
-          apply_rule(&port, vpnkit_client).await;
-        }
-      }
-    }
-    NativeEventAction::Destroy => {
-      let attributes = actor.attributes.unwrap_or_default();
-      let spec = attributes.get("Spec").cloned().ok_or_else(|| {
-        IoError::invalid_data("Resource spec", "attribute not found")
-      })?;
-      let resource = serde_json::from_value::<ResourceProxyRule>(spec)
-        .map_err(|err| err.map_err_context(|| "ncproxy.io/rule"))?;
-      for rule in resource.rules.into_iter() {
-        if let ProxyRule::Stream(stream) = rule {
-          match stream.network {
-            NetworkKind::All | NetworkKind::Public => {}
-            _ => continue,
-          }
-          let port = rule_stream_to_vpnkit_port(&stream);
-          remove_rule(&port, vpnkit_client).await;
-        }
-      }
+    NativeEventAction::Create
+    | NativeEventAction::Update
+    | NativeEventAction::Destroy => {
+      reconcile(nanocl_client, vpnkit_client).await
     }
     // Ignore other events
-    _ => {}
+    _ => Ok(()),
   }
-  Ok(())
 }
 
 #[ntex::main]
@@ -173,24 +234,6 @@ async fn main() -> std::io::Result<()> {
   };
   let vpnkit_client = VpnKitRc::connect_uds("/run/host-services/backend.sock");
   let nanocl_client = NanocldClient::connect_with_unix_default();
-  let proxy_default = vec![
-    VpnKitPort {
-      proto: Some(VpnKitProtocol::TCP),
-      out_ip: Some("0.0.0.0".into()),
-      out_port: Some(80),
-      in_ip: Some("127.0.0.1".into()),
-      in_port: Some(80),
-      ..Default::default()
-    },
-    VpnKitPort {
-      proto: Some(VpnKitProtocol::TCP),
-      out_ip: Some("0.0.0.0".into()),
-      out_port: Some(443),
-      in_ip: Some("127.0.0.1".into()),
-      in_port: Some(443),
-      ..Default::default()
-    },
-  ];
   let nanocld_unix_default = VpnKitPort {
     proto: Some(VpnKitProtocol::UNIX),
     out_path: Some(format!("{user_home}/.nanocl/run/nanocl.sock")),
@@ -205,10 +248,10 @@ async fn main() -> std::io::Result<()> {
       }
       Ok(mut stream) => {
         log::info!("Subscribed to nanocl daemon events");
-        for port in proxy_default.iter() {
-          apply_rule(port, &vpnkit_client).await;
-        }
         apply_rule(&nanocld_unix_default, &vpnkit_client).await;
+        if let Err(err) = reconcile(&nanocl_client, &vpnkit_client).await {
+          log::error!("{err}");
+        }
         while let Some(event) = stream.next().await {
           let event = match event {
             Err(err) => {
@@ -229,3 +272,39 @@ async fn main() -> std::io::Result<()> {
     ntex::time::sleep(std::time::Duration::from_secs(2)).await;
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ports() {
+    let rule = serde_json::from_value::<ResourceProxyRule>(serde_json::json!({
+      "Rules": [
+        {
+          "Network": "Public",
+          "Port": 8080,
+          "Locations": [],
+        },
+        {
+          "Network": "All",
+          "Protocol": "Udp",
+          "Port": 53,
+          "Target": { "UnixPath": "/run/test.sock" },
+        },
+        {
+          "Network": "Internal",
+          "Protocol": "Tcp",
+          "Port": 5432,
+          "Target": { "UnixPath": "/run/test.sock" },
+        }
+      ]
+    }))
+    .unwrap();
+    let keys = gen_ports(&[("test".to_owned(), rule)])
+      .iter()
+      .map(port_key)
+      .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["TCP:443", "TCP:80", "TCP:8080", "UDP:53"]);
+  }
+}
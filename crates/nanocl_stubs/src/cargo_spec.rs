@@ -9,7 +9,11 @@ pub use bollard_next::{
 #[cfg(feature = "utoipa")]
 use super::generic::Any;
 
-use crate::generic::ImagePullPolicy;
+use crate::{
+  generic::{ImagePullPolicy, NetworkKind},
+  proxy::ProxyStreamProtocol,
+  volume::VolumeMount,
+};
 
 /// Auto is used to automatically define that the number of replicas in the cluster
 /// Number is used to manually set the number of replicas
@@ -57,6 +61,112 @@ pub struct ReplicationStatic {
   pub number: usize,
 }
 
+/// A port of the cargo published on the nodes
+/// It's translated into a `ncproxy.io/rule` resource owned by the cargo
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct CargoPort {
+  /// Port of the container to publish
+  pub container_port: u16,
+  /// Port opened on the nodes, default to the container port
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub host_port: Option<u16>,
+  /// Protocol of the port, default to Tcp
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub protocol: Option<ProxyStreamProtocol>,
+  /// Network the port is opened on, default to All
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub network: Option<NetworkKind>,
+}
+
+impl CargoPort {
+  /// Port opened on the nodes
+  pub fn get_host_port(&self) -> u16 {
+    self.host_port.unwrap_or(self.container_port)
+  }
+
+  /// Protocol of the port
+  pub fn get_protocol(&self) -> ProxyStreamProtocol {
+    self.protocol.clone().unwrap_or(ProxyStreamProtocol::Tcp)
+  }
+}
+
+/// Parse a port in the format `[host_port:]container_port[/protocol]`
+impl std::str::FromStr for CargoPort {
+  type Err = std::io::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Port {s}: expected [host_port:]container_port[/tcp|udp]"),
+      )
+    };
+    let (ports, protocol) = match s.split_once('/') {
+      Some((ports, "tcp")) => (ports, Some(ProxyStreamProtocol::Tcp)),
+      Some((ports, "udp")) => (ports, Some(ProxyStreamProtocol::Udp)),
+      Some(_) => return Err(invalid()),
+      None => (s, None),
+    };
+    let parse_port = |port: &str| match port.parse::<u16>() {
+      Ok(port) if port != 0 => Ok(port),
+      _ => Err(invalid()),
+    };
+    let (host_port, container_port) = match ports.split_once(':') {
+      Some((host_port, container_port)) => {
+        (Some(parse_port(host_port)?), parse_port(container_port)?)
+      }
+      None => (None, parse_port(ports)?),
+    };
+    Ok(Self {
+      container_port,
+      host_port,
+      protocol,
+      network: None,
+    })
+  }
+}
+
+/// A file rendered in each instance of a cargo before it starts
+/// Its content is a liquid template that can use `Cargo`, `Metadata`,
+/// `Namespace`, `Node` and `Secrets`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct CargoConfig {
+  /// Absolute path of the file inside the container
+  pub path: String,
+  /// Unix permissions of the file, default to 0o644
+  /// or 0o600 when the template reads the `Secrets`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub mode: Option<u32>,
+  /// Template of the content of the file
+  pub content: String,
+}
+
 /// A cargo spec partial is used to create a Cargo
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -108,6 +218,87 @@ pub struct CargoSpecPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Ports of the container published on the nodes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ports: Option<Vec<CargoPort>>,
+  /// Files rendered for each instance before it starts
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub configs: Option<Vec<CargoConfig>>,
+  /// Volumes mounted in the instances
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub volumes: Option<Vec<VolumeMount>>,
+}
+
+impl CargoSpecPartial {
+  /// Ensure a node port is published only once by protocol
+  pub fn validate_ports(&self) -> std::io::Result<()> {
+    let ports = self.ports.as_deref().unwrap_or_default();
+    for (i, port) in ports.iter().enumerate() {
+      if port.container_port == 0 || port.host_port == Some(0) {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          "Port: 0 is not a valid port",
+        ));
+      }
+      let is_duplicated = ports[..i].iter().any(|other| {
+        other.get_host_port() == port.get_host_port()
+          && other.get_protocol() == port.get_protocol()
+      });
+      if is_duplicated {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!(
+            "Port {}/{}: published more than once",
+            port.get_host_port(),
+            port.get_protocol()
+          ),
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Ensure the configs are written at distinct absolute paths
+  /// with permissions only
+  pub fn validate_configs(&self) -> std::io::Result<()> {
+    let configs = self.configs.as_deref().unwrap_or_default();
+    for (i, config) in configs.iter().enumerate() {
+      let is_valid = config.path.starts_with('/')
+        && !config.path.ends_with('/')
+        && !config.path.split('/').any(|part| part == "..");
+      if !is_valid {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Config {}: expected an absolute file path", config.path),
+        ));
+      }
+      if let Some(mode) = config.mode.filter(|mode| mode & !0o777 != 0) {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!(
+            "Config {}: mode {mode:o} isn't a permission between 0 and 777",
+            config.path
+          ),
+        ));
+      }
+      if configs[..i].iter().any(|other| other.path == config.path) {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Config {}: declared more than once", config.path),
+        ));
+      }
+    }
+    Ok(())
+  }
 }
 
 /// Payload used to patch a cargo
@@ -171,6 +362,24 @@ pub struct CargoSpecUpdate {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Ports of the container published on the nodes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ports: Option<Vec<CargoPort>>,
+  /// Files rendered for each instance before it starts
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub configs: Option<Vec<CargoConfig>>,
+  /// Volumes mounted in the instances
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub volumes: Option<Vec<VolumeMount>>,
 }
 
 impl From<CargoSpecPartial> for CargoSpecUpdate {
@@ -184,6 +393,9 @@ impl From<CargoSpecPartial> for CargoSpecUpdate {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      ports: spec.ports,
+      configs: spec.configs,
+      volumes: spec.volumes,
     }
   }
 }
@@ -246,6 +458,24 @@ pub struct CargoSpec {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub replication: Option<ReplicationMode>,
+  /// Ports of the container published on the nodes
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ports: Option<Vec<CargoPort>>,
+  /// Files rendered for each instance before it starts
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub configs: Option<Vec<CargoConfig>>,
+  /// Volumes mounted in the instances
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub volumes: Option<Vec<VolumeMount>>,
 }
 
 impl From<CargoSpec> for CargoSpecPartial {
@@ -259,6 +489,9 @@ impl From<CargoSpec> for CargoSpecPartial {
       secrets: spec.secrets,
       image_pull_secret: spec.image_pull_secret,
       image_pull_policy: spec.image_pull_policy,
+      ports: spec.ports,
+      configs: spec.configs,
+      volumes: spec.volumes,
     }
   }
 }
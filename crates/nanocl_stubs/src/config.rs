@@ -33,6 +33,11 @@ pub struct DaemonConfig {
   pub gid: u32,
   /// Optional ssl configuration
   pub ssl: Option<SslConfig>,
+  /// Address pool of the overlay network between the nodes, disabled if not set
+  pub overlay_subnet: Option<String>,
+  /// Enforce the network policies on the host firewall
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub network_policies: bool,
 }
 
 /// Configuration File of the daemon
@@ -52,6 +57,10 @@ pub struct DaemonConfigFile {
   pub gateway: Option<String>,
   /// Hostname to use for the node automatically detected if not set
   pub hostname: Option<String>,
+  /// Address pool of the overlay network between the nodes, disabled if not set
+  pub overlay_subnet: Option<String>,
+  /// Enforce the network policies on the host firewall
+  pub network_policies: Option<bool>,
 }
 
 impl Default for DaemonConfig {
@@ -68,6 +77,8 @@ impl Default for DaemonConfig {
       nodes: Vec::default(),
       advertise_addr: String::default(),
       ssl: None,
+      overlay_subnet: None,
+      network_policies: false,
     }
   }
 }
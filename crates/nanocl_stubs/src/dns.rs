@@ -3,7 +3,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::generic::NetworkKind;
 
-#[derive(Clone, Debug)]
+/// Pattern of a domain name used in the dns records
+pub const DNS_NAME_PATTERN: &str = r"^[A-Za-z0-9_-]+(\.[A-Za-z0-9_-]+)*\.?$";
+
+/// Pattern of a domain name that can start with `*.` to match every subdomain
+pub const DNS_WILDCARD_NAME_PATTERN: &str =
+  r"^(\*\.)?[A-Za-z0-9_-]+(\.[A-Za-z0-9_-]+)*\.?$";
+
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -12,11 +19,161 @@ use crate::generic::NetworkKind;
   serde(deny_unknown_fields, rename_all = "PascalCase")
 )]
 pub struct DnsEntry {
+  /// Name to resolve, can start with `*.` to match every subdomain
+  #[cfg_attr(
+    feature = "schemars",
+    schemars(regex = "DNS_WILDCARD_NAME_PATTERN")
+  )]
   pub name: String,
   pub ip_address: NetworkKind,
 }
 
-#[derive(Clone, Debug)]
+/// An AAAA record resolving a name to an ipv6 address
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsAaaaRecord {
+  /// Name to resolve, can start with `*.` to match every subdomain
+  #[cfg_attr(
+    feature = "schemars",
+    schemars(regex = "DNS_WILDCARD_NAME_PATTERN")
+  )]
+  pub name: String,
+  #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+  pub address: std::net::Ipv6Addr,
+}
+
+/// A CNAME record aliasing a name to another one
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsCnameRecord {
+  /// Alias, can start with `*.` to match every subdomain
+  #[cfg_attr(
+    feature = "schemars",
+    schemars(regex = "DNS_WILDCARD_NAME_PATTERN")
+  )]
+  pub name: String,
+  /// Canonical name the alias points to
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub target: String,
+}
+
+/// A TXT record
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsTxtRecord {
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub name: String,
+  /// Strings of the record, each one is limited to 255 characters
+  #[cfg_attr(feature = "schemars", schemars(length(min = 1)))]
+  pub values: Vec<String>,
+}
+
+/// A SRV record locating a service eg: `_http._tcp.example.com`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsSrvRecord {
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub name: String,
+  /// Host providing the service
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub target: String,
+  pub port: u16,
+  /// Default to 0
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub priority: Option<u16>,
+  /// Default to 0
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub weight: Option<u16>,
+}
+
+/// A MX record designating a mail server
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsMxRecord {
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub name: String,
+  /// Host of the mail server
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub exchange: String,
+  /// Default to 1, lower values are preferred
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub preference: Option<u16>,
+}
+
+/// A PTR record eg: `4.0.0.10.in-addr.arpa` to `host.example.com`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct DnsPtrRecord {
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub name: String,
+  #[cfg_attr(feature = "schemars", schemars(regex = "DNS_NAME_PATTERN"))]
+  pub target: String,
+}
+
+/// A dns record other than the A records of the entries
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum DnsRecord {
+  Aaaa(DnsAaaaRecord),
+  Cname(DnsCnameRecord),
+  Txt(DnsTxtRecord),
+  Srv(DnsSrvRecord),
+  Mx(DnsMxRecord),
+  Ptr(DnsPtrRecord),
+}
+
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -26,5 +183,154 @@ pub struct DnsEntry {
 )]
 pub struct ResourceDnsRule {
   pub network: NetworkKind,
+  /// Only answer the instances of this namespace,
+  /// the entries are visible to every namespace when empty
+  /// and the ones of a namespace take precedence over them
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub namespace: Option<String>,
   pub entries: Vec<DnsEntry>,
+  /// Records of other types than A
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  pub records: Option<Vec<DnsRecord>>,
+}
+
+/// Build an invalid input error for a record field
+fn invalid_record(name: &str, msg: &str) -> std::io::Error {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidInput,
+    format!("Record {name}: {msg}"),
+  )
+}
+
+/// Ensure the name is a valid domain name, optionally starting with `*.`
+fn validate_name(name: &str, allow_wildcard: bool) -> std::io::Result<()> {
+  let domain = match name.strip_prefix("*.") {
+    Some(domain) if allow_wildcard => domain,
+    Some(_) => return Err(invalid_record(name, "wildcard is not supported")),
+    None => name,
+  };
+  let domain = domain.strip_suffix('.').unwrap_or(domain);
+  let is_valid = !domain.is_empty()
+    && domain.split('.').all(|label| {
+      !label.is_empty()
+        && label.len() <= 63
+        && label
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    });
+  if !is_valid {
+    return Err(invalid_record(name, "invalid domain name"));
+  }
+  Ok(())
+}
+
+impl DnsRecord {
+  /// Name the record is served for
+  pub fn name(&self) -> &str {
+    match self {
+      DnsRecord::Aaaa(record) => &record.name,
+      DnsRecord::Cname(record) => &record.name,
+      DnsRecord::Txt(record) => &record.name,
+      DnsRecord::Srv(record) => &record.name,
+      DnsRecord::Mx(record) => &record.name,
+      DnsRecord::Ptr(record) => &record.name,
+    }
+  }
+
+  /// Ensure the record can be rendered into a valid dns configuration
+  pub fn validate(&self) -> std::io::Result<()> {
+    match self {
+      DnsRecord::Aaaa(record) => validate_name(&record.name, true),
+      DnsRecord::Cname(record) => {
+        validate_name(&record.name, true)?;
+        validate_name(&record.target, false)
+      }
+      DnsRecord::Txt(record) => {
+        validate_name(&record.name, false)?;
+        if record.values.is_empty() {
+          return Err(invalid_record(&record.name, "Values cannot be empty"));
+        }
+        for value in &record.values {
+          if value.len() > 255 || value.contains(['"', '\n', '\\']) {
+            return Err(invalid_record(
+              &record.name,
+              "Values must be less than 256 characters without quotes",
+            ));
+          }
+        }
+        Ok(())
+      }
+      DnsRecord::Srv(record) => {
+        validate_name(&record.name, false)?;
+        validate_name(&record.target, false)
+      }
+      DnsRecord::Mx(record) => {
+        validate_name(&record.name, false)?;
+        validate_name(&record.exchange, false)
+      }
+      DnsRecord::Ptr(record) => {
+        validate_name(&record.name, false)?;
+        validate_name(&record.target, false)
+      }
+    }
+  }
+}
+
+impl ResourceDnsRule {
+  /// Ensure the entries and records can be rendered into a valid dns configuration
+  pub fn validate(&self) -> std::io::Result<()> {
+    if let Some(namespace) = &self.namespace {
+      let is_valid = !namespace.is_empty()
+        && namespace
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+      if !is_valid {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Namespace {namespace}: invalid name"),
+        ));
+      }
+    }
+    for entry in &self.entries {
+      validate_name(&entry.name, true)?;
+    }
+    for record in self.records.as_deref().unwrap_or_default() {
+      record.validate()?;
+    }
+    Ok(())
+  }
+}
+
+/// Query of the records answered by ncdns
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DnsRecordQuery {
+  /// Network the clients query the records from, default to internal
+  pub network: Option<NetworkKind>,
+  /// Namespace of the clients, only the global records are listed when empty
+  pub namespace: Option<String>,
+}
+
+/// A record answered by ncdns
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct DnsServedRecord {
+  /// Name of the record, it can start with `*.`
+  pub name: String,
+  /// Type of the record eg: A, CNAME
+  pub kind: String,
+  /// Value answered for the record
+  pub value: String,
+  /// Namespace the record is scoped to
+  pub namespace: Option<String>,
+  /// Zone serving the record
+  pub zone: String,
 }
@@ -0,0 +1,157 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::generic::NetworkKind;
+
+/// How an ingress serves the plain http traffic when tls is enabled
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum IngressTlsMode {
+  /// Redirect the http traffic to https
+  #[default]
+  Redirect,
+  /// Only listen for https
+  Strict,
+  /// Serve the paths over both http and https
+  Both,
+}
+
+/// Tls configuration of an ingress
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct IngressTls {
+  /// Name of the `nanocl.io/tls` secret holding the certificate
+  pub secret: String,
+  /// How the http traffic is served, default to Redirect
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub mode: Option<IngressTlsMode>,
+}
+
+/// A path of the host routed to a cargo
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct IngressPath {
+  /// The path to route eg: /api
+  pub path: String,
+  /// Key of the cargo to target eg: my-cargo.global
+  pub cargo: String,
+  /// Port of the cargo to target
+  pub port: u16,
+  /// Remove the path from the request before sending it to the cargo
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub strip_prefix: Option<bool>,
+}
+
+/// Resource `nanocl.io/ingress` publishing the cargoes on a host.
+/// Nanocld materializes it into a `ncproxy.io/rule` and a `ncdns.io/rule` resources
+/// named after the ingress and owned by it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ResourceIngress {
+  /// Domain name of the ingress eg: example.com
+  pub host: String,
+  /// Network the proxy listens on and the host resolves to
+  pub network: NetworkKind,
+  /// Paths of the host routed to the cargoes
+  pub paths: Vec<IngressPath>,
+  /// Serve the host over https, only http is served when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub tls: Option<IngressTls>,
+  /// Network of the dns server answering the host,
+  /// no `ncdns.io/rule` is created when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub dns: Option<NetworkKind>,
+}
+
+/// Build an invalid input error for an ingress field
+fn invalid_ingress(field: &str, msg: &str) -> std::io::Error {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidInput,
+    format!("Ingress {field}: {msg}"),
+  )
+}
+
+impl ResourceIngress {
+  /// Ensure the ingress can be rendered into proxy and dns rules
+  pub fn validate(&self) -> std::io::Result<()> {
+    let host = self.host.strip_suffix('.').unwrap_or(&self.host);
+    let is_valid = !host.is_empty()
+      && host.split('.').all(|label| {
+        !label.is_empty()
+          && label.len() <= 63
+          && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+      });
+    if !is_valid {
+      return Err(invalid_ingress(&self.host, "invalid host"));
+    }
+    if self.paths.is_empty() {
+      return Err(invalid_ingress(&self.host, "at least one path is required"));
+    }
+    for (i, path) in self.paths.iter().enumerate() {
+      if !path.path.starts_with('/') {
+        return Err(invalid_ingress(&path.path, "path must start with /"));
+      }
+      if self.paths[..i].iter().any(|other| other.path == path.path) {
+        return Err(invalid_ingress(&path.path, "path is defined twice"));
+      }
+      if path.port == 0 {
+        return Err(invalid_ingress(&path.path, "0 is not a valid port"));
+      }
+      let is_key = matches!(
+        path.cargo.split_once('.'),
+        Some((name, namespace)) if !name.is_empty() && !namespace.is_empty()
+      );
+      if !is_key {
+        return Err(invalid_ingress(
+          &path.path,
+          "cargo must be a key as name.namespace",
+        ));
+      }
+    }
+    if let Some(tls) = &self.tls {
+      if tls.secret.is_empty() {
+        return Err(invalid_ingress(&self.host, "tls secret cannot be empty"));
+      }
+    }
+    if self.dns.is_some() && self.network == NetworkKind::All {
+      return Err(invalid_ingress(
+        &self.host,
+        "the host can't resolve to All networks, choose a specific one",
+      ));
+    }
+    Ok(())
+  }
+}
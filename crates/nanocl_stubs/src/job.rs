@@ -6,6 +6,7 @@ use crate::{
   generic::ImagePullPolicy,
   process::Process,
   system::{EventActor, EventActorKind, ObjPsStatus},
+  volume::VolumeMount,
 };
 
 #[cfg(feature = "utoipa")]
@@ -59,6 +60,12 @@ pub struct JobPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Volumes mounted in the containers
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub volumes: Option<Vec<VolumeMount>>,
   /// List of container to run
   pub containers: Vec<Config>,
 }
@@ -75,6 +82,7 @@ impl From<Job> for JobPartial {
       containers: job.containers,
       image_pull_secret: job.image_pull_secret,
       image_pull_policy: job.image_pull_policy,
+      volumes: job.volumes,
     }
   }
 }
@@ -131,6 +139,12 @@ pub struct Job {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub image_pull_policy: Option<ImagePullPolicy>,
+  /// Volumes mounted in the containers
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub volumes: Option<Vec<VolumeMount>>,
   /// Containers to run
   pub containers: Vec<Config>,
 }
@@ -5,9 +5,11 @@ pub mod cargo;
 pub mod cargo_spec;
 pub mod config;
 pub mod dns;
+pub mod ingress;
 pub mod job;
 pub mod metric;
 pub mod namespace;
+pub mod network_policy;
 pub mod node;
 pub mod process;
 pub mod proxy;
@@ -18,3 +20,4 @@ pub mod statefile;
 pub mod vm;
 pub mod vm_image;
 pub mod vm_spec;
+pub mod volume;
@@ -185,6 +185,18 @@ pub struct HttpMetric {
     serde(deserialize_with = "deserialize_empty_string")
   )]
   pub http_accept_language: Option<String>,
+  /// The length of the request (request line, header, and body)
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, deserialize_with = "deserialize_string_to_i64")
+  )]
+  pub request_length: i64,
+  /// The proxy rule that handled the request
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, deserialize_with = "deserialize_empty_string")
+  )]
+  pub proxy_rule: Option<String>,
 }
 
 /// Represent a stream metric for a metric kind ncproxy.io/stream
@@ -225,4 +237,10 @@ pub struct StreamMetric {
   )]
   pub upstream_bytes_received: i64,
   pub upstream_connect_time: String,
+  /// The proxy rule that handled the session
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, deserialize_with = "deserialize_empty_string")
+  )]
+  pub proxy_rule: Option<String>,
 }
@@ -46,6 +46,81 @@ pub struct NamespacePartial {
   pub metadata: Option<serde_json::Value>,
 }
 
+/// Dedicated network of a namespace defined by the `Network` key of its metadata.
+/// The docker network is named after the namespace and is created and removed with it.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NamespaceNetwork {
+  /// Subnet of the network in CIDR format, docker picks one when empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub subnet: Option<String>,
+  /// Gateway of the network, docker picks one in the subnet when empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub gateway: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl NamespaceNetwork {
+  /// Read the dedicated network from the metadata of a namespace if any
+  pub fn from_metadata(
+    metadata: Option<&serde_json::Value>,
+  ) -> std::io::Result<Option<Self>> {
+    let Some(network) = metadata.and_then(|metadata| metadata.get("Network"))
+    else {
+      return Ok(None);
+    };
+    let network =
+      serde_json::from_value::<Self>(network.clone()).map_err(|err| {
+        std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Network: {err}"),
+        )
+      })?;
+    network.validate()?;
+    Ok(Some(network))
+  }
+
+  /// Ensure the subnet and the gateway are valid addresses
+  pub fn validate(&self) -> std::io::Result<()> {
+    if let Some(subnet) = &self.subnet {
+      let is_valid = subnet
+        .split_once('/')
+        .map(|(addr, prefix)| {
+          addr.parse::<std::net::IpAddr>().is_ok()
+            && prefix.parse::<u8>().is_ok_and(|prefix| prefix <= 128)
+        })
+        .unwrap_or_default();
+      if !is_valid {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Subnet {subnet}: invalid CIDR"),
+        ));
+      }
+    }
+    if let Some(gateway) = &self.gateway {
+      if gateway.parse::<std::net::IpAddr>().is_err() {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Gateway {gateway}: invalid address"),
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
 /// A Namespace Summary is a summary of a namespace
 /// It is used to list all the namespaces
 /// It contains the number of cargoes and instances existing in the namespace
@@ -0,0 +1,149 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Transport protocol of a port allowed by a network policy
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum NetworkPolicyProtocol {
+  Tcp,
+  Udp,
+}
+
+impl std::fmt::Display for NetworkPolicyProtocol {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      NetworkPolicyProtocol::Tcp => write!(f, "tcp"),
+      NetworkPolicyProtocol::Udp => write!(f, "udp"),
+    }
+  }
+}
+
+/// A port allowed by a network policy rule
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicyPort {
+  /// Destination port of the traffic
+  pub port: u16,
+  /// Protocol of the traffic, both tcp and udp are allowed when empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub protocol: Option<NetworkPolicyProtocol>,
+}
+
+/// Traffic allowed between the namespace of a policy and other namespaces
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct NetworkPolicyRule {
+  /// Namespaces of the peers, `*` selects every namespace
+  pub namespaces: Vec<String>,
+  /// Destination ports allowed, every port is allowed when empty
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ports: Option<Vec<NetworkPolicyPort>>,
+}
+
+/// Resource `nanocl.io/network-policy` isolating the instances of a namespace.
+/// Instances of the same namespace and of the `system` namespace can always reach each other,
+/// traffic from or to other namespaces is denied unless a rule allows it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ResourceNetworkPolicy {
+  /// Namespace the policy applies to
+  pub namespace: String,
+  /// Traffic allowed into the namespace, incoming traffic isn't restricted when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ingress: Option<Vec<NetworkPolicyRule>>,
+  /// Traffic allowed out of the namespace, outgoing traffic isn't restricted when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub egress: Option<Vec<NetworkPolicyRule>>,
+}
+
+fn validate_namespace(namespace: &str) -> std::io::Result<()> {
+  let is_valid = !namespace.is_empty()
+    && namespace
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if !is_valid {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidInput,
+      format!("Namespace {namespace}: invalid name"),
+    ));
+  }
+  Ok(())
+}
+
+impl NetworkPolicyRule {
+  /// Ensure the selectors and the ports can be rendered into firewall rules
+  pub fn validate(&self) -> std::io::Result<()> {
+    if self.namespaces.is_empty() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "Rule: at least one namespace is required",
+      ));
+    }
+    for namespace in &self.namespaces {
+      if namespace != "*" {
+        validate_namespace(namespace)?;
+      }
+    }
+    for port in self.ports.as_deref().unwrap_or_default() {
+      if port.port == 0 {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          "Port: 0 is not a valid port",
+        ));
+      }
+    }
+    Ok(())
+  }
+}
+
+impl ResourceNetworkPolicy {
+  /// Ensure the policy can be rendered into firewall rules
+  pub fn validate(&self) -> std::io::Result<()> {
+    validate_namespace(&self.namespace)?;
+    if self.namespace == "system" {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "Namespace system: can't be isolated",
+      ));
+    }
+    let ingress = self.ingress.as_deref().unwrap_or_default();
+    let egress = self.egress.as_deref().unwrap_or_default();
+    for rule in ingress.iter().chain(egress) {
+      rule.validate()?;
+    }
+    Ok(())
+  }
+}
@@ -13,6 +13,7 @@ pub struct Node {
   pub created_at: chrono::NaiveDateTime,
   /// The ip address of the node
   #[cfg_attr(feature = "utoipa", schema(value_type = String))]
+  #[cfg_attr(feature = "schemars", schemars(with = "String"))]
   pub ip_address: ipnet::IpNet,
   /// Endpoint to connect to the node
   pub endpoint: String,
@@ -118,6 +118,26 @@ pub struct Process {
   pub data: ContainerInspectResponse,
 }
 
+impl Process {
+  /// Address of the process reachable from the other nodes when connected
+  /// to the overlay network `nanoclov0`, otherwise on the network it was created on,
+  /// the dedicated network of its namespace or `nanoclbr0`
+  pub fn network_address(&self) -> Option<String> {
+    let networks = self.data.network_settings.as_ref()?.networks.as_ref()?;
+    let network_mode = self
+      .data
+      .host_config
+      .as_ref()
+      .and_then(|host_config| host_config.network_mode.as_ref());
+    networks
+      .get("nanoclov0")
+      .or_else(|| network_mode.and_then(|mode| networks.get(mode)))
+      .or_else(|| networks.get("nanoclbr0"))
+      .and_then(|network| network.ip_address.clone())
+      .filter(|ip_address| !ip_address.is_empty())
+  }
+}
+
 /// Kind of Output
 #[derive(Debug)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
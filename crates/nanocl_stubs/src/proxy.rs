@@ -137,6 +137,170 @@ pub struct HttpTarget {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub redirect: Option<UrlRedirect>,
+  /// TLS settings used to connect to an https url
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub tls: Option<ProxyUpstreamTls>,
+  /// Number of idle connections kept open to the url
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub keepalive: Option<u32>,
+  /// Passive health check of the url
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub health: Option<ProxyUpstreamHealth>,
+}
+
+/// Passive health check of an external upstream
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyUpstreamHealth {
+  /// Number of failed attempts before the upstream is considered unavailable
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_fails: Option<u32>,
+  /// Seconds the upstream is considered unavailable after too many failures
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub fail_timeout: Option<u64>,
+}
+
+/// TLS settings used when connecting to an external https upstream
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyUpstreamTls {
+  /// Name of the `nanocl.io/ca` secret used to verify the upstream certificate
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub ca_secret: Option<String>,
+  /// Verify the upstream certificate against the CA secret or the system CAs
+  /// from `/etc/ssl/certs/ca-certificates.crt`. It's enabled by default even
+  /// without a CA secret, so a self-signed upstream needs its CA in a
+  /// `CaSecret` or an explicit `false`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub verify: Option<bool>,
+  /// Server name sent with SNI, default to the host of the url
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub server_name: Option<String>,
+}
+
+/// Components of an http target url
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpTargetUrl {
+  pub scheme: String,
+  pub host: String,
+  pub port: u16,
+  pub path: String,
+}
+
+/// Build an invalid input error for a target
+fn invalid_target(target: &str, msg: &str) -> std::io::Error {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidInput,
+    format!("Target {target}: {msg}"),
+  )
+}
+
+/// Split an authority into its host and port
+fn split_host_port(
+  authority: &str,
+  default_port: Option<u16>,
+) -> std::io::Result<(String, u16)> {
+  // Ipv6 addresses are written between brackets eg: [::1]:80
+  let (host, port) = match authority.rsplit_once(':') {
+    Some((host, port)) if !port.contains(']') => (host, Some(port)),
+    _ => (authority, None),
+  };
+  if host.is_empty() || host.contains(['/', ' ']) {
+    return Err(invalid_target(authority, "invalid host"));
+  }
+  let port = match port {
+    Some(port) => port
+      .parse::<u16>()
+      .map_err(|_| invalid_target(authority, "invalid port"))?,
+    None => {
+      default_port.ok_or_else(|| invalid_target(authority, "missing port"))?
+    }
+  };
+  if port == 0 {
+    return Err(invalid_target(authority, "port must be greater than 0"));
+  }
+  Ok((host.to_owned(), port))
+}
+
+impl HttpTarget {
+  /// Parse the url into its scheme, host, port and path
+  pub fn parse_url(&self) -> std::io::Result<HttpTargetUrl> {
+    let (scheme, rest) = self
+      .url
+      .split_once("://")
+      .ok_or_else(|| invalid_target(&self.url, "missing scheme"))?;
+    let default_port = match scheme {
+      "http" => 80,
+      "https" => 443,
+      _ => return Err(invalid_target(&self.url, "expected http or https")),
+    };
+    let (authority, path) = match rest.find(['/', '?']) {
+      Some(index) => rest.split_at(index),
+      None => (rest, "/"),
+    };
+    let (host, port) = split_host_port(authority, Some(default_port))?;
+    Ok(HttpTargetUrl {
+      scheme: scheme.to_owned(),
+      host,
+      port,
+      path: path.to_owned(),
+    })
+  }
+
+  /// Ensure the target can be rendered into a valid nginx configuration
+  pub fn validate(&self) -> std::io::Result<()> {
+    // Redirects can point to any url
+    if self.redirect.is_some() {
+      return Ok(());
+    }
+    let url = self.parse_url()?;
+    if self.tls.is_some() && url.scheme != "https" {
+      return Err(invalid_target(&self.url, "Tls require an https url"));
+    }
+    if self.keepalive == Some(0) {
+      return Err(invalid_target(
+        &self.url,
+        "Keepalive must be greater than 0",
+      ));
+    }
+    Ok(())
+  }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -162,8 +326,28 @@ pub enum LocationTarget {
   serde(deny_unknown_fields, rename_all = "PascalCase")
 )]
 pub struct UriTarget {
-  /// Uri to target
+  /// Uri to target eg: tcp://example.com:5432
   pub uri: String,
+  /// Passive health check of the uri
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub health: Option<ProxyUpstreamHealth>,
+}
+
+impl UriTarget {
+  /// Parse the uri into its host and port, the tcp:// or udp:// scheme is optional
+  pub fn host_port(&self) -> std::io::Result<(String, u16)> {
+    let authority = match self.uri.split_once("://") {
+      Some(("tcp" | "udp", authority)) => authority,
+      Some(_) => {
+        return Err(invalid_target(&self.uri, "expected tcp or udp scheme"))
+      }
+      None => &self.uri,
+    };
+    split_host_port(authority, None)
+  }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -275,6 +459,156 @@ pub struct LimitReq {
   pub delay: Option<usize>,
 }
 
+/// Flag to apply after a rewrite rule matched
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum ProxyRewriteFlag {
+  /// Stop processing rewrites and search a new location
+  Last,
+  /// Stop processing rewrites and stay in the current location
+  Break,
+  /// Return a temporary redirect (302)
+  Redirect,
+  /// Return a permanent redirect (301)
+  Permanent,
+}
+
+/// Implement display for ProxyRewriteFlag
+/// This is used to render the flag in the nginx config
+impl std::fmt::Display for ProxyRewriteFlag {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      ProxyRewriteFlag::Last => "last",
+      ProxyRewriteFlag::Break => "break",
+      ProxyRewriteFlag::Redirect => "redirect",
+      ProxyRewriteFlag::Permanent => "permanent",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// Rewrite the request path before it reach the target
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyHttpRewrite {
+  /// Regex matching the request path
+  pub pattern: String,
+  /// Replacement of the matched path, can use captured groups like `$1`
+  pub replacement: String,
+  /// Optional flag to apply when the rewrite matched
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub flag: Option<ProxyRewriteFlag>,
+}
+
+/// Timeouts in seconds when talking to the target
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyHttpTimeouts {
+  /// Timeout to establish a connection with the target
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub connect: Option<u64>,
+  /// Timeout between two successive read operations
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub read: Option<u64>,
+  /// Timeout between two successive write operations
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub send: Option<u64>,
+}
+
+/// Cross-Origin Resource Sharing policy of a location
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyHttpCors {
+  /// Origins allowed to access the location, `*` allow any origin
+  pub allowed_origins: Vec<String>,
+  /// Methods allowed (default GET, POST, PUT, PATCH, DELETE, OPTIONS)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub allowed_methods: Option<Vec<String>>,
+  /// Headers allowed in the request
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub allowed_headers: Option<Vec<String>>,
+  /// Headers exposed to the client
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub expose_headers: Option<Vec<String>>,
+  /// Allow credentials (cookies, authorization headers)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub allow_credentials: Option<bool>,
+  /// How long in seconds the preflight response can be cached
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_age: Option<u64>,
+}
+
+/// Basic authentication backed by a `nanocl.io/htpasswd` secret
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyBasicAuth {
+  /// Name of the secret containing the htpasswd entries
+  pub secret: String,
+  /// Realm displayed by the browser
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub realm: Option<String>,
+}
+
 /// Defines a proxy rule location
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -313,6 +647,228 @@ pub struct ProxyHttpLocation {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub version: Option<f64>,
+  /// Remove the location path from the request before sending it to the target
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub strip_prefix: Option<bool>,
+  /// Rewrite rules to apply on the request path
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub rewrites: Option<Vec<ProxyHttpRewrite>>,
+  /// Extras header to add to the response
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub response_headers: Option<Vec<String>>,
+  /// Timeouts when talking to the target
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub timeouts: Option<ProxyHttpTimeouts>,
+  /// Max size of the request body eg: 10m
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub client_max_body_size: Option<String>,
+  /// Allow websocket connection upgrade
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub websocket: Option<bool>,
+  /// Cross-Origin Resource Sharing policy
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cors: Option<ProxyHttpCors>,
+  /// Protect the location with basic authentication
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub basic_auth: Option<ProxyBasicAuth>,
+}
+
+/// Build an invalid input error for a location field
+fn invalid_location(path: &str, field: &str, msg: &str) -> std::io::Error {
+  std::io::Error::new(
+    std::io::ErrorKind::InvalidInput,
+    format!("Location {path}: {field} {msg}"),
+  )
+}
+
+/// Ensure the values of a location field can't end the nginx directive
+/// or the quoted string they are rendered into
+fn validate_values<'a>(
+  path: &str,
+  field: &str,
+  values: impl IntoIterator<Item = &'a String>,
+) -> std::io::Result<()> {
+  for value in values {
+    if let Some(c) = value
+      .chars()
+      .find(|c| matches!(c, '"' | ';' | '{' | '}' | '\\') || c.is_control())
+    {
+      return Err(invalid_location(
+        path,
+        field,
+        &format!("cannot contain {c:?} got {value:?}"),
+      ));
+    }
+  }
+  Ok(())
+}
+
+impl ProxyHttpLocation {
+  /// Ensure the location can be rendered into a valid nginx configuration
+  pub fn validate(&self) -> std::io::Result<()> {
+    let path = &self.path;
+    if path.trim().is_empty() {
+      return Err(invalid_location(path, "Path", "cannot be empty"));
+    }
+    if self.strip_prefix.unwrap_or_default() && !path.starts_with('/') {
+      return Err(invalid_location(
+        path,
+        "StripPrefix",
+        "require a path starting with /",
+      ));
+    }
+    for rewrite in self.rewrites.as_deref().unwrap_or_default() {
+      if rewrite.pattern.trim().is_empty()
+        || rewrite.replacement.trim().is_empty()
+      {
+        return Err(invalid_location(
+          path,
+          "Rewrites",
+          "pattern and replacement cannot be empty",
+        ));
+      }
+      if rewrite.pattern.contains(char::is_whitespace)
+        || rewrite.replacement.contains(char::is_whitespace)
+      {
+        return Err(invalid_location(
+          path,
+          "Rewrites",
+          "pattern and replacement cannot contain whitespaces",
+        ));
+      }
+      // Patterns are regexes, a backslash is allowed outside of a quoted value
+      for value in [&rewrite.pattern, &rewrite.replacement] {
+        if let Some(c) = value
+          .chars()
+          .find(|c| matches!(c, '"' | ';' | '{' | '}') || c.is_control())
+        {
+          return Err(invalid_location(
+            path,
+            "Rewrites",
+            &format!("cannot contain {c:?} got {value:?}"),
+          ));
+        }
+      }
+    }
+    validate_values(path, "Headers", self.headers.iter().flatten())?;
+    validate_values(
+      path,
+      "ResponseHeaders",
+      self.response_headers.iter().flatten(),
+    )?;
+    for header in self.response_headers.as_deref().unwrap_or_default() {
+      let is_valid = header
+        .trim()
+        .split_once(char::is_whitespace)
+        .map(|(name, value)| !name.is_empty() && !value.trim().is_empty())
+        .unwrap_or_default();
+      if !is_valid {
+        return Err(invalid_location(
+          path,
+          "ResponseHeaders",
+          &format!("expected <name> <value> got {header}"),
+        ));
+      }
+    }
+    if let Some(timeouts) = &self.timeouts {
+      if [timeouts.connect, timeouts.read, timeouts.send].contains(&Some(0)) {
+        return Err(invalid_location(
+          path,
+          "Timeouts",
+          "must be greater than 0",
+        ));
+      }
+    }
+    if let Some(size) = &self.client_max_body_size {
+      let digits = size.trim_end_matches(['k', 'K', 'm', 'M', 'g', 'G']);
+      if digits.is_empty()
+        || size.len() - digits.len() > 1
+        || !digits.chars().all(|c| c.is_ascii_digit())
+      {
+        return Err(invalid_location(
+          path,
+          "ClientMaxBodySize",
+          &format!("expected <number>[k|m|g] got {size}"),
+        ));
+      }
+    }
+    if self.websocket.unwrap_or_default() && self.version.unwrap_or(1.1) < 1.1 {
+      return Err(invalid_location(
+        path,
+        "Websocket",
+        "require http version 1.1 or greater",
+      ));
+    }
+    if let Some(cors) = &self.cors {
+      if cors.allowed_origins.is_empty() {
+        return Err(invalid_location(
+          path,
+          "Cors.AllowedOrigins",
+          "cannot be empty",
+        ));
+      }
+      if cors.allow_credentials.unwrap_or_default()
+        && cors.allowed_origins.iter().any(|origin| origin == "*")
+      {
+        return Err(invalid_location(
+          path,
+          "Cors.AllowCredentials",
+          "cannot be used with a wildcard origin",
+        ));
+      }
+      validate_values(path, "Cors.AllowedOrigins", &cors.allowed_origins)?;
+      validate_values(
+        path,
+        "Cors.AllowedMethods",
+        cors.allowed_methods.iter().flatten(),
+      )?;
+      validate_values(
+        path,
+        "Cors.AllowedHeaders",
+        cors.allowed_headers.iter().flatten(),
+      )?;
+      validate_values(
+        path,
+        "Cors.ExposeHeaders",
+        cors.expose_headers.iter().flatten(),
+      )?;
+    }
+    if let Some(basic_auth) = &self.basic_auth {
+      if basic_auth.secret.trim().is_empty() {
+        return Err(invalid_location(
+          path,
+          "BasicAuth.Secret",
+          "cannot be empty",
+        ));
+      }
+      validate_values(path, "BasicAuth.Realm", basic_auth.realm.iter())?;
+    }
+    Ok(())
+  }
 }
 
 /// Defines a proxy rule http config
@@ -370,3 +926,160 @@ pub struct ResourceProxyRule {
   /// The rules to apply
   pub rules: Vec<ProxyRule>,
 }
+
+impl ResourceProxyRule {
+  /// Ensure every http locations of the rules are valid
+  pub fn validate(&self) -> std::io::Result<()> {
+    for rule in &self.rules {
+      match rule {
+        ProxyRule::Http(http) => {
+          for location in &http.locations {
+            location.validate()?;
+            if let LocationTarget::Http(target) = &location.target {
+              target.validate()?;
+            }
+          }
+        }
+        ProxyRule::Stream(stream) => {
+          if let StreamTarget::Uri(target) = &stream.target {
+            target.host_port()?;
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Protocol of a listener opened by a proxy rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub enum ProxyListenerProtocol {
+  Http,
+  Https,
+  Tcp,
+  Udp,
+}
+
+impl ProxyListenerProtocol {
+  /// Return true if the protocol is bound on udp
+  pub fn is_udp(&self) -> bool {
+    matches!(self, ProxyListenerProtocol::Udp)
+  }
+
+  /// Return true if the protocol is an http one routed by domain
+  pub fn is_http(&self) -> bool {
+    matches!(
+      self,
+      ProxyListenerProtocol::Http | ProxyListenerProtocol::Https
+    )
+  }
+}
+
+/// A listener claimed by a proxy rule resource
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ProxyListener {
+  /// Name of the resource owning the listener
+  pub resource: String,
+  /// Type of the network binding
+  pub network: NetworkKind,
+  /// The port to listen on
+  pub port: u16,
+  /// The protocol of the listener
+  pub protocol: ProxyListenerProtocol,
+  /// The domain when using http
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub domain: Option<String>,
+  /// The location path when using http
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub path: Option<String>,
+}
+
+impl ProxyListener {
+  /// Return true if both listeners cannot be served together.
+  /// Http listeners only conflict when they share the same domain
+  /// since nginx route them by server name.
+  pub fn conflicts_with(&self, other: &ProxyListener) -> bool {
+    if self.port != other.port
+      || self.protocol.is_udp() != other.protocol.is_udp()
+    {
+      return false;
+    }
+    let same_network = self.network == other.network
+      || self.network == NetworkKind::All
+      || other.network == NetworkKind::All;
+    if !same_network {
+      return false;
+    }
+    if self.protocol.is_http() && other.protocol.is_http() {
+      return self.domain == other.domain;
+    }
+    true
+  }
+}
+
+impl ResourceProxyRule {
+  /// List the listeners claimed by the rules of the resource
+  pub fn listeners(&self, resource: &str) -> Vec<ProxyListener> {
+    let mut listeners = Vec::new();
+    for rule in &self.rules {
+      match rule {
+        ProxyRule::Stream(stream) => listeners.push(ProxyListener {
+          resource: resource.to_owned(),
+          network: stream.network.clone(),
+          port: stream.port,
+          protocol: match stream.protocol {
+            ProxyStreamProtocol::Tcp => ProxyListenerProtocol::Tcp,
+            ProxyStreamProtocol::Udp => ProxyListenerProtocol::Udp,
+          },
+          domain: None,
+          path: None,
+        }),
+        ProxyRule::Http(http) => {
+          let (protocol, port) = match http.ssl {
+            Some(_) => (ProxyListenerProtocol::Https, http.port.unwrap_or(443)),
+            None => (ProxyListenerProtocol::Http, http.port.unwrap_or(80)),
+          };
+          let paths = match http.locations.is_empty() {
+            true => vec![None],
+            false => http
+              .locations
+              .iter()
+              .map(|location| Some(location.path.clone()))
+              .collect(),
+          };
+          for path in paths {
+            listeners.push(ProxyListener {
+              resource: resource.to_owned(),
+              network: http.network.clone(),
+              port,
+              protocol: protocol.clone(),
+              domain: http.domain.clone(),
+              path,
+            });
+          }
+        }
+      }
+    }
+    listeners
+  }
+}
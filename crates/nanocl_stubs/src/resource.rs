@@ -112,6 +112,27 @@ pub struct Resource {
   pub spec: ResourceSpec,
 }
 
+/// Result of the last apply of a resource managed by nanocld eg: an ingress,
+/// it's stored apart from the spec so the user metadata stays untouched
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct ResourceStatus {
+  /// Key of the resource
+  pub resource_key: String,
+  /// Status of the last apply eg: Applied, Failed
+  pub status: String,
+  /// Note or error of the last apply
+  pub message: String,
+  /// Date of the last apply
+  pub updated_at: chrono::NaiveDateTime,
+}
+
 /// Convert a Resource into an EventActor
 impl From<Resource> for EventActor {
   fn from(resource: Resource) -> Self {
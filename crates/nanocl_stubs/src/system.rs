@@ -186,6 +186,7 @@ pub enum EventActorKind {
   Secret,
   Process,
   ContainerImage,
+  Volume,
 }
 
 impl std::fmt::Display for EventActorKind {
@@ -199,6 +200,7 @@ impl std::fmt::Display for EventActorKind {
       EventActorKind::Secret => write!(f, "Secret"),
       EventActorKind::Process => write!(f, "Process"),
       EventActorKind::ContainerImage => write!(f, "ContainerImage"),
+      EventActorKind::Volume => write!(f, "Volume"),
     }
   }
 }
@@ -95,3 +95,150 @@ pub struct VmInspect {
   /// List of instances
   pub instances: Vec<Process>,
 }
+
+/// Point-in-time snapshot of a vm with its disks and memory,
+/// stored inside its qcow2 disks
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmSnapshot {
+  /// Name of the snapshot
+  pub name: String,
+  /// When the snapshot was created
+  pub created_at: chrono::NaiveDateTime,
+  /// Size of the saved memory state in bytes
+  pub vm_state_size: u64,
+  /// Time the vm was running when the snapshot was created in seconds
+  pub vm_clock: u64,
+}
+
+/// Payload used to create a snapshot of a running vm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmSnapshotPartial {
+  /// Name of the snapshot
+  pub name: String,
+}
+
+/// A network card hotplugged in a running vm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmNic {
+  /// Id of the card, also the name of its tap interface in the runtime
+  pub id: String,
+  /// Mac address of the card (default: generated by qemu)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub mac: Option<String>,
+}
+
+/// A device to hotplug in a running vm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmHotplug {
+  /// Number of cpu to add, up to the `MaxCpu` of the vm
+  Cpu(u64),
+  /// Memory to add in MB, up to the `MaxMemory` of the vm
+  Memory(u64),
+  /// A network card to add
+  Nic(VmNic),
+}
+
+/// Payload to migrate a vm to another node
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmMigratePayload {
+  /// Name of the node to migrate the vm to
+  pub node: String,
+}
+
+/// Progress of the copy of a vm image to the target node
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmMigrateTransfer {
+  /// Name of the vm image
+  pub image: String,
+  /// Bytes copied
+  pub current: u64,
+  /// Bytes to copy
+  pub total: u64,
+}
+
+/// Progress of the copy of the memory of a running vm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmMigrateMemory {
+  /// Bytes of memory sent
+  pub transferred: u64,
+  /// Bytes of memory left to send, it grows when the guest writes
+  pub remaining: u64,
+  /// Memory of the vm in bytes
+  pub total: u64,
+}
+
+/// Stream of a vm migration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmMigrateStream {
+  /// A disk or one of its backing images is copied
+  Transfer(VmMigrateTransfer),
+  /// The memory of the running vm is copied
+  Memory(VmMigrateMemory),
+  /// The vm now lives on the given node
+  Done(String),
+}
+
+/// Address where a node waits for the memory and the disks of a vm
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmMigrateIncoming {
+  /// Overlay address of the runtime of the vm on the target node
+  pub address: String,
+}
+
+/// Query of the endpoints receiving a migrated vm on the target node
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VmMigrateQuery {
+  /// Name of the namespace
+  pub namespace: Option<String>,
+  /// Token issued by the source node for this migration
+  pub token: String,
+}
@@ -49,3 +49,104 @@ pub enum VmImageCloneStream {
   /// The result of the clone operation
   Done(VmImage),
 }
+
+/// Where a vm image is pulled from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmImageSource {
+  /// Http(s) url of the image file
+  Url(String),
+  /// Reference of an OCI artifact eg: ghcr.io/org/image:tag,
+  /// its biggest layer must be the image file
+  Oci(String),
+}
+
+/// Payload used to pull a vm image
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmImagePullPayload {
+  /// Where to pull the image from
+  pub source: VmImageSource,
+  /// Expected sha256 of the downloaded file as `sha256:<hex>`,
+  /// the digest of the layer is used for an OCI artifact when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub checksum: Option<String>,
+  /// Secret holding the credentials of the OCI registry,
+  /// same as the `ImagePullSecret` of a cargo
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub image_pull_secret: Option<String>,
+}
+
+/// Progress of a vm image download
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VmImagePullProgress {
+  /// Bytes downloaded, including the ones of a resumed download
+  pub current: u64,
+  /// Size of the file when known
+  pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmImagePullStream {
+  /// The progress of the download
+  Progress(VmImagePullProgress),
+  /// The downloaded file is being converted from the given format to qcow2
+  Converting(String),
+  /// The result of the pull operation
+  Done(VmImage),
+}
+
+/// Format of an exported vm image
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum VmImageExportFormat {
+  #[default]
+  Qcow2,
+  Raw,
+}
+
+impl std::fmt::Display for VmImageExportFormat {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::Qcow2 => "qcow2",
+      Self::Raw => "raw",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// Export vm image query
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VmImageExportQuery {
+  /// Format of the exported image (default: qcow2)
+  pub format: Option<VmImageExportFormat>,
+  /// Compress the exported image, only supported by qcow2
+  pub compress: Option<bool>,
+}
@@ -26,6 +26,102 @@ pub struct VmDisk {
   pub size: Option<u64>,
 }
 
+/// Bus a data disk is attached to
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmDiskBus {
+  #[default]
+  Virtio,
+  Scsi,
+  Ide,
+}
+
+impl std::fmt::Display for VmDiskBus {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::Virtio => "virtio",
+      Self::Scsi => "scsi",
+      Self::Ide => "ide",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// Cache mode of a data disk
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub enum VmDiskCache {
+  None,
+  Writeback,
+  Writethrough,
+  Directsync,
+  Unsafe,
+}
+
+impl std::fmt::Display for VmDiskCache {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let data = match self {
+      Self::None => "none",
+      Self::Writeback => "writeback",
+      Self::Writethrough => "writethrough",
+      Self::Directsync => "directsync",
+      Self::Unsafe => "unsafe",
+    };
+    write!(f, "{data}")
+  }
+}
+
+/// Additional disk of a VM, a snapshot of a base image or a blank volume
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmDataDisk {
+  /// Name of the disk, unique for the vm
+  pub name: String,
+  /// Name of the base image to snapshot (default: blank volume)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub image: Option<String>,
+  /// Virtual size of the disk in GB (default: 20).
+  /// A bigger size grows the disk when the vm is updated, it's never shrunk
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub size: Option<u64>,
+  /// Bus the disk is attached to (default: virtio)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub bus: Option<VmDiskBus>,
+  /// Cache mode of the disk (default: qemu default)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cache: Option<VmDiskCache>,
+  /// Attach the disk as read only (default: false)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub read_only: Option<bool>,
+}
+
 /// A vm's resources (cpu, memory, network)
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -40,6 +136,24 @@ pub struct VmHostConfig {
   pub cpu: u64,
   /// Memory of the vm in MB (default: 512)
   pub memory: u64,
+  /// Number of cpu the vm can grow to with hotplug
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_cpu: Option<u64>,
+  /// Memory in MB the vm can grow to with hotplug
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub max_memory: Option<u64>,
+  /// Seconds to wait for the guest to power off before killing it (default: 60)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub shutdown_timeout: Option<u64>,
   /// Network interface of the vm to setup (default: ens3)
   #[cfg_attr(
     feature = "serde",
@@ -54,6 +168,12 @@ pub struct VmHostConfig {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub kvm: Option<bool>,
+  /// Expose the display of the vm through VNC (default: false)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub vnc: Option<bool>,
   /// A list of DNS servers for the vm to use
   #[cfg_attr(
     feature = "serde",
@@ -81,8 +201,12 @@ impl Default for VmHostConfig {
     Self {
       cpu: 1,
       memory: 512,
+      max_cpu: None,
+      max_memory: None,
+      shutdown_timeout: None,
       net_iface: None,
       kvm: None,
+      vnc: None,
       dns: None,
       runtime: None,
       host_tun: None,
@@ -92,6 +216,50 @@ impl Default for VmHostConfig {
   }
 }
 
+/// Cloud-init documents provided to a vm through a NoCloud seed.
+/// It's also the data of a `nanocl.io/cloud-init` secret.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VmCloudInit {
+  /// User-data, usually a yaml document starting with `#cloud-config`
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub user_data: Option<String>,
+  /// Meta-data (default: generated from the key and the hostname of the vm)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub meta_data: Option<String>,
+  /// Network configuration in the version 1 or 2 format
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub network_config: Option<String>,
+  /// Vendor-data
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub vendor_data: Option<String>,
+  /// Name of a `nanocl.io/cloud-init` secret to read the documents from,
+  /// the documents set inline take precedence over the ones of the secret
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub secret: Option<String>,
+}
+
 /// A vm spec partial is used to create a vm
 #[derive(Debug, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
@@ -137,6 +305,12 @@ pub struct VmSpecPartial {
   pub ssh_key: Option<String>,
   /// Disk config of the vm (image, size) required
   pub disk: VmDisk,
+  /// Additional disks of the vm attached after the main disk
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub disks: Option<Vec<VmDataDisk>>,
   /// Mac address of the vm (default: generated)
   #[cfg_attr(
     feature = "serde",
@@ -155,6 +329,47 @@ pub struct VmSpecPartial {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub host_config: Option<VmHostConfig>,
+  /// Cloud-init documents of the vm,
+  /// the user, password and ssh key are ignored when set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cloud_init: Option<VmCloudInit>,
+}
+
+impl VmSpecPartial {
+  /// Ensure the data disks can be created and attached
+  pub fn validate_disks(&self) -> std::io::Result<()> {
+    let disks = self.disks.as_deref().unwrap_or_default();
+    for (i, disk) in disks.iter().enumerate() {
+      let msg = if disk.name.is_empty() || disk.name.contains('.') {
+        Some("name cannot be empty or contain '.'")
+      } else if disks[..i].iter().any(|other| other.name == disk.name) {
+        Some("defined more than once")
+      } else if disk.size == Some(0) {
+        Some("size must be greater than 0")
+      } else if disk
+        .size
+        .is_some_and(|size| size.checked_mul(1 << 30).is_none())
+      {
+        Some("size is too big")
+      } else if disk.read_only.unwrap_or_default()
+        && disk.bus == Some(VmDiskBus::Ide)
+      {
+        Some("the ide bus can't be read only")
+      } else {
+        None
+      };
+      if let Some(msg) = msg {
+        return Err(std::io::Error::new(
+          std::io::ErrorKind::InvalidInput,
+          format!("Disk {}: {msg}", disk.name),
+        ));
+      }
+    }
+    Ok(())
+  }
 }
 
 /// ## VmSpecUpdate
@@ -209,6 +424,12 @@ pub struct VmSpecUpdate {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub ssh_key: Option<String>,
+  /// Additional disks of the vm attached after the main disk
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub disks: Option<Vec<VmDataDisk>>,
   /// User-defined key/value metadata.
   #[cfg_attr(
     feature = "serde",
@@ -221,6 +442,13 @@ pub struct VmSpecUpdate {
     serde(skip_serializing_if = "Option::is_none")
   )]
   pub host_config: Option<VmHostConfig>,
+  /// Cloud-init documents of the vm,
+  /// the user, password and ssh key are ignored when set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cloud_init: Option<VmCloudInit>,
 }
 
 impl From<VmSpecPartial> for VmSpecUpdate {
@@ -234,6 +462,8 @@ impl From<VmSpecPartial> for VmSpecUpdate {
       password: spec.password,
       ssh_key: spec.ssh_key,
       metadata: spec.metadata,
+      disks: spec.disks,
+      cloud_init: spec.cloud_init,
     }
   }
 }
@@ -290,6 +520,12 @@ pub struct VmSpec {
   pub user: Option<String>,
   /// Disk config of the vm
   pub disk: VmDisk,
+  /// Additional disks of the vm attached after the main disk
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub disks: Option<Vec<VmDataDisk>>,
   /// Mac address of the vm
   #[cfg_attr(
     feature = "serde",
@@ -304,6 +540,12 @@ pub struct VmSpec {
   pub labels: Option<HashMap<String, String>>,
   /// A vm's resources (cpu, memory, network)
   pub host_config: VmHostConfig,
+  /// Cloud-init documents of the vm
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub cloud_init: Option<VmCloudInit>,
 }
 
 impl From<VmSpec> for VmSpecUpdate {
@@ -317,6 +559,8 @@ impl From<VmSpec> for VmSpecUpdate {
       password: spec.password,
       ssh_key: spec.ssh_key,
       metadata: spec.metadata,
+      disks: spec.disks,
+      cloud_init: spec.cloud_init,
     }
   }
 }
@@ -334,6 +578,8 @@ impl From<VmSpec> for VmSpecPartial {
       metadata: spec.metadata,
       disk: spec.disk,
       mac_address: spec.mac_address,
+      disks: spec.disks,
+      cloud_init: spec.cloud_init,
     }
   }
 }
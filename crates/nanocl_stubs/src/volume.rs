@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "utoipa")]
+use super::generic::Any;
+
+use crate::system::{EventActor, EventActorKind};
+
+/// Policy to periodically archive the content of a volume on its node
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VolumeBackup {
+  /// Schedule of the backups (cron)
+  pub schedule: String,
+  /// Number of archives to keep, default to 7
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub retention: Option<usize>,
+}
+
+/// A partial volume object. This is used to create a volume.
+/// A volume is a persistent storage of a node that outlives the cargoes and jobs mounting it
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VolumePartial {
+  /// Name of the volume
+  pub name: String,
+  /// Docker volume driver, default to local
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub driver: Option<String>,
+  /// Expected size of the volume in MB, given to the drivers supporting it
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub size: Option<u64>,
+  /// Labels of the docker volume
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub labels: Option<HashMap<String, String>>,
+  /// Backup policy of the volume
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backup: Option<VolumeBackup>,
+  /// Metadata of the volume (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+/// This structure is used to update a volume.
+/// The driver, the size and the labels can't change once the volume exists
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VolumeUpdate {
+  /// New backup policy of the volume, the backups are disabled when not set
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backup: Option<VolumeBackup>,
+  /// New metadata of the volume (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl From<VolumePartial> for VolumeUpdate {
+  fn from(partial: VolumePartial) -> Self {
+    VolumeUpdate {
+      backup: partial.backup,
+      metadata: partial.metadata,
+    }
+  }
+}
+
+/// A volume is a persistent storage of a node that outlives the cargoes and jobs mounting it
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "test", derive(Default))]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct Volume {
+  /// Key of the volume generated with `name` and `namespace_name`,
+  /// it's also the name of the docker volume
+  pub key: String,
+  /// Name of the volume
+  pub name: String,
+  /// Namespace of the volume
+  pub namespace_name: String,
+  /// Node where the volume is stored
+  pub node_name: String,
+  /// The creation date
+  pub created_at: chrono::NaiveDateTime,
+  /// The last update date
+  pub updated_at: chrono::NaiveDateTime,
+  /// Docker volume driver
+  pub driver: String,
+  /// Expected size of the volume in MB
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub size: Option<u64>,
+  /// Labels of the docker volume
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub labels: Option<HashMap<String, String>>,
+  /// Backup policy of the volume
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub backup: Option<VolumeBackup>,
+  /// Metadata of the volume (user defined)
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  #[cfg_attr(feature = "utoipa", schema(value_type = HashMap<String, Any>))]
+  pub metadata: Option<serde_json::Value>,
+}
+
+impl From<Volume> for VolumePartial {
+  fn from(volume: Volume) -> Self {
+    VolumePartial {
+      name: volume.name,
+      driver: Some(volume.driver),
+      size: volume.size,
+      labels: volume.labels,
+      backup: volume.backup,
+      metadata: volume.metadata,
+    }
+  }
+}
+
+/// Convert a Volume into an EventActor
+impl From<Volume> for EventActor {
+  fn from(volume: Volume) -> Self {
+    Self {
+      key: Some(volume.key),
+      kind: EventActorKind::Volume,
+      attributes: Some(serde_json::json!({
+        "Name": volume.name,
+        "Namespace": volume.namespace_name,
+        "Driver": volume.driver,
+        "Metadata": volume.metadata,
+      })),
+    }
+  }
+}
+
+/// Detailed information about a volume
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "PascalCase"))]
+pub struct VolumeInspect {
+  /// Specification of the volume
+  pub spec: Volume,
+  /// Path of the volume on its node
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub mountpoint: Option<String>,
+  /// Keys of the cargoes and names of the jobs mounting the volume
+  pub used_by: Vec<String>,
+}
+
+/// A volume mounted in the containers of a cargo or a job
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+  feature = "serde",
+  serde(deny_unknown_fields, rename_all = "PascalCase")
+)]
+pub struct VolumeMount {
+  /// Name of the volume in the namespace of the cargo or global for a job,
+  /// `name.namespace` to mount a volume of another namespace
+  pub name: String,
+  /// Absolute path of the volume inside the containers
+  pub path: String,
+  /// Mount the volume in read only
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub read_only: Option<bool>,
+}
+
+/// Parse a mount in the format `name:path[:ro]`
+impl std::str::FromStr for VolumeMount {
+  type Err = std::io::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || {
+      std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Volume {s}: expected name:path[:ro]"),
+      )
+    };
+    let mut parts = s.split(':');
+    let (Some(name), Some(path)) = (parts.next(), parts.next()) else {
+      return Err(invalid());
+    };
+    let read_only = match parts.next() {
+      None => None,
+      Some("ro") => Some(true),
+      Some("rw") => Some(false),
+      Some(_) => return Err(invalid()),
+    };
+    if name.is_empty() || !path.starts_with('/') || parts.next().is_some() {
+      return Err(invalid());
+    }
+    Ok(Self {
+      name: name.to_owned(),
+      path: path.to_owned(),
+      read_only,
+    })
+  }
+}
@@ -13,3 +13,6 @@ pub mod versioning;
 
 #[cfg(feature = "build_tools")]
 pub mod build_tools;
+
+#[cfg(feature = "liquid")]
+pub mod liquid;
@@ -0,0 +1,38 @@
+use std::borrow::Cow;
+
+use liquid::{ObjectView, Parser};
+use regex::Regex;
+
+use nanocl_error::io::{IoError, IoResult};
+
+/// Replace the `${{ }}` syntax used by nanocl with the `{{ }}` syntax of liquid
+pub fn normalize(raw: &str) -> IoResult<Cow<'_, str>> {
+  let reg = Regex::new(r"\$\{\{(.+?)\}\}")
+    .map_err(|err| IoError::invalid_data("Regex", &format!("{err}")))?;
+  Ok(reg.replace_all(raw, "{{ $1 }}"))
+}
+
+/// Compile a template with the given object using a custom parser
+pub fn compile_with(
+  parser: &Parser,
+  raw: &str,
+  obj: &dyn ObjectView,
+) -> IoResult<String> {
+  let template = parser.parse(&normalize(raw)?).map_err(|err| {
+    IoError::invalid_data("Template parsing", &format!("{err}"))
+  })?;
+  let output = template.render(&obj).map_err(|err| {
+    IoError::invalid_data("Template rendering", &format!("{err}"))
+  })?;
+  Ok(output)
+}
+
+/// Compile a template with the given object using the standard library of liquid
+pub fn compile(raw: &str, obj: &dyn ObjectView) -> IoResult<String> {
+  let parser = liquid::ParserBuilder::with_stdlib()
+    .build()
+    .map_err(|err| {
+      IoError::invalid_data("Template parser", &format!("{err}"))
+    })?;
+  compile_with(&parser, raw, obj)
+}
@@ -0,0 +1,31 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::dns::{DnsRecordQuery, DnsServedRecord};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for dns
+  const DNS_PATH: &'static str = "/dns";
+
+  /// List the records answered by ncdns to the clients of a network
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_dns_record(None).await;
+  /// ```
+  ///
+  pub async fn list_dns_record(
+    &self,
+    query: Option<&DnsRecordQuery>,
+  ) -> HttpClientResult<Vec<DnsServedRecord>> {
+    let res = self
+      .send_get(&format!("{}/records", Self::DNS_PATH), query)
+      .await?;
+    Self::res_json(res).await
+  }
+}
@@ -1,6 +1,7 @@
 mod http_client;
 
 pub(crate) mod cargo;
+pub(crate) mod dns;
 pub(crate) mod exec;
 pub(crate) mod job;
 pub(crate) mod metric;
@@ -13,6 +14,7 @@ pub(crate) mod secret;
 pub(crate) mod system;
 pub(crate) mod vm;
 pub(crate) mod vm_image;
+pub(crate) mod volume;
 
 pub use bollard_next;
 pub mod error;
@@ -9,6 +9,7 @@ use nanocl_stubs::{
     Process, ProcessLogQuery, ProcessOutputLog, ProcessStats,
     ProcessStatsQuery, ProcessWaitQuery, ProcessWaitResponse,
   },
+  vm::VmHotplug,
 };
 
 use super::NanocldClient;
@@ -174,6 +175,116 @@ impl NanocldClient {
     Ok(())
   }
 
+  /// Pause a vm process by it's name and namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.pause_process("vm", "my-vm", None).await;
+  /// ```
+  ///
+  pub async fn pause_process(
+    &self,
+    kind: &str,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_post(
+        &format!("{}/{kind}/{name}/pause", Self::PROCESS_PATH),
+        None::<String>,
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Resume a paused vm process by it's name and namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.resume_process("vm", "my-vm", None).await;
+  /// ```
+  ///
+  pub async fn resume_process(
+    &self,
+    kind: &str,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_post(
+        &format!("{}/{kind}/{name}/resume", Self::PROCESS_PATH),
+        None::<String>,
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Reset a vm process by it's name and namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.reset_process("vm", "my-vm", None).await;
+  /// ```
+  ///
+  pub async fn reset_process(
+    &self,
+    kind: &str,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_post(
+        &format!("{}/{kind}/{name}/reset", Self::PROCESS_PATH),
+        None::<String>,
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Hotplug a cpu, memory or network card in a running vm process
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::vm::VmHotplug;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.hotplug_process("vm", "my-vm", &VmHotplug::Cpu(1), None).await;
+  /// ```
+  ///
+  pub async fn hotplug_process(
+    &self,
+    kind: &str,
+    name: &str,
+    device: &VmHotplug,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_post(
+        &format!("{}/{kind}/{name}/hotplug", Self::PROCESS_PATH),
+        Some(device),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
   /// A stream is returned, data are sent when processes reach status
   ///
   /// ## Example
@@ -2,7 +2,7 @@ use nanocl_error::http_client::HttpClientResult;
 
 use nanocl_stubs::generic::GenericFilter;
 use nanocl_stubs::resource::{
-  Resource, ResourcePartial, ResourceSpec, ResourceUpdate,
+  Resource, ResourcePartial, ResourceSpec, ResourceStatus, ResourceUpdate,
 };
 
 use super::http_client::NanocldClient;
@@ -31,6 +31,36 @@ impl NanocldClient {
     Self::res_json(res).await
   }
 
+  /// List every resource matching the filter page by page,
+  /// the limit and the offset of the filter are ignored
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_all_resource(&GenericFilter::new()).await;
+  /// ```
+  ///
+  pub async fn list_all_resource(
+    &self,
+    filter: &GenericFilter,
+  ) -> HttpClientResult<Vec<Resource>> {
+    const PAGE_SIZE: usize = 100;
+    let mut resources = Vec::new();
+    loop {
+      let filter = filter.clone().limit(PAGE_SIZE).offset(resources.len());
+      let page = self.list_resource(Some(&filter)).await?;
+      let len = page.len();
+      resources.extend(page);
+      if len < PAGE_SIZE {
+        break;
+      }
+    }
+    Ok(resources)
+  }
+
   /// Create a new resource from a partial resource in the system.
   ///
   /// ## Example
@@ -80,6 +110,29 @@ impl NanocldClient {
     Self::res_json(res).await
   }
 
+  /// Inspect the result of the last apply of a resource managed by nanocld
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.inspect_resource_status("my-ingress").await;
+  /// ```
+  pub async fn inspect_resource_status(
+    &self,
+    key: &str,
+  ) -> HttpClientResult<ResourceStatus> {
+    let res = self
+      .send_get(
+        &format!("{}/{key}/status", Self::RESOURCE_PATH),
+        None::<String>,
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
   /// Update the new resource spec and add an history entry
   ///
   /// ## Example
@@ -1,10 +1,15 @@
+use ntex::channel::mpsc::Receiver;
 use ntex::{io, rt, ws};
 
+use nanocl_error::http::HttpResult;
 use nanocl_error::http_client::HttpClientResult;
 use nanocl_error::io::FromIo;
 
 use nanocl_stubs::generic::{GenericFilterNsp, GenericNspQuery};
-use nanocl_stubs::vm::{Vm, VmInspect, VmSummary};
+use nanocl_stubs::vm::{
+  Vm, VmInspect, VmMigratePayload, VmMigrateStream, VmSnapshot,
+  VmSnapshotPartial, VmSummary,
+};
 use nanocl_stubs::vm_spec::{VmSpecPartial, VmSpecUpdate};
 
 use crate::NanocldClient;
@@ -114,20 +119,11 @@ impl NanocldClient {
     Ok(())
   }
 
-  /// Attach to a vm by it's name and namespace
-  /// and return websocket stream to send input and receive output from the vm tty
-  ///
-  /// ## Example
-  ///
-  /// ```no_run,ignore
-  /// use nanocld_client::NanocldClient;
-  ///
-  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
-  /// let res = client.attach_vm("my-vm", None).await;
-  /// ```
-  pub async fn attach_vm(
+  /// Open a websocket connection to a vm endpoint by it's name and namespace
+  async fn connect_vm_ws(
     &self,
     name: &str,
+    endpoint: &str,
     namespace: Option<&str>,
   ) -> HttpClientResult<ws::WsConnection<io::Base>> {
     let qs = if let Some(namespace) = namespace {
@@ -135,7 +131,8 @@ impl NanocldClient {
     } else {
       "".to_owned()
     };
-    let url = format!("{}/{}/vms/{name}/attach{qs}", self.url, &self.version);
+    let url =
+      format!("{}/{}/vms/{name}/{endpoint}{qs}", self.url, &self.version);
     // open websockets connection over http transport
     #[cfg(not(target_os = "windows"))]
     {
@@ -169,4 +166,174 @@ impl NanocldClient {
       Ok(con)
     }
   }
+
+  /// Attach to a vm by it's name and namespace
+  /// and return websocket stream to send input and receive output from the vm tty
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.attach_vm("my-vm", None).await;
+  /// ```
+  pub async fn attach_vm(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<ws::WsConnection<io::Base>> {
+    self.connect_vm_ws(name, "attach", namespace).await
+  }
+
+  /// Open the VNC display of a vm by it's name and namespace
+  /// and return websocket stream carrying the raw RFB protocol
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.vnc_vm("my-vm", None).await;
+  /// ```
+  pub async fn vnc_vm(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<ws::WsConnection<io::Base>> {
+    self.connect_vm_ws(name, "vnc", namespace).await
+  }
+
+  /// List the snapshots of a vm
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_vm_snapshots("my-vm", None).await;
+  /// ```
+  pub async fn list_vm_snapshots(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<Vec<VmSnapshot>> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/snapshots", Self::VM_PATH),
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Snapshot the disks and the memory of a running vm
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::vm::VmSnapshotPartial;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let snapshot = VmSnapshotPartial { name: "before-upgrade".to_owned() };
+  /// let res = client.create_vm_snapshot("my-vm", &snapshot, None).await;
+  /// ```
+  pub async fn create_vm_snapshot(
+    &self,
+    name: &str,
+    snapshot: &VmSnapshotPartial,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<VmSnapshot> {
+    let res = self
+      .send_post(
+        &format!("{}/{name}/snapshots", Self::VM_PATH),
+        Some(snapshot),
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Restore a running vm to a snapshot
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.restore_vm_snapshot("my-vm", "before-upgrade", None).await;
+  /// ```
+  pub async fn restore_vm_snapshot(
+    &self,
+    name: &str,
+    snapshot: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_post(
+        &format!("{}/{name}/snapshots/{snapshot}/restore", Self::VM_PATH),
+        None::<String>,
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Delete a snapshot of a vm
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.delete_vm_snapshot("my-vm", "before-upgrade", None).await;
+  /// ```
+  pub async fn delete_vm_snapshot(
+    &self,
+    name: &str,
+    snapshot: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(
+        &format!("{}/{name}/snapshots/{snapshot}", Self::VM_PATH),
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+
+  /// Move a vm and its disks to another node,
+  /// the progress of the migration is streamed back
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::vm::VmMigratePayload;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let payload = VmMigratePayload { node: "node-2".to_owned() };
+  /// let res = client.migrate_vm("my-vm", &payload, None).await;
+  /// ```
+  pub async fn migrate_vm(
+    &self,
+    name: &str,
+    payload: &VmMigratePayload,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<Receiver<HttpResult<VmMigrateStream>>> {
+    let res = self
+      .send_post(
+        &format!("{}/{name}/migrate", Self::VM_PATH),
+        Some(payload),
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(Self::res_stream(res).await)
+  }
 }
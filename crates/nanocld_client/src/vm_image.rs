@@ -1,15 +1,18 @@
 use std::error::Error;
 
-use futures::Stream;
+use futures::{Stream, StreamExt};
 use ntex::channel::mpsc::Receiver;
 use ntex::util::Bytes;
 
-use nanocl_error::http::HttpResult;
+use nanocl_error::http::{HttpError, HttpResult};
 use nanocl_error::http_client::HttpClientResult;
 
 use nanocl_stubs::{
   generic::GenericFilter,
-  vm_image::{VmImage, VmImageCloneStream, VmImageResizePayload},
+  vm_image::{
+    VmImage, VmImageCloneStream, VmImageExportQuery, VmImagePullPayload,
+    VmImagePullStream, VmImageResizePayload,
+  },
 };
 
 use crate::NanocldClient;
@@ -100,6 +103,65 @@ impl NanocldClient {
     Ok(Self::res_stream(res).await)
   }
 
+  /// Pull a vm image from an url or an OCI registry,
+  /// the progress of the download is streamed back
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  /// use nanocld_client::stubs::vm_image::{VmImagePullPayload, VmImageSource};
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.pull_vm_image("my-image", &VmImagePullPayload {
+  ///   source: VmImageSource::Oci("ghcr.io/org/ubuntu:24.04".to_owned()),
+  ///   checksum: None,
+  ///   image_pull_secret: None,
+  /// }).await;
+  /// ```
+  pub async fn pull_vm_image(
+    &self,
+    name: &str,
+    payload: &VmImagePullPayload,
+  ) -> HttpClientResult<Receiver<HttpResult<VmImagePullStream>>> {
+    let res = self
+      .send_post(
+        &format!("{}/{name}/pull", Self::VM_IMAGE_PATH),
+        Some(payload.clone()),
+        None::<String>,
+      )
+      .await?;
+    Ok(Self::res_stream(res).await)
+  }
+
+  /// Export a vm image as a stream of bytes,
+  /// a snapshot is flattened with its parents
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.export_vm_image("my-image", None).await;
+  /// ```
+  pub async fn export_vm_image(
+    &self,
+    name: &str,
+    query: Option<&VmImageExportQuery>,
+  ) -> HttpClientResult<impl Stream<Item = HttpResult<Bytes>>> {
+    let res = self
+      .send_get(&format!("{}/{name}/export", Self::VM_IMAGE_PATH), query)
+      .await?;
+    Ok(res.map(|bytes| {
+      bytes.map_err(|err| {
+        HttpError::internal_server_error(format!(
+          "Unable to read stream: {err}"
+        ))
+      })
+    }))
+  }
+
   /// Resize a vm image by it's name
   ///
   /// ## Example
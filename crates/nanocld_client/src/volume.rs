@@ -0,0 +1,126 @@
+use nanocl_error::http_client::HttpClientResult;
+
+use nanocl_stubs::{
+  generic::{GenericFilterNsp, GenericNspQuery},
+  volume::{Volume, VolumeInspect, VolumePartial, VolumeUpdate},
+};
+
+use super::http_client::NanocldClient;
+
+impl NanocldClient {
+  /// ## Default path for volumes
+  const VOLUME_PATH: &'static str = "/volumes";
+
+  /// List volumes of a namespace
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let res = client.list_volume(None).await;
+  /// ```
+  pub async fn list_volume(
+    &self,
+    query: Option<&GenericFilterNsp>,
+  ) -> HttpClientResult<Vec<Volume>> {
+    let query = Self::convert_query(query)?;
+    let res = self.send_get(Self::VOLUME_PATH, Some(query)).await?;
+    Self::res_json(res).await
+  }
+
+  /// Create a new volume on the node of the daemon
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let volume = VolumePartial {
+  ///   name: String::from("my-volume"),
+  ///   ..Default::default()
+  /// };
+  /// let res = client.create_volume(&volume, None).await;
+  /// ```
+  pub async fn create_volume(
+    &self,
+    item: &VolumePartial,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<Volume> {
+    let res = self
+      .send_post(
+        Self::VOLUME_PATH,
+        Some(item),
+        Some(&GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Patch the backup policy and the metadata of a volume by it's name
+  pub async fn patch_volume(
+    &self,
+    name: &str,
+    item: &VolumeUpdate,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<Volume> {
+    let res = self
+      .send_patch(
+        &format!("{}/{name}", Self::VOLUME_PATH),
+        Some(item),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Inspect a volume by it's name to get its mountpoint and who uses it
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// let volume = client.inspect_volume("my-volume", None).await?;
+  /// ```
+  pub async fn inspect_volume(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<VolumeInspect> {
+    let res = self
+      .send_get(
+        &format!("{}/{name}/inspect", Self::VOLUME_PATH),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Self::res_json(res).await
+  }
+
+  /// Delete a volume by it's name, it fails while a cargo or a job mounts it
+  ///
+  /// ## Example
+  ///
+  /// ```no_run,ignore
+  /// use nanocld_client::NanocldClient;
+  ///
+  /// let client = NanocldClient::connect_to("http://localhost:8585", None);
+  /// client.delete_volume("my-volume", None).await?;
+  /// ```
+  pub async fn delete_volume(
+    &self,
+    name: &str,
+    namespace: Option<&str>,
+  ) -> HttpClientResult<()> {
+    self
+      .send_delete(
+        &format!("{}/{name}", Self::VOLUME_PATH),
+        Some(GenericNspQuery::new(namespace)),
+      )
+      .await?;
+    Ok(())
+  }
+}